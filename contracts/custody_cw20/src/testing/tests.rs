@@ -102,7 +102,7 @@ fn deposit_collateral() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "addr0000".to_string(),
         amount: Uint128::from(100u128),
-        msg: to_binary(&Cw20HookMsg::DepositCollateral {}).unwrap(),
+        msg: to_binary(&Cw20HookMsg::DepositCollateral { borrower: None }).unwrap(),
     });
 
     // failed; cannot directly execute receive message
@@ -184,6 +184,44 @@ fn deposit_collateral() {
             spendable: Uint256::from(200u128),
         }
     );
+
+    // Deposit on behalf of another borrower
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "market".to_string(),
+        amount: Uint128::from(100u128),
+        msg: to_binary(&Cw20HookMsg::DepositCollateral {
+            borrower: Some("addr0001".to_string()),
+        })
+        .unwrap(),
+    });
+    let info = mock_info("lunax", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "deposit_collateral"),
+            attr("borrower", "addr0001"),
+            attr("amount", "100"),
+        ]
+    );
+
+    let query_res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Borrower {
+            address: "addr0001".to_string(),
+        },
+    )
+    .unwrap();
+    let borrower_res: BorrowerResponse = from_binary(&query_res).unwrap();
+    assert_eq!(
+        borrower_res,
+        BorrowerResponse {
+            borrower: "addr0001".to_string(),
+            balance: Uint256::from(100u128),
+            spendable: Uint256::from(100u128),
+        }
+    );
 }
 
 #[test]
@@ -205,7 +243,7 @@ fn withdraw_collateral() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "addr0000".to_string(),
         amount: Uint128::from(100u128),
-        msg: to_binary(&Cw20HookMsg::DepositCollateral {}).unwrap(),
+        msg: to_binary(&Cw20HookMsg::DepositCollateral { borrower: None }).unwrap(),
     });
 
     let info = mock_info("lunax", &[]);
@@ -336,7 +374,7 @@ fn lock_collateral() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "addr0000".to_string(),
         amount: Uint128::from(100u128),
-        msg: to_binary(&Cw20HookMsg::DepositCollateral {}).unwrap(),
+        msg: to_binary(&Cw20HookMsg::DepositCollateral { borrower: None }).unwrap(),
     });
 
     let info = mock_info("lunax", &[]);
@@ -521,7 +559,7 @@ fn liquidate_collateral() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "addr0000".to_string(),
         amount: Uint128::from(100u128),
-        msg: to_binary(&Cw20HookMsg::DepositCollateral {}).unwrap(),
+        msg: to_binary(&Cw20HookMsg::DepositCollateral { borrower: None }).unwrap(),
     });
 
     let info = mock_info("lunax", &[]);
@@ -554,6 +592,7 @@ fn liquidate_collateral() {
         liquidator: "addr0001".to_string(),
         borrower: "addr0000".to_string(),
         amount: Uint256::from(100u64),
+        position_id: None,
     };
     let info = mock_info("addr0000", &[]);
     let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
@@ -572,6 +611,7 @@ fn liquidate_collateral() {
         liquidator: "liquidator".to_string(),
         borrower: "addr0000".to_string(),
         amount: Uint256::from(10u64),
+        position_id: None,
     };
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
     assert_eq!(
@@ -597,6 +637,7 @@ fn liquidate_collateral() {
                     fee_address: Some("collector".to_string()),
                     repay_address: Some("market".to_string()),
                     borrower_address: Some("addr0000".to_string()),
+                    position_id: None,
                 })
                 .unwrap()
             })
@@ -604,3 +645,84 @@ fn liquidate_collateral() {
         }))]
     );
 }
+
+#[test]
+fn transfer_locked_collateral() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner: "owner".to_string(),
+        collateral_token: "lunax".to_string(),
+        overseer_contract: "overseer".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(100u128),
+        msg: to_binary(&Cw20HookMsg::DepositCollateral { borrower: None }).unwrap(),
+    });
+    let info = mock_info("lunax", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::LockCollateral {
+        borrower: "addr0000".to_string(),
+        amount: Uint256::from(60u64),
+    };
+    let info = mock_info("overseer", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::TransferLockedCollateral {
+        borrower: "addr0000".to_string(),
+        recipient: "addr0001".to_string(),
+        amount: Uint256::from(60u64),
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let info = mock_info("overseer", &[]);
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::TransferLockedCollateral {
+            borrower: "addr0000".to_string(),
+            recipient: "addr0001".to_string(),
+            amount: Uint256::from(61u64),
+        },
+    );
+    match res {
+        Err(ContractError::UnlockAmountExceedsLocked(60)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "transfer_locked_collateral"),
+            attr("borrower", "addr0000"),
+            attr("recipient", "addr0001"),
+            attr("amount", "60"),
+        ]
+    );
+    assert!(res.messages.is_empty());
+
+    // the spendable collateral stays with the borrower
+    let borrower_info = read_borrower_info(&deps.storage, &Addr::unchecked("addr0000"));
+    assert_eq!(borrower_info.balance, Uint256::from(40u64));
+    assert_eq!(borrower_info.spendable, Uint256::from(40u64));
+
+    let recipient_info = read_borrower_info(&deps.storage, &Addr::unchecked("addr0001"));
+    assert_eq!(recipient_info.balance, Uint256::from(60u64));
+    assert_eq!(recipient_info.spendable, Uint256::zero());
+}
@@ -8,7 +8,7 @@ use cosmwasm_std::{
 
 use crate::collateral::{
     deposit_collateral, liquidate_collateral, lock_collateral, query_borrower, query_borrowers,
-    unlock_collateral, withdraw_collateral,
+    transfer_locked_collateral, unlock_collateral, withdraw_collateral, withdraw_locked_collateral,
 };
 use crate::error::ContractError;
 use crate::state::{
@@ -87,10 +87,36 @@ pub fn execute(
             liquidator,
             borrower,
             amount,
+            position_id,
         } => {
             let liquidator_addr = deps.api.addr_validate(&liquidator)?;
             let borrower_addr = deps.api.addr_validate(&borrower)?;
-            liquidate_collateral(deps, info, liquidator_addr, borrower_addr, amount)
+            liquidate_collateral(
+                deps,
+                info,
+                liquidator_addr,
+                borrower_addr,
+                amount,
+                position_id,
+            )
+        }
+        ExecuteMsg::WithdrawLockedCollateral {
+            borrower,
+            recipient,
+            amount,
+        } => {
+            let borrower_addr = deps.api.addr_validate(&borrower)?;
+            let recipient_addr = deps.api.addr_validate(&recipient)?;
+            withdraw_locked_collateral(deps, info, borrower_addr, recipient_addr, amount)
+        }
+        ExecuteMsg::TransferLockedCollateral {
+            borrower,
+            recipient,
+            amount,
+        } => {
+            let borrower_addr = deps.api.addr_validate(&borrower)?;
+            let recipient_addr = deps.api.addr_validate(&recipient)?;
+            transfer_locked_collateral(deps, info, borrower_addr, recipient_addr, amount)
         }
     }
 }
@@ -104,7 +130,7 @@ pub fn receive_cw20(
     let contract_balance = read_contract_balance_info(deps.storage)?;
 
     match from_binary(&cw20_msg.msg) {
-        Ok(Cw20HookMsg::DepositCollateral {}) => {
+        Ok(Cw20HookMsg::DepositCollateral { borrower }) => {
             // only asset contract can execute this message
             let config: Config = read_config(deps.storage)?;
             if contract_addr != config.collateral_token {
@@ -118,8 +144,11 @@ pub fn receive_cw20(
                 return Err(ContractError::InvalidMaxDeposit(new_balance.into()));
             }
 
-            let cw20_sender_addr = deps.api.addr_validate(&cw20_msg.sender)?;
-            deposit_collateral(deps, cw20_sender_addr, cw20_msg.amount.into())
+            let borrower_validated = match borrower {
+                Some(borrower) => deps.api.addr_validate(&borrower)?,
+                None => deps.api.addr_validate(&cw20_msg.sender)?,
+            };
+            deposit_collateral(deps, borrower_validated, cw20_msg.amount.into())
         }
         _ => Err(ContractError::MissingDepositCollateralHook {}),
     }
@@ -12,6 +12,7 @@ use cosmwasm_std::{
 use cw20::Cw20ExecuteMsg;
 use moneymarket::custody::{BorrowerResponse, BorrowersResponse};
 use moneymarket::liquidation::Cw20HookMsg as LiquidationCw20HookMsg;
+use moneymarket::position::PositionId;
 
 /// Deposit new collateral
 /// Executor: Collateral token contract
@@ -172,6 +173,7 @@ pub fn liquidate_collateral(
     liquidator: Addr,
     borrower: Addr,
     amount: Uint256,
+    position_id: Option<PositionId>,
 ) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
     let mut contract_balance_info: ContractBalanceInfo = read_contract_balance_info(deps.storage)?;
@@ -208,6 +210,7 @@ pub fn liquidate_collateral(
                     fee_address: Some(config.collector_contract.to_string()),
                     repay_address: Some(config.market_contract.to_string()),
                     borrower_address: Some(borrower_validated.to_string()),
+                    position_id,
                 })?,
             })?,
         }))
@@ -219,6 +222,99 @@ pub fn liquidate_collateral(
         ]))
 }
 
+/// Release locked collateral of a borrower and send it to the recipient.
+/// Used by the market to repay loans with collateral.
+/// Executor: overseer
+pub fn withdraw_locked_collateral(
+    deps: DepsMut,
+    info: MessageInfo,
+    borrower: Addr,
+    recipient: Addr,
+    amount: Uint256,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let mut contract_balance_info: ContractBalanceInfo = read_contract_balance_info(deps.storage)?;
+
+    // Only overseer can execute the contract
+    if info.sender != config.overseer_contract {
+        return Err(ContractError::Unauthorized {});
+    }
+    // load borrower info and get the locked balance
+    let mut borrower_info: BorrowerInfo = read_borrower_info(deps.storage, &borrower);
+    let borrowed_amt = borrower_info.balance - borrower_info.spendable;
+    if amount > borrowed_amt {
+        return Err(ContractError::UnlockAmountExceedsLocked(
+            borrowed_amt.into(),
+        ));
+    }
+    // update borrower balance
+    borrower_info.balance = borrower_info.balance - amount;
+    contract_balance_info.balance = contract_balance_info.balance - amount;
+    if borrower_info.balance == Uint256::zero() {
+        remove_borrower_info(deps.storage, &borrower);
+    } else {
+        store_borrower_info(deps.storage, &borrower, &borrower_info)?;
+    }
+    store_contract_balance_info(deps.storage, &contract_balance_info)?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.collateral_token.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount: amount.into(),
+            })?,
+        }))
+        .add_attributes(vec![
+            attr("action", "withdraw_locked_collateral"),
+            attr("borrower", borrower),
+            attr("recipient", recipient),
+            attr("amount", amount),
+        ]))
+}
+
+pub fn transfer_locked_collateral(
+    deps: DepsMut,
+    info: MessageInfo,
+    borrower: Addr,
+    recipient: Addr,
+    amount: Uint256,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+
+    // Only overseer can execute the contract
+    if info.sender != config.overseer_contract {
+        return Err(ContractError::Unauthorized {});
+    }
+    // load borrower info and get the locked balance
+    let mut borrower_info: BorrowerInfo = read_borrower_info(deps.storage, &borrower);
+    let borrowed_amt = borrower_info.balance - borrower_info.spendable;
+    if amount > borrowed_amt {
+        return Err(ContractError::UnlockAmountExceedsLocked(
+            borrowed_amt.into(),
+        ));
+    }
+    // the collateral stays in custody, only its owner changes
+    borrower_info.balance = borrower_info.balance - amount;
+    if borrower_info.balance == Uint256::zero() {
+        remove_borrower_info(deps.storage, &borrower);
+    } else {
+        store_borrower_info(deps.storage, &borrower, &borrower_info)?;
+    }
+
+    let mut recipient_info: BorrowerInfo = read_borrower_info(deps.storage, &recipient);
+    recipient_info.balance += amount;
+    store_borrower_info(deps.storage, &recipient, &recipient_info)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "transfer_locked_collateral"),
+        attr("borrower", borrower),
+        attr("recipient", recipient),
+        attr("amount", amount),
+    ]))
+}
+
 pub fn query_borrower(deps: Deps, borrower: Addr) -> StdResult<BorrowerResponse> {
     let borrower_validated = deps.api.addr_validate(borrower.as_str())?;
     let borrower_info: BorrowerInfo = read_borrower_info(deps.storage, &borrower_validated);
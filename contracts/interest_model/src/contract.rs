@@ -0,0 +1,172 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use crate::error::ContractError;
+use crate::state::{read_config, store_config, Config};
+
+use cosmwasm_bignumber::math::Decimal256;
+use cosmwasm_std::{attr, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use moneymarket::interest_model::{
+    BorrowRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
+};
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    if msg.base_peg.is_zero() {
+        return Err(ContractError::InvalidBasePeg {});
+    }
+
+    store_config(
+        deps.storage,
+        &Config {
+            owner: deps.api.addr_validate(&msg.owner)?,
+            base_rate: msg.base_rate,
+            base_peg: msg.base_peg,
+            multiplier: msg.multiplier,
+            kink: msg.kink,
+            jump_multiplier: msg.jump_multiplier,
+        },
+    )?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::UpdateConfig {
+            owner,
+            base_rate,
+            base_peg,
+            multiplier,
+            kink,
+            jump_multiplier,
+        } => update_config(
+            deps,
+            info,
+            owner,
+            base_rate,
+            base_peg,
+            multiplier,
+            kink,
+            jump_multiplier,
+        ),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    owner: Option<String>,
+    base_rate: Option<Decimal256>,
+    base_peg: Option<Decimal256>,
+    multiplier: Option<Decimal256>,
+    kink: Option<Decimal256>,
+    jump_multiplier: Option<Decimal256>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(owner) = owner {
+        config.owner = deps.api.addr_validate(&owner)?;
+    }
+
+    if let Some(base_rate) = base_rate {
+        config.base_rate = base_rate;
+    }
+
+    if let Some(base_peg) = base_peg {
+        if base_peg.is_zero() {
+            return Err(ContractError::InvalidBasePeg {});
+        }
+        config.base_peg = base_peg;
+    }
+
+    if let Some(multiplier) = multiplier {
+        config.multiplier = multiplier;
+    }
+
+    if let Some(kink) = kink {
+        config.kink = kink;
+    }
+
+    if let Some(jump_multiplier) = jump_multiplier {
+        config.jump_multiplier = jump_multiplier;
+    }
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::new().add_attributes(vec![attr("action", "update_config")]))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::BorrowRate { actual_peg } => to_binary(&query_borrow_rate(deps, actual_peg)?),
+    }
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let resp = ConfigResponse {
+        owner: config.owner.to_string(),
+        base_rate: config.base_rate,
+        base_peg: config.base_peg,
+        multiplier: config.multiplier,
+        kink: config.kink,
+        jump_multiplier: config.jump_multiplier,
+    };
+
+    Ok(resp)
+}
+
+pub fn query_borrow_rate(deps: Deps, actual_peg: Decimal256) -> StdResult<BorrowRateResponse> {
+    let config: Config = read_config(deps.storage)?;
+
+    Ok(BorrowRateResponse {
+        rate: compute_borrow_rate(&config, actual_peg),
+    })
+}
+
+/// Yearly borrow rate for the given SOLID price.
+/// Below the peg the rate grows with `multiplier` per unit of deviation
+/// and with the steeper `jump_multiplier` once the deviation exceeds `kink`.
+/// Above the peg the rate decreases with `multiplier`, floored at zero.
+pub fn compute_borrow_rate(config: &Config, actual_peg: Decimal256) -> Decimal256 {
+    if actual_peg >= config.base_peg {
+        let discount = (actual_peg - config.base_peg) * config.multiplier;
+        if discount >= config.base_rate {
+            return Decimal256::zero();
+        }
+
+        return config.base_rate - discount;
+    }
+
+    let deviation = config.base_peg - actual_peg;
+    if deviation <= config.kink {
+        return config.base_rate + deviation * config.multiplier;
+    }
+
+    config.base_rate
+        + config.kink * config.multiplier
+        + (deviation - config.kink) * config.jump_multiplier
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+    Ok(Response::default())
+}
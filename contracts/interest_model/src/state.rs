@@ -0,0 +1,26 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::math::Decimal256;
+use cosmwasm_std::{Addr, StdResult, Storage};
+use cosmwasm_storage::{ReadonlySingleton, Singleton};
+
+const KEY_CONFIG: &[u8] = b"config";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Config {
+    pub owner: Addr,
+    pub base_rate: Decimal256,
+    pub base_peg: Decimal256,
+    pub multiplier: Decimal256,
+    pub kink: Decimal256,
+    pub jump_multiplier: Decimal256,
+}
+
+pub fn store_config(storage: &mut dyn Storage, data: &Config) -> StdResult<()> {
+    Singleton::new(storage, KEY_CONFIG).save(data)
+}
+
+pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
+    ReadonlySingleton::new(storage, KEY_CONFIG).load()
+}
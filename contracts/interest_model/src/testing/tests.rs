@@ -0,0 +1,186 @@
+use crate::contract::{execute, instantiate, query};
+use crate::error::ContractError;
+
+use cosmwasm_bignumber::math::Decimal256;
+use cosmwasm_std::from_binary;
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use moneymarket::interest_model::{
+    BorrowRateResponse, ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg,
+};
+use std::str::FromStr;
+
+fn default_instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        owner: "owner0000".to_string(),
+        base_rate: Decimal256::from_str("0.02").unwrap(),
+        base_peg: Decimal256::one(),
+        multiplier: Decimal256::from_str("2").unwrap(),
+        kink: Decimal256::from_str("0.05").unwrap(),
+        jump_multiplier: Decimal256::from_str("10").unwrap(),
+    }
+}
+
+fn query_rate(deps: cosmwasm_std::Deps, actual_peg: &str) -> Decimal256 {
+    let res = query(
+        deps,
+        mock_env(),
+        QueryMsg::BorrowRate {
+            actual_peg: Decimal256::from_str(actual_peg).unwrap(),
+        },
+    )
+    .unwrap();
+    let rate: BorrowRateResponse = from_binary(&res).unwrap();
+    rate.rate
+}
+
+#[test]
+fn proper_initialization() {
+    let mut deps = mock_dependencies();
+
+    let info = mock_info("addr0000", &[]);
+    let res = instantiate(deps.as_mut(), mock_env(), info, default_instantiate_msg()).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let value: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        value,
+        ConfigResponse {
+            owner: "owner0000".to_string(),
+            base_rate: Decimal256::from_str("0.02").unwrap(),
+            base_peg: Decimal256::one(),
+            multiplier: Decimal256::from_str("2").unwrap(),
+            kink: Decimal256::from_str("0.05").unwrap(),
+            jump_multiplier: Decimal256::from_str("10").unwrap(),
+        }
+    );
+
+    // zero peg is rejected
+    let mut msg = default_instantiate_msg();
+    msg.base_peg = Decimal256::zero();
+    let res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg);
+    assert_eq!(res, Err(ContractError::InvalidBasePeg {}));
+}
+
+#[test]
+fn update_config() {
+    let mut deps = mock_dependencies();
+
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, default_instantiate_msg()).unwrap();
+
+    // update owner
+    let info = mock_info("owner0000", &[]);
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: Some("owner0001".to_string()),
+        base_rate: None,
+        base_peg: None,
+        multiplier: None,
+        kink: None,
+        jump_multiplier: None,
+    };
+
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    // it worked, let's query the state
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!("owner0001", config_res.owner.as_str());
+
+    // Unauthorized err
+    let info = mock_info("owner0000", &[]);
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        base_rate: Some(Decimal256::from_str("0.1").unwrap()),
+        base_peg: None,
+        multiplier: None,
+        kink: None,
+        jump_multiplier: None,
+    };
+
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    // update curve parameters
+    let info = mock_info("owner0001", &[]);
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        base_rate: Some(Decimal256::from_str("0.1").unwrap()),
+        base_peg: Some(Decimal256::from_str("0.99").unwrap()),
+        multiplier: Some(Decimal256::from_str("3").unwrap()),
+        kink: Some(Decimal256::from_str("0.02").unwrap()),
+        jump_multiplier: Some(Decimal256::from_str("20").unwrap()),
+    };
+
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        config_res,
+        ConfigResponse {
+            owner: "owner0001".to_string(),
+            base_rate: Decimal256::from_str("0.1").unwrap(),
+            base_peg: Decimal256::from_str("0.99").unwrap(),
+            multiplier: Decimal256::from_str("3").unwrap(),
+            kink: Decimal256::from_str("0.02").unwrap(),
+            jump_multiplier: Decimal256::from_str("20").unwrap(),
+        }
+    );
+
+    // zero peg is rejected
+    let info = mock_info("owner0001", &[]);
+    let msg = ExecuteMsg::UpdateConfig {
+        owner: None,
+        base_rate: None,
+        base_peg: Some(Decimal256::zero()),
+        multiplier: None,
+        kink: None,
+        jump_multiplier: None,
+    };
+
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    assert_eq!(res, Err(ContractError::InvalidBasePeg {}));
+}
+
+#[test]
+fn proper_borrow_rate() {
+    let mut deps = mock_dependencies();
+
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, default_instantiate_msg()).unwrap();
+
+    // on peg
+    assert_eq!(
+        query_rate(deps.as_ref(), "1"),
+        Decimal256::from_str("0.02").unwrap()
+    );
+
+    // below peg, before the kink: 0.02 + 0.03 * 2
+    assert_eq!(
+        query_rate(deps.as_ref(), "0.97"),
+        Decimal256::from_str("0.08").unwrap()
+    );
+
+    // exactly at the kink: 0.02 + 0.05 * 2
+    assert_eq!(
+        query_rate(deps.as_ref(), "0.95"),
+        Decimal256::from_str("0.12").unwrap()
+    );
+
+    // past the kink: 0.02 + 0.05 * 2 + 0.05 * 10
+    assert_eq!(
+        query_rate(deps.as_ref(), "0.9"),
+        Decimal256::from_str("0.62").unwrap()
+    );
+
+    // above peg: 0.02 - 0.005 * 2
+    assert_eq!(
+        query_rate(deps.as_ref(), "1.005"),
+        Decimal256::from_str("0.01").unwrap()
+    );
+
+    // far above peg, floored at zero
+    assert_eq!(query_rate(deps.as_ref(), "1.1"), Decimal256::zero());
+}
@@ -2,8 +2,9 @@ use crate::asserts::{assert_activate_status, assert_withdraw_amount};
 use crate::querier::query_collateral_whitelist_info;
 use crate::state::{
     pop_bid_idx, read_bid, read_bid_pool, read_bids_by_user, read_collateral_info, read_config,
-    read_epoch_scale_sum, read_or_create_bid_pool, read_total_bids, remove_bid, store_bid,
-    store_bid_pool, store_epoch_scale_sum, store_total_bids, Bid, BidPool, CollateralInfo, Config,
+    read_epoch_scale_sum, read_fees_paid, read_or_create_bid_pool, read_total_bids, remove_bid,
+    store_bid, store_bid_pool, store_epoch_scale_sum, store_fees_paid, store_total_bids, Bid,
+    BidPool, CollateralInfo, Config,
 };
 use bigint::U256;
 use cosmwasm_bignumber::math::{Decimal256, Uint256};
@@ -12,9 +13,11 @@ use cosmwasm_std::{
     Storage, Uint128, WasmMsg,
 };
 use cw20::Cw20ExecuteMsg;
+use moneymarket::fee_distribution::{distribute_fee, fee_recipients};
 use moneymarket::liquidation::MarketExecuteMsg;
 
 use moneymarket::oracle::PriceResponse;
+use moneymarket::position::PositionId;
 use moneymarket::querier::{query_price, TimeConstraints};
 
 /// Stable asset is submitted to create a bid record. If available bids for the collateral is under
@@ -292,6 +295,7 @@ pub fn execute_liquidation(
     collateral_token: String,
     amount: Uint256,
     borrower_address: String,
+    position_id: Option<PositionId>,
 ) -> StdResult<Response> {
     let config: Config = read_config(deps.storage)?;
     let collateral_token_validated = deps.api.addr_validate(&collateral_token)?;
@@ -314,7 +318,7 @@ pub fn execute_liquidation(
 
     let price: PriceResponse = query_price(
         deps.as_ref(),
-        config.oracle_contract,
+        config.oracle_contract.clone(),
         collateral_token.to_string(),
         "uusd".to_string(),
         Some(TimeConstraints {
@@ -386,20 +390,21 @@ pub fn execute_liquidation(
                 amount: repay_amount.into(),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
                     borrower: borrower_address,
+                    position_id,
                 })?,
             })?,
         }));
     }
-    if !bid_fee.is_zero() {
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: config.stable_contract.to_string(),
-            funds: vec![],
-            msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                recipient: fee_address,
-                amount: bid_fee.into(),
-            })?,
-        }));
-    }
+    // The fee address sent by the custody is ignored once recipients are set
+    messages.extend(distribute_fee(
+        deps.storage,
+        read_fees_paid,
+        store_fees_paid,
+        config.stable_contract.to_string(),
+        &fee_recipients(&config.fee_recipients, fee_address),
+        bid_fee,
+        None,
+    )?);
 
     if !liquidator_fee.is_zero() {
         messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -6,7 +6,7 @@ use crate::bid::{activate_bids, claim_liquidations, execute_liquidation, retract
 use crate::querier::query_collateral_whitelist_info;
 use crate::query::{
     query_bid, query_bid_pool, query_bid_pools, query_bids_by_user, query_collateral_info,
-    query_config, query_liquidation_amount,
+    query_config, query_fee_distribution, query_liquidation_amount,
 };
 use crate::state::{
     read_collateral_info, read_config, store_collateral_info, store_config, CollateralInfo, Config,
@@ -17,6 +17,7 @@ use cosmwasm_std::{
     from_binary, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
 };
 use cw20::Cw20ReceiveMsg;
+use moneymarket::fee_distribution::{self, FeeRecipient};
 use moneymarket::liquidation_queue::{
     Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
 };
@@ -43,6 +44,7 @@ pub fn instantiate(
             price_timeframe: msg.price_timeframe,
             waiting_period: msg.waiting_period,
             overseer: deps.api.addr_validate(&msg.overseer)?,
+            fee_recipients: vec![],
         },
     )?;
 
@@ -103,6 +105,9 @@ pub fn execute(deps: DepsMut, env: Env, info: MessageInfo, msg: ExecuteMsg) -> S
             collateral_token,
             bids_idx,
         } => claim_liquidations(deps, env, info, collateral_token, bids_idx),
+        ExecuteMsg::UpdateFeeRecipients { recipients } => {
+            update_fee_recipients(deps, info, recipients)
+        }
     }
 }
 
@@ -119,6 +124,7 @@ pub fn receive_cw20(
             repay_address,
             fee_address,
             borrower_address,
+            position_id,
         } => {
             let collateral_token = contract_addr.to_string();
             let repay_address = repay_address.unwrap_or_default();
@@ -140,6 +146,7 @@ pub fn receive_cw20(
                 collateral_token,
                 cw20_msg.amount.into(),
                 borrower_address,
+                position_id,
             )
         }
 
@@ -224,6 +231,23 @@ pub fn update_config(
     Ok(Response::new().add_attribute("action", "update_config"))
 }
 
+pub fn update_fee_recipients(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipients: Vec<FeeRecipient>,
+) -> StdResult<Response> {
+    let mut config: Config = read_config(deps.storage)?;
+    if info.sender != config.owner {
+        return Err(StdError::generic_err("unauthorized"));
+    }
+
+    let res =
+        fee_distribution::update_fee_recipients(deps.api, &mut config.fee_recipients, recipients)?;
+
+    store_config(deps.storage, &config)?;
+    Ok(res)
+}
+
 pub fn whitelist_collateral(
     deps: DepsMut,
     info: MessageInfo,
@@ -304,17 +328,22 @@ pub fn update_collateral_info(
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::FeeDistribution {} => to_binary(&query_fee_distribution(deps)?),
         QueryMsg::LiquidationAmount {
             borrow_amount,
             borrow_limit,
+            liquidation_limit,
             collaterals,
             collateral_prices,
+            collateral_max_ltvs,
         } => to_binary(&query_liquidation_amount(
             deps,
             borrow_amount,
             borrow_limit,
+            liquidation_limit,
             collaterals,
             collateral_prices,
+            collateral_max_ltvs,
         )?),
         QueryMsg::CollateralInfo { collateral_token } => {
             to_binary(&query_collateral_info(deps, collateral_token)?)
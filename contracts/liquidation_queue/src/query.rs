@@ -2,10 +2,11 @@ use crate::bid::{calculate_liquidated_collateral, calculate_remaining_bid};
 use crate::querier::query_collateral_whitelist_info;
 use crate::state::{
     read_bid, read_bid_pool, read_bid_pools, read_bids_by_user, read_collateral_info, read_config,
-    read_total_bids, Bid, BidPool, CollateralInfo, Config,
+    read_fees_paid, read_total_bids, Bid, BidPool, CollateralInfo, Config,
 };
 use cosmwasm_bignumber::math::{Decimal256, Uint256};
-use cosmwasm_std::{Deps, StdResult, Uint128};
+use cosmwasm_std::{Deps, StdError, StdResult, Uint128};
+use moneymarket::fee_distribution::{self, FeeDistributionResponse};
 use moneymarket::liquidation_queue::{
     BidPoolResponse, BidPoolsResponse, BidResponse, BidsResponse, CollateralInfoResponse,
     ConfigResponse, LiquidationAmountResponse,
@@ -13,6 +14,11 @@ use moneymarket::liquidation_queue::{
 
 use moneymarket::tokens::TokensHuman;
 
+pub fn query_fee_distribution(deps: Deps) -> StdResult<FeeDistributionResponse> {
+    let config = read_config(deps.storage)?;
+    fee_distribution::query_fee_distribution(deps.storage, read_fees_paid, config.fee_recipients)
+}
+
 pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let config = read_config(deps.storage)?;
     let resp = ConfigResponse {
@@ -41,14 +47,16 @@ pub fn query_liquidation_amount(
     deps: Deps,
     borrow_amount: Uint256,
     borrow_limit: Uint256,
+    liquidation_limit: Option<Uint256>,
     collaterals: TokensHuman,
     collateral_prices: Vec<Decimal256>,
+    collateral_max_ltvs: Option<Vec<Decimal256>>,
 ) -> StdResult<LiquidationAmountResponse> {
     let config: Config = read_config(deps.storage)?;
     let overseer: String = config.overseer.to_string();
 
-    // Safely collateralized check
-    if borrow_amount <= borrow_limit {
+    // Safely collateralized check, the liquidation then targets the borrow limit
+    if borrow_amount <= liquidation_limit.unwrap_or(borrow_limit) {
         return Ok(LiquidationAmountResponse {
             collaterals: vec![],
         });
@@ -56,7 +64,13 @@ pub fn query_liquidation_amount(
 
     // calculate value of all collaterals and weights
     let (collaterals_value, total_weight, collateral_weights, max_ltvs) =
-        compute_collateral_weights(deps, overseer, &collaterals, &collateral_prices)?;
+        compute_collateral_weights(
+            deps,
+            overseer,
+            &collaterals,
+            &collateral_prices,
+            collateral_max_ltvs,
+        )?;
 
     // check partial liquidation condition
     let safe_ratio = if collaterals_value <= config.liquidation_threshold {
@@ -137,32 +151,52 @@ pub fn query_liquidation_amount(
 }
 
 /// The portion of collateral that liquidated from the available set is calculated
-/// based on weight = min(collateral_value, available_bids) / max_ltv
+/// based on weight = min(collateral_value, available_bids) / max_ltv, where max_ltv
+/// is the one applied to the position, boosted by its e-mode category for instance
 #[allow(clippy::ptr_arg)]
 fn compute_collateral_weights(
     deps: Deps,
     overseer: String,
     collaterals: &TokensHuman,
     collateral_prices: &Vec<Decimal256>,
+    collateral_max_ltvs: Option<Vec<Decimal256>>,
 ) -> StdResult<(Uint256, Uint256, Vec<Uint256>, Vec<Decimal256>)> {
+    if let Some(collateral_max_ltvs) = collateral_max_ltvs.as_ref() {
+        if collateral_max_ltvs.len() != collaterals.len() {
+            return Err(StdError::generic_err(
+                "One max LTV is expected for each collateral",
+            ));
+        }
+    }
+
     let mut collaterals_value = Uint256::zero();
     let mut total_weight = Uint256::zero();
     let mut collateral_weights: Vec<Uint256> = vec![];
     let mut max_ltvs: Vec<Decimal256> = vec![];
 
-    for (collateral, price) in collaterals.iter().zip(collateral_prices.iter()) {
+    for (i, (collateral, price)) in collaterals.iter().zip(collateral_prices.iter()).enumerate() {
         let collateral_available_bids =
             read_total_bids(deps.storage, &deps.api.addr_validate(&collateral.0)?)
                 .unwrap_or_default();
-        let max_ltv = query_collateral_whitelist_info(
-            &deps.querier,
-            overseer.to_string(),
-            collateral.0.to_string(),
-        )?
-        .max_ltv;
+        let max_ltv = match collateral_max_ltvs.as_ref() {
+            Some(collateral_max_ltvs) => collateral_max_ltvs[i],
+            None => {
+                query_collateral_whitelist_info(
+                    &deps.querier,
+                    overseer.to_string(),
+                    collateral.0.to_string(),
+                )?
+                .max_ltv
+            }
+        };
 
         let collateral_value = collateral.1 * *price;
-        let weigth = collateral_value.min(collateral_available_bids) / max_ltv;
+        // a collateral without borrowing power left, deprecated for instance, is weighted by its value
+        let weigth = if max_ltv.is_zero() {
+            collateral_value.min(collateral_available_bids)
+        } else {
+            collateral_value.min(collateral_available_bids) / max_ltv
+        };
 
         total_weight += weigth;
         collaterals_value += collateral_value;
@@ -1,12 +1,14 @@
 use cosmwasm_bignumber::math::{Decimal256, Uint256};
 use cosmwasm_std::{Addr, Order, StdError, StdResult, Storage, Uint128};
 use cosmwasm_storage::{singleton, singleton_read, Bucket, ReadonlyBucket};
+use moneymarket::fee_distribution::{FeePaid, FeeRecipient};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::convert::TryInto;
 
 static KEY_CONFIG: &[u8] = b"config";
 static KEY_BID_IDX: &[u8] = b"bid_idx";
+static KEY_FEES_PAID: &[u8] = b"fees_paid";
 
 static PREFIX_BID: &[u8] = b"bid";
 static PREFIX_BID_BY_USER: &[u8] = b"bid_by_user";
@@ -30,6 +32,9 @@ pub struct Config {
     pub price_timeframe: u64,
     pub waiting_period: u64,
     pub overseer: Addr,
+    /// Split of the bid fee, all of it goes to the fee address when empty
+    #[serde(default)]
+    pub fee_recipients: Vec<FeeRecipient>,
 }
 
 pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
@@ -40,6 +45,17 @@ pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
     singleton_read(storage, KEY_CONFIG).load()
 }
 
+/// Cumulative bid fees paid to each recipient kind
+pub fn store_fees_paid(storage: &mut dyn Storage, fees_paid: &[FeePaid]) -> StdResult<()> {
+    singleton(storage, KEY_FEES_PAID).save(&fees_paid.to_vec())
+}
+
+pub fn read_fees_paid(storage: &dyn Storage) -> StdResult<Vec<FeePaid>> {
+    Ok(singleton_read(storage, KEY_FEES_PAID)
+        .may_load()?
+        .unwrap_or_default())
+}
+
 pub fn pop_bid_idx(storage: &mut dyn Storage) -> StdResult<Uint128> {
     let mut idx_store = singleton(storage, KEY_BID_IDX);
     let last_idx: Uint128 = idx_store.load().unwrap_or_else(|_| Uint128::from(1u128));
@@ -83,6 +83,7 @@ fn one_bidder_distribution() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -194,6 +195,7 @@ fn two_bidder_distribution() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -242,6 +244,7 @@ fn two_bidder_distribution() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -370,6 +373,7 @@ fn two_bidder_distribution_big_numbers() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -419,6 +423,7 @@ fn two_bidder_distribution_big_numbers() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -561,6 +566,7 @@ fn one_user_two_bid_slots() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -592,6 +598,7 @@ fn one_user_two_bid_slots() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -710,6 +717,7 @@ fn partial_withdraw_after_execution() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -754,6 +762,7 @@ fn partial_withdraw_after_execution() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -896,6 +905,7 @@ fn completely_empty_pool() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -963,6 +973,7 @@ fn completely_empty_pool() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -1072,6 +1083,7 @@ fn product_truncated_to_zero() {
                 fee_address: Some("fee0000".to_string()),
                 repay_address: Some("repay0000".to_string()),
                 borrower_address: Some("addr0000".to_string()),
+                position_id: None,
             })
             .unwrap(),
         });
@@ -1226,6 +1238,7 @@ fn two_bidder_reward_distribution_common_slot() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -1447,6 +1460,7 @@ fn two_bidder_distribution_multiple_common_slots() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -1463,6 +1477,7 @@ fn two_bidder_distribution_multiple_common_slots() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -1703,6 +1718,7 @@ fn two_bidder_unequal_deposit_reward_distribution() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -1909,6 +1925,7 @@ fn scalable_reward_distribution_after_multiple_liquidations() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -1979,6 +1996,7 @@ fn scalable_reward_distribution_after_multiple_liquidations() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -2123,6 +2141,7 @@ fn not_enough_bid_for_collateral() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -2236,6 +2255,7 @@ fn two_bidder_reward_distribution_common_slot_large_numbers() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
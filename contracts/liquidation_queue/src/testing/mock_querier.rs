@@ -1,11 +1,11 @@
-use cosmwasm_bignumber::math::Decimal256;
+use cosmwasm_bignumber::math::{Decimal256, Uint256};
 use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
     from_binary, from_slice, to_binary, Coin, ContractResult, OwnedDeps, Querier, QuerierResult,
     QueryRequest, SystemError, SystemResult, WasmQuery,
 };
 use moneymarket::oracle::PriceResponse;
-use moneymarket::overseer::{WhitelistResponse, WhitelistResponseElem};
+use moneymarket::overseer::{CollateralStatus, WhitelistResponse, WhitelistResponseElem};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -153,6 +153,14 @@ impl WasmMockQuerier {
                                     max_ltv: *v,
                                     custody_contract: "custody0000".to_string(),
                                     collateral_token: "token0000".to_string(),
+                                    debt_ceiling: None,
+                                    total_debt: Uint256::zero(),
+                                    liquidation_threshold: *v,
+                                    status: CollateralStatus::Active,
+                                    total_locked: Uint256::zero(),
+                                    target_max_ltv: *v,
+                                    target_liquidation_threshold: *v,
+                                    ramp_end_time: None,
                                 }],
                             })))
                         }
@@ -171,6 +171,7 @@ fn simulate_bids_with_2_liq_amounts(
                     fee_address: Some("fee0000".to_string()),
                     repay_address: Some("repay0000".to_string()),
                     borrower_address: Some("addr0000".to_string()),
+                    position_id: None,
                 })
                 .unwrap(),
             });
@@ -188,6 +189,7 @@ fn simulate_bids_with_2_liq_amounts(
                     fee_address: Some("fee0000".to_string()),
                     repay_address: Some("repay0000".to_string()),
                     borrower_address: Some("addr0000".to_string()),
+                    position_id: None,
                 })
                 .unwrap(),
             });
@@ -57,6 +57,8 @@ fn partial_one_collateral_one_slot_high_ltv() {
         borrow_limit: Uint256::from(18000u64),
         collaterals: vec![("token0000".to_string(), Uint256::from(20000u64))], // value 20000 (LTV 90%), limit = 18,000
         collateral_prices: vec![Decimal256::percent(100)],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -94,6 +96,7 @@ fn partial_one_collateral_one_slot_high_ltv() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -108,7 +111,8 @@ fn partial_one_collateral_one_slot_high_ltv() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(16433u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -165,6 +169,8 @@ fn partial_one_collateral_one_slot() {
         borrow_limit: Uint256::from(1000u64),
         collaterals: vec![("token0000".to_string(), Uint256::from(20000u64))],
         collateral_prices: vec![Decimal256::percent(10)],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -202,6 +208,7 @@ fn partial_one_collateral_one_slot() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -216,7 +223,8 @@ fn partial_one_collateral_one_slot() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(692u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -273,6 +281,8 @@ fn partial_one_collateral_one_slot_with_fees() {
         borrow_limit: Uint256::from(1000u64),
         collaterals: vec![("token0000".to_string(), Uint256::from(20000u64))],
         collateral_prices: vec![Decimal256::percent(10)],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -310,6 +320,7 @@ fn partial_one_collateral_one_slot_with_fees() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -325,7 +336,8 @@ fn partial_one_collateral_one_slot_with_fees() {
                     contract: "repay0000".to_string(),
                     amount: Uint128::from(710u128),
                     msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                        borrower: "addr0000".to_string()
+                        borrower: "addr0000".to_string(),
+                        position_id: None,
                     })
                     .unwrap(),
                 })
@@ -392,6 +404,8 @@ fn partial_one_collateral_one_slot_with_fees_all() {
         borrow_limit: Uint256::from(1000u64),
         collaterals: vec![("token0000".to_string(), Uint256::from(20000u64))],
         collateral_prices: vec![Decimal256::percent(10)],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -429,6 +443,7 @@ fn partial_one_collateral_one_slot_with_fees_all() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -444,7 +459,8 @@ fn partial_one_collateral_one_slot_with_fees_all() {
                     contract: "repay0000".to_string(),
                     amount: Uint128::from(716u128),
                     msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                        borrower: "addr0000".to_string()
+                        borrower: "addr0000".to_string(),
+                        position_id: None,
                     })
                     .unwrap(),
                 })
@@ -530,6 +546,8 @@ fn partial_one_collateral_two_slots() {
         borrow_limit: Uint256::from(10000u64),
         collaterals: vec![("token0000".to_string(), Uint256::from(200000u64))],
         collateral_prices: vec![Decimal256::percent(10)],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -569,6 +587,7 @@ fn partial_one_collateral_two_slots() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -582,7 +601,8 @@ fn partial_one_collateral_two_slots() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(4015u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -650,6 +670,8 @@ fn partial_one_collateral_two_slots_with_fees() {
         borrow_limit: Uint256::from(10000u64),
         collaterals: vec![("token0000".to_string(), Uint256::from(200000u64))],
         collateral_prices: vec![Decimal256::percent(10)],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -689,6 +711,7 @@ fn partial_one_collateral_two_slots_with_fees() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -705,7 +728,8 @@ fn partial_one_collateral_two_slots_with_fees() {
                     contract: "repay0000".to_string(),
                     amount: Uint128::from(4117u128),
                     msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                        borrower: "addr0000".to_string()
+                        borrower: "addr0000".to_string(),
+                        position_id: None,
                     })
                     .unwrap(),
                 })
@@ -732,7 +756,8 @@ fn partial_one_collateral_two_slots_with_fees() {
                     contract: "repay0000".to_string(),
                     amount: Uint128::from(4117u128),
                     msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                        borrower: "addr0000".to_string()
+                        borrower: "addr0000".to_string(),
+                        position_id: None,
                     })
                     .unwrap(),
                 })
@@ -799,6 +824,8 @@ fn non_partial_liquidation() {
         borrow_limit: Uint256::from(1000u64),
         collaterals: vec![("token0000".to_string(), Uint256::from(20000u64))],
         collateral_prices: vec![Decimal256::percent(10)],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -829,6 +856,7 @@ fn non_partial_liquidation() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -842,7 +870,8 @@ fn non_partial_liquidation() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(1201u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -909,6 +938,8 @@ fn non_partial_liquidation_two_slots() {
         borrow_limit: Uint256::from(1000u64),
         collaterals: vec![("token0000".to_string(), Uint256::from(20000u64))],
         collateral_prices: vec![Decimal256::percent(10)],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -939,6 +970,7 @@ fn non_partial_liquidation_two_slots() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -953,7 +985,8 @@ fn non_partial_liquidation_two_slots() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(1200u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -1010,6 +1043,8 @@ fn non_partial_liquidation_with_fees() {
         borrow_limit: Uint256::from(1000u64),
         collaterals: vec![("token0000".to_string(), Uint256::from(20000u64))],
         collateral_prices: vec![Decimal256::percent(10)],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -1040,6 +1075,7 @@ fn non_partial_liquidation_with_fees() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -1055,7 +1091,8 @@ fn non_partial_liquidation_with_fees() {
                     contract: "repay0000".to_string(),
                     amount: Uint128::from(1213u128),
                     msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                        borrower: "addr0000".to_string()
+                        borrower: "addr0000".to_string(),
+                        position_id: None,
                     })
                     .unwrap(),
                 })
@@ -1132,6 +1169,8 @@ fn non_partial_liquidation_two_slots_with_fees() {
         borrow_limit: Uint256::from(1000u64),
         collaterals: vec![("token0000".to_string(), Uint256::from(20000u64))],
         collateral_prices: vec![Decimal256::percent(10)],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -1162,6 +1201,7 @@ fn non_partial_liquidation_two_slots_with_fees() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -1177,7 +1217,8 @@ fn non_partial_liquidation_two_slots_with_fees() {
                     contract: "repay0000".to_string(),
                     amount: Uint128::from(1212u128),
                     msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                        borrower: "addr0000".to_string()
+                        borrower: "addr0000".to_string(),
+                        position_id: None,
                     })
                     .unwrap(),
                 })
@@ -1255,6 +1296,8 @@ fn non_partial_liquidation_two_slots_with_fees_big_nums() {
         borrow_limit: Uint256::from(1000000000u64),
         collaterals: vec![("token0000".to_string(), Uint256::from(20000000000u64))], // value = 2,000,000,000
         collateral_prices: vec![Decimal256::percent(10)],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -1285,6 +1328,7 @@ fn non_partial_liquidation_two_slots_with_fees_big_nums() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -1300,7 +1344,8 @@ fn non_partial_liquidation_two_slots_with_fees_big_nums() {
                     contract: "repay0000".to_string(),
                     amount: Uint128::from(1301000000u128),
                     msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                        borrower: "addr0000".to_string()
+                        borrower: "addr0000".to_string(),
+                        position_id: None,
                     })
                     .unwrap(),
                 })
@@ -1389,6 +1434,8 @@ fn partial_two_collaterals_ltv_diff() {
             ("token0001".to_string(), Uint256::from(1000000000u64)), // value = 1000000000 (LTV 1%) limit = 10..
         ],
         collateral_prices: vec![Decimal256::percent(100), Decimal256::percent(100)],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -1443,6 +1490,7 @@ fn partial_two_collaterals_ltv_diff() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -1458,7 +1506,8 @@ fn partial_two_collaterals_ltv_diff() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(19230775u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -1474,6 +1523,7 @@ fn partial_two_collaterals_ltv_diff() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -1488,7 +1538,8 @@ fn partial_two_collaterals_ltv_diff() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(399193550u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -1588,6 +1639,8 @@ fn partial_two_collaterals_multi_slots_per_col() {
             ("token0001".to_string(), Uint256::from(30000u64)), // value = 1500 (LTV = 30%) limit = 450
         ],
         collateral_prices: vec![Decimal256::percent(10), Decimal256::percent(5)],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -1642,6 +1695,7 @@ fn partial_two_collaterals_multi_slots_per_col() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -1657,7 +1711,8 @@ fn partial_two_collaterals_multi_slots_per_col() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(355u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -1673,6 +1728,7 @@ fn partial_two_collaterals_multi_slots_per_col() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -1687,7 +1743,8 @@ fn partial_two_collaterals_multi_slots_per_col() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(453u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -1767,6 +1824,8 @@ fn partial_two_collaterals_one_slot_diff_ltv() {
             ("token0001".to_string(), Uint256::from(30000u64)), // value = 1500 LTV = 30% (limit = 450)
         ],
         collateral_prices: vec![Decimal256::percent(10), Decimal256::percent(5)],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -1821,6 +1880,7 @@ fn partial_two_collaterals_one_slot_diff_ltv() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -1836,7 +1896,8 @@ fn partial_two_collaterals_one_slot_diff_ltv() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(288u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -1852,6 +1913,7 @@ fn partial_two_collaterals_one_slot_diff_ltv() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -1867,7 +1929,8 @@ fn partial_two_collaterals_one_slot_diff_ltv() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(369u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -1969,6 +2032,8 @@ fn partial_three_collaterals_one_slot_diff_ltv() {
             Decimal256::percent(5),
             Decimal256::percent(110),
         ],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -2034,6 +2099,7 @@ fn partial_three_collaterals_one_slot_diff_ltv() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -2049,7 +2115,8 @@ fn partial_three_collaterals_one_slot_diff_ltv() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(316u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -2065,6 +2132,7 @@ fn partial_three_collaterals_one_slot_diff_ltv() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -2080,7 +2148,8 @@ fn partial_three_collaterals_one_slot_diff_ltv() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(262u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -2096,6 +2165,7 @@ fn partial_three_collaterals_one_slot_diff_ltv() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -2111,7 +2181,8 @@ fn partial_three_collaterals_one_slot_diff_ltv() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(1317u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -2218,6 +2289,8 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts() {
             Decimal256::percent(500),
             Decimal256::percent(11000),
         ],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -2284,6 +2357,7 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -2299,7 +2373,8 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(660240040618u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -2315,6 +2390,7 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -2330,7 +2406,8 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(11121330091u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -2346,6 +2423,7 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -2361,7 +2439,8 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(5550186292u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -2469,6 +2548,8 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts_2() {
             Decimal256::percent(500),
             Decimal256::percent(11000),
         ],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -2535,6 +2616,7 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts_2() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -2550,7 +2632,8 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts_2() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(219350041u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -2566,6 +2649,7 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts_2() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -2581,7 +2665,8 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts_2() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(1755769756u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -2597,6 +2682,7 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts_2() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -2612,7 +2698,8 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts_2() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(876230635u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -2694,6 +2781,8 @@ fn not_enough_bids_for_one_of_two_col() {
             ("token0001".to_string(), Uint256::from(30000000u64)), // value = 1,500,000 (LTV 30%) limit = 450,000
         ],
         collateral_prices: vec![Decimal256::percent(10), Decimal256::percent(5)],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -2748,6 +2837,7 @@ fn not_enough_bids_for_one_of_two_col() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -2764,7 +2854,8 @@ fn not_enough_bids_for_one_of_two_col() {
                     contract: "repay0000".to_string(),
                     amount: Uint128::from(1115649u128),
                     msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                        borrower: "addr0000".to_string()
+                        borrower: "addr0000".to_string(),
+                        position_id: None,
                     })
                     .unwrap(),
                 })
@@ -2790,6 +2881,7 @@ fn not_enough_bids_for_one_of_two_col() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -2806,7 +2898,8 @@ fn not_enough_bids_for_one_of_two_col() {
                     contract: "repay0000".to_string(),
                     amount: Uint128::from(291409u128),
                     msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                        borrower: "addr0000".to_string()
+                        borrower: "addr0000".to_string(),
+                        position_id: None,
                     })
                     .unwrap(),
                 })
@@ -2890,6 +2983,8 @@ fn integration_test_simul() {
             ("token0000".to_string(), Uint256::from(10000000000u64)), // value = 9,000,000,000 (LTV 60%) limit = 5,400,000,000
         ],
         collateral_prices: vec![Decimal256::percent(90)],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -2926,6 +3021,7 @@ fn integration_test_simul() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -2941,7 +3037,8 @@ fn integration_test_simul() {
                 contract: "repay0000".to_string(),
                 amount: Uint128::from(5348633145u128),
                 msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                    borrower: "addr0000".to_string()
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
                 })
                 .unwrap(),
             })
@@ -2949,3 +3046,229 @@ fn integration_test_simul() {
         }))]
     );
 }
+
+#[test]
+fn liquidation_limit_over_borrow_limit() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_collateral_max_ltv(&[(&"token0000".to_string(), &Decimal256::percent(90))]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        oracle_contract: "oracle0000".to_string(),
+        stable_contract: "stable0000".to_string(),
+        safe_ratio: Decimal256::percent(80),
+        bid_fee: Decimal256::percent(0),
+        liquidator_fee: Decimal256::percent(0),
+        liquidation_threshold: Uint256::zero(),
+        price_timeframe: 60u64,
+        waiting_period: 60u64,
+        overseer: "overseer0000".to_string(),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::WhitelistCollateral {
+        collateral_token: "token0000".to_string(),
+        max_slot: 30u8,
+        bid_threshold: Uint256::from(10000u128), // to get instant activation
+        premium_rate_per_slot: Decimal256::percent(1),
+    };
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("stable0000", &[]);
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(10000000u128),
+        msg: to_binary(&Cw20HookMsg::SubmitBid {
+            collateral_token: "token0000".to_string(),
+            premium_slot: 0u8,
+        })
+        .unwrap(),
+    });
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // over the borrow limit but not over the liquidation limit
+    let msg = QueryMsg::LiquidationAmount {
+        borrow_amount: Uint256::from(19000u64),
+        borrow_limit: Uint256::from(18000u64),
+        liquidation_limit: Some(Uint256::from(19000u64)),
+        collaterals: vec![("token0000".to_string(), Uint256::from(20000u64))],
+        collateral_prices: vec![Decimal256::percent(100)],
+        collateral_max_ltvs: None,
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let res: LiquidationAmountResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        LiquidationAmountResponse {
+            collaterals: vec![]
+        }
+    );
+
+    // once liquidated, the loan is brought back under the borrow limit
+    let msg = QueryMsg::LiquidationAmount {
+        borrow_amount: Uint256::from(19000u64),
+        borrow_limit: Uint256::from(18000u64),
+        liquidation_limit: Some(Uint256::from(18500u64)),
+        collaterals: vec![("token0000".to_string(), Uint256::from(20000u64))],
+        collateral_prices: vec![Decimal256::percent(100)],
+        collateral_max_ltvs: None,
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let res: LiquidationAmountResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        LiquidationAmountResponse {
+            collaterals: vec![("token0000".to_string(), Uint256::from(16429u64))],
+        }
+    );
+}
+
+#[test]
+fn collateral_max_ltvs_override_whitelist() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_collateral_max_ltv(&[(&"token0000".to_string(), &Decimal256::percent(50))]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        oracle_contract: "oracle0000".to_string(),
+        stable_contract: "stable0000".to_string(),
+        safe_ratio: Decimal256::percent(80),
+        bid_fee: Decimal256::percent(0),
+        liquidator_fee: Decimal256::percent(0),
+        liquidation_threshold: Uint256::zero(),
+        price_timeframe: 60u64,
+        waiting_period: 60u64,
+        overseer: "overseer0000".to_string(),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::WhitelistCollateral {
+        collateral_token: "token0000".to_string(),
+        max_slot: 30u8,
+        bid_threshold: Uint256::from(10000u128), // to get instant activation
+        premium_rate_per_slot: Decimal256::percent(1),
+    };
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("stable0000", &[]);
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(10000000u128),
+        msg: to_binary(&Cw20HookMsg::SubmitBid {
+            collateral_token: "token0000".to_string(),
+            premium_slot: 0u8,
+        })
+        .unwrap(),
+    });
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // one max LTV is expected for each collateral
+    let msg = QueryMsg::LiquidationAmount {
+        borrow_amount: Uint256::from(19000u64),
+        borrow_limit: Uint256::from(18000u64),
+        liquidation_limit: None,
+        collaterals: vec![("token0000".to_string(), Uint256::from(20000u64))],
+        collateral_prices: vec![Decimal256::percent(100)],
+        collateral_max_ltvs: Some(vec![]),
+    };
+    query(deps.as_ref(), mock_env(), msg).unwrap_err();
+
+    // the max LTV applied by the overseer, boosted by e-mode, replaces the whitelist one
+    let msg = QueryMsg::LiquidationAmount {
+        borrow_amount: Uint256::from(19000u64),
+        borrow_limit: Uint256::from(18000u64),
+        liquidation_limit: None,
+        collaterals: vec![("token0000".to_string(), Uint256::from(20000u64))],
+        collateral_prices: vec![Decimal256::percent(100)],
+        collateral_max_ltvs: Some(vec![Decimal256::percent(90)]),
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let res: LiquidationAmountResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        LiquidationAmountResponse {
+            collaterals: vec![("token0000".to_string(), Uint256::from(16429u64))],
+        }
+    );
+}
+
+#[test]
+fn deprecated_collateral_without_max_ltv() {
+    let mut deps = mock_dependencies(&[]);
+    // the overseer reports no max LTV once the deprecation of token0001 is over
+    deps.querier.with_collateral_max_ltv(&[
+        (&"token0000".to_string(), &Decimal256::percent(50)),
+        (&"token0001".to_string(), &Decimal256::zero()),
+    ]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        oracle_contract: "oracle0000".to_string(),
+        stable_contract: "stable0000".to_string(),
+        safe_ratio: Decimal256::percent(80),
+        bid_fee: Decimal256::percent(0),
+        liquidator_fee: Decimal256::percent(0),
+        liquidation_threshold: Uint256::zero(),
+        price_timeframe: 60u64,
+        waiting_period: 60u64,
+        overseer: "overseer0000".to_string(),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    for collateral_token in ["token0000", "token0001"] {
+        let msg = ExecuteMsg::WhitelistCollateral {
+            collateral_token: collateral_token.to_string(),
+            max_slot: 30u8,
+            bid_threshold: Uint256::from(10000u128), // to get instant activation
+            premium_rate_per_slot: Decimal256::percent(1),
+        };
+        let info = mock_info("owner0000", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("stable0000", &[]);
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "addr0000".to_string(),
+            amount: Uint128::from(10000000u128),
+            msg: to_binary(&Cw20HookMsg::SubmitBid {
+                collateral_token: collateral_token.to_string(),
+                premium_slot: 0u8,
+            })
+            .unwrap(),
+        });
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    }
+
+    // token0001 adds nothing to the borrow limit, it is weighted by its value
+    let msg = QueryMsg::LiquidationAmount {
+        borrow_amount: Uint256::from(12000u64),
+        borrow_limit: Uint256::from(10000u64),
+        liquidation_limit: None,
+        collaterals: vec![
+            ("token0000".to_string(), Uint256::from(20000u64)),
+            ("token0001".to_string(), Uint256::from(20000u64)),
+        ],
+        collateral_prices: vec![Decimal256::percent(100), Decimal256::percent(100)],
+        collateral_max_ltvs: None,
+    };
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let res: LiquidationAmountResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        LiquidationAmountResponse {
+            collaterals: vec![
+                ("token0000".to_string(), Uint256::from(4446u64)),
+                ("token0001".to_string(), Uint256::from(1334u64)),
+            ],
+        }
+    );
+}
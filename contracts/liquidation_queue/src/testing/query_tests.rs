@@ -97,6 +97,8 @@ fn query_liquidation_amount() {
         borrow_limit: Uint256::from(900000u64),
         collaterals: vec![("token0000".to_string(), Uint256::from(1000000u64))],
         collateral_prices: vec![Decimal256::percent(10)],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -113,6 +115,8 @@ fn query_liquidation_amount() {
         borrow_limit: Uint256::from(1000000u64),
         collaterals: vec![("token0000".to_string(), Uint256::from(1000000u64))],
         collateral_prices: vec![Decimal256::one()],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     let res = query(deps.as_ref(), mock_env(), msg).unwrap();
@@ -137,6 +141,8 @@ fn query_liquidation_amount() {
             Decimal256::percent(50),
             Decimal256::percent(50),
         ],
+        liquidation_limit: None,
+        collateral_max_ltvs: None,
     };
 
     // fee_deductor = 0.931095
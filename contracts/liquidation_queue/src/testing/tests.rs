@@ -5,11 +5,15 @@ use cosmwasm_bignumber::math::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, mock_info};
 use cosmwasm_std::{attr, from_binary, to_binary, CosmosMsg, StdError, SubMsg, Uint128, WasmMsg};
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use moneymarket::fee_distribution::{
+    FeeDistributionResponse, FeePaid, FeeRecipient, FeeRecipientKind,
+};
 use moneymarket::liquidation::MarketExecuteMsg;
 use moneymarket::liquidation_queue::{
     BidResponse, CollateralInfoResponse, ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg,
     QueryMsg,
 };
+use moneymarket::overseer::Cw20HookMsg as OverseerCw20HookMsg;
 
 #[test]
 fn proper_initialization() {
@@ -610,6 +614,7 @@ fn execute_bid() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -631,6 +636,7 @@ fn execute_bid() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -647,7 +653,8 @@ fn execute_bid() {
                     contract: "repay0000".to_string(),
                     amount: Uint128::from(485100u128),
                     msg: to_binary(&MarketExecuteMsg::RepayStableFromLiquidation {
-                        borrower: "addr0000".to_string()
+                        borrower: "addr0000".to_string(),
+                        position_id: None,
                     })
                     .unwrap(),
                 })
@@ -682,6 +689,7 @@ fn execute_bid() {
             fee_address: None,
             repay_address: None,
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -700,6 +708,7 @@ fn execute_bid() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -784,6 +793,7 @@ fn claim_liquidations() {
             fee_address: Some("fee0000".to_string()),
             repay_address: Some("repay0000".to_string()),
             borrower_address: Some("addr0000".to_string()),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -873,3 +883,178 @@ fn update_collateral_info() {
         }
     );
 }
+
+#[test]
+fn execute_bid_fee_recipients() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_collateral_max_ltv(&[(&"asset0000".to_string(), &Decimal256::percent(90))]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        oracle_contract: "oracle0000".to_string(),
+        stable_contract: "stable0000".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::percent(1),
+        liquidation_threshold: Uint256::from(100000000u64),
+        price_timeframe: 100000u64,
+        waiting_period: 60u64,
+        overseer: "overseer0000".to_string(),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let env = mock_env();
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::percent(50),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::WhitelistCollateral {
+        collateral_token: "asset0000".to_string(),
+        max_slot: 30u8,
+        bid_threshold: Uint256::zero(),
+        premium_rate_per_slot: Decimal256::percent(1),
+    };
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), env, info, msg).unwrap();
+    let info = mock_info("stable0000", &[]);
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(2000000u128),
+        msg: to_binary(&Cw20HookMsg::SubmitBid {
+            collateral_token: "asset0000".to_string(),
+            premium_slot: 1u8,
+        })
+        .unwrap(),
+    });
+    let env = mock_env();
+    let wait_end = env.block.time.plus_seconds(60u64);
+    execute(deps.as_mut(), env, info, msg).unwrap();
+
+    let msg = ExecuteMsg::ActivateBids {
+        collateral_token: "asset0000".to_string(),
+        bids_idx: Some(vec![Uint128::from(1u128)]),
+    };
+    let mut env = mock_env();
+    env.block.time = wait_end;
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env, info, msg).unwrap();
+
+    // bid_fee 4,950 on each liquidation
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "custody0000".to_string(),
+        amount: Uint128::from(1000000u128),
+        msg: to_binary(&Cw20HookMsg::ExecuteBid {
+            liquidator: "liquidator0000".to_string(),
+            fee_address: Some("fee0000".to_string()),
+            repay_address: Some("repay0000".to_string()),
+            borrower_address: Some("addr0000".to_string()),
+            position_id: None,
+        })
+        .unwrap(),
+    });
+
+    // without recipients the whole bid fee goes to the fee address
+    let info = mock_info("asset0000", &[]);
+    let env = mock_env();
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "stable0000".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "fee0000".to_string(),
+                amount: Uint128::from(4950u128),
+            })
+            .unwrap(),
+        }))
+    );
+
+    let recipients = vec![
+        FeeRecipient {
+            kind: FeeRecipientKind::Reserve,
+            address: "reserve0000".to_string(),
+            weight: 5000,
+        },
+        FeeRecipient {
+            kind: FeeRecipientKind::CapaStakers,
+            address: "gov0000".to_string(),
+            weight: 5000,
+        },
+    ];
+    let update_msg = ExecuteMsg::UpdateFeeRecipients {
+        recipients: recipients.clone(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        update_msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(err, StdError::generic_err("unauthorized"));
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner0000", &[]),
+        update_msg,
+    )
+    .unwrap();
+
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.messages[1..3],
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "stable0000".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: "reserve0000".to_string(),
+                    amount: Uint128::from(2475u128),
+                    msg: to_binary(&OverseerCw20HookMsg::FundReserve {}).unwrap(),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "stable0000".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "gov0000".to_string(),
+                    amount: Uint128::from(2475u128),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    let res: FeeDistributionResponse =
+        from_binary(&query(deps.as_ref(), env, QueryMsg::FeeDistribution {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        FeeDistributionResponse {
+            recipients,
+            paid: vec![
+                FeePaid {
+                    kind: FeeRecipientKind::Collector,
+                    amount: Uint256::from(4950u64),
+                },
+                FeePaid {
+                    kind: FeeRecipientKind::Reserve,
+                    amount: Uint256::from(2475u64),
+                },
+                FeePaid {
+                    kind: FeeRecipientKind::CapaStakers,
+                    amount: Uint256::from(2475u64),
+                },
+            ],
+        }
+    );
+}
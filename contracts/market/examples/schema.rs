@@ -5,7 +5,7 @@ use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use moneymarket::market::{
     BorrowerInfoResponse, BorrowerInfosResponse, ConfigResponse, Cw20HookMsg, ExecuteMsg,
-    InstantiateMsg, QueryMsg,
+    FlashMintFeeResponse, InstantiateMsg, QueryMsg, SimulateBorrowResponse, SimulateRepayResponse,
 };
 use moneymarket_market::state::State;
 
@@ -23,4 +23,7 @@ fn main() {
     export_schema(&schema_for!(State), &out_dir);
     export_schema(&schema_for!(BorrowerInfoResponse), &out_dir);
     export_schema(&schema_for!(BorrowerInfosResponse), &out_dir);
+    export_schema(&schema_for!(SimulateBorrowResponse), &out_dir);
+    export_schema(&schema_for!(SimulateRepayResponse), &out_dir);
+    export_schema(&schema_for!(FlashMintFeeResponse), &out_dir);
 }
@@ -0,0 +1,128 @@
+use cosmwasm_bignumber::math::Uint256;
+use cosmwasm_std::{attr, Addr, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
+use moneymarket::market::{BorrowAllowanceResponse, BorrowAllowancesResponse};
+use moneymarket::position::PositionId;
+
+use crate::borrow::borrow_stable;
+use crate::error::ContractError;
+use crate::state::{
+    read_borrow_allowance, read_borrow_allowances, remove_borrow_allowance, store_borrow_allowance,
+    BorrowAllowance,
+};
+
+/// Set the amount `spender` can borrow against the sender position
+pub fn approve_borrow(
+    deps: DepsMut,
+    info: MessageInfo,
+    spender: Addr,
+    amount: Uint256,
+    expires: Option<u64>,
+    position_id: PositionId,
+) -> Result<Response, ContractError> {
+    let owner = info.sender;
+
+    if amount.is_zero() {
+        remove_borrow_allowance(deps.storage, &owner, position_id, &spender);
+    } else {
+        store_borrow_allowance(
+            deps.storage,
+            &owner,
+            position_id,
+            &spender,
+            &BorrowAllowance { amount, expires },
+        )?;
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "approve_borrow"),
+        attr("owner", owner),
+        attr("position_id", position_id.to_string()),
+        attr("spender", spender),
+        attr("amount", amount),
+    ]))
+}
+
+/// Borrow against a position of `owner` spending the allowance granted to the sender.
+/// The borrowed SOLID goes to the sender unless `to` is given
+pub fn borrow_stable_on_behalf(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: Addr,
+    position_id: PositionId,
+    borrow_amount: Uint256,
+    to: Option<Addr>,
+) -> Result<Response, ContractError> {
+    let spender = info.sender;
+    let mut allowance: BorrowAllowance =
+        read_borrow_allowance(deps.storage, &owner, position_id, &spender);
+
+    if let Some(expires) = allowance.expires {
+        if env.block.time.seconds() > expires {
+            return Err(ContractError::BorrowAllowanceExpired {});
+        }
+    }
+    if borrow_amount > allowance.amount {
+        return Err(ContractError::BorrowAllowanceExceeded(
+            allowance.amount.into(),
+        ));
+    }
+
+    allowance.amount = allowance.amount - borrow_amount;
+    if allowance.amount.is_zero() {
+        remove_borrow_allowance(deps.storage, &owner, position_id, &spender);
+    } else {
+        store_borrow_allowance(deps.storage, &owner, position_id, &spender, &allowance)?;
+    }
+
+    let res = borrow_stable(
+        deps,
+        env,
+        owner,
+        position_id,
+        borrow_amount,
+        Some(to.unwrap_or_else(|| spender.clone())),
+    )?;
+
+    Ok(res.add_attribute("spender", spender))
+}
+
+pub fn query_borrow_allowance(
+    deps: Deps,
+    owner: Addr,
+    spender: Addr,
+    position_id: PositionId,
+) -> StdResult<BorrowAllowanceResponse> {
+    let allowance: BorrowAllowance =
+        read_borrow_allowance(deps.storage, &owner, position_id, &spender);
+
+    Ok(BorrowAllowanceResponse {
+        owner: owner.to_string(),
+        position_id,
+        spender: spender.to_string(),
+        amount: allowance.amount,
+        expires: allowance.expires,
+    })
+}
+
+pub fn query_borrow_allowances(
+    deps: Deps,
+    owner: Addr,
+    position_id: PositionId,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<BorrowAllowancesResponse> {
+    let allowances: Vec<BorrowAllowanceResponse> =
+        read_borrow_allowances(deps, &owner, position_id, start_after, limit)?
+            .into_iter()
+            .map(|(spender, allowance)| BorrowAllowanceResponse {
+                owner: owner.to_string(),
+                position_id,
+                spender: spender.to_string(),
+                amount: allowance.amount,
+                expires: allowance.expires,
+            })
+            .collect();
+
+    Ok(BorrowAllowancesResponse { allowances })
+}
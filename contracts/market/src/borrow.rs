@@ -5,22 +5,32 @@ use cosmwasm_std::{
     attr, to_binary, Addr, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StdResult, WasmMsg,
 };
 use cw20::Cw20ExecuteMsg;
-use moneymarket::market::{BorrowerInfoResponse, BorrowerInfosResponse};
+use moneymarket::fee_distribution::{distribute_fee, fee_recipients};
+use moneymarket::interest_model::BorrowRateResponse;
+use moneymarket::market::{
+    BorrowFeeCurve, BorrowFeeDiscountResponse, BorrowFeeDiscountTiersResponse,
+    BorrowerInfoResponse, BorrowerInfosResponse, SimulateBorrowResponse, SimulateRepayResponse,
+};
 use moneymarket::oracle::PriceResponse;
-use moneymarket::overseer::BorrowLimitResponse;
+use moneymarket::overseer::{BorrowLimitResponse, ExecuteMsg as OverseerExecuteMsg};
+use moneymarket::position::{optional_position_id, PositionId};
 use moneymarket::querier::{query_price, TimeConstraints};
 
+use crate::contract::SECONDS_PER_YEAR;
 use crate::error::ContractError;
-use crate::querier::query_borrow_limit;
+use crate::querier::{query_borrow_limit, query_borrow_rate, query_gov_staker};
 use crate::state::{
-    read_borrower_info, read_borrower_infos, read_config, read_state, store_borrower_info,
-    store_state, BorrowerInfo, Config, State,
+    read_all_term_loans, read_borrower_info, read_borrower_infos, read_config, read_fees_paid,
+    read_state, remove_borrower_info, remove_term_loan, store_borrower_info, store_fees_paid,
+    store_state, store_term_loan, BorrowerInfo, Config, State,
 };
+use crate::term_loan::{compute_term_loans_amount, compute_term_loans_principal};
 
 pub fn borrow_stable(
     deps: DepsMut,
     env: Env,
-    info: MessageInfo,
+    borrower: Addr,
+    position_id: PositionId,
     borrow_amount: Uint256,
     to: Option<Addr>,
 ) -> Result<Response, ContractError> {
@@ -28,21 +38,41 @@ pub fn borrow_stable(
 
     let mut state: State = read_state(deps.storage)?;
 
-    let borrower = info.sender;
-    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower);
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower, position_id);
+
+    // accrue interest up to the current block before touching the loan
+    compute_interest(deps.as_ref(), &config, &mut state, env.block.time.seconds())?;
+    compute_loan(&state, &mut liability);
 
     // compute fee to borrow
-    let one_time_borrow_fee = compute_borrow_fee(deps.as_ref(), &env, &config, borrow_amount)?;
+    let one_time_borrow_fee = compute_borrow_fee(
+        deps.as_ref(),
+        &env,
+        &config,
+        &mut state,
+        &borrower,
+        borrow_amount,
+    )?;
 
     let borrow_limit_res: BorrowLimitResponse = query_borrow_limit(
         deps.as_ref(),
-        config.overseer_contract,
+        config.overseer_contract.clone(),
         borrower.clone(),
+        position_id,
         Some(env.block.time.seconds()),
     )?;
+    let term_loan_amount = compute_term_loans_amount(
+        deps.storage,
+        &config,
+        &borrower,
+        position_id,
+        env.block.time.seconds(),
+    )?;
     let borrow_amount_with_fee = borrow_amount + one_time_borrow_fee;
     // if borrow limit is greater then the total debt plus the new one with the one time fee return error
-    if borrow_limit_res.borrow_limit < borrow_amount_with_fee + liability.loan_amount {
+    if borrow_limit_res.borrow_limit
+        < borrow_amount_with_fee + liability.loan_amount + term_loan_amount
+    {
         return Err(ContractError::BorrowExceedsLimit(
             borrow_limit_res.borrow_limit.into(),
         ));
@@ -52,8 +82,14 @@ pub fn borrow_stable(
     liability.loan_amount_without_interest += borrow_amount;
 
     state.total_liabilities += Decimal256::from_uint256(borrow_amount);
+    if let Some(mint_ceiling) = config.mint_ceiling {
+        if state.total_liabilities > Decimal256::from_uint256(mint_ceiling) {
+            return Err(ContractError::MintCeilingExceeded(mint_ceiling.into()));
+        }
+    }
+
     store_state(deps.storage, &state)?;
-    store_borrower_info(deps.storage, &borrower, &liability)?;
+    store_borrower_info(deps.storage, &borrower, position_id, &liability)?;
 
     // Mint solid and send to address
     Ok(Response::new()
@@ -65,9 +101,21 @@ pub fn borrow_stable(
                 amount: borrow_amount.into(),
             })?,
         }))
+        // Overseer checks the collateral debt ceilings
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.overseer_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                borrower: borrower.to_string(),
+                loan_amount: liability.loan_amount + term_loan_amount,
+                position_id: optional_position_id(position_id),
+                is_borrow: true,
+            })?,
+        }))
         .add_attributes(vec![
             attr("action", "borrow_stable"),
             attr("borrower", borrower),
+            attr("position_id", position_id.to_string()),
             attr("borrow_amount", borrow_amount),
             attr("mint_fee", one_time_borrow_fee),
         ]))
@@ -77,9 +125,13 @@ pub fn borrow_stable(
 // loan_amount : repay_amount = loan_amount_without_interest : burn_amount
 // burn_amount = (repay_amount * loan_amount_without_interest) / loan_amount
 // interest_amount = repay_amount - burn_amount
+/// Repay the loan of a position; any amount exceeding the loan is refunded to `payer`
 pub fn repay_stable(
     deps: DepsMut,
+    env: Env,
     borrower: Addr,
+    position_id: PositionId,
+    payer: Addr,
     amount: Uint256,
 ) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
@@ -92,34 +144,24 @@ pub fn repay_stable(
     let mut state: State = read_state(deps.storage)?;
 
     let borrower_validated = deps.api.addr_validate(borrower.as_str())?;
-    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower_validated);
+    let mut liability: BorrowerInfo =
+        read_borrower_info(deps.storage, &borrower_validated, position_id);
 
-    let repay_amount: Uint256;
-    let burn_amount: Uint256;
-    let mut messages: Vec<CosmosMsg> = vec![];
-    if liability.loan_amount < amount {
-        repay_amount = liability.loan_amount;
-        burn_amount = liability.loan_amount_without_interest;
-        liability.loan_amount = Uint256::zero();
-        liability.loan_amount_without_interest = Uint256::zero();
+    compute_interest(deps.as_ref(), &config, &mut state, env.block.time.seconds())?;
+    compute_loan(&state, &mut liability);
 
-        // Payback left repay amount to sender
+    let (repay_amount, burn_amount) = compute_repay(&mut liability, amount);
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if repay_amount < amount {
+        // Payback left repay amount to payer
         messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: config.stable_contract.to_string(),
             funds: vec![],
             msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                recipient: borrower.to_string(),
+                recipient: payer.to_string(),
                 amount: (amount - repay_amount).into(),
             })?,
         }));
-    } else {
-        repay_amount = amount;
-        burn_amount = repay_amount
-            .mul(liability.loan_amount_without_interest)
-            .div(Decimal256::from_uint256(liability.loan_amount));
-        liability.loan_amount = liability.loan_amount - repay_amount;
-        liability.loan_amount_without_interest =
-            liability.loan_amount_without_interest - burn_amount;
     }
 
     // BURN Message
@@ -133,78 +175,506 @@ pub fn repay_stable(
 
     let interest_amount = repay_amount - burn_amount;
 
-    if !interest_amount.is_zero() {
-        // Transfer to collector
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: config.stable_contract.to_string(),
-            funds: vec![],
-            msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                recipient: config.collector_contract.to_string(),
-                amount: interest_amount.into(),
-            })?,
-        }));
-    }
+    // Split the interest between the fee recipients
+    messages.extend(distribute_fee(
+        deps.storage,
+        read_fees_paid,
+        store_fees_paid,
+        config.stable_contract.to_string(),
+        &fee_recipients(
+            &config.fee_recipients,
+            config.collector_contract.to_string(),
+        ),
+        interest_amount,
+        None,
+    )?);
+    // Release the debt attributed to the borrower collaterals
+    let term_loan_amount = compute_term_loans_amount(
+        deps.storage,
+        &config,
+        &borrower_validated,
+        position_id,
+        env.block.time.seconds(),
+    )?;
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.overseer_contract.to_string(),
+        funds: vec![],
+        msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+            borrower: borrower_validated.to_string(),
+            loan_amount: liability.loan_amount + term_loan_amount,
+            position_id: optional_position_id(position_id),
+            is_borrow: false,
+        })?,
+    }));
+
     state.total_liabilities = state.total_liabilities - Decimal256::from_uint256(burn_amount);
-    store_borrower_info(deps.storage, &borrower_validated, &liability)?;
+    store_borrower_info(deps.storage, &borrower_validated, position_id, &liability)?;
     store_state(deps.storage, &state)?;
 
     Ok(Response::new().add_messages(messages).add_attributes(vec![
         attr("action", "repay_stable"),
         attr("borrower", borrower),
+        attr("position_id", position_id.to_string()),
         attr("repay_amount", repay_amount),
     ]))
 }
 
-/// Compute fee to borrow
+/// Move the loan and the term loans of a position to the same, empty, position of `recipient`.
+/// The overseer moves the collaterals and the attributed debt along with them
+pub fn transfer_liability(
+    deps: DepsMut,
+    info: MessageInfo,
+    borrower: Addr,
+    recipient: Addr,
+    position_id: PositionId,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if info.sender != config.overseer_contract {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let recipient_liability = read_borrower_info(deps.storage, &recipient, position_id);
+    if !recipient_liability.loan_amount.is_zero()
+        || !read_all_term_loans(deps.storage, &recipient, position_id)?.is_empty()
+    {
+        return Err(ContractError::PositionAlreadyExists {});
+    }
+
+    // the interest index moves with the loan, so the interest keeps accruing unchanged
+    let liability = read_borrower_info(deps.storage, &borrower, position_id);
+    remove_borrower_info(deps.storage, &borrower, position_id);
+    store_borrower_info(deps.storage, &recipient, position_id, &liability)?;
+
+    for term_loan in read_all_term_loans(deps.storage, &borrower, position_id)? {
+        remove_term_loan(deps.storage, &borrower, position_id, term_loan.loan_id);
+        store_term_loan(deps.storage, &recipient, position_id, &term_loan)?;
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "transfer_liability"),
+        attr("borrower", borrower),
+        attr("recipient", recipient),
+        attr("position_id", position_id.to_string()),
+    ]))
+}
+
+/// Apply a repay of `amount` to the loan, returning the part of it actually repaid
+/// and the principal to burn; the rest of the repaid amount is interest
+pub fn compute_repay(liability: &mut BorrowerInfo, amount: Uint256) -> (Uint256, Uint256) {
+    compute_repay_raw(
+        &mut liability.loan_amount,
+        &mut liability.loan_amount_without_interest,
+        amount,
+    )
+}
+
+pub fn compute_repay_raw(
+    loan_amount: &mut Uint256,
+    loan_amount_without_interest: &mut Uint256,
+    amount: Uint256,
+) -> (Uint256, Uint256) {
+    if amount.is_zero() {
+        return (Uint256::zero(), Uint256::zero());
+    }
+
+    if *loan_amount < amount {
+        let repay_amount = *loan_amount;
+        let burn_amount = *loan_amount_without_interest;
+        *loan_amount = Uint256::zero();
+        *loan_amount_without_interest = Uint256::zero();
+
+        (repay_amount, burn_amount)
+    } else {
+        let repay_amount = amount;
+        let burn_amount = repay_amount
+            .mul(*loan_amount_without_interest)
+            .div(Decimal256::from_uint256(*loan_amount));
+        *loan_amount = *loan_amount - repay_amount;
+        *loan_amount_without_interest = *loan_amount_without_interest - burn_amount;
+
+        (repay_amount, burn_amount)
+    }
+}
+
+/// Compute fee to borrow from the fee curve at the time-weighted peg,
+/// minus the discount of the borrower
 pub fn compute_borrow_fee(
     deps: Deps,
     env: &Env,
     config: &Config,
+    state: &mut State,
+    borrower: &Addr,
     borrow_amount: Uint256,
 ) -> StdResult<Uint256> {
+    record_peg_sample(deps, config, state, env.block.time.seconds())?;
+
+    let rate: Decimal256 = compute_fee_rate(&config.borrow_fee_curve, state.peg_twap);
+
+    let one_time_fee = rate * borrow_amount;
+    let (_, discount) = compute_borrow_fee_discount(deps, config, borrower)?;
+
+    Ok(one_time_fee - one_time_fee * discount)
+}
+
+/// Rate of the one time borrow fee charged to `borrower`, net of its discount
+pub fn compute_borrow_fee_rate(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    state: &mut State,
+    borrower: &Addr,
+) -> StdResult<Decimal256> {
+    record_peg_sample(deps, config, state, env.block.time.seconds())?;
+
+    let rate: Decimal256 = compute_fee_rate(&config.borrow_fee_curve, state.peg_twap);
+    let (_, discount) = compute_borrow_fee_discount(deps, config, borrower)?;
+
+    Ok(rate - rate * discount)
+}
+
+/// CAPA staked in gov by `borrower` and the discount of the highest tier it reaches
+pub fn compute_borrow_fee_discount(
+    deps: Deps,
+    config: &Config,
+    borrower: &Addr,
+) -> StdResult<(Uint256, Decimal256)> {
+    let capa_gov_contract = match config.capa_gov_contract.clone() {
+        Some(capa_gov_contract) if !config.borrow_fee_discount_tiers.is_empty() => {
+            capa_gov_contract
+        }
+        _ => return Ok((Uint256::zero(), Decimal256::zero())),
+    };
+
+    let staked_amount: Uint256 = query_gov_staker(deps, capa_gov_contract, borrower.clone())?
+        .balance
+        .into();
+    let discount = config
+        .borrow_fee_discount_tiers
+        .iter()
+        .rev()
+        .find(|tier| staked_amount >= tier.min_staked)
+        .map(|tier| tier.discount)
+        .unwrap_or_else(Decimal256::zero);
+
+    Ok((staked_amount, discount))
+}
+
+/// Sample the SOLID peg into the time-weighted peg,
+/// failing on a price older than the peg price timeframe
+pub fn record_peg_sample(
+    deps: Deps,
+    config: &Config,
+    state: &mut State,
+    block_time: u64,
+) -> StdResult<()> {
     let price: PriceResponse = query_price(
         deps,
         config.oracle_contract.clone(),
         config.stable_contract.to_string(),
         "uusd".to_string(),
         Some(TimeConstraints {
-            block_time: env.block.time.seconds(),
-            valid_timeframe: 1200u64,
+            block_time,
+            valid_timeframe: config.peg_price_timeframe,
         }),
     )?;
 
-    let rate: Decimal256 = if price.rate > Decimal256::one() {
-        config.base_borrow_fee
-    } else {
-        config.base_borrow_fee + (Decimal256::one() - price.rate).div(config.fee_increase_factor)
+    compute_peg_twap(state, price.rate, block_time, config.peg_twap_window);
+
+    Ok(())
+}
+
+pub fn update_peg_twap(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+
+    record_peg_sample(deps.as_ref(), &config, &mut state, env.block.time.seconds())?;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_peg_twap"),
+        attr("peg_twap", state.peg_twap.to_string()),
+    ]))
+}
+
+/// Move the time-weighted peg towards the peg observed at the previous update,
+/// weighted by the time passed over the window, and record the current peg.
+/// The current peg only weights the time until the next update,
+/// so a price moved within a block barely affects the average
+pub fn compute_peg_twap(state: &mut State, peg: Decimal256, block_time: u64, window: u64) {
+    if state.peg_last_updated == 0 {
+        state.peg_twap = peg;
+    } else if block_time > state.peg_last_updated {
+        let passed_time = std::cmp::min(block_time - state.peg_last_updated, window);
+        let weight = if window == 0 {
+            Decimal256::one()
+        } else {
+            Decimal256::from_ratio(passed_time, window)
+        };
+
+        state.peg_twap =
+            state.peg_twap * (Decimal256::one() - weight) + state.peg_last_price * weight;
+    }
+
+    state.peg_last_price = peg;
+    state.peg_last_updated = block_time;
+}
+
+/// Interpolate the fee curve at the given peg, clamped between its min and max fee
+pub fn compute_fee_rate(curve: &BorrowFeeCurve, peg: Decimal256) -> Decimal256 {
+    let breakpoints = &curve.breakpoints;
+
+    let rate = match breakpoints
+        .iter()
+        .position(|(point_peg, _)| *point_peg >= peg)
+    {
+        None => breakpoints
+            .last()
+            .map(|(_, fee)| *fee)
+            .unwrap_or(curve.min_fee),
+        Some(0) => breakpoints[0].1,
+        Some(i) => {
+            let (peg0, fee0) = breakpoints[i - 1];
+            let (peg1, fee1) = breakpoints[i];
+            let ratio = (peg - peg0) / (peg1 - peg0);
+            if fee1 >= fee0 {
+                fee0 + (fee1 - fee0) * ratio
+            } else {
+                fee0 - (fee0 - fee1) * ratio
+            }
+        }
     };
 
-    let one_time_fee = rate * borrow_amount;
+    std::cmp::min(std::cmp::max(rate, curve.min_fee), curve.max_fee)
+}
+
+/// Accrue interest on the global borrow index for the time passed since the last update.
+/// The yearly rate comes from the interest model given the current SOLID peg
+pub fn compute_interest(
+    deps: Deps,
+    config: &Config,
+    state: &mut State,
+    block_time: u64,
+) -> StdResult<()> {
+    if state.last_interest_updated >= block_time {
+        return Ok(());
+    }
+
+    // interest model not registered yet, nothing to accrue
+    if config.interest_model_contract == Addr::unchecked("") {
+        state.last_interest_updated = block_time;
+        return Ok(());
+    }
+
+    let price: PriceResponse = query_price(
+        deps,
+        config.oracle_contract.clone(),
+        config.stable_contract.to_string(),
+        "uusd".to_string(),
+        None,
+    )?;
 
-    Ok(one_time_fee)
+    // every interest accrual samples the peg, so the twap does not only move on borrows
+    let valid_update_time = block_time.saturating_sub(config.peg_price_timeframe);
+    if price.last_updated_base >= valid_update_time && price.last_updated_quote >= valid_update_time
+    {
+        compute_peg_twap(state, price.rate, block_time, config.peg_twap_window);
+    }
+
+    let borrow_rate_res: BorrowRateResponse =
+        query_borrow_rate(deps, config.interest_model_contract.clone(), price.rate)?;
+
+    compute_interest_raw(state, block_time, borrow_rate_res.rate);
+
+    Ok(())
+}
+
+pub fn compute_interest_raw(state: &mut State, block_time: u64, borrow_rate: Decimal256) {
+    if state.last_interest_updated >= block_time {
+        return;
+    }
+
+    let passed_time = Decimal256::from_uint256(block_time - state.last_interest_updated);
+    let interest_factor = passed_time * borrow_rate / Decimal256::from_uint256(SECONDS_PER_YEAR);
+
+    state.global_interest_index =
+        state.global_interest_index * (Decimal256::one() + interest_factor);
+    state.last_interest_updated = block_time;
 }
 
-pub fn query_borrower_info(deps: Deps, borrower: Addr) -> StdResult<BorrowerInfoResponse> {
-    let borrower_info: BorrowerInfo =
-        read_borrower_info(deps.storage, &deps.api.addr_validate(borrower.as_str())?);
+/// Bring the borrower loan up to date with the global borrow index
+pub fn compute_loan(state: &State, liability: &mut BorrowerInfo) {
+    liability.loan_amount = liability
+        .loan_amount
+        .multiply_ratio(state.global_interest_index.0, liability.interest_index.0);
+    liability.interest_index = state.global_interest_index;
+}
+
+pub fn query_borrower_info(
+    deps: Deps,
+    env: Env,
+    borrower: Addr,
+    position_id: PositionId,
+) -> StdResult<BorrowerInfoResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let mut borrower_info: BorrowerInfo = read_borrower_info(
+        deps.storage,
+        &deps.api.addr_validate(borrower.as_str())?,
+        position_id,
+    );
+
+    compute_interest(deps, &config, &mut state, env.block.time.seconds())?;
+    compute_loan(&state, &mut borrower_info);
+    let term_loan_amount = compute_term_loans_amount(
+        deps.storage,
+        &config,
+        &borrower,
+        position_id,
+        env.block.time.seconds(),
+    )?;
+
+    let principal_amount = borrower_info.loan_amount_without_interest
+        + compute_term_loans_principal(deps.storage, &borrower, position_id)?;
 
     Ok(BorrowerInfoResponse {
         borrower: borrower.to_string(),
-        loan_amount: borrower_info.loan_amount,
+        position_id,
+        loan_amount: borrower_info.loan_amount + term_loan_amount,
+        term_loan_amount,
+        principal_amount,
     })
 }
 
 pub fn query_borrower_infos(
     deps: Deps,
+    env: Env,
     start_after: Option<Addr>,
+    start_after_position_id: PositionId,
     limit: Option<u32>,
 ) -> StdResult<BorrowerInfosResponse> {
     let start_after = if let Some(start_after) = start_after {
-        Some(deps.api.addr_validate(start_after.as_str())?)
+        Some((
+            deps.api.addr_validate(start_after.as_str())?,
+            start_after_position_id,
+        ))
     } else {
         None
     };
 
-    let borrower_infos: Vec<BorrowerInfoResponse> = read_borrower_infos(deps, start_after, limit)?;
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    compute_interest(deps, &config, &mut state, env.block.time.seconds())?;
+
+    let borrower_infos: Vec<BorrowerInfoResponse> = read_borrower_infos(deps, start_after, limit)?
+        .into_iter()
+        .map(|(borrower, position_id, mut borrower_info)| {
+            compute_loan(&state, &mut borrower_info);
+            let term_loan_amount = compute_term_loans_amount(
+                deps.storage,
+                &config,
+                &borrower,
+                position_id,
+                env.block.time.seconds(),
+            )?;
+            let principal_amount = borrower_info.loan_amount_without_interest
+                + compute_term_loans_principal(deps.storage, &borrower, position_id)?;
+            Ok(BorrowerInfoResponse {
+                borrower: borrower.to_string(),
+                position_id,
+                loan_amount: borrower_info.loan_amount + term_loan_amount,
+                term_loan_amount,
+                principal_amount,
+            })
+        })
+        .collect::<StdResult<Vec<BorrowerInfoResponse>>>()?;
+
     Ok(BorrowerInfosResponse { borrower_infos })
 }
+
+pub fn query_borrow_fee_discount_tiers(deps: Deps) -> StdResult<BorrowFeeDiscountTiersResponse> {
+    let config: Config = read_config(deps.storage)?;
+
+    Ok(BorrowFeeDiscountTiersResponse {
+        tiers: config.borrow_fee_discount_tiers,
+    })
+}
+
+pub fn query_borrow_fee_discount(
+    deps: Deps,
+    borrower: Addr,
+) -> StdResult<BorrowFeeDiscountResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let (staked_amount, discount) = compute_borrow_fee_discount(deps, &config, &borrower)?;
+
+    Ok(BorrowFeeDiscountResponse {
+        borrower: borrower.to_string(),
+        staked_amount,
+        discount,
+    })
+}
+
+pub fn query_simulate_borrow(
+    deps: Deps,
+    env: Env,
+    borrower: Addr,
+    position_id: PositionId,
+    amount: Uint256,
+) -> StdResult<SimulateBorrowResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower, position_id);
+
+    compute_interest(deps, &config, &mut state, env.block.time.seconds())?;
+    compute_loan(&state, &mut liability);
+
+    let mint_fee = compute_borrow_fee(deps, &env, &config, &mut state, &borrower, amount)?;
+    let borrow_limit_res: BorrowLimitResponse = query_borrow_limit(
+        deps,
+        config.overseer_contract.clone(),
+        borrower.clone(),
+        position_id,
+        Some(env.block.time.seconds()),
+    )?;
+
+    let term_loan_amount = compute_term_loans_amount(
+        deps.storage,
+        &config,
+        &borrower,
+        position_id,
+        env.block.time.seconds(),
+    )?;
+    let loan_amount = liability.loan_amount + term_loan_amount + amount + mint_fee;
+    Ok(SimulateBorrowResponse {
+        borrower: borrower.to_string(),
+        borrow_amount: amount,
+        mint_fee,
+        loan_amount,
+        borrow_limit: borrow_limit_res.borrow_limit,
+        exceeds_limit: borrow_limit_res.borrow_limit < loan_amount,
+    })
+}
+
+pub fn query_simulate_repay(
+    deps: Deps,
+    env: Env,
+    borrower: Addr,
+    position_id: PositionId,
+    amount: Uint256,
+) -> StdResult<SimulateRepayResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower, position_id);
+
+    compute_interest(deps, &config, &mut state, env.block.time.seconds())?;
+    compute_loan(&state, &mut liability);
+
+    let (repay_amount, burn_amount) = compute_repay(&mut liability, amount);
+    Ok(SimulateRepayResponse {
+        borrower: borrower.to_string(),
+        repay_amount,
+        burn_amount,
+        collector_amount: repay_amount - burn_amount,
+        refund_amount: amount - repay_amount,
+        loan_amount: liability.loan_amount,
+    })
+}
@@ -0,0 +1,209 @@
+use astroport::asset::AssetInfo;
+use astroport::router::{Cw20HookMsg as RouterCw20HookMsg, SwapOperation};
+use cosmwasm_bignumber::math::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, to_binary, Addr, CosmosMsg, DepsMut, Env, MessageInfo, Response, Uint128, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use moneymarket::fee_distribution::{distribute_fee, fee_recipients};
+use moneymarket::market::ExecuteMsg;
+use moneymarket::overseer::ExecuteMsg as OverseerExecuteMsg;
+use moneymarket::position::{optional_position_id, PositionId};
+use moneymarket::querier::query_token_balance;
+
+use crate::borrow::{query_borrower_info, repay_stable};
+use crate::error::ContractError;
+use crate::flash_mint::{compute_flash_mint_fee, lock_flash_mint, DEFAULT_FLASH_MINT_FEE};
+use crate::state::{read_config, read_fees_paid, store_fees_paid, store_flash_mint_lock, Config};
+
+/// Repays the loan of a sender position by selling `amount` of its locked `collateral_token`.
+/// The market flash mints the repaid SOLID to itself, repays the loan, has the overseer
+/// release the collateral, swaps it through the astroport router for at least
+/// `min_solid_out` SOLID and settles the flash mint, all within one transaction.
+pub fn repay_with_collateral(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collateral_token: Addr,
+    amount: Uint256,
+    min_solid_out: Uint256,
+    position_id: PositionId,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let astroport_router = config
+        .astroport_router
+        .clone()
+        .ok_or(ContractError::RouterNotConfigured {})?;
+
+    if amount.is_zero() {
+        return Err(ContractError::ZeroCollateral {});
+    }
+
+    let borrower = info.sender;
+    // Only the open loan is repaid, term loans are repaid by id
+    let borrower_info =
+        query_borrower_info(deps.as_ref(), env.clone(), borrower.clone(), position_id)?;
+    let loan_amount = borrower_info.loan_amount - borrower_info.term_loan_amount;
+
+    // The swap output must cover the flash minted amount plus its fee
+    let flash_mint_fee = config.flash_mint_fee.unwrap_or(DEFAULT_FLASH_MINT_FEE);
+    let flash_amount = std::cmp::min(
+        loan_amount,
+        min_solid_out.multiply_ratio(Decimal256::one().0, (Decimal256::one() + flash_mint_fee).0),
+    );
+    let fee_amount = compute_flash_mint_fee(&config, flash_amount);
+    lock_flash_mint(deps.storage, &config, flash_amount)?;
+
+    // Remember the market balance to find out the swap proceeds at the end
+    let prev_balance = query_token_balance(
+        deps.as_ref(),
+        config.stable_contract.clone(),
+        env.contract.address.clone(),
+    )?;
+
+    // Flash mint the SOLID used to repay
+    let mut messages: Vec<CosmosMsg> = vec![CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.stable_contract.to_string(),
+        funds: vec![],
+        msg: to_binary(&Cw20ExecuteMsg::Mint {
+            recipient: env.contract.address.to_string(),
+            amount: flash_amount.into(),
+        })?,
+    })];
+
+    let repay_res = repay_stable(
+        deps.branch(),
+        env.clone(),
+        borrower.clone(),
+        position_id,
+        env.contract.address.clone(),
+        flash_amount,
+    )?;
+    messages.extend(repay_res.messages.into_iter().map(|sub_msg| sub_msg.msg));
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.overseer_contract.to_string(),
+        funds: vec![],
+        msg: to_binary(&OverseerExecuteMsg::ReleaseCollateral {
+            borrower: borrower.to_string(),
+            collaterals: vec![(collateral_token.to_string(), amount)],
+            recipient: env.contract.address.to_string(),
+            position_id: optional_position_id(position_id),
+        })?,
+    }));
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: collateral_token.to_string(),
+        funds: vec![],
+        msg: to_binary(&Cw20ExecuteMsg::Send {
+            contract: astroport_router.to_string(),
+            amount: amount.into(),
+            msg: to_binary(&RouterCw20HookMsg::ExecuteSwapOperations {
+                operations: vec![SwapOperation::AstroSwap {
+                    offer_asset_info: AssetInfo::Token {
+                        contract_addr: collateral_token.clone(),
+                    },
+                    ask_asset_info: AssetInfo::Token {
+                        contract_addr: config.stable_contract.clone(),
+                    },
+                }],
+                minimum_receive: Some(min_solid_out.into()),
+                to: None,
+                max_spread: None,
+            })?,
+        })?,
+    }));
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        funds: vec![],
+        msg: to_binary(&ExecuteMsg::PrivateRepayWithCollateralEnd {
+            borrower: borrower.to_string(),
+            burn_amount: flash_amount,
+            fee_amount,
+            prev_balance,
+        })?,
+    }));
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "repay_with_collateral"),
+        attr("borrower", borrower),
+        attr("position_id", position_id.to_string()),
+        attr("collateral_token", collateral_token),
+        attr("collateral_amount", amount),
+        attr("repay_amount", flash_amount),
+        attr("fee_amount", fee_amount),
+    ]))
+}
+
+/// Settles the flash mint of a repay with collateral:
+/// burns the flash minted amount, distributes the fee
+/// and sends the left over swap proceeds to the borrower
+pub fn private_repay_with_collateral_end(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrower: Addr,
+    burn_amount: Uint256,
+    fee_amount: Uint256,
+    prev_balance: Uint256,
+) -> Result<Response, ContractError> {
+    // The sender must be the contract itself
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let config = read_config(deps.storage)?;
+
+    let balance = query_token_balance(
+        deps.as_ref(),
+        config.stable_contract.clone(),
+        env.contract.address,
+    )?;
+    // The swap output must cover the flash mint and its fee
+    let left_amount = Uint128::from(balance)
+        .checked_sub(prev_balance.into())
+        .and_then(|amount| amount.checked_sub(burn_amount.into()))
+        .and_then(|amount| amount.checked_sub(fee_amount.into()))
+        .map_err(|_| ContractError::InsufficientSwapOutput {})?;
+
+    store_flash_mint_lock(deps.storage, false)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.stable_contract.to_string(),
+        funds: vec![],
+        msg: to_binary(&Cw20ExecuteMsg::Burn {
+            amount: burn_amount.into(),
+        })?,
+    })];
+
+    messages.extend(distribute_fee(
+        deps.storage,
+        read_fees_paid,
+        store_fees_paid,
+        config.stable_contract.to_string(),
+        &fee_recipients(
+            &config.fee_recipients,
+            config.collector_contract.to_string(),
+        ),
+        fee_amount,
+        None,
+    )?);
+
+    if !left_amount.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.stable_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: borrower.to_string(),
+                amount: left_amount,
+            })?,
+        }));
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "private_repay_with_collateral_end"),
+        attr("borrower", borrower),
+        attr("refund_amount", left_amount),
+    ]))
+}
@@ -1,13 +1,32 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 
-use crate::borrow::{borrow_stable, query_borrower_info, query_borrower_infos, repay_stable};
+use crate::allowance::{
+    approve_borrow, borrow_stable_on_behalf, query_borrow_allowance, query_borrow_allowances,
+};
+use crate::borrow::{
+    borrow_stable, compute_interest, query_borrow_fee_discount, query_borrow_fee_discount_tiers,
+    query_borrower_info, query_borrower_infos, query_simulate_borrow, query_simulate_repay,
+    repay_stable, transfer_liability, update_peg_twap,
+};
+use crate::collateral_swap::{private_repay_with_collateral_end, repay_with_collateral};
 use crate::error::ContractError;
-use crate::flash_mint::{flash_mint, private_flash_end};
+use crate::flash_mint::{
+    flash_mint, mint_stable, private_flash_end, query_flash_mint_fee, update_flash_minters,
+};
+use crate::multiply::{multiply, private_multiply_end, private_multiply_lock};
+use crate::redemption::redeem;
 use crate::response::MsgInstantiateContractResponse;
-use crate::state::{read_config, read_state, store_config, store_state, Config, State};
+use crate::state::{
+    read_config, read_fees_paid, read_legacy_fee_config, read_state, store_config, store_state,
+    Config, LegacyFeeConfig, State,
+};
+use crate::term_loan::{
+    borrow_term, query_term_loan, query_term_loan_quote, query_term_loans,
+    repay_stable_from_liquidation, repay_term_loan, settle_bad_debt,
+};
 
-use cosmwasm_bignumber::math::Decimal256;
+use cosmwasm_bignumber::math::{Decimal256, Uint256};
 use cosmwasm_std::{
     attr, from_binary, to_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Reply,
     Response, StdError, StdResult, SubMsg, Uint128, WasmMsg,
@@ -15,13 +34,19 @@ use cosmwasm_std::{
 use cw20::{Cw20Coin, Cw20ReceiveMsg, MinterResponse};
 
 use moneymarket::common::optional_addr_validate;
+use moneymarket::fee_distribution::{self, FeeDistributionResponse, FeeRecipient};
 use moneymarket::market::{
-    ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, StateResponse,
+    BorrowFeeCurve, BorrowFeeDiscountTier, ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg,
+    MigrateMsg, QueryMsg, StateResponse,
 };
+use moneymarket::position::DEFAULT_POSITION_ID;
 use moneymarket::terraswap::InstantiateMsg as TokenInstantiateMsg;
 use protobuf::Message;
 
 pub const INITIAL_DEPOSIT_AMOUNT: u128 = 1000000;
+pub const SECONDS_PER_YEAR: u64 = 31536000;
+pub const DEFAULT_PEG_TWAP_WINDOW: u64 = 1200;
+pub const DEFAULT_PEG_PRICE_TIMEFRAME: u64 = 60;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -30,6 +55,11 @@ pub fn instantiate(
     _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
+    validate_borrow_fee_curve(&msg.borrow_fee_curve)?;
+    if msg.peg_twap_window == 0 {
+        return Err(ContractError::InvalidPegTwapWindow {});
+    }
+
     store_config(
         deps.storage,
         &Config {
@@ -40,9 +70,23 @@ pub fn instantiate(
             collector_contract: Addr::unchecked("".to_string()),
             liquidation_contract: Addr::unchecked("".to_string()),
             oracle_contract: Addr::unchecked("".to_string()),
-            base_borrow_fee: msg.base_borrow_fee,
-            fee_increase_factor: msg.fee_increase_factor,
+            interest_model_contract: Addr::unchecked("".to_string()),
+            borrow_fee_curve: msg.borrow_fee_curve,
+            peg_twap_window: msg.peg_twap_window,
+            peg_price_timeframe: msg.peg_price_timeframe,
             flash_mint_fee: msg.flash_mint_fee,
+            flash_mint_max_amount: None,
+            flash_minters: None,
+            mint_ceiling: None,
+            astroport_router: None,
+            psm_contract: None,
+            redemption_fee_floor: None,
+            redemption_decay_factor: None,
+            term_loan_rate: None,
+            term_loan_penalty_rate: None,
+            capa_gov_contract: None,
+            borrow_fee_discount_tiers: vec![],
+            fee_recipients: vec![],
         },
     )?;
 
@@ -50,6 +94,14 @@ pub fn instantiate(
         deps.storage,
         &State {
             total_liabilities: Decimal256::zero(),
+            global_interest_index: Decimal256::one(),
+            last_interest_updated: env.block.time.seconds(),
+            peg_twap: Decimal256::zero(),
+            peg_last_price: Decimal256::zero(),
+            peg_last_updated: 0u64,
+            redemption_base_rate: Decimal256::zero(),
+            last_redemption_time: 0u64,
+            last_term_loan_id: 0u64,
         },
     )?;
 
@@ -88,12 +140,13 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::Receive(msg) => receive_cw20(deps, info, msg),
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
         ExecuteMsg::RegisterContracts {
             overseer_contract,
             collector_contract,
             liquidation_contract,
             oracle_contract,
+            interest_model_contract,
         } => {
             let api = deps.api;
             register_contracts(
@@ -103,50 +156,256 @@ pub fn execute(
                 api.addr_validate(&collector_contract)?,
                 api.addr_validate(&liquidation_contract)?,
                 api.addr_validate(&oracle_contract)?,
+                api.addr_validate(&interest_model_contract)?,
             )
         }
         ExecuteMsg::UpdateConfig {
             owner_addr,
             liquidation_contract,
-            base_borrow_fee,
-            fee_increase_factor,
+            borrow_fee_curve,
+            peg_twap_window,
+            peg_price_timeframe,
             flash_mint_fee,
             oracle_addr,
+            interest_model_contract,
+            mint_ceiling,
+            remove_mint_ceiling,
+            astroport_router,
+            psm_contract,
+            redemption_fee_floor,
+            redemption_decay_factor,
+            flash_mint_max_amount,
+            term_loan_rate,
+            term_loan_penalty_rate,
+            remove_term_loans,
+            capa_gov_contract,
+            borrow_fee_discount_tiers,
         } => {
             let api = deps.api;
             update_config(
                 deps,
+                env,
                 info,
                 optional_addr_validate(api, owner_addr)?,
                 optional_addr_validate(api, liquidation_contract)?,
-                base_borrow_fee,
-                fee_increase_factor,
+                borrow_fee_curve,
+                peg_twap_window,
+                peg_price_timeframe,
                 flash_mint_fee,
                 optional_addr_validate(api, oracle_addr)?,
+                optional_addr_validate(api, interest_model_contract)?,
+                mint_ceiling,
+                remove_mint_ceiling.unwrap_or(false),
+                optional_addr_validate(api, astroport_router)?,
+                optional_addr_validate(api, psm_contract)?,
+                redemption_fee_floor,
+                redemption_decay_factor,
+                flash_mint_max_amount,
+                term_loan_rate,
+                term_loan_penalty_rate,
+                remove_term_loans.unwrap_or(false),
+                optional_addr_validate(api, capa_gov_contract)?,
+                borrow_fee_discount_tiers,
             )
         }
 
-        ExecuteMsg::BorrowStable { borrow_amount, to } => {
+        ExecuteMsg::UpdateFlashMinters { flash_minters } => {
+            let flash_minters = match flash_minters {
+                Some(flash_minters) => Some(
+                    flash_minters
+                        .iter()
+                        .map(|flash_minter| deps.api.addr_validate(flash_minter))
+                        .collect::<StdResult<Vec<Addr>>>()?,
+                ),
+                None => None,
+            };
+            update_flash_minters(deps, info, flash_minters)
+        }
+        ExecuteMsg::UpdateFeeRecipients { recipients } => {
+            update_fee_recipients(deps, info, recipients)
+        }
+
+        ExecuteMsg::BorrowStable {
+            borrow_amount,
+            to,
+            on_behalf_of,
+            position_id,
+        } => {
             let api = deps.api;
-            borrow_stable(
+            let position_id = position_id.unwrap_or(DEFAULT_POSITION_ID);
+            match on_behalf_of {
+                Some(owner) => borrow_stable_on_behalf(
+                    deps,
+                    env,
+                    info,
+                    api.addr_validate(&owner)?,
+                    position_id,
+                    borrow_amount,
+                    optional_addr_validate(api, to)?,
+                ),
+                None => borrow_stable(
+                    deps,
+                    env,
+                    info.sender,
+                    position_id,
+                    borrow_amount,
+                    optional_addr_validate(api, to)?,
+                ),
+            }
+        }
+
+        ExecuteMsg::BorrowTerm {
+            borrow_amount,
+            maturity,
+            max_fee,
+            to,
+            position_id,
+        } => {
+            let api = deps.api;
+            borrow_term(
                 deps,
                 env,
-                info,
+                info.sender,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
                 borrow_amount,
+                maturity,
+                max_fee,
                 optional_addr_validate(api, to)?,
             )
         }
 
+        ExecuteMsg::ApproveBorrow {
+            spender,
+            amount,
+            expires,
+            position_id,
+        } => {
+            let api = deps.api;
+            approve_borrow(
+                deps,
+                info,
+                api.addr_validate(&spender)?,
+                amount,
+                expires,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
+            )
+        }
+
+        ExecuteMsg::UpdatePegTwap {} => update_peg_twap(deps, env),
         ExecuteMsg::FlashMint {
             amount,
-            msg_callback,
-        } => flash_mint(deps, env, info, msg_callback, amount),
+            receiver,
+            data,
+        } => {
+            let api = deps.api;
+            flash_mint(
+                deps,
+                env,
+                info,
+                optional_addr_validate(api, receiver)?,
+                amount,
+                data,
+            )
+        }
 
         ExecuteMsg::PrivateFlashEnd {
             flash_minter,
             burn_amount,
             fee_amount,
         } => private_flash_end(deps, env, info, flash_minter, burn_amount, fee_amount),
+
+        ExecuteMsg::MintStable { recipient, amount } => {
+            let api = deps.api;
+            mint_stable(deps, info, api.addr_validate(&recipient)?, amount)
+        }
+
+        ExecuteMsg::RepayWithCollateral {
+            collateral_token,
+            amount,
+            min_solid_out,
+            position_id,
+        } => {
+            let api = deps.api;
+            repay_with_collateral(
+                deps,
+                env,
+                info,
+                api.addr_validate(&collateral_token)?,
+                amount,
+                min_solid_out,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
+            )
+        }
+
+        ExecuteMsg::PrivateRepayWithCollateralEnd {
+            borrower,
+            burn_amount,
+            fee_amount,
+            prev_balance,
+        } => {
+            let api = deps.api;
+            private_repay_with_collateral_end(
+                deps,
+                env,
+                info,
+                api.addr_validate(&borrower)?,
+                burn_amount,
+                fee_amount,
+                prev_balance,
+            )
+        }
+
+        ExecuteMsg::PrivateMultiplyLock {
+            borrower,
+            collateral_token,
+            collateral_amount,
+            prev_balance,
+            position_id,
+        } => {
+            let api = deps.api;
+            private_multiply_lock(
+                deps,
+                env,
+                info,
+                api.addr_validate(&borrower)?,
+                api.addr_validate(&collateral_token)?,
+                collateral_amount,
+                prev_balance,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
+            )
+        }
+
+        ExecuteMsg::TransferLiability {
+            borrower,
+            recipient,
+            position_id,
+        } => {
+            let api = deps.api;
+            transfer_liability(
+                deps,
+                info,
+                api.addr_validate(&borrower)?,
+                api.addr_validate(&recipient)?,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
+            )
+        }
+        ExecuteMsg::PrivateMultiplyEnd {
+            borrower,
+            flash_amount,
+            fee_amount,
+            position_id,
+        } => {
+            let api = deps.api;
+            private_multiply_end(
+                deps,
+                env,
+                info,
+                api.addr_validate(&borrower)?,
+                flash_amount,
+                fee_amount,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
+            )
+        }
     }
 }
 
@@ -174,22 +433,40 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 
 pub fn receive_cw20(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     cw20_msg: Cw20ReceiveMsg,
 ) -> Result<Response, ContractError> {
     let contract_addr = info.sender;
     match from_binary(&cw20_msg.msg) {
-        Ok(Cw20HookMsg::RepayStable {}) => {
+        Ok(Cw20HookMsg::RepayStable {
+            borrower,
+            position_id,
+        }) => {
             let config: Config = read_config(deps.storage)?;
             if contract_addr != config.stable_contract {
                 return Err(ContractError::Unauthorized {});
             }
 
             let cw20_sender_addr = deps.api.addr_validate(&cw20_msg.sender)?;
-            repay_stable(deps, cw20_sender_addr, cw20_msg.amount.into())
+            let borrower_validated = match borrower {
+                Some(borrower) => deps.api.addr_validate(&borrower)?,
+                None => cw20_sender_addr.clone(),
+            };
+            repay_stable(
+                deps,
+                env,
+                borrower_validated,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
+                cw20_sender_addr,
+                cw20_msg.amount.into(),
+            )
         }
 
-        Ok(Cw20HookMsg::RepayStableFromLiquidation { borrower }) => {
+        Ok(Cw20HookMsg::RepayStableFromLiquidation {
+            borrower,
+            position_id,
+        }) => {
             let config: Config = read_config(deps.storage)?;
             let cw20_sender_addr = deps.api.addr_validate(&cw20_msg.sender)?;
 
@@ -200,7 +477,90 @@ pub fn receive_cw20(
             }
 
             let borrower_validated = deps.api.addr_validate(&borrower)?;
-            repay_stable(deps, borrower_validated, cw20_msg.amount.into())
+            repay_stable_from_liquidation(
+                deps,
+                env,
+                borrower_validated,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
+                cw20_msg.amount.into(),
+            )
+        }
+
+        Ok(Cw20HookMsg::SettleBadDebt {
+            borrower,
+            position_id,
+        }) => {
+            let config: Config = read_config(deps.storage)?;
+            let cw20_sender_addr = deps.api.addr_validate(&cw20_msg.sender)?;
+
+            if contract_addr != config.stable_contract
+                || config.overseer_contract != cw20_sender_addr
+            {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let borrower_validated = deps.api.addr_validate(&borrower)?;
+            settle_bad_debt(
+                deps,
+                env,
+                borrower_validated,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
+                cw20_msg.amount.into(),
+            )
+        }
+
+        Ok(Cw20HookMsg::RepayTermLoan {
+            loan_id,
+            borrower,
+            position_id,
+        }) => {
+            let config: Config = read_config(deps.storage)?;
+            if contract_addr != config.stable_contract {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let cw20_sender_addr = deps.api.addr_validate(&cw20_msg.sender)?;
+            let borrower_validated = match borrower {
+                Some(borrower) => deps.api.addr_validate(&borrower)?,
+                None => cw20_sender_addr.clone(),
+            };
+            repay_term_loan(
+                deps,
+                env,
+                borrower_validated,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
+                cw20_sender_addr,
+                loan_id,
+                cw20_msg.amount.into(),
+            )
+        }
+
+        Ok(Cw20HookMsg::Multiply {
+            target_ltv,
+            min_collateral_out,
+            position_id,
+        }) => {
+            // The sent token is the collateral, the overseer whitelist validates it
+            let cw20_sender_addr = deps.api.addr_validate(&cw20_msg.sender)?;
+            multiply(
+                deps,
+                env,
+                cw20_sender_addr,
+                contract_addr,
+                cw20_msg.amount.into(),
+                target_ltv,
+                min_collateral_out,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
+            )
+        }
+        Ok(Cw20HookMsg::Redeem { max_fee }) => {
+            let config: Config = read_config(deps.storage)?;
+            if contract_addr != config.stable_contract {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let cw20_sender_addr = deps.api.addr_validate(&cw20_msg.sender)?;
+            redeem(deps, env, cw20_sender_addr, cw20_msg.amount.into(), max_fee)
         }
         _ => Err(ContractError::MissingRedeemStableHook {}),
     }
@@ -225,6 +585,7 @@ pub fn register_contracts(
     collector_contract: Addr,
     liquidation_contract: Addr,
     oracle_contract: Addr,
+    interest_model_contract: Addr,
 ) -> Result<Response, ContractError> {
     let mut config: Config = read_config(deps.storage)?;
 
@@ -236,6 +597,7 @@ pub fn register_contracts(
         || config.collector_contract != Addr::unchecked("".to_string())
         || config.liquidation_contract != Addr::unchecked("".to_string())
         || config.oracle_contract != Addr::unchecked("".to_string())
+        || config.interest_model_contract != Addr::unchecked("".to_string())
     {
         return Err(ContractError::Unauthorized {});
     }
@@ -244,20 +606,87 @@ pub fn register_contracts(
     config.collector_contract = deps.api.addr_validate(collector_contract.as_str())?;
     config.liquidation_contract = deps.api.addr_validate(liquidation_contract.as_str())?;
     config.oracle_contract = deps.api.addr_validate(oracle_contract.as_str())?;
+    config.interest_model_contract = deps.api.addr_validate(interest_model_contract.as_str())?;
     store_config(deps.storage, &config)?;
 
     Ok(Response::default())
 }
 
+/// The fee curve needs increasing peg breakpoints and fees below 100%
+pub fn validate_borrow_fee_curve(curve: &BorrowFeeCurve) -> Result<(), ContractError> {
+    let increasing_pegs = curve
+        .breakpoints
+        .windows(2)
+        .all(|points| points[0].0 < points[1].0);
+
+    if curve.breakpoints.is_empty()
+        || !increasing_pegs
+        || curve.min_fee > curve.max_fee
+        || curve.max_fee >= Decimal256::one()
+    {
+        return Err(ContractError::InvalidBorrowFeeCurve {});
+    }
+
+    Ok(())
+}
+
+/// Term loans need a penalty rate for the overdue ones, both rates are yearly and below 100%
+pub fn validate_term_loan_rates(
+    term_loan_rate: Option<Decimal256>,
+    term_loan_penalty_rate: Option<Decimal256>,
+) -> Result<(), ContractError> {
+    let rate_too_high =
+        |rate: Option<Decimal256>| matches!(rate, Some(rate) if rate >= Decimal256::one());
+    if (term_loan_rate.is_some() && term_loan_penalty_rate.is_none())
+        || rate_too_high(term_loan_rate)
+        || rate_too_high(term_loan_penalty_rate)
+    {
+        return Err(ContractError::InvalidTermLoanRates {});
+    }
+
+    Ok(())
+}
+
+/// Discount tiers need increasing staked amounts and discounts up to 100%
+pub fn validate_borrow_fee_discount_tiers(
+    tiers: &[BorrowFeeDiscountTier],
+) -> Result<(), ContractError> {
+    let increasing_stakes = tiers
+        .windows(2)
+        .all(|tiers| tiers[0].min_staked < tiers[1].min_staked);
+
+    if !increasing_stakes || tiers.iter().any(|tier| tier.discount > Decimal256::one()) {
+        return Err(ContractError::InvalidBorrowFeeDiscountTiers {});
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn update_config(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     owner_addr: Option<Addr>,
     liquidation_contract: Option<Addr>,
-    base_borrow_fee: Option<Decimal256>,
-    fee_increase_factor: Option<Decimal256>,
+    borrow_fee_curve: Option<BorrowFeeCurve>,
+    peg_twap_window: Option<u64>,
+    peg_price_timeframe: Option<u64>,
     flash_mint_fee: Option<Decimal256>,
     oracle_addr: Option<Addr>,
+    interest_model_contract: Option<Addr>,
+    mint_ceiling: Option<Uint256>,
+    remove_mint_ceiling: bool,
+    astroport_router: Option<Addr>,
+    psm_contract: Option<Addr>,
+    redemption_fee_floor: Option<Decimal256>,
+    redemption_decay_factor: Option<Decimal256>,
+    flash_mint_max_amount: Option<Uint256>,
+    term_loan_rate: Option<Decimal256>,
+    term_loan_penalty_rate: Option<Decimal256>,
+    remove_term_loans: bool,
+    capa_gov_contract: Option<Addr>,
+    borrow_fee_discount_tiers: Option<Vec<BorrowFeeDiscountTier>>,
 ) -> Result<Response, ContractError> {
     let mut config: Config = read_config(deps.storage)?;
 
@@ -266,6 +695,13 @@ pub fn update_config(
         return Err(ContractError::Unauthorized {});
     }
 
+    // accrue interest with the current interest model before any change
+    if oracle_addr.is_some() || interest_model_contract.is_some() {
+        let mut state: State = read_state(deps.storage)?;
+        compute_interest(deps.as_ref(), &config, &mut state, env.block.time.seconds())?;
+        store_state(deps.storage, &state)?;
+    }
+
     if let Some(owner_addr) = owner_addr {
         config.owner_addr = deps.api.addr_validate(owner_addr.as_str())?;
     }
@@ -274,41 +710,220 @@ pub fn update_config(
         config.liquidation_contract = deps.api.addr_validate(liquidation_contract.as_str())?;
     }
 
-    if let Some(base_borrow_fee) = base_borrow_fee {
-        if base_borrow_fee < Decimal256::one() {
-            config.base_borrow_fee = base_borrow_fee
+    if let Some(borrow_fee_curve) = borrow_fee_curve {
+        validate_borrow_fee_curve(&borrow_fee_curve)?;
+        config.borrow_fee_curve = borrow_fee_curve;
+    }
+
+    if let Some(peg_twap_window) = peg_twap_window {
+        if peg_twap_window == 0 {
+            return Err(ContractError::InvalidPegTwapWindow {});
         }
+        config.peg_twap_window = peg_twap_window;
     }
 
-    if let Some(fee_increase_factor) = fee_increase_factor {
-        config.fee_increase_factor = fee_increase_factor
+    if let Some(peg_price_timeframe) = peg_price_timeframe {
+        config.peg_price_timeframe = peg_price_timeframe;
     }
 
     if let Some(oracle_addr) = oracle_addr {
         config.oracle_contract = deps.api.addr_validate(&oracle_addr.as_str())?;
     }
 
+    if let Some(interest_model_contract) = interest_model_contract {
+        config.interest_model_contract =
+            deps.api.addr_validate(interest_model_contract.as_str())?;
+    }
+
     if let Some(flash_mint_fee) = flash_mint_fee {
         if flash_mint_fee < Decimal256::one() {
             config.flash_mint_fee = Some(flash_mint_fee)
         }
     }
 
+    if let Some(flash_mint_max_amount) = flash_mint_max_amount {
+        config.flash_mint_max_amount = Some(flash_mint_max_amount);
+    }
+
+    if let Some(mint_ceiling) = mint_ceiling {
+        config.mint_ceiling = Some(mint_ceiling);
+    } else if remove_mint_ceiling {
+        config.mint_ceiling = None;
+    }
+
+    if let Some(astroport_router) = astroport_router {
+        config.astroport_router = Some(astroport_router);
+    }
+
+    if let Some(psm_contract) = psm_contract {
+        config.psm_contract = Some(psm_contract);
+    }
+
+    if let Some(redemption_fee_floor) = redemption_fee_floor {
+        if redemption_fee_floor >= Decimal256::one() {
+            return Err(ContractError::InvalidRedemptionFeeFloor {});
+        }
+        config.redemption_fee_floor = Some(redemption_fee_floor);
+    }
+
+    if let Some(redemption_decay_factor) = redemption_decay_factor {
+        if redemption_decay_factor >= Decimal256::one() {
+            return Err(ContractError::InvalidRedemptionDecayFactor {});
+        }
+        config.redemption_decay_factor = Some(redemption_decay_factor);
+    }
+
+    if term_loan_rate.is_some() || term_loan_penalty_rate.is_some() {
+        let term_loan_rate = term_loan_rate.or(config.term_loan_rate);
+        let term_loan_penalty_rate = term_loan_penalty_rate.or(config.term_loan_penalty_rate);
+        validate_term_loan_rates(term_loan_rate, term_loan_penalty_rate)?;
+        config.term_loan_rate = term_loan_rate;
+        config.term_loan_penalty_rate = term_loan_penalty_rate;
+    } else if remove_term_loans {
+        config.term_loan_rate = None;
+        config.term_loan_penalty_rate = None;
+    }
+
+    if let Some(capa_gov_contract) = capa_gov_contract {
+        config.capa_gov_contract = Some(capa_gov_contract);
+    }
+
+    if let Some(borrow_fee_discount_tiers) = borrow_fee_discount_tiers {
+        validate_borrow_fee_discount_tiers(&borrow_fee_discount_tiers)?;
+        config.borrow_fee_discount_tiers = borrow_fee_discount_tiers;
+    }
+
     store_config(deps.storage, &config)?;
     Ok(Response::new().add_attributes(vec![attr("action", "update_config")]))
 }
 
+pub fn update_fee_recipients(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipients: Vec<FeeRecipient>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+
+    // permission check
+    if info.sender != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let res =
+        fee_distribution::update_fee_recipients(deps.api, &mut config.fee_recipients, recipients)?;
+    store_config(deps.storage, &config)?;
+
+    Ok(res)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::State {} => to_binary(&query_state(deps)?),
-        QueryMsg::BorrowerInfo { borrower } => to_binary(&query_borrower_info(
+        QueryMsg::BorrowerInfo {
+            borrower,
+            position_id,
+        } => to_binary(&query_borrower_info(
+            deps,
+            env,
+            deps.api.addr_validate(&borrower)?,
+            position_id.unwrap_or(DEFAULT_POSITION_ID),
+        )?),
+        QueryMsg::BorrowerInfos {
+            start_after,
+            start_after_position_id,
+            limit,
+        } => to_binary(&query_borrower_infos(
+            deps,
+            env,
+            optional_addr_validate(deps.api, start_after)?,
+            start_after_position_id.unwrap_or(DEFAULT_POSITION_ID),
+            limit,
+        )?),
+        QueryMsg::SimulateBorrow {
+            borrower,
+            amount,
+            position_id,
+        } => to_binary(&query_simulate_borrow(
+            deps,
+            env,
+            deps.api.addr_validate(&borrower)?,
+            position_id.unwrap_or(DEFAULT_POSITION_ID),
+            amount,
+        )?),
+        QueryMsg::SimulateRepay {
+            borrower,
+            amount,
+            position_id,
+        } => to_binary(&query_simulate_repay(
+            deps,
+            env,
+            deps.api.addr_validate(&borrower)?,
+            position_id.unwrap_or(DEFAULT_POSITION_ID),
+            amount,
+        )?),
+        QueryMsg::TermLoan {
+            borrower,
+            loan_id,
+            position_id,
+        } => to_binary(&query_term_loan(
+            deps,
+            env,
+            deps.api.addr_validate(&borrower)?,
+            position_id.unwrap_or(DEFAULT_POSITION_ID),
+            loan_id,
+        )?),
+        QueryMsg::TermLoans {
+            borrower,
+            start_after,
+            limit,
+            position_id,
+        } => to_binary(&query_term_loans(
+            deps,
+            env,
+            deps.api.addr_validate(&borrower)?,
+            position_id.unwrap_or(DEFAULT_POSITION_ID),
+            start_after,
+            limit,
+        )?),
+        QueryMsg::TermLoanQuote {
+            borrower,
+            amount,
+            maturity,
+        } => to_binary(&query_term_loan_quote(
             deps,
+            env,
             deps.api.addr_validate(&borrower)?,
+            amount,
+            maturity,
+        )?),
+        QueryMsg::BorrowFeeDiscountTiers {} => to_binary(&query_borrow_fee_discount_tiers(deps)?),
+        QueryMsg::BorrowFeeDiscount { borrower } => to_binary(&query_borrow_fee_discount(
+            deps,
+            deps.api.addr_validate(&borrower)?,
+        )?),
+        QueryMsg::FlashMintFee { amount } => to_binary(&query_flash_mint_fee(deps, amount)?),
+        QueryMsg::FeeDistribution {} => to_binary(&query_fee_distribution(deps)?),
+        QueryMsg::BorrowAllowance {
+            owner,
+            spender,
+            position_id,
+        } => to_binary(&query_borrow_allowance(
+            deps,
+            deps.api.addr_validate(&owner)?,
+            deps.api.addr_validate(&spender)?,
+            position_id.unwrap_or(DEFAULT_POSITION_ID),
         )?),
-        QueryMsg::BorrowerInfos { start_after, limit } => to_binary(&query_borrower_infos(
+        QueryMsg::BorrowAllowances {
+            owner,
+            position_id,
+            start_after,
+            limit,
+        } => to_binary(&query_borrow_allowances(
             deps,
+            deps.api.addr_validate(&owner)?,
+            position_id.unwrap_or(DEFAULT_POSITION_ID),
             optional_addr_validate(deps.api, start_after)?,
             limit,
         )?),
@@ -324,7 +939,26 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         collector_contract: config.collector_contract.to_string(),
         liquidation_contract: config.liquidation_contract.to_string(),
         oracle_contract: config.oracle_contract.to_string(),
+        interest_model_contract: config.interest_model_contract.to_string(),
         flash_mint_fee: config.flash_mint_fee,
+        flash_mint_max_amount: config.flash_mint_max_amount,
+        flash_minters: config.flash_minters.map(|flash_minters| {
+            flash_minters
+                .iter()
+                .map(|flash_minter| flash_minter.to_string())
+                .collect()
+        }),
+        mint_ceiling: config.mint_ceiling,
+        astroport_router: config.astroport_router.map(|router| router.to_string()),
+        psm_contract: config.psm_contract.map(|psm| psm.to_string()),
+        redemption_fee_floor: config.redemption_fee_floor,
+        redemption_decay_factor: config.redemption_decay_factor,
+        term_loan_rate: config.term_loan_rate,
+        term_loan_penalty_rate: config.term_loan_penalty_rate,
+        capa_gov_contract: config.capa_gov_contract.map(|gov| gov.to_string()),
+        borrow_fee_curve: config.borrow_fee_curve,
+        peg_twap_window: config.peg_twap_window,
+        peg_price_timeframe: config.peg_price_timeframe,
     })
 }
 
@@ -333,10 +967,58 @@ pub fn query_state(deps: Deps) -> StdResult<StateResponse> {
 
     Ok(StateResponse {
         total_liabilities: state.total_liabilities,
+        global_interest_index: state.global_interest_index,
+        last_interest_updated: state.last_interest_updated,
+        peg_twap: state.peg_twap,
+        redemption_base_rate: state.redemption_base_rate,
+        last_redemption_time: state.last_redemption_time,
     })
 }
 
+pub fn query_fee_distribution(deps: Deps) -> StdResult<FeeDistributionResponse> {
+    let config: Config = read_config(deps.storage)?;
+    fee_distribution::query_fee_distribution(deps.storage, read_fees_paid, config.fee_recipients)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // start accruing interest from the migration time on states stored without a borrow index
+    let mut state: State = read_state(deps.storage)?;
+    if state.last_interest_updated == 0 {
+        state.last_interest_updated = env.block.time.seconds();
+        store_state(deps.storage, &state)?;
+    }
+
+    // convert the former linear borrow fee to the equivalent fee curve
+    let mut config: Config = read_config(deps.storage)?;
+    if config.borrow_fee_curve.breakpoints.is_empty() {
+        let legacy: LegacyFeeConfig = read_legacy_fee_config(deps.storage)?;
+        if let (Some(base_borrow_fee), Some(fee_increase_factor)) =
+            (legacy.base_borrow_fee, legacy.fee_increase_factor)
+        {
+            if fee_increase_factor.is_zero() {
+                return Err(ContractError::InvalidBorrowFeeCurve {});
+            }
+            let max_fee = base_borrow_fee + Decimal256::one() / fee_increase_factor;
+            let borrow_fee_curve = BorrowFeeCurve {
+                breakpoints: vec![
+                    (Decimal256::zero(), max_fee),
+                    (Decimal256::one(), base_borrow_fee),
+                ],
+                min_fee: base_borrow_fee,
+                max_fee,
+            };
+            validate_borrow_fee_curve(&borrow_fee_curve)?;
+            config.borrow_fee_curve = borrow_fee_curve;
+        }
+        if config.peg_twap_window == 0 {
+            config.peg_twap_window = DEFAULT_PEG_TWAP_WINDOW;
+        }
+    }
+    if config.peg_price_timeframe == 0 {
+        config.peg_price_timeframe = DEFAULT_PEG_PRICE_TIMEFRAME;
+    }
+    store_config(deps.storage, &config)?;
+
     Ok(Response::default())
 }
@@ -15,6 +15,9 @@ pub enum ContractError {
     #[error("Borrow amount too high; Loan liability becomes greater than borrow limit: {0}")]
     BorrowExceedsLimit(u128),
 
+    #[error("Borrow amount too high; Total liabilities become greater than mint ceiling: {0}")]
+    MintCeilingExceeded(u128),
+
     #[error("Must deposit initial funds {0}{0}")]
     InitialFundsNotDeposited(u128, String),
 
@@ -35,4 +38,73 @@ pub enum ContractError {
 
     #[error("Repay amount must be greater than 0 {0}")]
     ZeroRepay(String),
+
+    #[error("Collateral amount must be greater than 0")]
+    ZeroCollateral {},
+
+    #[error("Astroport router is not configured")]
+    RouterNotConfigured {},
+
+    #[error("Borrow amount exceeds the borrow allowance: {0}")]
+    BorrowAllowanceExceeded(u128),
+
+    #[error("Borrow allowance expired")]
+    BorrowAllowanceExpired {},
+
+    #[error("Invalid borrow fee curve")]
+    InvalidBorrowFeeCurve {},
+
+    #[error("Peg twap window must be greater than 0")]
+    InvalidPegTwapWindow {},
+
+    #[error("Invalid borrow fee discount tiers")]
+    InvalidBorrowFeeDiscountTiers {},
+
+    #[error("Target LTV exceeds the collateral max LTV: {0}")]
+    TargetLtvExceedsMaxLtv(String),
+
+    #[error("Redemption fee exceeds the max fee: {0}")]
+    RedemptionFeeExceedsMax(String),
+
+    #[error("Redemption fee floor must be lower than 1")]
+    InvalidRedemptionFeeFloor {},
+
+    #[error("Redemption decay factor must be lower than 1")]
+    InvalidRedemptionDecayFactor {},
+
+    #[error("No position can be redeemed")]
+    NoRedeemablePosition {},
+
+    #[error("Flash mint amount too high; max flash mint amount: {0}")]
+    FlashMintAmountExceeded(u128),
+
+    #[error("Flash minter is not whitelisted")]
+    FlashMinterNotWhitelisted {},
+
+    #[error("A flash mint is already in progress")]
+    FlashMintInProgress {},
+
+    #[error("Swap output does not cover the flash mint and its fee")]
+    InsufficientSwapOutput {},
+
+    #[error("Term loans are not enabled")]
+    TermLoansDisabled {},
+
+    #[error("Invalid term loan rates")]
+    InvalidTermLoanRates {},
+
+    #[error("Maturity must be later than the current block time")]
+    InvalidMaturity {},
+
+    #[error("Term loan fee exceeds the max fee: {0}")]
+    TermLoanFeeExceedsMax(u128),
+
+    #[error("Term loan not found: {0}")]
+    TermLoanNotFound(u64),
+
+    #[error("Recipient already has a position")]
+    PositionAlreadyExists {},
+
+    #[error("Settle amount exceeds the principal of the position: {0}")]
+    SettleAmountExceedsPrincipal(u128),
 }
@@ -1,27 +1,46 @@
 use cosmwasm_bignumber::math::{Decimal256, Uint256};
 use cosmwasm_std::{
-    attr, to_binary, Binary, CosmosMsg, DepsMut, Env, MessageInfo, Response, WasmMsg,
+    attr, to_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    Storage, WasmMsg,
 };
 use cw20::Cw20ExecuteMsg;
-use moneymarket::market::ExecuteMsg;
+use moneymarket::fee_distribution::{distribute_fee, fee_recipients};
+use moneymarket::market::{ExecuteMsg, FlashMintCallbackMsg, FlashMintFeeResponse};
 
-use crate::{error::ContractError, state::read_config};
+use crate::error::ContractError;
+use crate::state::{
+    read_config, read_fees_paid, read_flash_mint_lock, store_config, store_fees_paid,
+    store_flash_mint_lock, Config,
+};
+
+pub const DEFAULT_FLASH_MINT_FEE: Decimal256 = Decimal256::zero();
 
-const DEFAULT_FLASH_MINT_FEE: Decimal256 = Decimal256::zero();
+pub fn compute_flash_mint_fee(config: &Config, amount: Uint256) -> Uint256 {
+    config.flash_mint_fee.unwrap_or(DEFAULT_FLASH_MINT_FEE) * amount
+}
 
 pub fn flash_mint(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    msg_callback: Binary,
+    receiver: Option<Addr>,
     amount: Uint256,
+    data: Binary,
 ) -> Result<Response, ContractError> {
     // Load config
     let config = read_config(deps.storage)?;
 
+    if let Some(flash_minters) = config.flash_minters.as_ref() {
+        if !flash_minters.contains(&info.sender) {
+            return Err(ContractError::FlashMinterNotWhitelisted {});
+        }
+    }
+
+    lock_flash_mint(deps.storage, &config, amount)?;
+
     // Compute fee amount
-    let flash_mint_fee = config.flash_mint_fee.unwrap_or(DEFAULT_FLASH_MINT_FEE);
-    let fee_amount = flash_mint_fee * amount;
+    let fee_amount = compute_flash_mint_fee(&config, amount);
+    let receiver = receiver.unwrap_or_else(|| info.sender.clone());
 
     let messages: Vec<CosmosMsg> = vec![
         // Mint
@@ -29,22 +48,27 @@ pub fn flash_mint(
             contract_addr: config.stable_contract.to_string(),
             funds: vec![],
             msg: to_binary(&Cw20ExecuteMsg::Mint {
-                recipient: info.sender.to_string(),
+                recipient: receiver.to_string(),
                 amount: amount.into(),
             })?,
         }),
         //Callback
         CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: info.sender.to_string(),
+            contract_addr: receiver.to_string(),
             funds: vec![],
-            msg: msg_callback,
+            msg: to_binary(&FlashMintCallbackMsg::FlashMintCallback {
+                initiator: info.sender.to_string(),
+                amount,
+                fee: fee_amount,
+                data,
+            })?,
         }),
         // Private flash end
         CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: env.contract.address.to_string(),
             funds: vec![],
             msg: to_binary(&ExecuteMsg::PrivateFlashEnd {
-                flash_minter: info.sender.to_string(),
+                flash_minter: receiver.to_string(),
                 burn_amount: amount,
                 fee_amount,
             })?,
@@ -53,12 +77,38 @@ pub fn flash_mint(
 
     Ok(Response::new().add_messages(messages).add_attributes(vec![
         attr("action", "flash_mint"),
-        attr("flash_minter", info.sender),
+        attr("initiator", info.sender),
+        attr("flash_minter", receiver),
         attr("amount", amount),
         attr("fee_amount", fee_amount),
     ]))
 }
 
+/// Checks the flash mint max amount and takes the flash mint lock, released when the
+/// flash mint is settled. The market own flash mints of multiply and repay with collateral
+/// go through it as well, they skip the allowlist only as the market is their initiator
+pub fn lock_flash_mint(
+    storage: &mut dyn Storage,
+    config: &Config,
+    amount: Uint256,
+) -> Result<(), ContractError> {
+    if let Some(flash_mint_max_amount) = config.flash_mint_max_amount {
+        if amount > flash_mint_max_amount {
+            return Err(ContractError::FlashMintAmountExceeded(
+                flash_mint_max_amount.into(),
+            ));
+        }
+    }
+
+    // Reject nested flash mints until the flash mint end releases the lock
+    if read_flash_mint_lock(storage)? {
+        return Err(ContractError::FlashMintInProgress {});
+    }
+    store_flash_mint_lock(storage, true)?;
+
+    Ok(())
+}
+
 pub fn private_flash_end(
     deps: DepsMut,
     env: Env,
@@ -73,6 +123,7 @@ pub fn private_flash_end(
     }
 
     let config = read_config(deps.storage)?;
+    store_flash_mint_lock(deps.storage, false)?;
 
     let mut messages: Vec<CosmosMsg> = vec![];
 
@@ -86,20 +137,80 @@ pub fn private_flash_end(
         })?,
     }));
 
-    // Insert msg fee transfer to collector only if fee_amount > 0
-    if fee_amount > Uint256::zero() {
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: config.stable_contract.to_string(),
-            funds: vec![],
-            msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
-                owner: flash_minter,
-                recipient: config.collector_contract.to_string(),
-                amount: fee_amount.into(),
-            })?,
-        }));
-    }
+    // Insert msgs pulling the fee to the fee recipients
+    messages.extend(distribute_fee(
+        deps.storage,
+        read_fees_paid,
+        store_fees_paid,
+        config.stable_contract.to_string(),
+        &fee_recipients(
+            &config.fee_recipients,
+            config.collector_contract.to_string(),
+        ),
+        fee_amount,
+        Some(flash_minter),
+    )?);
 
     Ok(Response::new()
         .add_messages(messages)
         .add_attribute("action", "private_flash_end"))
 }
+
+pub fn update_flash_minters(
+    deps: DepsMut,
+    info: MessageInfo,
+    flash_minters: Option<Vec<Addr>>,
+) -> Result<Response, ContractError> {
+    let mut config = read_config(deps.storage)?;
+
+    // permission check
+    if info.sender != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.flash_minters = flash_minters;
+    store_config(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "update_flash_minters")]))
+}
+
+/// Mint SOLID requested by the peg stability module, it is backed
+/// by the stables the module holds so no debt is recorded
+pub fn mint_stable(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipient: Addr,
+    amount: Uint256,
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+
+    if config.psm_contract != Some(info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.stable_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: recipient.to_string(),
+                amount: amount.into(),
+            })?,
+        }))
+        .add_attributes(vec![
+            attr("action", "mint_stable"),
+            attr("recipient", recipient),
+            attr("amount", amount),
+        ]))
+}
+
+pub fn query_flash_mint_fee(deps: Deps, amount: Uint256) -> StdResult<FlashMintFeeResponse> {
+    let config = read_config(deps.storage)?;
+    let fee_amount = compute_flash_mint_fee(&config, amount);
+
+    Ok(FlashMintFeeResponse {
+        amount,
+        fee_amount,
+        total_amount: amount + fee_amount,
+    })
+}
@@ -1,10 +1,15 @@
+pub mod allowance;
 pub mod borrow;
+pub mod collateral_swap;
 pub mod contract;
 pub mod error;
 pub mod flash_mint;
+pub mod multiply;
 pub mod querier;
+pub mod redemption;
 pub mod response;
 pub mod state;
+pub mod term_loan;
 
 #[cfg(test)]
 mod testing;
@@ -0,0 +1,284 @@
+use astroport::asset::AssetInfo;
+use astroport::router::{Cw20HookMsg as RouterCw20HookMsg, SwapOperation};
+use cosmwasm_bignumber::math::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, to_binary, Addr, CosmosMsg, DepsMut, Env, MessageInfo, Response, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use moneymarket::custody::Cw20HookMsg as CustodyCw20HookMsg;
+use moneymarket::fee_distribution::{distribute_fee, fee_recipients};
+use moneymarket::market::ExecuteMsg;
+use moneymarket::oracle::PriceResponse;
+use moneymarket::overseer::{ExecuteMsg as OverseerExecuteMsg, WhitelistResponseElem};
+use moneymarket::position::{optional_position_id, PositionId};
+use moneymarket::querier::{query_price, query_token_balance, TimeConstraints};
+
+use crate::borrow::{borrow_stable, compute_borrow_fee_rate};
+use crate::error::ContractError;
+use crate::flash_mint::{compute_flash_mint_fee, lock_flash_mint, DEFAULT_FLASH_MINT_FEE};
+use crate::querier::{query_price_timeframe, query_whitelist_elem};
+use crate::state::{
+    read_config, read_fees_paid, read_state, store_fees_paid, store_flash_mint_lock, Config, State,
+};
+
+/// Opens a leveraged position with the `collateral_amount` of `collateral_token` sent by the borrower.
+/// The market flash mints SOLID, buys collateral with it through the astroport router,
+/// deposits and locks the whole collateral for the borrower and borrows the flash minted
+/// SOLID plus its fee to settle the flash mint, all within one transaction.
+#[allow(clippy::too_many_arguments)]
+pub fn multiply(
+    deps: DepsMut,
+    env: Env,
+    borrower: Addr,
+    collateral_token: Addr,
+    collateral_amount: Uint256,
+    target_ltv: Decimal256,
+    min_collateral_out: Uint256,
+    position_id: PositionId,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let astroport_router = config
+        .astroport_router
+        .clone()
+        .ok_or(ContractError::RouterNotConfigured {})?;
+
+    if collateral_amount.is_zero() {
+        return Err(ContractError::ZeroCollateral {});
+    }
+
+    // Only whitelisted collaterals can be multiplied, up to their max ltv
+    let whitelist_elem: WhitelistResponseElem = query_whitelist_elem(
+        deps.as_ref(),
+        config.overseer_contract.clone(),
+        collateral_token.clone(),
+    )?;
+    if target_ltv > whitelist_elem.max_ltv {
+        return Err(ContractError::TargetLtvExceedsMaxLtv(
+            whitelist_elem.max_ltv.to_string(),
+        ));
+    }
+
+    let price: PriceResponse = query_price(
+        deps.as_ref(),
+        config.oracle_contract.clone(),
+        collateral_token.to_string(),
+        "uusd".to_string(),
+        Some(TimeConstraints {
+            block_time: env.block.time.seconds(),
+            valid_timeframe: query_price_timeframe(
+                deps.as_ref(),
+                config.overseer_contract.clone(),
+            )?,
+        }),
+    )?;
+
+    // The loan is the flash minted amount plus its fee, grown by the one time borrow fee,
+    // the position value is the sent collateral plus the bought one,
+    // so flash mint enough to reach the target ltv
+    let mut state: State = read_state(deps.storage)?;
+    let borrow_fee_rate =
+        compute_borrow_fee_rate(deps.as_ref(), &env, &config, &mut state, &borrower)?;
+    let flash_mint_fee = config.flash_mint_fee.unwrap_or(DEFAULT_FLASH_MINT_FEE);
+    let flash_amount = collateral_amount * price.rate * target_ltv
+        / ((Decimal256::one() + flash_mint_fee) * (Decimal256::one() + borrow_fee_rate)
+            - target_ltv);
+    let fee_amount = compute_flash_mint_fee(&config, flash_amount);
+    lock_flash_mint(deps.storage, &config, flash_amount)?;
+
+    // Remember the market balance to find out the swap proceeds,
+    // the collateral sent by the borrower is already in it
+    let prev_balance = query_token_balance(
+        deps.as_ref(),
+        collateral_token.clone(),
+        env.contract.address.clone(),
+    )?;
+
+    let messages: Vec<CosmosMsg> = vec![
+        // Flash mint the SOLID used to buy collateral
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.stable_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: env.contract.address.to_string(),
+                amount: flash_amount.into(),
+            })?,
+        }),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.stable_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: astroport_router.to_string(),
+                amount: flash_amount.into(),
+                msg: to_binary(&RouterCw20HookMsg::ExecuteSwapOperations {
+                    operations: vec![SwapOperation::AstroSwap {
+                        offer_asset_info: AssetInfo::Token {
+                            contract_addr: config.stable_contract.clone(),
+                        },
+                        ask_asset_info: AssetInfo::Token {
+                            contract_addr: collateral_token.clone(),
+                        },
+                    }],
+                    minimum_receive: Some(min_collateral_out.into()),
+                    to: None,
+                    max_spread: None,
+                })?,
+            })?,
+        }),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_binary(&ExecuteMsg::PrivateMultiplyLock {
+                borrower: borrower.to_string(),
+                collateral_token: collateral_token.to_string(),
+                collateral_amount,
+                prev_balance,
+                position_id: optional_position_id(position_id),
+            })?,
+        }),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_binary(&ExecuteMsg::PrivateMultiplyEnd {
+                borrower: borrower.to_string(),
+                flash_amount,
+                fee_amount,
+                position_id: optional_position_id(position_id),
+            })?,
+        }),
+    ];
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "multiply"),
+        attr("borrower", borrower),
+        attr("position_id", position_id.to_string()),
+        attr("collateral_token", collateral_token),
+        attr("collateral_amount", collateral_amount),
+        attr("flash_amount", flash_amount),
+        attr("fee_amount", fee_amount),
+    ]))
+}
+
+/// Deposits the bought collateral together with the one sent by the borrower
+/// into the custody on behalf of the borrower and locks it
+#[allow(clippy::too_many_arguments)]
+pub fn private_multiply_lock(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrower: Addr,
+    collateral_token: Addr,
+    collateral_amount: Uint256,
+    prev_balance: Uint256,
+    position_id: PositionId,
+) -> Result<Response, ContractError> {
+    // The sender must be the contract itself
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let config: Config = read_config(deps.storage)?;
+    let whitelist_elem: WhitelistResponseElem = query_whitelist_elem(
+        deps.as_ref(),
+        config.overseer_contract.clone(),
+        collateral_token.clone(),
+    )?;
+
+    let balance = query_token_balance(
+        deps.as_ref(),
+        collateral_token.clone(),
+        env.contract.address,
+    )?;
+    let deposit_amount = balance - prev_balance + collateral_amount;
+
+    let messages: Vec<CosmosMsg> = vec![
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: collateral_token.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: whitelist_elem.custody_contract,
+                amount: deposit_amount.into(),
+                msg: to_binary(&CustodyCw20HookMsg::DepositCollateral {
+                    borrower: Some(borrower.to_string()),
+                })?,
+            })?,
+        }),
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.overseer_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&OverseerExecuteMsg::LockCollateralFor {
+                borrower: borrower.to_string(),
+                collaterals: vec![(collateral_token.to_string(), deposit_amount)],
+                position_id: optional_position_id(position_id),
+            })?,
+        }),
+    ];
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "private_multiply_lock"),
+        attr("borrower", borrower),
+        attr("deposit_amount", deposit_amount),
+    ]))
+}
+
+/// Settles the flash mint of a multiply: borrows the flash minted amount plus its fee
+/// for the borrower, burns the flash minted amount and distributes the fee.
+/// The borrow limit check rejects positions above the collateral max ltv
+pub fn private_multiply_end(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrower: Addr,
+    flash_amount: Uint256,
+    fee_amount: Uint256,
+    position_id: PositionId,
+) -> Result<Response, ContractError> {
+    // The sender must be the contract itself
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let config: Config = read_config(deps.storage)?;
+    let market = env.contract.address.clone();
+    store_flash_mint_lock(deps.storage, false)?;
+
+    let borrow_res = borrow_stable(
+        deps.branch(),
+        env,
+        borrower.clone(),
+        position_id,
+        flash_amount + fee_amount,
+        Some(market),
+    )?;
+    let mut messages: Vec<CosmosMsg> = borrow_res
+        .messages
+        .into_iter()
+        .map(|sub_msg| sub_msg.msg)
+        .collect();
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.stable_contract.to_string(),
+        funds: vec![],
+        msg: to_binary(&Cw20ExecuteMsg::Burn {
+            amount: flash_amount.into(),
+        })?,
+    }));
+
+    messages.extend(distribute_fee(
+        deps.storage,
+        read_fees_paid,
+        store_fees_paid,
+        config.stable_contract.to_string(),
+        &fee_recipients(
+            &config.fee_recipients,
+            config.collector_contract.to_string(),
+        ),
+        fee_amount,
+        None,
+    )?);
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "private_multiply_end"),
+        attr("borrower", borrower),
+        attr("borrow_amount", flash_amount + fee_amount),
+    ]))
+}
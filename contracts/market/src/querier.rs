@@ -1,8 +1,14 @@
 use cosmwasm_bignumber::math::Decimal256;
-use cosmwasm_std::{to_binary, Addr, Deps, QueryRequest, StdResult, WasmQuery};
+use cosmwasm_std::{to_binary, Addr, Deps, QueryRequest, StdError, StdResult, WasmQuery};
 
+use capa_token::gov::{QueryMsg as GovQueryMsg, StakerResponse};
 use moneymarket::interest_model::{BorrowRateResponse, QueryMsg as InterestQueryMsg};
-use moneymarket::overseer::{BorrowLimitResponse, QueryMsg as OverseerQueryMsg};
+use moneymarket::overseer::{
+    BorrowLimitResponse, BorrowersByHealthResponse, CollateralsResponse,
+    ConfigResponse as OverseerConfigResponse, QueryMsg as OverseerQueryMsg, WhitelistResponse,
+    WhitelistResponseElem,
+};
+use moneymarket::position::{optional_position_id, PositionId};
 
 pub fn query_borrow_rate(
     deps: Deps,
@@ -18,10 +24,22 @@ pub fn query_borrow_rate(
     Ok(borrow_rate)
 }
 
+pub fn query_gov_staker(deps: Deps, gov_addr: Addr, staker: Addr) -> StdResult<StakerResponse> {
+    let staker: StakerResponse = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: gov_addr.to_string(),
+        msg: to_binary(&GovQueryMsg::Staker {
+            address: staker.to_string(),
+        })?,
+    }))?;
+
+    Ok(staker)
+}
+
 pub fn query_borrow_limit(
     deps: Deps,
     overseer_addr: Addr,
     borrower: Addr,
+    position_id: PositionId,
     block_time: Option<u64>,
 ) -> StdResult<BorrowLimitResponse> {
     let borrow_limit: BorrowLimitResponse =
@@ -30,8 +48,83 @@ pub fn query_borrow_limit(
             msg: to_binary(&OverseerQueryMsg::BorrowLimit {
                 borrower: borrower.to_string(),
                 block_time,
+                position_id: optional_position_id(position_id),
             })?,
         }))?;
 
     Ok(borrow_limit)
 }
+
+/// Max age of the collateral prices accepted by the overseer
+pub fn query_price_timeframe(deps: Deps, overseer_addr: Addr) -> StdResult<u64> {
+    let overseer_config: OverseerConfigResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: overseer_addr.to_string(),
+            msg: to_binary(&OverseerQueryMsg::Config {})?,
+        }))?;
+
+    Ok(overseer_config.price_timeframe)
+}
+
+pub fn query_whitelist_elem(
+    deps: Deps,
+    overseer_addr: Addr,
+    collateral_token: Addr,
+) -> StdResult<WhitelistResponseElem> {
+    let whitelist: WhitelistResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: overseer_addr.to_string(),
+            msg: to_binary(&OverseerQueryMsg::Whitelist {
+                collateral_token: Some(collateral_token.to_string()),
+                start_after: None,
+                limit: None,
+            })?,
+        }))?;
+
+    whitelist
+        .elems
+        .into_iter()
+        .next()
+        .ok_or_else(|| StdError::generic_err("Collateral is not whitelisted"))
+}
+
+pub fn query_collaterals(
+    deps: Deps,
+    overseer_addr: Addr,
+    borrower: Addr,
+    position_id: PositionId,
+) -> StdResult<CollateralsResponse> {
+    let collaterals: CollateralsResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: overseer_addr.to_string(),
+            msg: to_binary(&OverseerQueryMsg::Collaterals {
+                borrower: borrower.to_string(),
+                position_id: optional_position_id(position_id),
+            })?,
+        }))?;
+
+    Ok(collaterals)
+}
+
+pub fn query_borrowers_by_health(
+    deps: Deps,
+    overseer_addr: Addr,
+    start_after: Option<(String, PositionId)>,
+    limit: Option<u32>,
+) -> StdResult<BorrowersByHealthResponse> {
+    let (start_after, start_after_position_id) = match start_after {
+        Some((borrower, position_id)) => (Some(borrower), Some(position_id)),
+        None => (None, None),
+    };
+    let borrowers: BorrowersByHealthResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: overseer_addr.to_string(),
+            msg: to_binary(&OverseerQueryMsg::BorrowersByHealth {
+                start_after,
+                start_after_position_id,
+                limit,
+            })?,
+        }))?;
+
+    Ok(borrowers)
+}
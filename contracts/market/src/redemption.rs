@@ -0,0 +1,383 @@
+use cosmwasm_bignumber::math::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, to_binary, Addr, CosmosMsg, Deps, DepsMut, Env, Response, StdResult, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use moneymarket::fee_distribution::{distribute_fee, fee_recipients};
+use moneymarket::oracle::PriceResponse;
+use moneymarket::overseer::{
+    BorrowLimitResponse, BorrowersByHealthResponse, CollateralsResponse,
+    ExecuteMsg as OverseerExecuteMsg,
+};
+use moneymarket::position::{optional_position_id, PositionId};
+use moneymarket::querier::{query_price, TimeConstraints};
+
+use crate::borrow::{compute_interest, compute_loan, compute_repay};
+use crate::error::ContractError;
+use crate::querier::{
+    query_borrow_limit, query_borrowers_by_health, query_collaterals, query_price_timeframe,
+};
+use crate::state::{
+    read_borrower_info, read_config, read_fees_paid, read_state, store_borrower_info,
+    store_fees_paid, store_state, BorrowerInfo, Config, State,
+};
+use crate::term_loan::{compute_term_loans_amount, repay_term_loans};
+
+/// Fee charged on redemptions when the base rate is fully decayed
+pub fn default_redemption_fee_floor() -> Decimal256 {
+    Decimal256::permille(5)
+}
+
+/// Base rate decay per minute, it halves the base rate every 12 hours
+pub fn default_redemption_decay_factor() -> Decimal256 {
+    Decimal256::from_ratio(999037758833783u64, 1000000000000000u64)
+}
+
+/// Decay stops after a year, the base rate is negligible by then
+const MAX_DECAY_MINUTES: u64 = 525600;
+
+/// Share of the supply redeemed that is added to the base rate
+const REDEMPTION_BETA: u64 = 2;
+
+/// Positions of the overseer health index queried per page
+const REDEMPTION_PAGE_LIMIT: u32 = 30;
+
+/// Base rate decayed by the minutes passed since the last redemption
+pub fn compute_decayed_base_rate(config: &Config, state: &State, block_time: u64) -> Decimal256 {
+    let minutes = std::cmp::min(
+        block_time.saturating_sub(state.last_redemption_time) / 60,
+        MAX_DECAY_MINUTES,
+    );
+    let decay_factor = config
+        .redemption_decay_factor
+        .unwrap_or_else(default_redemption_decay_factor);
+
+    state.redemption_base_rate * decimal_pow(decay_factor, minutes)
+}
+
+fn decimal_pow(base: Decimal256, mut exp: u64) -> Decimal256 {
+    let mut base = base;
+    let mut result = Decimal256::one();
+    while exp > 0 {
+        if exp % 2 == 1 {
+            result = result * base;
+        }
+        base = base * base;
+        exp /= 2;
+    }
+
+    result
+}
+
+/// Base rate bumped by the share of the supply redeemed with `redeemed_amount`
+fn bump_base_rate(state: &State, base_rate: Decimal256, redeemed_amount: Uint256) -> Decimal256 {
+    let mut base_rate = base_rate;
+    if !state.total_liabilities.is_zero() {
+        base_rate += Decimal256::from_uint256(redeemed_amount)
+            / state.total_liabilities
+            / Decimal256::from_uint256(REDEMPTION_BETA);
+    }
+
+    std::cmp::min(base_rate, Decimal256::one())
+}
+
+fn compute_redemption_fee_rate(config: &Config, base_rate: Decimal256) -> Decimal256 {
+    std::cmp::min(
+        config
+            .redemption_fee_floor
+            .unwrap_or_else(default_redemption_fee_floor)
+            + base_rate,
+        Decimal256::one(),
+    )
+}
+
+/// Position that can be redeemed and its borrow health at current prices
+struct RedemptionCandidate {
+    borrower: Addr,
+    position_id: PositionId,
+    collaterals: Vec<(String, Uint256)>,
+    total_value: Uint256,
+    borrow_health: Decimal256,
+}
+
+/// Redeemable positions of a page of the overseer health index, sorted by their
+/// health at current prices as the overseer only updates it along with the position.
+/// Also returns where the next page starts, `None` once the index is exhausted
+#[allow(clippy::type_complexity)]
+fn query_redemption_candidates(
+    deps: Deps,
+    config: &Config,
+    state: &State,
+    block_time: u64,
+    price_timeframe: u64,
+    start_after: Option<(String, PositionId)>,
+) -> StdResult<(Vec<RedemptionCandidate>, Option<(String, PositionId)>)> {
+    let borrowers: BorrowersByHealthResponse = query_borrowers_by_health(
+        deps,
+        config.overseer_contract.clone(),
+        start_after,
+        Some(REDEMPTION_PAGE_LIMIT),
+    )?;
+    let next_start_after = match borrowers.borrowers.last() {
+        Some(last) if borrowers.borrowers.len() == REDEMPTION_PAGE_LIMIT as usize => {
+            Some((last.borrower.clone(), last.position_id))
+        }
+        _ => None,
+    };
+
+    let mut candidates: Vec<RedemptionCandidate> = vec![];
+    for borrower in borrowers.borrowers {
+        let position_id = borrower.position_id;
+        let borrower = deps.api.addr_validate(&borrower.borrower)?;
+        let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower, position_id);
+        compute_loan(state, &mut liability);
+        let loan_amount = liability.loan_amount
+            + compute_term_loans_amount(deps.storage, config, &borrower, position_id, block_time)?;
+        if loan_amount.is_zero() {
+            continue;
+        }
+
+        let collaterals: CollateralsResponse = query_collaterals(
+            deps,
+            config.overseer_contract.clone(),
+            borrower.clone(),
+            position_id,
+        )?;
+        let mut total_value = Uint256::zero();
+        for collateral in collaterals.collaterals.iter() {
+            let price: PriceResponse = query_price(
+                deps,
+                config.oracle_contract.clone(),
+                collateral.0.clone(),
+                "uusd".to_string(),
+                Some(TimeConstraints {
+                    block_time,
+                    valid_timeframe: price_timeframe,
+                }),
+            )?;
+            total_value += collateral.1 * price.rate;
+        }
+
+        // Underwater positions are left to the liquidations
+        if total_value <= loan_amount {
+            continue;
+        }
+
+        let borrow_limit_res: BorrowLimitResponse = query_borrow_limit(
+            deps,
+            config.overseer_contract.clone(),
+            borrower.clone(),
+            position_id,
+            Some(block_time),
+        )?;
+        candidates.push(RedemptionCandidate {
+            borrower,
+            position_id,
+            collaterals: collaterals.collaterals,
+            total_value,
+            borrow_health: Decimal256::from_ratio(borrow_limit_res.borrow_limit, loan_amount),
+        });
+    }
+
+    candidates.sort_by_key(|candidate| candidate.borrow_health);
+    Ok((candidates, next_start_after))
+}
+
+/// Burn `amount` SOLID sent by the redeemer against the positions with the
+/// lowest borrow health, sending the redeemer collaterals worth the redeemed
+/// SOLID. The fee grows with the redeemed share of the supply and decays over time,
+/// the SOLID that could not be redeemed is sent back
+pub fn redeem(
+    mut deps: DepsMut,
+    env: Env,
+    redeemer: Addr,
+    amount: Uint256,
+    max_fee: Decimal256,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let block_time = env.block.time.seconds();
+
+    if amount.is_zero() {
+        return Err(ContractError::ZeroRepay("Solid".to_string()));
+    }
+
+    compute_interest(deps.as_ref(), &config, &mut state, block_time)?;
+    // The term loan repayments below update the stored state
+    store_state(deps.storage, &state)?;
+
+    let decayed_base_rate = compute_decayed_base_rate(&config, &state, block_time);
+    let min_fee_rate = compute_redemption_fee_rate(&config, decayed_base_rate);
+    if min_fee_rate > max_fee {
+        return Err(ContractError::RedemptionFeeExceedsMax(
+            min_fee_rate.to_string(),
+        ));
+    }
+
+    // The fee rate bumped by the whole sent amount bounds the redeemed SOLID,
+    // the base rate is then only bumped by the SOLID actually redeemed
+    let max_fee_rate =
+        compute_redemption_fee_rate(&config, bump_base_rate(&state, decayed_base_rate, amount));
+    let mut remaining = amount / (Decimal256::one() + max_fee_rate);
+
+    let price_timeframe = query_price_timeframe(deps.as_ref(), config.overseer_contract.clone())?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut redeemed_amount = Uint256::zero();
+    let mut burn_amount = Uint256::zero();
+    let mut interest_amount = Uint256::zero();
+    // The health index is paged through until the redeemed SOLID is consumed
+    let mut start_after: Option<(String, PositionId)> = None;
+    loop {
+        let (candidates, next_start_after) = query_redemption_candidates(
+            deps.as_ref(),
+            &config,
+            &state,
+            block_time,
+            price_timeframe,
+            start_after,
+        )?;
+
+        for candidate in candidates {
+            if remaining.is_zero() {
+                break;
+            }
+
+            let RedemptionCandidate {
+                borrower,
+                position_id,
+                collaterals,
+                total_value,
+                ..
+            } = candidate;
+
+            // Term loans are redeemed first, as on liquidations
+            let (term_loan_messages, left_amount) =
+                repay_term_loans(deps.branch(), &env, &borrower, position_id, remaining)?;
+            messages.extend(term_loan_messages);
+
+            let mut liability: BorrowerInfo =
+                read_borrower_info(deps.storage, &borrower, position_id);
+            compute_loan(&state, &mut liability);
+            let redeem_amount = std::cmp::min(left_amount, liability.loan_amount);
+            let (repay_amount, principal_amount) = compute_repay(&mut liability, redeem_amount);
+            store_borrower_info(deps.storage, &borrower, position_id, &liability)?;
+
+            let position_redeemed_amount = remaining - left_amount + repay_amount;
+            let term_loan_amount = compute_term_loans_amount(
+                deps.storage,
+                &config,
+                &borrower,
+                position_id,
+                block_time,
+            )?;
+
+            // Collaterals are taken pro-rata so that their value matches the redeemed SOLID
+            let redeemed_collaterals: Vec<(String, Uint256)> = collaterals
+                .iter()
+                .map(|collateral| {
+                    (
+                        collateral.0.clone(),
+                        collateral
+                            .1
+                            .multiply_ratio(position_redeemed_amount, total_value),
+                    )
+                })
+                .filter(|collateral| !collateral.1.is_zero())
+                .collect();
+
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: config.overseer_contract.to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::RedeemCollateral {
+                    borrower: borrower.to_string(),
+                    collaterals: redeemed_collaterals,
+                    recipient: redeemer.to_string(),
+                    position_id: optional_position_id(position_id),
+                })?,
+            }));
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: config.overseer_contract.to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                    borrower: borrower.to_string(),
+                    loan_amount: liability.loan_amount + term_loan_amount,
+                    position_id: optional_position_id(position_id),
+                    is_borrow: false,
+                })?,
+            }));
+
+            remaining = remaining - position_redeemed_amount;
+            redeemed_amount += position_redeemed_amount;
+            burn_amount += principal_amount;
+            interest_amount += repay_amount - principal_amount;
+        }
+
+        start_after = match next_start_after {
+            Some(next_start_after) if !remaining.is_zero() => Some(next_start_after),
+            _ => break,
+        };
+    }
+
+    if redeemed_amount.is_zero() {
+        return Err(ContractError::NoRedeemablePosition {});
+    }
+
+    let base_rate = bump_base_rate(&state, decayed_base_rate, redeemed_amount);
+    let fee_rate = compute_redemption_fee_rate(&config, base_rate);
+    if fee_rate > max_fee {
+        return Err(ContractError::RedemptionFeeExceedsMax(fee_rate.to_string()));
+    }
+
+    let fee_amount = redeemed_amount * fee_rate;
+    let refund_amount = amount - redeemed_amount - fee_amount;
+
+    if !burn_amount.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.stable_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Burn {
+                amount: burn_amount.into(),
+            })?,
+        }));
+    }
+
+    // The redemption fee is distributed with the repaid interest
+    messages.extend(distribute_fee(
+        deps.storage,
+        read_fees_paid,
+        store_fees_paid,
+        config.stable_contract.to_string(),
+        &fee_recipients(
+            &config.fee_recipients,
+            config.collector_contract.to_string(),
+        ),
+        interest_amount + fee_amount,
+        None,
+    )?);
+
+    if !refund_amount.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.stable_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: redeemer.to_string(),
+                amount: refund_amount.into(),
+            })?,
+        }));
+    }
+
+    let mut state: State = read_state(deps.storage)?;
+    state.total_liabilities = state.total_liabilities - Decimal256::from_uint256(burn_amount);
+    state.redemption_base_rate = base_rate;
+    state.last_redemption_time = block_time;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "redeem"),
+        attr("redeemer", redeemer),
+        attr("redeemed_amount", redeemed_amount),
+        attr("fee_amount", fee_amount),
+        attr("refund_amount", refund_amount),
+    ]))
+}
@@ -3,14 +3,19 @@ use serde::{Deserialize, Serialize};
 
 use cosmwasm_bignumber::math::{Decimal256, Uint256};
 use cosmwasm_std::{Addr, Deps, Order, StdResult, Storage};
-use cosmwasm_storage::{bucket, bucket_read, ReadonlyBucket, ReadonlySingleton, Singleton};
-
-use moneymarket::market::BorrowerInfoResponse;
+use cosmwasm_storage::{bucket, bucket_read, Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
+use moneymarket::fee_distribution::{FeePaid, FeeRecipient};
+use moneymarket::market::{BorrowFeeCurve, BorrowFeeDiscountTier};
+use moneymarket::position::{parse_position_key, position_key, position_range_start, PositionId};
 
 pub const KEY_CONFIG: &[u8] = b"config";
 pub const KEY_STATE: &[u8] = b"state";
+pub const KEY_FLASH_MINT_LOCK: &[u8] = b"flash_mint_lock";
+pub const KEY_FEES_PAID: &[u8] = b"fees_paid";
 
 const PREFIX_LIABILITY: &[u8] = b"liability";
+const PREFIX_BORROW_ALLOWANCE: &[u8] = b"borrow_allowance";
+const PREFIX_TERM_LOAN: &[u8] = b"term_loan";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct Config {
@@ -21,20 +26,112 @@ pub struct Config {
     pub collector_contract: Addr,
     pub liquidation_contract: Addr,
     pub oracle_contract: Addr,
-    pub base_borrow_fee: Decimal256,
-    pub fee_increase_factor: Decimal256,
+    #[serde(default = "default_addr")]
+    pub interest_model_contract: Addr,
+    #[serde(default)]
+    pub borrow_fee_curve: BorrowFeeCurve,
+    #[serde(default)]
+    pub peg_twap_window: u64,
+    /// Max age of the SOLID price sampled into the peg twap
+    #[serde(default)]
+    pub peg_price_timeframe: u64,
     pub flash_mint_fee: Option<Decimal256>,
+    #[serde(default)]
+    pub flash_mint_max_amount: Option<Uint256>,
+    /// Initiators allowed to flash mint, anyone can when unset
+    #[serde(default)]
+    pub flash_minters: Option<Vec<Addr>>,
+    /// Protocol-wide cap on `total_liabilities`, the PSM-backed supply
+    /// is backed 1:1 by its reserve and deliberately not counted
+    #[serde(default)]
+    pub mint_ceiling: Option<Uint256>,
+    #[serde(default)]
+    pub astroport_router: Option<Addr>,
+    #[serde(default)]
+    pub psm_contract: Option<Addr>,
+    #[serde(default)]
+    pub redemption_fee_floor: Option<Decimal256>,
+    #[serde(default)]
+    pub redemption_decay_factor: Option<Decimal256>,
+    /// Yearly rate of the fixed term loan fee, term borrows are disabled when unset
+    #[serde(default)]
+    pub term_loan_rate: Option<Decimal256>,
+    /// Yearly penalty rate accrued by term loans past their maturity
+    #[serde(default)]
+    pub term_loan_penalty_rate: Option<Decimal256>,
+    #[serde(default)]
+    pub capa_gov_contract: Option<Addr>,
+    #[serde(default)]
+    pub borrow_fee_discount_tiers: Vec<BorrowFeeDiscountTier>,
+    /// Split of the protocol fees, all of them go to the collector when empty
+    #[serde(default)]
+    pub fee_recipients: Vec<FeeRecipient>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct State {
     pub total_liabilities: Decimal256,
+    #[serde(default = "default_interest_index")]
+    pub global_interest_index: Decimal256,
+    #[serde(default)]
+    pub last_interest_updated: u64,
+    #[serde(default)]
+    pub peg_twap: Decimal256,
+    /// Peg observed at the last twap update, it weights the time until the next one
+    #[serde(default)]
+    pub peg_last_price: Decimal256,
+    #[serde(default)]
+    pub peg_last_updated: u64,
+    #[serde(default)]
+    pub redemption_base_rate: Decimal256,
+    #[serde(default)]
+    pub last_redemption_time: u64,
+    #[serde(default)]
+    pub last_term_loan_id: u64,
+}
+
+/// Borrow fee params stored before the fee curve was introduced
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LegacyFeeConfig {
+    pub base_borrow_fee: Option<Decimal256>,
+    pub fee_increase_factor: Option<Decimal256>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct BorrowerInfo {
     pub loan_amount: Uint256,
     pub loan_amount_without_interest: Uint256,
+    #[serde(default = "default_interest_index")]
+    pub interest_index: Decimal256,
+}
+
+/// Fixed-term loan, kept apart from the open-ended borrower loan
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TermLoan {
+    pub loan_id: u64,
+    /// Principal plus the fixed fee and the overdue penalty left to repay
+    pub loan_amount: Uint256,
+    pub loan_amount_without_interest: Uint256,
+    pub maturity: u64,
+    /// Last time the overdue penalty was accrued
+    pub penalty_updated: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BorrowAllowance {
+    pub amount: Uint256,
+    /// Block time after which the allowance can not be used
+    pub expires: Option<u64>,
+}
+
+// Entries stored before the borrow index was introduced have no interest
+// accrued on them, so they start from the initial index
+fn default_interest_index() -> Decimal256 {
+    Decimal256::one()
+}
+
+fn default_addr() -> Addr {
+    Addr::unchecked("")
 }
 
 pub fn store_config(storage: &mut dyn Storage, data: &Config) -> StdResult<()> {
@@ -45,6 +142,10 @@ pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
     ReadonlySingleton::new(storage, KEY_CONFIG).load()
 }
 
+pub fn read_legacy_fee_config(storage: &dyn Storage) -> StdResult<LegacyFeeConfig> {
+    ReadonlySingleton::new(storage, KEY_CONFIG).load()
+}
+
 pub fn store_state(storage: &mut dyn Storage, data: &State) -> StdResult<()> {
     Singleton::new(storage, KEY_STATE).save(data)
 }
@@ -53,20 +154,52 @@ pub fn read_state(storage: &dyn Storage) -> StdResult<State> {
     ReadonlySingleton::new(storage, KEY_STATE).load()
 }
 
+/// Set while a flash mint is in progress, until its private flash end
+pub fn store_flash_mint_lock(storage: &mut dyn Storage, locked: bool) -> StdResult<()> {
+    Singleton::new(storage, KEY_FLASH_MINT_LOCK).save(&locked)
+}
+
+pub fn read_flash_mint_lock(storage: &dyn Storage) -> StdResult<bool> {
+    Ok(ReadonlySingleton::new(storage, KEY_FLASH_MINT_LOCK)
+        .may_load()?
+        .unwrap_or(false))
+}
+
+/// Cumulative protocol fees paid to each recipient kind
+pub fn store_fees_paid(storage: &mut dyn Storage, fees_paid: &[FeePaid]) -> StdResult<()> {
+    Singleton::new(storage, KEY_FEES_PAID).save(&fees_paid.to_vec())
+}
+
+pub fn read_fees_paid(storage: &dyn Storage) -> StdResult<Vec<FeePaid>> {
+    Ok(ReadonlySingleton::new(storage, KEY_FEES_PAID)
+        .may_load()?
+        .unwrap_or_default())
+}
+
 pub fn store_borrower_info(
     storage: &mut dyn Storage,
     borrower: &Addr,
+    position_id: PositionId,
     liability: &BorrowerInfo,
 ) -> StdResult<()> {
-    bucket(storage, PREFIX_LIABILITY).save(borrower.as_bytes(), liability)
+    bucket(storage, PREFIX_LIABILITY).save(&position_key(borrower, position_id), liability)
 }
 
-pub fn read_borrower_info(storage: &dyn Storage, borrower: &Addr) -> BorrowerInfo {
-    match bucket_read(storage, PREFIX_LIABILITY).load(borrower.as_bytes()) {
+pub fn remove_borrower_info(storage: &mut dyn Storage, borrower: &Addr, position_id: PositionId) {
+    bucket::<BorrowerInfo>(storage, PREFIX_LIABILITY).remove(&position_key(borrower, position_id))
+}
+
+pub fn read_borrower_info(
+    storage: &dyn Storage,
+    borrower: &Addr,
+    position_id: PositionId,
+) -> BorrowerInfo {
+    match bucket_read(storage, PREFIX_LIABILITY).load(&position_key(borrower, position_id)) {
         Ok(v) => v,
         _ => BorrowerInfo {
             loan_amount: Uint256::zero(),
             loan_amount_without_interest: Uint256::zero(),
+            interest_index: Decimal256::one(),
         },
     }
 }
@@ -76,29 +209,178 @@ const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
 pub fn read_borrower_infos(
     deps: Deps,
-    start_after: Option<Addr>,
+    start_after: Option<(Addr, PositionId)>,
     limit: Option<u32>,
-) -> StdResult<Vec<BorrowerInfoResponse>> {
+) -> StdResult<Vec<(Addr, PositionId, BorrowerInfo)>> {
     let liability_bucket: ReadonlyBucket<BorrowerInfo> =
         bucket_read(deps.storage, PREFIX_LIABILITY);
 
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = calc_range_start(start_after);
+    let start =
+        start_after.map(|(borrower, position_id)| position_range_start(&borrower, position_id));
 
     liability_bucket
         .range(start.as_deref(), None, Order::Ascending)
         .take(limit)
         .map(|elem| {
             let (k, v) = elem?;
-            let borrower = String::from_utf8(k)?;
-            Ok(BorrowerInfoResponse {
-                borrower,
-                loan_amount: v.loan_amount,
-            })
+            let (borrower, position_id) = parse_position_key(&k)?;
+            Ok((Addr::unchecked(borrower), position_id, v))
+        })
+        .collect()
+}
+
+/// Allowances are namespaced by position, so approving one position leaves the others out
+pub fn store_borrow_allowance(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    position_id: PositionId,
+    spender: &Addr,
+    allowance: &BorrowAllowance,
+) -> StdResult<()> {
+    let mut allowance_bucket: Bucket<BorrowAllowance> = Bucket::multilevel(
+        storage,
+        &[PREFIX_BORROW_ALLOWANCE, &position_key(owner, position_id)],
+    );
+    allowance_bucket.save(spender.as_bytes(), allowance)
+}
+
+pub fn remove_borrow_allowance(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    position_id: PositionId,
+    spender: &Addr,
+) {
+    let mut allowance_bucket: Bucket<BorrowAllowance> = Bucket::multilevel(
+        storage,
+        &[PREFIX_BORROW_ALLOWANCE, &position_key(owner, position_id)],
+    );
+    allowance_bucket.remove(spender.as_bytes())
+}
+
+pub fn read_borrow_allowance(
+    storage: &dyn Storage,
+    owner: &Addr,
+    position_id: PositionId,
+    spender: &Addr,
+) -> BorrowAllowance {
+    let allowance_bucket: ReadonlyBucket<BorrowAllowance> = ReadonlyBucket::multilevel(
+        storage,
+        &[PREFIX_BORROW_ALLOWANCE, &position_key(owner, position_id)],
+    );
+    match allowance_bucket.load(spender.as_bytes()) {
+        Ok(v) => v,
+        _ => BorrowAllowance {
+            amount: Uint256::zero(),
+            expires: None,
+        },
+    }
+}
+
+pub fn read_borrow_allowances(
+    deps: Deps,
+    owner: &Addr,
+    position_id: PositionId,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(Addr, BorrowAllowance)>> {
+    let allowance_bucket: ReadonlyBucket<BorrowAllowance> = ReadonlyBucket::multilevel(
+        deps.storage,
+        &[PREFIX_BORROW_ALLOWANCE, &position_key(owner, position_id)],
+    );
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start(start_after);
+
+    allowance_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let spender = String::from_utf8(k)?;
+            Ok((Addr::unchecked(spender), v))
         })
         .collect()
 }
 
+pub fn store_term_loan(
+    storage: &mut dyn Storage,
+    borrower: &Addr,
+    position_id: PositionId,
+    term_loan: &TermLoan,
+) -> StdResult<()> {
+    let mut term_loan_bucket: Bucket<TermLoan> = Bucket::multilevel(
+        storage,
+        &[PREFIX_TERM_LOAN, &position_key(borrower, position_id)],
+    );
+    term_loan_bucket.save(&term_loan.loan_id.to_be_bytes(), term_loan)
+}
+
+pub fn remove_term_loan(
+    storage: &mut dyn Storage,
+    borrower: &Addr,
+    position_id: PositionId,
+    loan_id: u64,
+) {
+    let mut term_loan_bucket: Bucket<TermLoan> = Bucket::multilevel(
+        storage,
+        &[PREFIX_TERM_LOAN, &position_key(borrower, position_id)],
+    );
+    term_loan_bucket.remove(&loan_id.to_be_bytes())
+}
+
+pub fn read_term_loan(
+    storage: &dyn Storage,
+    borrower: &Addr,
+    position_id: PositionId,
+    loan_id: u64,
+) -> StdResult<Option<TermLoan>> {
+    let term_loan_bucket: ReadonlyBucket<TermLoan> = ReadonlyBucket::multilevel(
+        storage,
+        &[PREFIX_TERM_LOAN, &position_key(borrower, position_id)],
+    );
+    term_loan_bucket.may_load(&loan_id.to_be_bytes())
+}
+
+pub fn read_term_loans(
+    storage: &dyn Storage,
+    borrower: &Addr,
+    position_id: PositionId,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<TermLoan>> {
+    let term_loan_bucket: ReadonlyBucket<TermLoan> = ReadonlyBucket::multilevel(
+        storage,
+        &[PREFIX_TERM_LOAN, &position_key(borrower, position_id)],
+    );
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|loan_id| (loan_id + 1).to_be_bytes().to_vec());
+
+    term_loan_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| Ok(elem?.1))
+        .collect()
+}
+
+/// All the term loans of a position, to compute its total loan
+pub fn read_all_term_loans(
+    storage: &dyn Storage,
+    borrower: &Addr,
+    position_id: PositionId,
+) -> StdResult<Vec<TermLoan>> {
+    let term_loan_bucket: ReadonlyBucket<TermLoan> = ReadonlyBucket::multilevel(
+        storage,
+        &[PREFIX_TERM_LOAN, &position_key(borrower, position_id)],
+    );
+
+    term_loan_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| Ok(elem?.1))
+        .collect()
+}
+
 // this will set the first key after the provided key, by appending a 1 byte
 fn calc_range_start(start_after: Option<Addr>) -> Option<Vec<u8>> {
     start_after.map(|addr| {
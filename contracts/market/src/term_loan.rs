@@ -0,0 +1,584 @@
+use cosmwasm_bignumber::math::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, to_binary, Addr, CosmosMsg, Deps, DepsMut, Env, Response, StdError, StdResult, Storage,
+    WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use moneymarket::fee_distribution::{distribute_fee, fee_recipients};
+use moneymarket::market::{TermLoanQuoteResponse, TermLoanResponse, TermLoansResponse};
+use moneymarket::overseer::{BorrowLimitResponse, ExecuteMsg as OverseerExecuteMsg};
+use moneymarket::position::{optional_position_id, PositionId};
+
+use crate::borrow::{
+    compute_borrow_fee, compute_interest, compute_loan, compute_repay_raw, repay_stable,
+};
+use crate::contract::SECONDS_PER_YEAR;
+use crate::error::ContractError;
+use crate::querier::query_borrow_limit;
+use crate::state::{
+    read_all_term_loans, read_borrower_info, read_config, read_fees_paid, read_state,
+    read_term_loan, read_term_loans, remove_term_loan, store_borrower_info, store_fees_paid,
+    store_state, store_term_loan, BorrowerInfo, Config, State, TermLoan,
+};
+
+/// Fixed fee of a term borrow: the one time borrow fee plus the term rate
+/// over the time left until the maturity
+pub fn compute_term_loan_fee(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    state: &mut State,
+    borrower: &Addr,
+    borrow_amount: Uint256,
+    maturity: u64,
+) -> Result<Uint256, ContractError> {
+    let term_loan_rate = config
+        .term_loan_rate
+        .ok_or(ContractError::TermLoansDisabled {})?;
+
+    let block_time = env.block.time.seconds();
+    if maturity <= block_time {
+        return Err(ContractError::InvalidMaturity {});
+    }
+
+    let one_time_fee = compute_borrow_fee(deps, env, config, state, borrower, borrow_amount)?;
+    let term_fee = borrow_amount
+        * term_loan_rate
+        * Decimal256::from_ratio(maturity - block_time, SECONDS_PER_YEAR);
+
+    Ok(one_time_fee + term_fee)
+}
+
+/// Accrue the penalty of a term loan for the time it spent past its maturity
+pub fn compute_term_loan_penalty(config: &Config, term_loan: &mut TermLoan, block_time: u64) {
+    if block_time <= term_loan.maturity {
+        return;
+    }
+
+    let penalty_start = std::cmp::max(term_loan.penalty_updated, term_loan.maturity);
+    if let Some(penalty_rate) = config.term_loan_penalty_rate {
+        if block_time > penalty_start {
+            term_loan.loan_amount += term_loan.loan_amount
+                * penalty_rate
+                * Decimal256::from_ratio(block_time - penalty_start, SECONDS_PER_YEAR);
+        }
+    }
+    term_loan.penalty_updated = block_time;
+}
+
+/// Amount owed by a position on all its term loans, overdue penalties included
+pub fn compute_term_loans_amount(
+    storage: &dyn Storage,
+    config: &Config,
+    borrower: &Addr,
+    position_id: PositionId,
+    block_time: u64,
+) -> StdResult<Uint256> {
+    Ok(read_all_term_loans(storage, borrower, position_id)?
+        .into_iter()
+        .fold(Uint256::zero(), |total, mut term_loan| {
+            compute_term_loan_penalty(config, &mut term_loan, block_time);
+            total + term_loan.loan_amount
+        }))
+}
+
+/// Principal owed by a position on all its term loans, without fees nor penalties
+pub fn compute_term_loans_principal(
+    storage: &dyn Storage,
+    borrower: &Addr,
+    position_id: PositionId,
+) -> StdResult<Uint256> {
+    Ok(read_all_term_loans(storage, borrower, position_id)?
+        .into_iter()
+        .fold(Uint256::zero(), |total, term_loan| {
+            total + term_loan.loan_amount_without_interest
+        }))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn borrow_term(
+    deps: DepsMut,
+    env: Env,
+    borrower: Addr,
+    position_id: PositionId,
+    borrow_amount: Uint256,
+    maturity: u64,
+    max_fee: Uint256,
+    to: Option<Addr>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let block_time = env.block.time.seconds();
+
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower, position_id);
+    compute_interest(deps.as_ref(), &config, &mut state, block_time)?;
+    compute_loan(&state, &mut liability);
+
+    let fee_amount = compute_term_loan_fee(
+        deps.as_ref(),
+        &env,
+        &config,
+        &mut state,
+        &borrower,
+        borrow_amount,
+        maturity,
+    )?;
+    if fee_amount > max_fee {
+        return Err(ContractError::TermLoanFeeExceedsMax(fee_amount.into()));
+    }
+
+    let borrow_limit_res: BorrowLimitResponse = query_borrow_limit(
+        deps.as_ref(),
+        config.overseer_contract.clone(),
+        borrower.clone(),
+        position_id,
+        Some(block_time),
+    )?;
+    let term_loan_amount =
+        compute_term_loans_amount(deps.storage, &config, &borrower, position_id, block_time)?;
+    let loan_amount = liability.loan_amount + term_loan_amount + borrow_amount + fee_amount;
+    if borrow_limit_res.borrow_limit < loan_amount {
+        return Err(ContractError::BorrowExceedsLimit(
+            borrow_limit_res.borrow_limit.into(),
+        ));
+    }
+
+    state.total_liabilities += Decimal256::from_uint256(borrow_amount);
+    if let Some(mint_ceiling) = config.mint_ceiling {
+        if state.total_liabilities > Decimal256::from_uint256(mint_ceiling) {
+            return Err(ContractError::MintCeilingExceeded(mint_ceiling.into()));
+        }
+    }
+
+    state.last_term_loan_id += 1;
+    let loan_id = state.last_term_loan_id;
+    store_state(deps.storage, &state)?;
+    store_term_loan(
+        deps.storage,
+        &borrower,
+        position_id,
+        &TermLoan {
+            loan_id,
+            loan_amount: borrow_amount + fee_amount,
+            loan_amount_without_interest: borrow_amount,
+            maturity,
+            penalty_updated: block_time,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.stable_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: to.unwrap_or_else(|| borrower.clone()).to_string(),
+                amount: borrow_amount.into(),
+            })?,
+        }))
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.overseer_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                borrower: borrower.to_string(),
+                loan_amount,
+                position_id: optional_position_id(position_id),
+                is_borrow: true,
+            })?,
+        }))
+        .add_attributes(vec![
+            attr("action", "borrow_term"),
+            attr("borrower", borrower),
+            attr("position_id", position_id.to_string()),
+            attr("loan_id", loan_id.to_string()),
+            attr("borrow_amount", borrow_amount),
+            attr("fee_amount", fee_amount),
+            attr("maturity", maturity.to_string()),
+        ]))
+}
+
+/// Apply `amount` to the term loans of a position by increasing maturity and
+/// return the burn and fee messages along with the amount left.
+/// The fixed fee and the penalty are distributed, the principal is burnt
+pub fn repay_term_loans(
+    deps: DepsMut,
+    env: &Env,
+    borrower: &Addr,
+    position_id: PositionId,
+    amount: Uint256,
+) -> Result<(Vec<CosmosMsg>, Uint256), ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let block_time = env.block.time.seconds();
+
+    let mut term_loans = read_all_term_loans(deps.storage, borrower, position_id)?;
+    term_loans.sort_by_key(|term_loan| term_loan.maturity);
+
+    let mut remaining = amount;
+    let mut burn_amount = Uint256::zero();
+    let mut collector_amount = Uint256::zero();
+    for mut term_loan in term_loans {
+        if remaining.is_zero() {
+            break;
+        }
+
+        compute_term_loan_penalty(&config, &mut term_loan, block_time);
+        let (repay_amount, principal_amount) = compute_repay_raw(
+            &mut term_loan.loan_amount,
+            &mut term_loan.loan_amount_without_interest,
+            remaining,
+        );
+        store_or_remove_term_loan(deps.storage, borrower, position_id, &term_loan)?;
+
+        remaining = remaining - repay_amount;
+        burn_amount += principal_amount;
+        collector_amount += repay_amount - principal_amount;
+    }
+
+    state.total_liabilities = state.total_liabilities - Decimal256::from_uint256(burn_amount);
+    store_state(deps.storage, &state)?;
+
+    Ok((
+        repay_messages(deps.storage, &config, burn_amount, collector_amount)?,
+        remaining,
+    ))
+}
+
+/// Repay the term loans of a position with the liquidation proceeds, then its open loan.
+/// Proceeds left over once all the loans are repaid belong to the borrower
+pub fn repay_stable_from_liquidation(
+    mut deps: DepsMut,
+    env: Env,
+    borrower: Addr,
+    position_id: PositionId,
+    amount: Uint256,
+) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::ZeroRepay("Solid".to_string()));
+    }
+
+    let (messages, remaining) =
+        repay_term_loans(deps.branch(), &env, &borrower, position_id, amount)?;
+    if !remaining.is_zero() {
+        let repay_res = repay_stable(
+            deps,
+            env,
+            borrower.clone(),
+            position_id,
+            borrower,
+            remaining,
+        )?;
+        return Ok(Response::new()
+            .add_messages(messages)
+            .add_submessages(repay_res.messages)
+            .add_attributes(repay_res.attributes));
+    }
+
+    // Release the debt attributed to the borrower collaterals
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let block_time = env.block.time.seconds();
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower, position_id);
+    compute_interest(deps.as_ref(), &config, &mut state, block_time)?;
+    compute_loan(&state, &mut liability);
+    let term_loan_amount =
+        compute_term_loans_amount(deps.storage, &config, &borrower, position_id, block_time)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.overseer_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                borrower: borrower.to_string(),
+                loan_amount: liability.loan_amount + term_loan_amount,
+                position_id: optional_position_id(position_id),
+                is_borrow: false,
+            })?,
+        }))
+        .add_attributes(vec![
+            attr("action", "repay_stable"),
+            attr("borrower", borrower),
+            attr("position_id", position_id.to_string()),
+            attr("repay_amount", amount),
+        ]))
+}
+
+/// Write off the bad debt of a position with `amount` taken from the overseer reserve.
+/// The principal of the term loans then of the open loan is burnt, the interest, fees
+/// and penalties owed by the position are forgiven rather than paid to the fee recipients
+pub fn settle_bad_debt(
+    deps: DepsMut,
+    env: Env,
+    borrower: Addr,
+    position_id: PositionId,
+    amount: Uint256,
+) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::ZeroRepay("Solid".to_string()));
+    }
+
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let block_time = env.block.time.seconds();
+    compute_interest(deps.as_ref(), &config, &mut state, block_time)?;
+
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower, position_id);
+    compute_loan(&state, &mut liability);
+    let principal_amount = liability.loan_amount_without_interest
+        + compute_term_loans_principal(deps.storage, &borrower, position_id)?;
+    if amount > principal_amount {
+        return Err(ContractError::SettleAmountExceedsPrincipal(
+            principal_amount.into(),
+        ));
+    }
+
+    let mut term_loans = read_all_term_loans(deps.storage, &borrower, position_id)?;
+    term_loans.sort_by_key(|term_loan| term_loan.maturity);
+
+    let mut remaining = amount;
+    let mut term_loan_amount = Uint256::zero();
+    for mut term_loan in term_loans {
+        let settled = std::cmp::min(remaining, term_loan.loan_amount_without_interest);
+        term_loan.loan_amount_without_interest = term_loan.loan_amount_without_interest - settled;
+        term_loan.loan_amount = term_loan.loan_amount_without_interest;
+        term_loan.penalty_updated = block_time;
+        store_or_remove_term_loan(deps.storage, &borrower, position_id, &term_loan)?;
+
+        remaining = remaining - settled;
+        term_loan_amount += term_loan.loan_amount;
+    }
+
+    liability.loan_amount_without_interest = liability.loan_amount_without_interest - remaining;
+    liability.loan_amount = liability.loan_amount_without_interest;
+    store_borrower_info(deps.storage, &borrower, position_id, &liability)?;
+
+    state.total_liabilities = state.total_liabilities - Decimal256::from_uint256(amount);
+    store_state(deps.storage, &state)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.stable_contract.to_string(),
+        funds: vec![],
+        msg: to_binary(&Cw20ExecuteMsg::Burn {
+            amount: amount.into(),
+        })?,
+    })];
+    // Release the debt attributed to the borrower collaterals
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.overseer_contract.to_string(),
+        funds: vec![],
+        msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+            borrower: borrower.to_string(),
+            loan_amount: liability.loan_amount + term_loan_amount,
+            position_id: optional_position_id(position_id),
+            is_borrow: false,
+        })?,
+    }));
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "settle_bad_debt"),
+        attr("borrower", borrower),
+        attr("position_id", position_id.to_string()),
+        attr("settle_amount", amount),
+    ]))
+}
+
+/// Repay the term loan `loan_id` of a position; any amount exceeding it is refunded to `payer`
+pub fn repay_term_loan(
+    deps: DepsMut,
+    env: Env,
+    borrower: Addr,
+    position_id: PositionId,
+    payer: Addr,
+    loan_id: u64,
+    amount: Uint256,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+    let block_time = env.block.time.seconds();
+
+    if amount.is_zero() {
+        return Err(ContractError::ZeroRepay("Solid".to_string()));
+    }
+
+    let mut term_loan: TermLoan = read_term_loan(deps.storage, &borrower, position_id, loan_id)?
+        .ok_or(ContractError::TermLoanNotFound(loan_id))?;
+
+    compute_term_loan_penalty(&config, &mut term_loan, block_time);
+    let (repay_amount, burn_amount) = compute_repay_raw(
+        &mut term_loan.loan_amount,
+        &mut term_loan.loan_amount_without_interest,
+        amount,
+    );
+    store_or_remove_term_loan(deps.storage, &borrower, position_id, &term_loan)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if repay_amount < amount {
+        // Payback left repay amount to payer
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.stable_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: payer.to_string(),
+                amount: (amount - repay_amount).into(),
+            })?,
+        }));
+    }
+    messages.extend(repay_messages(
+        deps.storage,
+        &config,
+        burn_amount,
+        repay_amount - burn_amount,
+    )?);
+
+    // Release the debt attributed to the borrower collaterals
+    let mut liability: BorrowerInfo = read_borrower_info(deps.storage, &borrower, position_id);
+    compute_interest(deps.as_ref(), &config, &mut state, block_time)?;
+    compute_loan(&state, &mut liability);
+    let term_loan_amount =
+        compute_term_loans_amount(deps.storage, &config, &borrower, position_id, block_time)?;
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.overseer_contract.to_string(),
+        funds: vec![],
+        msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+            borrower: borrower.to_string(),
+            loan_amount: liability.loan_amount + term_loan_amount,
+            position_id: optional_position_id(position_id),
+            is_borrow: false,
+        })?,
+    }));
+
+    state.total_liabilities = state.total_liabilities - Decimal256::from_uint256(burn_amount);
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "repay_term_loan"),
+        attr("borrower", borrower),
+        attr("position_id", position_id.to_string()),
+        attr("loan_id", loan_id.to_string()),
+        attr("repay_amount", repay_amount),
+    ]))
+}
+
+fn store_or_remove_term_loan(
+    storage: &mut dyn Storage,
+    borrower: &Addr,
+    position_id: PositionId,
+    term_loan: &TermLoan,
+) -> StdResult<()> {
+    if term_loan.loan_amount.is_zero() {
+        remove_term_loan(storage, borrower, position_id, term_loan.loan_id);
+        Ok(())
+    } else {
+        store_term_loan(storage, borrower, position_id, term_loan)
+    }
+}
+
+fn repay_messages(
+    storage: &mut dyn Storage,
+    config: &Config,
+    burn_amount: Uint256,
+    fee_amount: Uint256,
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !burn_amount.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.stable_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Burn {
+                amount: burn_amount.into(),
+            })?,
+        }));
+    }
+
+    messages.extend(distribute_fee(
+        storage,
+        read_fees_paid,
+        store_fees_paid,
+        config.stable_contract.to_string(),
+        &fee_recipients(
+            &config.fee_recipients,
+            config.collector_contract.to_string(),
+        ),
+        fee_amount,
+        None,
+    )?);
+
+    Ok(messages)
+}
+
+fn to_term_loan_response(
+    config: &Config,
+    borrower: &Addr,
+    mut term_loan: TermLoan,
+    block_time: u64,
+) -> TermLoanResponse {
+    compute_term_loan_penalty(config, &mut term_loan, block_time);
+    TermLoanResponse {
+        borrower: borrower.to_string(),
+        loan_id: term_loan.loan_id,
+        loan_amount: term_loan.loan_amount,
+        maturity: term_loan.maturity,
+        overdue: block_time > term_loan.maturity,
+    }
+}
+
+pub fn query_term_loan(
+    deps: Deps,
+    env: Env,
+    borrower: Addr,
+    position_id: PositionId,
+    loan_id: u64,
+) -> StdResult<TermLoanResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let term_loan: TermLoan = read_term_loan(deps.storage, &borrower, position_id, loan_id)?
+        .ok_or_else(|| StdError::not_found("TermLoan"))?;
+
+    Ok(to_term_loan_response(
+        &config,
+        &borrower,
+        term_loan,
+        env.block.time.seconds(),
+    ))
+}
+
+pub fn query_term_loans(
+    deps: Deps,
+    env: Env,
+    borrower: Addr,
+    position_id: PositionId,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<TermLoansResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let term_loans: Vec<TermLoanResponse> =
+        read_term_loans(deps.storage, &borrower, position_id, start_after, limit)?
+            .into_iter()
+            .map(|term_loan| {
+                to_term_loan_response(&config, &borrower, term_loan, env.block.time.seconds())
+            })
+            .collect();
+
+    Ok(TermLoansResponse { term_loans })
+}
+
+pub fn query_term_loan_quote(
+    deps: Deps,
+    env: Env,
+    borrower: Addr,
+    amount: Uint256,
+    maturity: u64,
+) -> StdResult<TermLoanQuoteResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let mut state: State = read_state(deps.storage)?;
+
+    let fee_amount =
+        compute_term_loan_fee(deps, &env, &config, &mut state, &borrower, amount, maturity)
+            .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    Ok(TermLoanQuoteResponse {
+        borrow_amount: amount,
+        maturity,
+        fee_amount,
+        loan_amount: amount + fee_amount,
+    })
+}
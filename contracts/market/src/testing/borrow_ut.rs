@@ -1,11 +1,16 @@
 use std::str::FromStr;
 
-use crate::borrow::compute_borrow_fee;
-use crate::state::{BorrowerInfo, Config};
+use crate::borrow::{
+    compute_borrow_fee, compute_fee_rate, compute_interest, compute_interest_raw, compute_loan,
+    compute_peg_twap, update_peg_twap,
+};
+use crate::contract::SECONDS_PER_YEAR;
+use crate::state::{read_state, store_config, store_state, BorrowerInfo, Config, State};
 use crate::testing::mock_querier::mock_dependencies;
 use cosmwasm_bignumber::math::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{Addr, Coin, Uint128};
+use moneymarket::market::BorrowFeeCurve;
 
 #[test]
 fn proper_compute_borrower_interest() {
@@ -23,9 +28,30 @@ fn proper_compute_borrower_interest() {
         collector_contract: Addr::unchecked("collector"),
         overseer_contract: Addr::unchecked("overseer"),
         oracle_contract: Addr::unchecked("oracle"),
-        base_borrow_fee: Decimal256::from_str("0.005").unwrap(),
-        fee_increase_factor: Decimal256::from_str("2").unwrap(),
+        interest_model_contract: Addr::unchecked("interest"),
+        borrow_fee_curve: BorrowFeeCurve {
+            breakpoints: vec![
+                (Decimal256::zero(), Decimal256::from_str("0.505").unwrap()),
+                (Decimal256::one(), Decimal256::permille(5)),
+            ],
+            min_fee: Decimal256::permille(5),
+            max_fee: Decimal256::from_str("0.505").unwrap(),
+        },
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
         flash_mint_fee: Some(Decimal256::from_str("0.00025").unwrap()),
+        flash_mint_max_amount: None,
+        flash_minters: None,
+        mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: vec![],
+        fee_recipients: vec![],
     };
     // 1 solid price borrow_fee = 0
     deps.querier.with_oracle_price(&[(
@@ -40,13 +66,33 @@ fn proper_compute_borrower_interest() {
     let mut liability1 = BorrowerInfo {
         loan_amount: Uint256::zero(),
         loan_amount_without_interest: Uint256::zero(),
+        interest_index: Decimal256::one(),
     };
-    let one_time_fee =
-        compute_borrow_fee(deps.as_ref(), &env, &mock_config, liability1.loan_amount).unwrap();
+    let mut state = State {
+        total_liabilities: Decimal256::zero(),
+        global_interest_index: Decimal256::one(),
+        last_interest_updated: env.block.time.seconds(),
+        peg_twap: Decimal256::zero(),
+        peg_last_price: Decimal256::zero(),
+        peg_last_updated: 0u64,
+        redemption_base_rate: Decimal256::zero(),
+        last_redemption_time: 0u64,
+        last_term_loan_id: 0u64,
+    };
+    let one_time_fee = compute_borrow_fee(
+        deps.as_ref(),
+        &env,
+        &mock_config,
+        &mut state,
+        &Addr::unchecked("addr0000"),
+        liability1.loan_amount,
+    )
+    .unwrap();
     liability1.loan_amount += one_time_fee;
     let liability2 = BorrowerInfo {
         loan_amount: Uint256::zero(),
         loan_amount_without_interest: Uint256::zero(),
+        interest_index: Decimal256::one(),
     };
     assert_eq!(liability1, liability2);
 
@@ -63,11 +109,13 @@ fn proper_compute_borrower_interest() {
     let liability3 = BorrowerInfo {
         loan_amount: Uint256::from(800u128),
         loan_amount_without_interest: Uint256::zero(),
+        interest_index: Decimal256::one(),
     };
 
     let liability4 = BorrowerInfo {
         loan_amount: Uint256::from(800u128),
         loan_amount_without_interest: Uint256::zero(),
+        interest_index: Decimal256::one(),
     };
     assert_eq!(liability3, liability4);
 }
@@ -80,7 +128,7 @@ fn proper_compute_fee() {
         amount: Uint128::from(2000000u128),
     }]);
 
-    let _mock_config = Config {
+    let mock_config = Config {
         contract_addr: Addr::unchecked(MOCK_CONTRACT_ADDR),
         owner_addr: Addr::unchecked("owner"),
         stable_contract: Addr::unchecked("solid"),
@@ -88,11 +136,58 @@ fn proper_compute_fee() {
         collector_contract: Addr::unchecked("collector"),
         overseer_contract: Addr::unchecked("overseer"),
         oracle_contract: Addr::unchecked("oracle"),
-        base_borrow_fee: Decimal256::from_str("0.005").unwrap(),
-        fee_increase_factor: Decimal256::from_str("2").unwrap(),
+        interest_model_contract: Addr::unchecked("interest"),
+        borrow_fee_curve: BorrowFeeCurve {
+            breakpoints: vec![
+                (Decimal256::percent(95), Decimal256::percent(5)),
+                (Decimal256::percent(99), Decimal256::percent(1)),
+                (Decimal256::one(), Decimal256::permille(5)),
+            ],
+            min_fee: Decimal256::permille(5),
+            max_fee: Decimal256::percent(4),
+        },
+        peg_twap_window: 1000u64,
+        peg_price_timeframe: 60u64,
         flash_mint_fee: Some(Decimal256::from_str("0.00025").unwrap()),
+        flash_mint_max_amount: None,
+        flash_minters: None,
+        mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: vec![],
+        fee_recipients: vec![],
     };
-    // 1 solid price borrow_fee = 0 + 0.0025
+    let curve = &mock_config.borrow_fee_curve;
+
+    // flat beyond the breakpoints, clamped to the max fee
+    assert_eq!(
+        compute_fee_rate(curve, Decimal256::from_str("1.02").unwrap()),
+        Decimal256::permille(5)
+    );
+    assert_eq!(
+        compute_fee_rate(curve, Decimal256::percent(90)),
+        Decimal256::percent(4)
+    );
+    // interpolated between breakpoints
+    assert_eq!(
+        compute_fee_rate(curve, Decimal256::from_str("0.995").unwrap()),
+        Decimal256::from_str("0.0075").unwrap()
+    );
+    assert_eq!(
+        compute_fee_rate(curve, Decimal256::percent(98)),
+        Decimal256::percent(2)
+    );
+    assert_eq!(
+        compute_fee_rate(curve, Decimal256::percent(99)),
+        Decimal256::percent(1)
+    );
+
+    // the first observation sets the twap
     deps.querier.with_oracle_price(&[(
         &("solid".to_string(), "uusd".to_string()),
         &(
@@ -101,56 +196,208 @@ fn proper_compute_fee() {
             env.block.time.seconds(),
         ),
     )]);
-
-    let liability1 = BorrowerInfo {
-        loan_amount: Uint256::zero(),
-        loan_amount_without_interest: Uint256::zero(),
-    };
-    let liability2 = BorrowerInfo {
-        loan_amount: Uint256::zero(),
-        loan_amount_without_interest: Uint256::zero(),
+    let mut state = State {
+        total_liabilities: Decimal256::zero(),
+        global_interest_index: Decimal256::one(),
+        last_interest_updated: env.block.time.seconds(),
+        peg_twap: Decimal256::zero(),
+        peg_last_price: Decimal256::zero(),
+        peg_last_updated: 0u64,
+        redemption_base_rate: Decimal256::zero(),
+        last_redemption_time: 0u64,
+        last_term_loan_id: 0u64,
     };
-    assert_eq!(liability1, liability2);
+    let fee = compute_borrow_fee(
+        deps.as_ref(),
+        &env,
+        &mock_config,
+        &mut state,
+        &Addr::unchecked("addr0000"),
+        Uint256::from(1000000u64),
+    )
+    .unwrap();
+    assert_eq!(fee, Uint256::from(5000u64));
+    assert_eq!(state.peg_twap, Decimal256::one());
 
-    // 0.98 solid price repay_fee = 0
+    // a dip in the same block does not move the twap
     deps.querier.with_oracle_price(&[(
         &("solid".to_string(), "uusd".to_string()),
         &(
-            Decimal256::from_str("0.98").unwrap(),
+            Decimal256::percent(95),
             env.block.time.seconds(),
             env.block.time.seconds(),
         ),
     )]);
+    let fee = compute_borrow_fee(
+        deps.as_ref(),
+        &env,
+        &mock_config,
+        &mut state,
+        &Addr::unchecked("addr0000"),
+        Uint256::from(1000000u64),
+    )
+    .unwrap();
+    assert_eq!(fee, Uint256::from(5000u64));
+    assert_eq!(state.peg_last_price, Decimal256::percent(95));
 
-    let liability3 = BorrowerInfo {
-        loan_amount: Uint256::from(800u128),
-        loan_amount_without_interest: Uint256::zero(),
-    };
+    // the dip weights the time passed over the window
+    compute_peg_twap(
+        &mut state,
+        Decimal256::percent(95),
+        env.block.time.seconds() + 100u64,
+        mock_config.peg_twap_window,
+    );
+    assert_eq!(state.peg_twap, Decimal256::from_str("0.995").unwrap());
 
-    let liability4 = BorrowerInfo {
-        loan_amount: Uint256::from(800u128),
-        loan_amount_without_interest: Uint256::zero(),
+    // a full window takes the last observed peg
+    compute_peg_twap(
+        &mut state,
+        Decimal256::one(),
+        env.block.time.seconds() + 5000u64,
+        mock_config.peg_twap_window,
+    );
+    assert_eq!(state.peg_twap, Decimal256::percent(95));
+
+    // a price older than the peg price timeframe is not sampled, even within the window
+    let mut stale_env = env.clone();
+    stale_env.block.time = env.block.time.plus_seconds(61);
+    assert!(compute_borrow_fee(
+        deps.as_ref(),
+        &stale_env,
+        &mock_config,
+        &mut state,
+        &Addr::unchecked("addr0000"),
+        Uint256::from(1000000u64),
+    )
+    .is_err());
+
+    // anyone can sample the peg between borrows
+    store_config(deps.as_mut().storage, &mock_config).unwrap();
+    let mut state = State {
+        total_liabilities: Decimal256::zero(),
+        global_interest_index: Decimal256::one(),
+        last_interest_updated: env.block.time.seconds(),
+        peg_twap: Decimal256::one(),
+        peg_last_price: Decimal256::one(),
+        peg_last_updated: env.block.time.seconds(),
+        redemption_base_rate: Decimal256::zero(),
+        last_redemption_time: 0u64,
+        last_term_loan_id: 0u64,
     };
-    assert_eq!(liability3, liability4);
+    store_state(deps.as_mut().storage, &state).unwrap();
 
-    // 1.02 solid price interest_rate = (1.02 - 1)/2 = 0.01
+    let mut sample_env = env.clone();
+    sample_env.block.time = env.block.time.plus_seconds(500);
     deps.querier.with_oracle_price(&[(
         &("solid".to_string(), "uusd".to_string()),
         &(
-            Decimal256::from_str("1.02").unwrap(),
-            env.block.time.seconds(),
-            env.block.time.seconds(),
+            Decimal256::percent(95),
+            sample_env.block.time.seconds(),
+            sample_env.block.time.seconds(),
         ),
     )]);
+    update_peg_twap(deps.as_mut(), sample_env.clone()).unwrap();
+    let stored_state = read_state(deps.as_ref().storage).unwrap();
+    assert_eq!(stored_state.peg_twap, Decimal256::one());
+    assert_eq!(stored_state.peg_last_price, Decimal256::percent(95));
 
-    let liability5 = BorrowerInfo {
-        loan_amount: Uint256::from(800u128),
-        loan_amount_without_interest: Uint256::zero(),
+    // interest accruals sample the peg as well
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(1))]);
+    compute_interest(
+        deps.as_ref(),
+        &mock_config,
+        &mut state,
+        sample_env.block.time.seconds(),
+    )
+    .unwrap();
+    assert_eq!(state.peg_last_price, Decimal256::percent(95));
+    let mut next_env = sample_env.clone();
+    next_env.block.time = sample_env.block.time.plus_seconds(500);
+    deps.querier.with_oracle_price(&[(
+        &("solid".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::percent(95),
+            next_env.block.time.seconds(),
+            next_env.block.time.seconds(),
+        ),
+    )]);
+    compute_interest(
+        deps.as_ref(),
+        &mock_config,
+        &mut state,
+        next_env.block.time.seconds(),
+    )
+    .unwrap();
+    assert_eq!(state.peg_twap, Decimal256::from_str("0.975").unwrap());
+
+    // a stale price accrues interest without being sampled
+    compute_interest(
+        deps.as_ref(),
+        &mock_config,
+        &mut state,
+        next_env.block.time.seconds() + 500u64,
+    )
+    .unwrap();
+    assert_eq!(
+        state.last_interest_updated,
+        next_env.block.time.seconds() + 500u64
+    );
+    assert_eq!(state.peg_last_updated, next_env.block.time.seconds());
+}
+
+#[test]
+fn proper_compute_interest() {
+    let mut state = State {
+        total_liabilities: Decimal256::from_uint256(1000000u128),
+        global_interest_index: Decimal256::one(),
+        last_interest_updated: 1000u64,
+        peg_twap: Decimal256::one(),
+        peg_last_price: Decimal256::one(),
+        peg_last_updated: 1000u64,
+        redemption_base_rate: Decimal256::zero(),
+        last_redemption_time: 0u64,
+        last_term_loan_id: 0u64,
     };
 
-    let liability6 = BorrowerInfo {
-        loan_amount: Uint256::from(800u128),
-        loan_amount_without_interest: Uint256::zero(),
+    // same time, nothing accrued
+    compute_interest_raw(&mut state, 1000u64, Decimal256::percent(10));
+    assert_eq!(state.global_interest_index, Decimal256::one());
+
+    // half year at 10% yearly rate
+    compute_interest_raw(
+        &mut state,
+        1000u64 + SECONDS_PER_YEAR / 2,
+        Decimal256::percent(10),
+    );
+    assert_eq!(
+        state.global_interest_index,
+        Decimal256::from_str("1.05").unwrap()
+    );
+    assert_eq!(state.last_interest_updated, 1000u64 + SECONDS_PER_YEAR / 2);
+    // principal is untouched by interest accrual
+    assert_eq!(
+        state.total_liabilities,
+        Decimal256::from_uint256(1000000u128)
+    );
+
+    // loan opened before the accrual grows with the index
+    let mut liability = BorrowerInfo {
+        loan_amount: Uint256::from(1000u128),
+        loan_amount_without_interest: Uint256::from(1000u128),
+        interest_index: Decimal256::one(),
     };
-    assert_eq!(liability5, liability6);
+    compute_loan(&state, &mut liability);
+    assert_eq!(
+        liability,
+        BorrowerInfo {
+            loan_amount: Uint256::from(1050u128),
+            loan_amount_without_interest: Uint256::from(1000u128),
+            interest_index: Decimal256::from_str("1.05").unwrap(),
+        }
+    );
+
+    // a loan already at the current index is unchanged
+    compute_loan(&state, &mut liability);
+    assert_eq!(liability.loan_amount, Uint256::from(1050u128));
 }
@@ -12,9 +12,13 @@ use cosmwasm_storage::to_length_prefixed;
 use std::collections::HashMap;
 use std::marker::PhantomData;
 
-use cw20::TokenInfoResponse;
+use capa_token::gov::StakerResponse;
+use cw20::{BalanceResponse, TokenInfoResponse};
 use moneymarket::interest_model::BorrowRateResponse;
-use moneymarket::overseer::{BorrowLimitResponse, ConfigResponse};
+use moneymarket::overseer::{
+    BorrowLimitResponse, BorrowerHealthResponse, BorrowersByHealthResponse, CollateralStatus,
+    CollateralsResponse, ConfigResponse, WhitelistResponse, WhitelistResponseElem,
+};
 use terra_cosmwasm::TerraQueryWrapper;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -26,9 +30,26 @@ pub enum QueryMsg {
     BorrowLimit {
         borrower: String,
         block_time: Option<u64>,
+        position_id: Option<u32>,
     },
     /// Query overseer config to get target deposit rate
     Config {},
+    /// Query overseer whitelist to get the collateral custody and max ltv
+    Whitelist {
+        collateral_token: Option<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// Query overseer collaterals of a borrower
+    Collaterals {
+        borrower: String,
+    },
+    /// Query overseer borrowers sorted by health
+    BorrowersByHealth {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 
     Price {
         base: String,
@@ -36,6 +57,14 @@ pub enum QueryMsg {
     },
     /// Query cw20 Token Info
     TokenInfo {},
+    /// Query cw20 Token Balance
+    Balance {
+        address: String,
+    },
+    /// Query gov staker info
+    Staker {
+        address: String,
+    },
 }
 
 /// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
@@ -60,6 +89,9 @@ pub struct WasmMockQuerier {
     borrow_rate_querier: BorrowRateQuerier,
     borrow_limit_querier: BorrowLimitQuerier,
     oracle_price_querier: OraclePriceQuerier,
+    whitelist_querier: WhitelistQuerier,
+    collaterals_querier: CollateralsQuerier,
+    gov_staker_querier: GovStakerQuerier,
 }
 
 #[derive(Clone, Default)]
@@ -68,7 +100,28 @@ pub struct TokenQuerier {
     balances: HashMap<String, HashMap<String, Uint128>>,
 }
 
-impl TokenQuerier {}
+impl TokenQuerier {
+    pub fn new(balances: &[(&String, &[(&String, &Uint128)])]) -> Self {
+        TokenQuerier {
+            balances: balances_to_map(balances),
+        }
+    }
+}
+
+pub(crate) fn balances_to_map(
+    balances: &[(&String, &[(&String, &Uint128)])],
+) -> HashMap<String, HashMap<String, Uint128>> {
+    let mut balances_map: HashMap<String, HashMap<String, Uint128>> = HashMap::new();
+    for (contract_addr, balances) in balances.iter() {
+        let mut contract_balances_map: HashMap<String, Uint128> = HashMap::new();
+        for (addr, balance) in balances.iter() {
+            contract_balances_map.insert(addr.to_string(), **balance);
+        }
+
+        balances_map.insert(contract_addr.to_string(), contract_balances_map);
+    }
+    balances_map
+}
 
 #[derive(Clone, Default)]
 pub struct TaxQuerier {}
@@ -147,6 +200,44 @@ pub(crate) fn oracle_price_to_map(
     oracle_price_map
 }
 
+#[derive(Clone, Default)]
+pub struct WhitelistQuerier {
+    // collateral token -> (custody contract, max ltv)
+    whitelist: HashMap<String, (String, Decimal256)>,
+}
+
+impl WhitelistQuerier {
+    pub fn new(whitelist: &[(&String, &(String, Decimal256))]) -> Self {
+        WhitelistQuerier {
+            whitelist: whitelist_to_map(whitelist),
+        }
+    }
+}
+
+pub(crate) fn whitelist_to_map(
+    whitelist: &[(&String, &(String, Decimal256))],
+) -> HashMap<String, (String, Decimal256)> {
+    let mut whitelist_map: HashMap<String, (String, Decimal256)> = HashMap::new();
+    for (collateral_token, elem) in whitelist.iter() {
+        whitelist_map.insert((*collateral_token).clone(), (*elem).clone());
+    }
+    whitelist_map
+}
+
+#[derive(Clone, Default)]
+pub struct CollateralsQuerier {
+    // borrower -> locked collaterals
+    collaterals: HashMap<String, Vec<(String, Uint256)>>,
+    // borrowers sorted by increasing health factor
+    borrowers_by_health: Vec<(String, Decimal256)>,
+}
+
+#[derive(Clone, Default)]
+pub struct GovStakerQuerier {
+    // staker -> staked balance
+    stakers: HashMap<String, Uint128>,
+}
+
 impl Querier for WasmMockQuerier {
     fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
         // MockQuerier doesn't support Custom, so we ignore it completely here
@@ -188,7 +279,15 @@ impl WasmMockQuerier {
                     QueryMsg::BorrowLimit {
                         borrower,
                         block_time: _,
-                    } => match self.borrow_limit_querier.borrow_limit.get(&borrower) {
+                        position_id,
+                    } => match self
+                        .borrow_limit_querier
+                        .borrow_limit
+                        .get(&match position_id {
+                            // positions other than the default one are registered as "borrower/id"
+                            Some(position_id) => format!("{}/{}", borrower, position_id),
+                            None => borrower.clone(),
+                        }) {
                         Some(v) => SystemResult::Ok(ContractResult::from(to_binary(
                             &BorrowLimitResponse {
                                 borrower,
@@ -212,6 +311,80 @@ impl WasmMockQuerier {
                         })))
                     }
 
+                    QueryMsg::Whitelist {
+                        collateral_token,
+                        start_after: _,
+                        limit: _,
+                    } => {
+                        let collateral_token = collateral_token.unwrap_or_default();
+                        match self.whitelist_querier.whitelist.get(&collateral_token) {
+                            Some(v) => SystemResult::Ok(ContractResult::from(to_binary(
+                                &WhitelistResponse {
+                                    elems: vec![WhitelistResponseElem {
+                                        name: collateral_token.clone(),
+                                        symbol: collateral_token.clone(),
+                                        max_ltv: v.1,
+                                        custody_contract: v.0.clone(),
+                                        collateral_token,
+                                        debt_ceiling: None,
+                                        total_debt: Uint256::zero(),
+                                        liquidation_threshold: v.1,
+                                        status: CollateralStatus::Active,
+                                        total_locked: Uint256::zero(),
+                                        target_max_ltv: v.1,
+                                        target_liquidation_threshold: v.1,
+                                        ramp_end_time: None,
+                                    }],
+                                },
+                            ))),
+                            None => SystemResult::Err(SystemError::InvalidRequest {
+                                error: "No whitelist elem exists".to_string(),
+                                request: msg.as_slice().into(),
+                            }),
+                        }
+                    }
+
+                    QueryMsg::Collaterals { borrower } => {
+                        let collaterals = self
+                            .collaterals_querier
+                            .collaterals
+                            .get(&borrower)
+                            .cloned()
+                            .unwrap_or_default();
+
+                        SystemResult::Ok(ContractResult::from(to_binary(&CollateralsResponse {
+                            borrower,
+                            collaterals,
+                            position_id: 0,
+                        })))
+                    }
+                    QueryMsg::BorrowersByHealth { start_after, limit } => SystemResult::Ok(
+                        ContractResult::from(to_binary(&BorrowersByHealthResponse {
+                            borrowers: self
+                                .collaterals_querier
+                                .borrowers_by_health
+                                .iter()
+                                // every mocked borrower uses the default position
+                                .skip(match start_after {
+                                    Some(start_after) => self
+                                        .collaterals_querier
+                                        .borrowers_by_health
+                                        .iter()
+                                        .position(|(borrower, _)| *borrower == start_after)
+                                        .map(|index| index + 1)
+                                        .unwrap_or_default(),
+                                    None => 0,
+                                })
+                                .take(limit.unwrap_or(10) as usize)
+                                .map(|(borrower, borrow_health)| BorrowerHealthResponse {
+                                    borrower: borrower.clone(),
+                                    borrow_health: *borrow_health,
+                                    position_id: 0,
+                                })
+                                .collect(),
+                        })),
+                    ),
+
                     QueryMsg::Price { base, quote } => {
                         match self.oracle_price_querier.oracle_price.get(&(base, quote)) {
                             Some(v) => {
@@ -247,6 +420,33 @@ impl WasmMockQuerier {
                             total_supply,
                         })))
                     }
+                    QueryMsg::Balance { address } => {
+                        let balance = self
+                            .token_querier
+                            .balances
+                            .get(contract_addr)
+                            .and_then(|balances| balances.get(&address))
+                            .cloned()
+                            .unwrap_or_default();
+
+                        SystemResult::Ok(ContractResult::from(to_binary(&BalanceResponse {
+                            balance,
+                        })))
+                    }
+                    QueryMsg::Staker { address } => {
+                        let balance = self
+                            .gov_staker_querier
+                            .stakers
+                            .get(&address)
+                            .cloned()
+                            .unwrap_or_default();
+
+                        SystemResult::Ok(ContractResult::from(to_binary(&StakerResponse {
+                            balance,
+                            share: Uint128::zero(),
+                            locked_balance: vec![],
+                        })))
+                    }
                 }
             }
             QueryRequest::Wasm(WasmQuery::Raw { contract_addr, key }) => {
@@ -300,9 +500,47 @@ impl WasmMockQuerier {
             borrow_rate_querier: BorrowRateQuerier::default(),
             borrow_limit_querier: BorrowLimitQuerier::default(),
             oracle_price_querier: OraclePriceQuerier::default(),
+            whitelist_querier: WhitelistQuerier::default(),
+            collaterals_querier: CollateralsQuerier::default(),
+            gov_staker_querier: GovStakerQuerier::default(),
         }
     }
 
+    pub fn with_gov_stakers(&mut self, stakers: &[(&String, &Uint128)]) {
+        self.gov_staker_querier = GovStakerQuerier {
+            stakers: stakers
+                .iter()
+                .map(|(staker, balance)| ((*staker).clone(), **balance))
+                .collect(),
+        };
+    }
+
+    /// Borrowers with their collaterals, sorted by increasing health factor
+    #[allow(clippy::type_complexity)]
+    pub fn with_collaterals(
+        &mut self,
+        collaterals: &[(&String, &Decimal256, &[(String, Uint256)])],
+    ) {
+        self.collaterals_querier = CollateralsQuerier {
+            collaterals: collaterals
+                .iter()
+                .map(|(borrower, _, collaterals)| ((*borrower).clone(), collaterals.to_vec()))
+                .collect(),
+            borrowers_by_health: collaterals
+                .iter()
+                .map(|(borrower, borrow_health, _)| ((*borrower).clone(), **borrow_health))
+                .collect(),
+        };
+    }
+
+    pub fn with_token_balances(&mut self, balances: &[(&String, &[(&String, &Uint128)])]) {
+        self.token_querier = TokenQuerier::new(balances);
+    }
+
+    pub fn with_whitelist(&mut self, whitelist: &[(&String, &(String, Decimal256))]) {
+        self.whitelist_querier = WhitelistQuerier::new(whitelist);
+    }
+
     pub fn with_borrow_rate(&mut self, borrow_rate: &[(&String, &Decimal256)]) {
         self.borrow_rate_querier = BorrowRateQuerier::new(borrow_rate);
     }
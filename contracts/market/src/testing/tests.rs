@@ -1,25 +1,55 @@
 use crate::borrow::borrow_stable as _borrow_stable;
-use crate::contract::{execute, instantiate, query, reply, INITIAL_DEPOSIT_AMOUNT};
+use crate::contract::{
+    execute, instantiate, query, reply, INITIAL_DEPOSIT_AMOUNT, SECONDS_PER_YEAR,
+};
 use crate::error::ContractError;
+use crate::redemption::compute_decayed_base_rate;
 use crate::response::MsgInstantiateContractResponse;
-use crate::state::{read_borrower_infos, read_state, store_state, State};
+use crate::state::{
+    read_all_term_loans, read_borrower_info, read_borrower_infos, read_config, read_state,
+    store_borrower_info, store_state, store_term_loan, BorrowerInfo, State, TermLoan,
+};
 use crate::testing::mock_querier::mock_dependencies;
 
+use astroport::asset::AssetInfo;
+use astroport::router::{Cw20HookMsg as RouterCw20HookMsg, SwapOperation};
 use cosmwasm_bignumber::math::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, from_binary, to_binary, Addr, Coin, CosmosMsg, Reply, SubMsg, SubMsgResponse,
+    attr, from_binary, to_binary, Addr, Coin, CosmosMsg, Reply, StdError, SubMsg, SubMsgResponse,
     SubMsgResult, Uint128, WasmMsg,
 };
 use cw20::{Cw20Coin, Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
+use moneymarket::custody::Cw20HookMsg as CustodyCw20HookMsg;
+use moneymarket::fee_distribution::{
+    FeeDistributionResponse, FeePaid, FeeRecipient, FeeRecipientKind,
+};
 use moneymarket::market::{
-    BorrowerInfoResponse, ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg,
-    StateResponse,
+    BorrowAllowanceResponse, BorrowAllowancesResponse, BorrowFeeCurve, BorrowFeeDiscountResponse,
+    BorrowFeeDiscountTier, BorrowFeeDiscountTiersResponse, BorrowerInfoResponse,
+    BorrowerInfosResponse, ConfigResponse, Cw20HookMsg, ExecuteMsg, FlashMintCallbackMsg,
+    FlashMintFeeResponse, InstantiateMsg, QueryMsg, SimulateBorrowResponse, SimulateRepayResponse,
+    StateResponse, TermLoanQuoteResponse, TermLoanResponse, TermLoansResponse,
 };
+use moneymarket::overseer::{Cw20HookMsg as OverseerCw20HookMsg, ExecuteMsg as OverseerExecuteMsg};
+use moneymarket::position::DEFAULT_POSITION_ID;
+use moneymarket::querier::query_token_balance;
 use moneymarket::terraswap::InstantiateMsg as TokenInstantiateMsg;
 use protobuf::Message;
 use std::str::FromStr;
 
+/// Fee of 0.5% at peg, increasing by half of the depeg below it
+fn borrow_fee_curve() -> BorrowFeeCurve {
+    BorrowFeeCurve {
+        breakpoints: vec![
+            (Decimal256::zero(), Decimal256::from_str("0.505").unwrap()),
+            (Decimal256::one(), Decimal256::permille(5)),
+        ],
+        min_fee: Decimal256::permille(5),
+        max_fee: Decimal256::from_str("0.505").unwrap(),
+    }
+}
+
 #[test]
 fn proper_initialization() {
     let mut deps = mock_dependencies(&[Coin {
@@ -32,8 +62,9 @@ fn proper_initialization() {
     let msg = InstantiateMsg {
         owner_addr: "owner".to_string(),
         stable_code_id: 123u64,
-        base_borrow_fee: Decimal256::from_str("0.05").unwrap(),
-        fee_increase_factor: Decimal256::from_str("2").unwrap(),
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
         flash_mint_fee: Some(Decimal256::from_str("0.00025").unwrap()),
     };
 
@@ -95,6 +126,7 @@ fn proper_initialization() {
         collector_contract: "collector".to_string(),
         liquidation_contract: "liquidation".to_string(),
         oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
     };
     let info = mock_info("owner", &[]);
     let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -105,6 +137,7 @@ fn proper_initialization() {
         collector_contract: "collector".to_string(),
         liquidation_contract: "liquidation".to_string(),
         oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
     };
     let info = mock_info("addr0000", &[]);
     let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
@@ -156,8 +189,9 @@ fn update_config() {
     let msg = InstantiateMsg {
         owner_addr: "owner".to_string(),
         stable_code_id: 123u64,
-        base_borrow_fee: Decimal256::from_str("0.05").unwrap(),
-        fee_increase_factor: Decimal256::from_str("2").unwrap(),
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
         flash_mint_fee: Some(Decimal256::from_str("0.00025").unwrap()),
     };
 
@@ -190,6 +224,7 @@ fn update_config() {
         collector_contract: "collector".to_string(),
         liquidation_contract: "liquidation".to_string(),
         oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
     };
     let info = mock_info("owner", &[]);
     let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -199,10 +234,24 @@ fn update_config() {
     let msg = ExecuteMsg::UpdateConfig {
         owner_addr: Some("owner1".to_string()),
         liquidation_contract: None,
-        base_borrow_fee: Some(Decimal256::from_str("0.006").unwrap()),
-        fee_increase_factor: Some(Decimal256::from_str("2").unwrap()),
+        borrow_fee_curve: None,
+        peg_twap_window: None,
+        peg_price_timeframe: None,
         flash_mint_fee: None,
         oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: None,
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -218,10 +267,31 @@ fn update_config() {
     let msg = ExecuteMsg::UpdateConfig {
         owner_addr: None,
         liquidation_contract: Some("liquidation2".to_string()),
-        base_borrow_fee: None,
-        fee_increase_factor: None,
+        borrow_fee_curve: Some(BorrowFeeCurve {
+            breakpoints: vec![
+                (Decimal256::percent(95), Decimal256::percent(5)),
+                (Decimal256::one(), Decimal256::permille(5)),
+            ],
+            min_fee: Decimal256::permille(5),
+            max_fee: Decimal256::percent(5),
+        }),
+        peg_twap_window: Some(3600u64),
+        peg_price_timeframe: None,
         flash_mint_fee: None,
         oracle_addr: Some("neworacle".to_string()),
+        interest_model_contract: Some("interest2".to_string()),
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: None,
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -233,16 +303,185 @@ fn update_config() {
     assert_eq!("owner1".to_string(), config_res.owner_addr);
     assert_eq!("liquidation2".to_string(), config_res.liquidation_contract);
     assert_eq!("neworacle".to_string(), config_res.oracle_contract);
+    assert_eq!("interest2".to_string(), config_res.interest_model_contract);
+    assert_eq!(
+        BorrowFeeCurve {
+            breakpoints: vec![
+                (Decimal256::percent(95), Decimal256::percent(5)),
+                (Decimal256::one(), Decimal256::permille(5)),
+            ],
+            min_fee: Decimal256::permille(5),
+            max_fee: Decimal256::percent(5),
+        },
+        config_res.borrow_fee_curve
+    );
+    assert_eq!(3600u64, config_res.peg_twap_window);
+
+    // the twap window can not be zero
+    let info = mock_info("owner1", &[]);
+    let msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        liquidation_contract: None,
+        borrow_fee_curve: None,
+        peg_twap_window: Some(0u64),
+        peg_price_timeframe: Some(120u64),
+        flash_mint_fee: None,
+        oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: None,
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
+    };
+    match execute(deps.as_mut(), mock_env(), info.clone(), msg) {
+        Err(ContractError::InvalidPegTwapWindow {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        liquidation_contract: None,
+        borrow_fee_curve: None,
+        peg_twap_window: None,
+        peg_price_timeframe: Some(120u64),
+        flash_mint_fee: None,
+        oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: None,
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(3600u64, config_res.peg_twap_window);
+    assert_eq!(120u64, config_res.peg_price_timeframe);
+
+    // breakpoints must have increasing pegs
+    let info = mock_info("owner1", &[]);
+    let msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        liquidation_contract: None,
+        borrow_fee_curve: Some(BorrowFeeCurve {
+            breakpoints: vec![
+                (Decimal256::one(), Decimal256::permille(5)),
+                (Decimal256::percent(95), Decimal256::percent(5)),
+            ],
+            min_fee: Decimal256::permille(5),
+            max_fee: Decimal256::percent(5),
+        }),
+        peg_twap_window: None,
+        peg_price_timeframe: None,
+        flash_mint_fee: None,
+        oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: None,
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    assert_eq!(res, Err(ContractError::InvalidBorrowFeeCurve {}));
+
+    // redemption parameters must be lower than 1
+    let msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        liquidation_contract: None,
+        borrow_fee_curve: None,
+        peg_twap_window: None,
+        peg_price_timeframe: None,
+        flash_mint_fee: None,
+        oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: Some(Decimal256::one()),
+        redemption_decay_factor: None,
+        flash_mint_max_amount: None,
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner1", &[]), msg);
+    assert_eq!(res, Err(ContractError::InvalidRedemptionFeeFloor {}));
+
+    let msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        liquidation_contract: None,
+        borrow_fee_curve: None,
+        peg_twap_window: None,
+        peg_price_timeframe: None,
+        flash_mint_fee: None,
+        oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: Some(Decimal256::one()),
+        flash_mint_max_amount: None,
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner1", &[]), msg);
+    assert_eq!(res, Err(ContractError::InvalidRedemptionDecayFactor {}));
 
     // Unauthorized err
     let info = mock_info("owner", &[]);
     let msg = ExecuteMsg::UpdateConfig {
         owner_addr: None,
         liquidation_contract: None,
-        base_borrow_fee: None,
-        fee_increase_factor: None,
+        borrow_fee_curve: None,
+        peg_twap_window: None,
+        peg_price_timeframe: None,
         flash_mint_fee: None,
         oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: None,
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
     };
 
     let res = execute(deps.as_mut(), mock_env(), info, msg);
@@ -262,8 +501,9 @@ fn borrow_stable() {
     let msg = InstantiateMsg {
         owner_addr: "owner".to_string(),
         stable_code_id: 123u64,
-        base_borrow_fee: Decimal256::from_str("0.005").unwrap(),
-        fee_increase_factor: Decimal256::from_str("2").unwrap(),
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
         flash_mint_fee: Some(Decimal256::from_str("0.00025").unwrap()),
     };
 
@@ -296,6 +536,7 @@ fn borrow_stable() {
         collector_contract: "collector".to_string(),
         liquidation_contract: "liquidation".to_string(),
         oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
     };
     let mut env = mock_env();
     let info = mock_info("owner", &[]);
@@ -315,6 +556,14 @@ fn borrow_stable() {
         deps.as_mut().storage,
         &State {
             total_liabilities: Decimal256::from_uint256(1000000u128),
+            global_interest_index: Decimal256::one(),
+            last_interest_updated: env.block.time.seconds(),
+            peg_twap: Decimal256::zero(),
+            peg_last_price: Decimal256::zero(),
+            peg_last_updated: 0u64,
+            redemption_base_rate: Decimal256::zero(),
+            last_redemption_time: 0u64,
+            last_term_loan_id: 0u64,
         },
     )
     .unwrap();
@@ -337,6 +586,8 @@ fn borrow_stable() {
     let msg = ExecuteMsg::BorrowStable {
         borrow_amount: Uint256::from(500000u64),
         to: None,
+        on_behalf_of: None,
+        position_id: None,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -347,6 +598,7 @@ fn borrow_stable() {
         vec![
             attr("action", "borrow_stable"),
             attr("borrower", "addr0000"),
+            attr("position_id", "0"),
             attr("borrow_amount", "500000"),
             attr("mint_fee", "2500")
         ]
@@ -354,15 +606,28 @@ fn borrow_stable() {
 
     assert_eq!(
         res.messages,
-        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: "solid".to_string(),
-            funds: vec![],
-            msg: to_binary(&Cw20ExecuteMsg::Mint {
-                recipient: "addr0000".to_string(),
-                amount: Uint128::from(500000u128),
-            })
-            .unwrap(),
-        }))]
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: "addr0000".to_string(),
+                    amount: Uint128::from(500000u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                    borrower: "addr0000".to_string(),
+                    loan_amount: Uint256::from(502500u128),
+                    position_id: None,
+                    is_borrow: true,
+                })
+                .unwrap(),
+            })),
+        ]
     );
 
     let res = query(
@@ -370,6 +635,7 @@ fn borrow_stable() {
         mock_env(),
         QueryMsg::BorrowerInfo {
             borrower: "addr0000".to_string(),
+            position_id: None,
         },
     )
     .unwrap();
@@ -379,6 +645,9 @@ fn borrow_stable() {
         BorrowerInfoResponse {
             borrower: "addr0000".to_string(),
             loan_amount: Uint256::from(502500u64),
+            term_loan_amount: Uint256::zero(),
+            principal_amount: Uint256::from(500000u64),
+            position_id: 0,
         }
     );
 
@@ -396,6 +665,7 @@ fn borrow_stable() {
         mock_env(),
         QueryMsg::BorrowerInfo {
             borrower: "addr0000".to_string(),
+            position_id: None,
         },
     )
     .unwrap();
@@ -406,6 +676,9 @@ fn borrow_stable() {
         BorrowerInfoResponse {
             borrower: "addr0000".to_string(),
             loan_amount: Uint256::from(502500u64),
+            term_loan_amount: Uint256::zero(),
+            principal_amount: Uint256::from(500000u64),
+            position_id: 0,
         }
     );
 
@@ -423,6 +696,7 @@ fn borrow_stable() {
         mock_env(),
         QueryMsg::BorrowerInfo {
             borrower: "addr0000".to_string(),
+            position_id: None,
         },
     )
     .unwrap();
@@ -433,6 +707,9 @@ fn borrow_stable() {
         BorrowerInfoResponse {
             borrower: "addr0000".to_string(),
             loan_amount: Uint256::from(502500u128),
+            term_loan_amount: Uint256::zero(),
+            principal_amount: Uint256::from(500000u64),
+            position_id: 0,
         }
     );
 
@@ -440,6 +717,8 @@ fn borrow_stable() {
     let msg = ExecuteMsg::BorrowStable {
         borrow_amount: Uint256::from(500001u64),
         to: None,
+        on_behalf_of: None,
+        position_id: None,
     };
     let res = execute(deps.as_mut(), env, info, msg);
     match res {
@@ -458,8 +737,9 @@ fn repay_stable() {
     let msg = InstantiateMsg {
         owner_addr: "owner".to_string(),
         stable_code_id: 123u64,
-        base_borrow_fee: Decimal256::from_str("0.005").unwrap(),
-        fee_increase_factor: Decimal256::from_str("2").unwrap(),
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
         flash_mint_fee: Some(Decimal256::from_str("0.00025").unwrap()),
     };
 
@@ -491,6 +771,7 @@ fn repay_stable() {
         collector_contract: "collector".to_string(),
         liquidation_contract: "liquidation".to_string(),
         oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
     };
     let mut env = mock_env();
     let info = mock_info("owner", &[]);
@@ -512,6 +793,14 @@ fn repay_stable() {
         deps.as_mut().storage,
         &State {
             total_liabilities: Decimal256::from_uint256(1000000u128),
+            global_interest_index: Decimal256::one(),
+            last_interest_updated: env.block.time.seconds(),
+            peg_twap: Decimal256::zero(),
+            peg_last_price: Decimal256::zero(),
+            peg_last_updated: 0u64,
+            redemption_base_rate: Decimal256::zero(),
+            last_redemption_time: 0u64,
+            last_term_loan_id: 0u64,
         },
     )
     .unwrap();
@@ -519,6 +808,8 @@ fn repay_stable() {
     let msg = ExecuteMsg::BorrowStable {
         borrow_amount: Uint256::from(500000u64),
         to: None,
+        on_behalf_of: None,
+        position_id: None,
     };
 
     env.block.height += 100;
@@ -530,7 +821,11 @@ fn repay_stable() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "addr0000".to_string(),
         amount: Uint128::from(110u128),
-        msg: to_binary(&Cw20HookMsg::RepayStable {}).unwrap(),
+        msg: to_binary(&Cw20HookMsg::RepayStable {
+            borrower: None,
+            position_id: None,
+        })
+        .unwrap(),
     });
 
     let res = execute(deps.as_mut(), env.clone(), info, msg);
@@ -544,7 +839,11 @@ fn repay_stable() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "addr0000".to_string(),
         amount: Uint128::from(0u128),
-        msg: to_binary(&Cw20HookMsg::RepayStable {}).unwrap(),
+        msg: to_binary(&Cw20HookMsg::RepayStable {
+            borrower: None,
+            position_id: None,
+        })
+        .unwrap(),
     });
 
     let _solid_string = "Solid";
@@ -557,7 +856,11 @@ fn repay_stable() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "addr0000".to_string(),
         amount: Uint128::from(100000u128),
-        msg: to_binary(&Cw20HookMsg::RepayStable {}).unwrap(),
+        msg: to_binary(&Cw20HookMsg::RepayStable {
+            borrower: None,
+            position_id: None,
+        })
+        .unwrap(),
     });
 
     let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
@@ -566,6 +869,7 @@ fn repay_stable() {
         vec![
             attr("action", "repay_stable"),
             attr("borrower", "addr0000"),
+            attr("position_id", "0"),
             attr("repay_amount", "100000"),
         ]
     );
@@ -589,7 +893,18 @@ fn repay_stable() {
                     amount: Uint128::from(498u128),
                 })
                 .unwrap(),
-            }))
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                    borrower: "addr0000".to_string(),
+                    loan_amount: Uint256::from(402500u128),
+                    position_id: None,
+                    is_borrow: false,
+                })
+                .unwrap(),
+            })),
         ]
     );
 
@@ -598,6 +913,7 @@ fn repay_stable() {
         .unwrap()
         .get(0)
         .unwrap()
+        .2
         .loan_amount;
     assert_eq!(res_loan, Uint256::from(402500u128));
     assert_eq!(
@@ -609,7 +925,11 @@ fn repay_stable() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "addr0000".to_string(),
         amount: Uint128::from(500000u128),
-        msg: to_binary(&Cw20HookMsg::RepayStable {}).unwrap(),
+        msg: to_binary(&Cw20HookMsg::RepayStable {
+            borrower: None,
+            position_id: None,
+        })
+        .unwrap(),
     });
     let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
     assert_eq!(
@@ -617,6 +937,7 @@ fn repay_stable() {
         vec![
             attr("action", "repay_stable"),
             attr("borrower", "addr0000"),
+            attr("position_id", "0"),
             attr("repay_amount", "402500"),
         ]
     );
@@ -626,6 +947,7 @@ fn repay_stable() {
         .unwrap()
         .get(0)
         .unwrap()
+        .2
         .loan_amount;
     assert_eq!(res_loan, Uint256::zero());
     assert_eq!(
@@ -661,7 +983,18 @@ fn repay_stable() {
                     amount: Uint128::from(2002u128),
                 })
                 .unwrap(),
-            }))
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                    borrower: "addr0000".to_string(),
+                    loan_amount: Uint256::zero(),
+                    position_id: None,
+                    is_borrow: false,
+                })
+                .unwrap(),
+            })),
         ]
     );
 
@@ -679,6 +1012,14 @@ fn repay_stable() {
         deps.as_mut().storage,
         &State {
             total_liabilities: Decimal256::from_uint256(1000000u128),
+            global_interest_index: Decimal256::one(),
+            last_interest_updated: env.block.time.seconds(),
+            peg_twap: Decimal256::zero(),
+            peg_last_price: Decimal256::zero(),
+            peg_last_updated: 0u64,
+            redemption_base_rate: Decimal256::zero(),
+            last_redemption_time: 0u64,
+            last_term_loan_id: 0u64,
         },
     )
     .unwrap();
@@ -686,6 +1027,8 @@ fn repay_stable() {
     let msg = ExecuteMsg::BorrowStable {
         borrow_amount: Uint256::from(500000u64),
         to: None,
+        on_behalf_of: None,
+        position_id: None,
     };
 
     env.block.height += 100;
@@ -698,7 +1041,11 @@ fn repay_stable() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "addr0000".to_string(),
         amount: Uint128::from(100000u128),
-        msg: to_binary(&Cw20HookMsg::RepayStable {}).unwrap(),
+        msg: to_binary(&Cw20HookMsg::RepayStable {
+            borrower: None,
+            position_id: None,
+        })
+        .unwrap(),
     });
     let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
     assert_eq!(
@@ -706,6 +1053,7 @@ fn repay_stable() {
         vec![
             attr("action", "repay_stable"),
             attr("borrower", "addr0000"),
+            attr("position_id", "0"),
             attr("repay_amount", "100000"),
         ]
     );
@@ -715,6 +1063,7 @@ fn repay_stable() {
         .unwrap()
         .get(0)
         .unwrap()
+        .2
         .loan_amount;
     assert_eq!(res_loan, Uint256::from(402500u64));
     assert_eq!(
@@ -741,7 +1090,18 @@ fn repay_stable() {
                     amount: Uint128::from(498u128),
                 })
                 .unwrap(),
-            }))
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                    borrower: "addr0000".to_string(),
+                    loan_amount: Uint256::from(402500u128),
+                    position_id: None,
+                    is_borrow: false,
+                })
+                .unwrap(),
+            })),
         ]
     );
 
@@ -759,7 +1119,11 @@ fn repay_stable() {
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "addr0000".to_string(),
         amount: Uint128::from(500000u128),
-        msg: to_binary(&Cw20HookMsg::RepayStable {}).unwrap(),
+        msg: to_binary(&Cw20HookMsg::RepayStable {
+            borrower: None,
+            position_id: None,
+        })
+        .unwrap(),
     });
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
     assert_eq!(
@@ -767,6 +1131,7 @@ fn repay_stable() {
         vec![
             attr("action", "repay_stable"),
             attr("borrower", "addr0000"),
+            attr("position_id", "0"),
             attr("repay_amount", "402500"),
         ]
     );
@@ -776,6 +1141,7 @@ fn repay_stable() {
         .unwrap()
         .get(0)
         .unwrap()
+        .2
         .loan_amount;
     assert_eq!(res_loan, Uint256::zero());
     assert_eq!(
@@ -811,9 +1177,166 @@ fn repay_stable() {
                     amount: Uint128::from(2002u128),
                 })
                 .unwrap(),
-            }))
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                    borrower: "addr0000".to_string(),
+                    loan_amount: Uint256::zero(),
+                    position_id: None,
+                    is_borrow: false,
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+}
+
+#[test]
+fn repay_stable_on_behalf() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "solid".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_code_id: 123u64,
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
+        flash_mint_fee: Some(Decimal256::from_str("0.00025").unwrap()),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // Register solid token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("solid".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        collector_contract: "collector".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
+    };
+    let env = mock_env();
+    let info = mock_info("owner", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    deps.querier.with_oracle_price(&[(
+        &("solid".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_str("1.03").unwrap(),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+        on_behalf_of: None,
+        position_id: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // keeper repays part of the borrower debt
+    let info = mock_info("solid", &[]);
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "keeper".to_string(),
+        amount: Uint128::from(100000u128),
+        msg: to_binary(&Cw20HookMsg::RepayStable {
+            borrower: Some("addr0000".to_string()),
+            position_id: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable"),
+            attr("borrower", "addr0000"),
+            attr("position_id", "0"),
+            attr("repay_amount", "100000"),
+        ]
+    );
+
+    let res: BorrowerInfoResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::BorrowerInfo {
+                borrower: "addr0000".to_string(),
+                position_id: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.loan_amount, Uint256::from(402500u64));
+
+    // keeper has no loan on its own
+    let res: BorrowerInfoResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::BorrowerInfo {
+                borrower: "keeper".to_string(),
+                position_id: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.loan_amount, Uint256::zero());
+
+    // overpayment is refunded to the keeper
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "keeper".to_string(),
+        amount: Uint128::from(500000u128),
+        msg: to_binary(&Cw20HookMsg::RepayStable {
+            borrower: Some("addr0000".to_string()),
+            position_id: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable"),
+            attr("borrower", "addr0000"),
+            attr("position_id", "0"),
+            attr("repay_amount", "402500"),
         ]
     );
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "solid".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "keeper".to_string(),
+                amount: Uint128::from(97500u128),
+            })
+            .unwrap(),
+        }))
+    );
 }
 
 #[test]
@@ -823,8 +1346,9 @@ fn repay_stable_from_liquidation() {
     let msg = InstantiateMsg {
         owner_addr: "owner".to_string(),
         stable_code_id: 123u64,
-        base_borrow_fee: Decimal256::from_str("0.005").unwrap(),
-        fee_increase_factor: Decimal256::from_str("2").unwrap(),
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
         flash_mint_fee: Some(Decimal256::from_str("0.00025").unwrap()),
     };
 
@@ -851,6 +1375,7 @@ fn repay_stable_from_liquidation() {
         collector_contract: "collector".to_string(),
         liquidation_contract: "liquidation".to_string(),
         oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
     };
     let env = mock_env();
     let info = mock_info("owner", &[]);
@@ -871,6 +1396,14 @@ fn repay_stable_from_liquidation() {
         deps.as_mut().storage,
         &State {
             total_liabilities: Decimal256::from_uint256(1000000u128),
+            global_interest_index: Decimal256::one(),
+            last_interest_updated: env.block.time.seconds(),
+            peg_twap: Decimal256::zero(),
+            peg_last_price: Decimal256::zero(),
+            peg_last_updated: 0u64,
+            redemption_base_rate: Decimal256::zero(),
+            last_redemption_time: 0u64,
+            last_term_loan_id: 0u64,
         },
     )
     .unwrap();
@@ -880,7 +1413,8 @@ fn repay_stable_from_liquidation() {
     _borrow_stable(
         deps.as_mut(),
         env.clone(),
-        info,
+        info.sender,
+        0,
         Uint256::from(500000u64),
         Some(Addr::unchecked("addr0000".to_string())),
     )
@@ -900,6 +1434,7 @@ fn repay_stable_from_liquidation() {
         amount: Uint128::from(100000u128),
         msg: to_binary(&Cw20HookMsg::RepayStableFromLiquidation {
             borrower: "addr0000".to_string(),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -920,6 +1455,7 @@ fn repay_stable_from_liquidation() {
         amount: Uint128::from(10u128),
         msg: to_binary(&Cw20HookMsg::RepayStableFromLiquidation {
             borrower: "addr0000".to_string(),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -936,6 +1472,7 @@ fn repay_stable_from_liquidation() {
         amount: Uint128::from(0u128),
         msg: to_binary(&Cw20HookMsg::RepayStableFromLiquidation {
             borrower: "addr0000".to_string(),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -952,6 +1489,7 @@ fn repay_stable_from_liquidation() {
         amount: Uint128::from(100000u128),
         msg: to_binary(&Cw20HookMsg::RepayStableFromLiquidation {
             borrower: "addr0000".to_string(),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -963,6 +1501,7 @@ fn repay_stable_from_liquidation() {
         vec![
             attr("action", "repay_stable"),
             attr("borrower", "addr0000"),
+            attr("position_id", "0"),
             attr("repay_amount", "100000"),
         ]
     );
@@ -987,6 +1526,17 @@ fn repay_stable_from_liquidation() {
                 })
                 .unwrap(),
             })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                    borrower: "addr0000".to_string(),
+                    loan_amount: Uint256::from(405000u128),
+                    position_id: None,
+                    is_borrow: false,
+                })
+                .unwrap(),
+            })),
         ]
     );
 
@@ -1005,6 +1555,7 @@ fn repay_stable_from_liquidation() {
         amount: Uint128::from(500000u128),
         msg: to_binary(&Cw20HookMsg::RepayStableFromLiquidation {
             borrower: "addr0000".to_string(),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -1015,6 +1566,7 @@ fn repay_stable_from_liquidation() {
         vec![
             attr("action", "repay_stable"),
             attr("borrower", "addr0000"),
+            attr("position_id", "0"),
             attr("repay_amount", "405000"),
         ]
     );
@@ -1048,13 +1600,25 @@ fn repay_stable_from_liquidation() {
                 })
                 .unwrap(),
             })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                    borrower: "addr0000".to_string(),
+                    loan_amount: Uint256::zero(),
+                    position_id: None,
+                    is_borrow: false,
+                })
+                .unwrap(),
+            })),
         ]
     );
 
     let mut env = mock_env();
     let info = mock_info("addr0000", &[]);
 
-    // simulate borrow stable again with 0.5% fee
+    // simulate borrow stable again, the twap still holds the 0.99 peg
+    // observed in this block so the fee stays at 1%
     deps.querier.with_oracle_price(&[(
         &("solid".to_string(), "uusd".to_string()),
         &(
@@ -1067,7 +1631,8 @@ fn repay_stable_from_liquidation() {
     _borrow_stable(
         deps.as_mut(),
         env.clone(),
-        info,
+        info.sender,
+        0,
         Uint256::from(500000u64),
         Some(Addr::unchecked("")),
     )
@@ -1089,9 +1654,10 @@ fn repay_stable_from_liquidation() {
     // repay exact amount + interest
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "liquidation".to_string(),
-        amount: Uint128::from(502500u128),
+        amount: Uint128::from(505000u128),
         msg: to_binary(&Cw20HookMsg::RepayStableFromLiquidation {
             borrower: "addr0000".to_string(),
+            position_id: None,
         })
         .unwrap(),
     });
@@ -1102,7 +1668,8 @@ fn repay_stable_from_liquidation() {
         vec![
             attr("action", "repay_stable"),
             attr("borrower", "addr0000"),
-            attr("repay_amount", "502500"),
+            attr("position_id", "0"),
+            attr("repay_amount", "505000"),
         ]
     );
 
@@ -1122,7 +1689,18 @@ fn repay_stable_from_liquidation() {
                 funds: vec![],
                 msg: to_binary(&Cw20ExecuteMsg::Transfer {
                     recipient: "collector".to_string(),
-                    amount: Uint128::from(2500u128),
+                    amount: Uint128::from(5000u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                    borrower: "addr0000".to_string(),
+                    loan_amount: Uint256::zero(),
+                    position_id: None,
+                    is_borrow: false,
                 })
                 .unwrap(),
             })),
@@ -1143,8 +1721,9 @@ fn flash_mint() {
     let msg = InstantiateMsg {
         owner_addr: "owner".to_string(),
         stable_code_id: 123u64,
-        base_borrow_fee: Decimal256::from_str("0.005").unwrap(),
-        fee_increase_factor: Decimal256::from_str("2").unwrap(),
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
         flash_mint_fee: None,
     };
 
@@ -1171,6 +1750,7 @@ fn flash_mint() {
         collector_contract: "collector".to_string(),
         liquidation_contract: "liquidation".to_string(),
         oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
     };
     let env = mock_env();
     let info = mock_info("owner", &[]);
@@ -1180,10 +1760,24 @@ fn flash_mint() {
     let msg = ExecuteMsg::UpdateConfig {
         owner_addr: None,
         liquidation_contract: None,
-        base_borrow_fee: None,
-        fee_increase_factor: None,
+        borrow_fee_curve: None,
+        peg_twap_window: None,
+        peg_price_timeframe: None,
         flash_mint_fee: Some(flash_mint_fee),
         oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: None,
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
@@ -1194,7 +1788,8 @@ fn flash_mint() {
 
     let msg = ExecuteMsg::FlashMint {
         amount: amount_flash_mint,
-        msg_callback: to_binary("msg_callback").unwrap(),
+        receiver: None,
+        data: to_binary("data").unwrap(),
     };
 
     let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
@@ -1213,7 +1808,13 @@ fn flash_mint() {
         SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: String::from("flash_minter"),
             funds: vec![],
-            msg: to_binary("msg_callback").unwrap(),
+            msg: to_binary(&FlashMintCallbackMsg::FlashMintCallback {
+                initiator: String::from("flash_minter"),
+                amount: amount_flash_mint,
+                fee: flash_mint_fee_amount,
+                data: to_binary("data").unwrap(),
+            })
+            .unwrap(),
         })),
         SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: env.contract.address.to_string(),
@@ -1231,6 +1832,7 @@ fn flash_mint() {
         res.attributes,
         vec![
             attr("action", "flash_mint"),
+            attr("initiator", "flash_minter"),
             attr("flash_minter", "flash_minter"),
             attr("amount", amount_flash_mint),
             attr("fee_amount", amount_flash_mint * flash_mint_fee)
@@ -1239,6 +1841,20 @@ fn flash_mint() {
 
     assert_eq!(res.messages, messages);
 
+    // Nested flash mints are rejected until the flash end
+    let msg = ExecuteMsg::FlashMint {
+        amount: amount_flash_mint,
+        receiver: None,
+        data: to_binary("data").unwrap(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("flash_minter", &[]),
+        msg,
+    );
+    assert_eq!(res, Err(ContractError::FlashMintInProgress {}));
+
     // Call private flash end
 
     // Try to call from non env.contract.address
@@ -1267,7 +1883,7 @@ fn flash_mint() {
         fee_amount: flash_mint_fee_amount,
     };
 
-    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     // Msgs that should be retrive
     let mut messages: Vec<SubMsg> = vec![];
@@ -1298,4 +1914,3291 @@ fn flash_mint() {
     }
 
     assert_eq!(res.messages, messages);
+
+    // Cap the flash mint amount and whitelist the initiators
+    let msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        liquidation_contract: None,
+        borrow_fee_curve: None,
+        peg_twap_window: None,
+        peg_price_timeframe: None,
+        flash_mint_fee: None,
+        oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: Some(amount_flash_mint),
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::UpdateFlashMinters {
+        flash_minters: Some(vec!["flash_minter".to_string()]),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("flash_minter", &[]),
+        msg.clone(),
+    );
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config_res.flash_mint_max_amount, Some(amount_flash_mint));
+    assert_eq!(
+        config_res.flash_minters,
+        Some(vec!["flash_minter".to_string()])
+    );
+
+    let msg = ExecuteMsg::FlashMint {
+        amount: amount_flash_mint + Uint256::one(),
+        receiver: None,
+        data: to_binary("data").unwrap(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("flash_minter", &[]),
+        msg,
+    );
+    assert_eq!(res, Err(ContractError::FlashMintAmountExceeded(100000u128)));
+
+    // Only the whitelisted initiator can flash mint, to any receiver
+    let msg = ExecuteMsg::FlashMint {
+        amount: amount_flash_mint,
+        receiver: Some("receiver".to_string()),
+        data: to_binary("data").unwrap(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    assert_eq!(res, Err(ContractError::FlashMinterNotWhitelisted {}));
+
+    let res = execute(deps.as_mut(), env, mock_info("flash_minter", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: String::from("receiver"),
+            funds: vec![],
+            msg: to_binary(&FlashMintCallbackMsg::FlashMintCallback {
+                initiator: String::from("flash_minter"),
+                amount: amount_flash_mint,
+                fee: flash_mint_fee_amount,
+                data: to_binary("data").unwrap(),
+            })
+            .unwrap(),
+        }))
+    );
+}
+
+#[test]
+fn accrue_interest() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_code_id: 123u64,
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
+        flash_mint_fee: None,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Register solid token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("solid".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        collector_contract: "collector".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
+    };
+    let mut env = mock_env();
+    let info = mock_info("owner", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("solid".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::one(),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    // 10% yearly stability fee
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::percent(10))]);
+
+    // borrow with 0.005 fee
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+        on_behalf_of: None,
+        position_id: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // half a year later the loan has grown by 5%
+    env.block.time = env.block.time.plus_seconds(SECONDS_PER_YEAR / 2);
+    deps.querier.with_oracle_price(&[(
+        &("solid".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::one(),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowerInfo {
+            borrower: "addr0000".to_string(),
+            position_id: None,
+        },
+    )
+    .unwrap();
+    let borrower_info: BorrowerInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(borrower_info.loan_amount, Uint256::from(527625u64));
+
+    // queries do not persist the accrual
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::State {}).unwrap();
+    let state: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state.global_interest_index, Decimal256::one());
+
+    // repay everything, principal is burned and fee plus interest go to the collector
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(527625u128),
+        msg: to_binary(&Cw20HookMsg::RepayStable {
+            borrower: None,
+            position_id: None,
+        })
+        .unwrap(),
+    });
+    let info = mock_info("solid", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::from(500000u128),
+                })
+                .unwrap()
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "collector".to_string(),
+                    amount: Uint128::from(27625u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                    borrower: "addr0000".to_string(),
+                    loan_amount: Uint256::zero(),
+                    position_id: None,
+                    is_borrow: false,
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::State {}).unwrap();
+    let state: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        state.global_interest_index,
+        Decimal256::from_str("1.05").unwrap()
+    );
+    assert_eq!(state.last_interest_updated, env.block.time.seconds());
+    assert_eq!(state.total_liabilities, Decimal256::zero());
+}
+
+#[test]
+fn simulate_queries() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_code_id: 123u64,
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
+        flash_mint_fee: Some(Decimal256::from_str("0.00025").unwrap()),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // Register solid token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("solid".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        collector_contract: "collector".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
+    };
+    let env = mock_env();
+    let info = mock_info("owner", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    deps.querier.with_oracle_price(&[(
+        &("solid".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_str("1.03").unwrap(),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    // quote matches the actual borrow
+    let res: SimulateBorrowResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SimulateBorrow {
+                borrower: "addr0000".to_string(),
+                amount: Uint256::from(500000u64),
+                position_id: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        SimulateBorrowResponse {
+            borrower: "addr0000".to_string(),
+            borrow_amount: Uint256::from(500000u64),
+            mint_fee: Uint256::from(2500u64),
+            loan_amount: Uint256::from(502500u64),
+            borrow_limit: Uint256::from(1000000u64),
+            exceeds_limit: false,
+        }
+    );
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+        on_behalf_of: None,
+        position_id: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(res.attributes[4], attr("mint_fee", "2500"));
+
+    // second borrow would exceed the limit
+    let res: SimulateBorrowResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SimulateBorrow {
+                borrower: "addr0000".to_string(),
+                amount: Uint256::from(600000u64),
+                position_id: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.mint_fee, Uint256::from(3000u64));
+    assert_eq!(res.loan_amount, Uint256::from(1105500u64));
+    assert!(res.exceeds_limit);
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(600000u64),
+        to: None,
+        on_behalf_of: None,
+        position_id: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg);
+    assert_eq!(res, Err(ContractError::BorrowExceedsLimit(1000000u128)));
+
+    // partial repay
+    let res: SimulateRepayResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SimulateRepay {
+                borrower: "addr0000".to_string(),
+                amount: Uint256::from(100000u64),
+                position_id: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        SimulateRepayResponse {
+            borrower: "addr0000".to_string(),
+            repay_amount: Uint256::from(100000u64),
+            burn_amount: Uint256::from(99502u64),
+            collector_amount: Uint256::from(498u64),
+            refund_amount: Uint256::zero(),
+            loan_amount: Uint256::from(402500u64),
+        }
+    );
+
+    // repay more then needed
+    let res: SimulateRepayResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SimulateRepay {
+                borrower: "addr0000".to_string(),
+                amount: Uint256::from(600000u64),
+                position_id: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        SimulateRepayResponse {
+            borrower: "addr0000".to_string(),
+            repay_amount: Uint256::from(502500u64),
+            burn_amount: Uint256::from(500000u64),
+            collector_amount: Uint256::from(2500u64),
+            refund_amount: Uint256::from(97500u64),
+            loan_amount: Uint256::zero(),
+        }
+    );
+
+    // simulations do not touch the loan
+    let res: BorrowerInfoResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::BorrowerInfo {
+                borrower: "addr0000".to_string(),
+                position_id: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.loan_amount, Uint256::from(502500u64));
+
+    let res: FlashMintFeeResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::FlashMintFee {
+                amount: Uint256::from(100000u64),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        FlashMintFeeResponse {
+            amount: Uint256::from(100000u64),
+            fee_amount: Uint256::from(25u64),
+            total_amount: Uint256::from(100025u64),
+        }
+    );
+}
+
+#[test]
+fn mint_ceiling() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_code_id: 123u64,
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
+        flash_mint_fee: None,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // Register solid token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("solid".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        collector_contract: "collector".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
+    };
+    let env = mock_env();
+    let info = mock_info("owner", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        liquidation_contract: None,
+        borrow_fee_curve: None,
+        peg_twap_window: None,
+        peg_price_timeframe: None,
+        flash_mint_fee: None,
+        oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: Some(Uint256::from(1000000u64)),
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: None,
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let res = query(deps.as_ref(), env.clone(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(config_res.mint_ceiling, Some(Uint256::from(1000000u64)));
+
+    deps.querier.with_borrow_limit(&[
+        (&"addr0000".to_string(), &Uint256::from(10000000u64)),
+        (&"addr0001".to_string(), &Uint256::from(10000000u64)),
+    ]);
+    deps.querier.with_oracle_price(&[(
+        &("solid".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::one(),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(600000u64),
+        to: None,
+        on_behalf_of: None,
+        position_id: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // the ceiling is global, other borrowers are limited too
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(400001u64),
+        to: None,
+        on_behalf_of: None,
+        position_id: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("addr0001", &[]), msg);
+    assert_eq!(res, Err(ContractError::MintCeilingExceeded(1000000u128)));
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(400000u64),
+        to: None,
+        on_behalf_of: None,
+        position_id: None,
+    };
+    let _res = execute(deps.as_mut(), env, mock_info("addr0001", &[]), msg).unwrap();
+    assert_eq!(
+        read_state(deps.as_ref().storage).unwrap().total_liabilities,
+        Decimal256::from_uint256(1000000u64)
+    );
+}
+
+#[test]
+fn repay_with_collateral() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_code_id: 123u64,
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
+        flash_mint_fee: Some(Decimal256::percent(1)),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // Register solid token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("solid".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        collector_contract: "collector".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
+    };
+    let env = mock_env();
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(10000000u64))]);
+    deps.querier.with_oracle_price(&[(
+        &("solid".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::one(),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(1000000u64),
+        to: None,
+        on_behalf_of: None,
+        position_id: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowerInfo {
+            borrower: "addr0000".to_string(),
+            position_id: None,
+        },
+    )
+    .unwrap();
+    let loan_amount = from_binary::<BorrowerInfoResponse>(&res)
+        .unwrap()
+        .loan_amount;
+
+    let msg = ExecuteMsg::RepayWithCollateral {
+        collateral_token: "bluna".to_string(),
+        amount: Uint256::from(1000u64),
+        min_solid_out: Uint256::from(505000u64),
+        position_id: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    assert_eq!(res, Err(ContractError::RouterNotConfigured {}));
+
+    let msg_config = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        liquidation_contract: None,
+        borrow_fee_curve: None,
+        peg_twap_window: None,
+        peg_price_timeframe: None,
+        flash_mint_fee: None,
+        oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: Some("router".to_string()),
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: None,
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
+    };
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        msg_config,
+    )
+    .unwrap();
+
+    // 505000 SOLID out cover a 500000 repay and its 1% flash mint fee
+    let res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: MOCK_CONTRACT_ADDR.to_string(),
+                    amount: Uint128::from(500000u64),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::from(497512u64),
+                })
+                .unwrap(),
+            })),
+            // borrow fee part of the loan
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "collector".to_string(),
+                    amount: Uint128::from(2488u64),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                    borrower: "addr0000".to_string(),
+                    loan_amount: loan_amount - Uint256::from(500000u64),
+                    position_id: None,
+                    is_borrow: false,
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::ReleaseCollateral {
+                    borrower: "addr0000".to_string(),
+                    collaterals: vec![("bluna".to_string(), Uint256::from(1000u64))],
+                    recipient: MOCK_CONTRACT_ADDR.to_string(),
+                    position_id: None,
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "bluna".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: "router".to_string(),
+                    amount: Uint128::from(1000u64),
+                    msg: to_binary(&RouterCw20HookMsg::ExecuteSwapOperations {
+                        operations: vec![SwapOperation::AstroSwap {
+                            offer_asset_info: AssetInfo::Token {
+                                contract_addr: Addr::unchecked("bluna"),
+                            },
+                            ask_asset_info: AssetInfo::Token {
+                                contract_addr: Addr::unchecked("solid"),
+                            },
+                        }],
+                        minimum_receive: Some(Uint128::from(505000u64)),
+                        to: None,
+                        max_spread: None,
+                    })
+                    .unwrap(),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::PrivateRepayWithCollateralEnd {
+                    borrower: "addr0000".to_string(),
+                    burn_amount: Uint256::from(500000u64),
+                    fee_amount: Uint256::from(5000u64),
+                    prev_balance: Uint256::zero(),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    // no flash mint can start before the repay with collateral ends
+    let flash_mint_msg = ExecuteMsg::FlashMint {
+        amount: Uint256::from(1000u64),
+        receiver: None,
+        data: to_binary("data").unwrap(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        flash_mint_msg.clone(),
+    );
+    assert_eq!(res, Err(ContractError::FlashMintInProgress {}));
+
+    // the swap output must cover the flash mint and its fee
+    deps.querier.with_token_balances(&[(
+        &"solid".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(504000u64))],
+    )]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        ExecuteMsg::PrivateRepayWithCollateralEnd {
+            borrower: "addr0000".to_string(),
+            burn_amount: Uint256::from(500000u64),
+            fee_amount: Uint256::from(5000u64),
+            prev_balance: Uint256::zero(),
+        },
+    );
+    assert_eq!(res, Err(ContractError::InsufficientSwapOutput {}));
+
+    // the swap returned 510000 SOLID
+    deps.querier.with_token_balances(&[(
+        &"solid".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(510000u64))],
+    )]);
+
+    let msg = ExecuteMsg::PrivateRepayWithCollateralEnd {
+        borrower: "addr0000".to_string(),
+        burn_amount: Uint256::from(500000u64),
+        fee_amount: Uint256::from(5000u64),
+        prev_balance: Uint256::zero(),
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::from(500000u64),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "collector".to_string(),
+                    amount: Uint128::from(5000u64),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "addr0000".to_string(),
+                    amount: Uint128::from(5000u64),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("addr0000", &[]),
+        flash_mint_msg,
+    );
+    assert!(res.is_ok());
+}
+
+#[test]
+fn multiply() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_code_id: 123u64,
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
+        flash_mint_fee: Some(Decimal256::percent(1)),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // Register solid token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("solid".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        collector_contract: "collector".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
+    };
+    let env = mock_env();
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+    deps.querier.with_whitelist(&[(
+        &"bluna".to_string(),
+        &("custody_bluna".to_string(), Decimal256::percent(60)),
+    )]);
+    deps.querier.with_oracle_price(&[
+        (
+            &("solid".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::one(),
+                env.block.time.seconds(),
+                env.block.time.seconds(),
+            ),
+        ),
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time.seconds(),
+                env.block.time.seconds(),
+            ),
+        ),
+    ]);
+    // the sent collateral is already in the market balance
+    deps.querier.with_token_balances(&[(
+        &"bluna".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1000u64))],
+    )]);
+
+    let multiply_msg = |target_ltv: Decimal256| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "addr0000".to_string(),
+            amount: Uint128::from(1000u64),
+            msg: to_binary(&Cw20HookMsg::Multiply {
+                target_ltv,
+                min_collateral_out: Uint128::from(900u64).into(),
+                position_id: None,
+            })
+            .unwrap(),
+        })
+    };
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("bluna", &[]),
+        multiply_msg(Decimal256::percent(50)),
+    );
+    assert_eq!(res, Err(ContractError::RouterNotConfigured {}));
+
+    let msg_config = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        liquidation_contract: None,
+        borrow_fee_curve: None,
+        peg_twap_window: None,
+        peg_price_timeframe: None,
+        flash_mint_fee: None,
+        oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: Some("router".to_string()),
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: None,
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
+    };
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        msg_config,
+    )
+    .unwrap();
+
+    // not whitelisted collateral
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("batom", &[]),
+        multiply_msg(Decimal256::percent(50)),
+    );
+    assert!(res.is_err());
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("bluna", &[]),
+        multiply_msg(Decimal256::percent(61)),
+    );
+    assert_eq!(
+        res,
+        Err(ContractError::TargetLtvExceedsMaxLtv("0.6".to_string()))
+    );
+
+    // 1000 bluna worth 1000000 at 50% ltv with a 1% flash mint fee and a 0.5% borrow fee:
+    // flash mint 500000 / ((1 + 0.01) * (1 + 0.005) - 0.5) = 970779 SOLID
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("bluna", &[]),
+        multiply_msg(Decimal256::percent(50)),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: MOCK_CONTRACT_ADDR.to_string(),
+                    amount: Uint128::from(970779u64),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: "router".to_string(),
+                    amount: Uint128::from(970779u64),
+                    msg: to_binary(&RouterCw20HookMsg::ExecuteSwapOperations {
+                        operations: vec![SwapOperation::AstroSwap {
+                            offer_asset_info: AssetInfo::Token {
+                                contract_addr: Addr::unchecked("solid"),
+                            },
+                            ask_asset_info: AssetInfo::Token {
+                                contract_addr: Addr::unchecked("bluna"),
+                            },
+                        }],
+                        minimum_receive: Some(Uint128::from(900u64)),
+                        to: None,
+                        max_spread: None,
+                    })
+                    .unwrap(),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::PrivateMultiplyLock {
+                    borrower: "addr0000".to_string(),
+                    collateral_token: "bluna".to_string(),
+                    collateral_amount: Uint256::from(1000u64),
+                    prev_balance: Uint256::from(1000u64),
+                    position_id: None,
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::PrivateMultiplyEnd {
+                    borrower: "addr0000".to_string(),
+                    flash_amount: Uint256::from(970779u64),
+                    fee_amount: Uint256::from(9707u64),
+                    position_id: None,
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    // the flash mint lock rejects another flash mint until the multiply ends
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("bluna", &[]),
+        multiply_msg(Decimal256::percent(50)),
+    );
+    assert_eq!(res, Err(ContractError::FlashMintInProgress {}));
+
+    // the swap returned 975 bluna
+    deps.querier.with_token_balances(&[(
+        &"bluna".to_string(),
+        &[(&MOCK_CONTRACT_ADDR.to_string(), &Uint128::from(1975u64))],
+    )]);
+
+    let msg = ExecuteMsg::PrivateMultiplyLock {
+        borrower: "addr0000".to_string(),
+        collateral_token: "bluna".to_string(),
+        collateral_amount: Uint256::from(1000u64),
+        prev_balance: Uint256::from(1000u64),
+        position_id: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        msg,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "bluna".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: "custody_bluna".to_string(),
+                    amount: Uint128::from(1975u64),
+                    msg: to_binary(&CustodyCw20HookMsg::DepositCollateral {
+                        borrower: Some("addr0000".to_string()),
+                    })
+                    .unwrap(),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::LockCollateralFor {
+                    borrower: "addr0000".to_string(),
+                    collaterals: vec![("bluna".to_string(), Uint256::from(1975u64))],
+                    position_id: None,
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    let msg = ExecuteMsg::PrivateMultiplyEnd {
+        borrower: "addr0000".to_string(),
+        flash_amount: Uint256::from(970779u64),
+        fee_amount: Uint256::from(9707u64),
+        position_id: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    // the borrow limit rejects a position above max ltv
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(985000u64))]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        msg.clone(),
+    );
+    assert_eq!(res, Err(ContractError::BorrowExceedsLimit(985000u128)));
+
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1185000u64))]);
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        msg,
+    )
+    .unwrap();
+
+    let res_info = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::BorrowerInfo {
+            borrower: "addr0000".to_string(),
+            position_id: None,
+        },
+    )
+    .unwrap();
+    let loan_amount = from_binary::<BorrowerInfoResponse>(&res_info)
+        .unwrap()
+        .loan_amount;
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: MOCK_CONTRACT_ADDR.to_string(),
+                    amount: Uint128::from(980486u64),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                    borrower: "addr0000".to_string(),
+                    loan_amount,
+                    position_id: None,
+                    is_borrow: true,
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::from(970779u64),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "collector".to_string(),
+                    amount: Uint128::from(9707u64),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    // the multiply end released the lock, the market flash mint is capped like the others
+    let msg_config = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        liquidation_contract: None,
+        borrow_fee_curve: None,
+        peg_twap_window: None,
+        peg_price_timeframe: None,
+        flash_mint_fee: None,
+        oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: Some(Uint256::from(900000u64)),
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
+    };
+    let _res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        msg_config,
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bluna", &[]),
+        multiply_msg(Decimal256::percent(50)),
+    );
+    assert_eq!(res, Err(ContractError::FlashMintAmountExceeded(900000u128)));
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bluna", &[]),
+        multiply_msg(Decimal256::percent(40)),
+    );
+    assert!(res.is_ok());
+}
+
+#[test]
+fn borrow_allowance() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_code_id: 123u64,
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
+        flash_mint_fee: Some(Decimal256::percent(1)),
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // Register solid token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("solid".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        collector_contract: "collector".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
+    };
+    let mut env = mock_env();
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    deps.querier.with_oracle_price(&[(
+        &("solid".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::one(),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let borrow_msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(200000u64),
+        to: None,
+        on_behalf_of: Some("addr0000".to_string()),
+        position_id: None,
+    };
+
+    // not approved
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("strategy", &[]),
+        borrow_msg.clone(),
+    );
+    assert_eq!(res, Err(ContractError::BorrowAllowanceExceeded(0u128)));
+
+    let msg = ExecuteMsg::ApproveBorrow {
+        spender: "strategy".to_string(),
+        amount: Uint256::from(300000u64),
+        expires: Some(env.block.time.seconds() + 100u64),
+        position_id: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "approve_borrow"),
+            attr("owner", "addr0000"),
+            attr("position_id", "0"),
+            attr("spender", "strategy"),
+            attr("amount", "300000"),
+        ]
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("strategy", &[]),
+        borrow_msg.clone(),
+    )
+    .unwrap();
+    let res_info = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowerInfo {
+            borrower: "addr0000".to_string(),
+            position_id: None,
+        },
+    )
+    .unwrap();
+    let loan_amount = from_binary::<BorrowerInfoResponse>(&res_info)
+        .unwrap()
+        .loan_amount;
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: "strategy".to_string(),
+                    amount: Uint128::from(200000u64),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                    borrower: "addr0000".to_string(),
+                    loan_amount,
+                    position_id: None,
+                    is_borrow: true,
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    // the allowance does not cover the other positions of the owner
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("strategy", &[]),
+        ExecuteMsg::BorrowStable {
+            borrow_amount: Uint256::from(50000u64),
+            to: None,
+            on_behalf_of: Some("addr0000".to_string()),
+            position_id: Some(1u32),
+        },
+    );
+    assert_eq!(res, Err(ContractError::BorrowAllowanceExceeded(0u128)));
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowAllowances {
+            owner: "addr0000".to_string(),
+            position_id: Some(1u32),
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        from_binary::<BorrowAllowancesResponse>(&res).unwrap(),
+        BorrowAllowancesResponse { allowances: vec![] }
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowAllowance {
+            owner: "addr0000".to_string(),
+            spender: "strategy".to_string(),
+            position_id: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        from_binary::<BorrowAllowanceResponse>(&res).unwrap(),
+        BorrowAllowanceResponse {
+            owner: "addr0000".to_string(),
+            position_id: DEFAULT_POSITION_ID,
+            spender: "strategy".to_string(),
+            amount: Uint256::from(100000u64),
+            expires: Some(env.block.time.seconds() + 100u64),
+        }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("strategy", &[]),
+        borrow_msg.clone(),
+    );
+    assert_eq!(res, Err(ContractError::BorrowAllowanceExceeded(100000u128)));
+
+    let msg = ExecuteMsg::ApproveBorrow {
+        spender: "strategy2".to_string(),
+        amount: Uint256::from(50000u64),
+        expires: None,
+        position_id: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowAllowances {
+            owner: "addr0000".to_string(),
+            position_id: None,
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        from_binary::<BorrowAllowancesResponse>(&res).unwrap(),
+        BorrowAllowancesResponse {
+            allowances: vec![
+                BorrowAllowanceResponse {
+                    owner: "addr0000".to_string(),
+                    position_id: DEFAULT_POSITION_ID,
+                    spender: "strategy".to_string(),
+                    amount: Uint256::from(100000u64),
+                    expires: Some(env.block.time.seconds() + 100u64),
+                },
+                BorrowAllowanceResponse {
+                    owner: "addr0000".to_string(),
+                    position_id: DEFAULT_POSITION_ID,
+                    spender: "strategy2".to_string(),
+                    amount: Uint256::from(50000u64),
+                    expires: None,
+                },
+            ]
+        }
+    );
+
+    // expired allowance
+    env.block.time = env.block.time.plus_seconds(101u64);
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(10000u64),
+        to: None,
+        on_behalf_of: Some("addr0000".to_string()),
+        position_id: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("strategy", &[]), msg);
+    assert_eq!(res, Err(ContractError::BorrowAllowanceExpired {}));
+
+    // revoke
+    let msg = ExecuteMsg::ApproveBorrow {
+        spender: "strategy".to_string(),
+        amount: Uint256::zero(),
+        expires: None,
+        position_id: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::BorrowAllowances {
+            owner: "addr0000".to_string(),
+            position_id: None,
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        from_binary::<BorrowAllowancesResponse>(&res).unwrap(),
+        BorrowAllowancesResponse {
+            allowances: vec![BorrowAllowanceResponse {
+                owner: "addr0000".to_string(),
+                position_id: DEFAULT_POSITION_ID,
+                spender: "strategy2".to_string(),
+                amount: Uint256::from(50000u64),
+                expires: None,
+            }]
+        }
+    );
+}
+
+#[test]
+fn mint_stable() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_code_id: 123u64,
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
+        flash_mint_fee: None,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // Register solid token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("solid".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let mint_msg = ExecuteMsg::MintStable {
+        recipient: "addr0000".to_string(),
+        amount: Uint256::from(100000u64),
+    };
+
+    // no psm registered
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("psm", &[]),
+        mint_msg.clone(),
+    );
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    let msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        liquidation_contract: None,
+        borrow_fee_curve: None,
+        peg_twap_window: None,
+        peg_price_timeframe: None,
+        flash_mint_fee: None,
+        oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: Some("psm".to_string()),
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: None,
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+    // only the psm can mint
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        mint_msg.clone(),
+    );
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("psm", &[]), mint_msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "solid".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Mint {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::from(100000u128),
+            })
+            .unwrap(),
+        }))]
+    );
+
+    // no debt recorded
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+    let state: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state.total_liabilities, Decimal256::zero());
+}
+
+#[test]
+fn redeem() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_code_id: 123u64,
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
+        flash_mint_fee: None,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // Register solid token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("solid".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        collector_contract: "collector".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
+    };
+    let info = mock_info("owner", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let env = mock_env();
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.total_liabilities = Decimal256::from_uint256(101000000u64);
+    state.last_interest_updated = env.block.time.seconds();
+    store_state(&mut deps.storage, &state).unwrap();
+
+    // addr0001 is underwater, addr0002 has no interest and addr0003 has some plus a term loan
+    for (borrower, loan_amount, loan_amount_without_interest) in [
+        ("addr0001", 500000u64, 500000u64),
+        ("addr0002", 300000u64, 300000u64),
+        ("addr0003", 200000u64, 150000u64),
+    ] {
+        store_borrower_info(
+            &mut deps.storage,
+            &Addr::unchecked(borrower),
+            0,
+            &BorrowerInfo {
+                loan_amount: Uint256::from(loan_amount),
+                loan_amount_without_interest: Uint256::from(loan_amount_without_interest),
+                interest_index: Decimal256::one(),
+            },
+        )
+        .unwrap();
+    }
+
+    store_term_loan(
+        &mut deps.storage,
+        &Addr::unchecked("addr0003"),
+        0,
+        &TermLoan {
+            loan_id: 1u64,
+            loan_amount: Uint256::from(100000u64),
+            loan_amount_without_interest: Uint256::from(95000u64),
+            maturity: env.block.time.seconds() + 86400u64,
+            penalty_updated: env.block.time.seconds(),
+        },
+    )
+    .unwrap();
+
+    deps.querier.with_collaterals(&[
+        (
+            &"addr0001".to_string(),
+            &Decimal256::percent(50),
+            &[("bluna".to_string(), Uint256::from(1000u64))],
+        ),
+        (
+            &"addr0002".to_string(),
+            &Decimal256::percent(120),
+            &[("bluna".to_string(), Uint256::from(600000u64))],
+        ),
+        (
+            &"addr0003".to_string(),
+            &Decimal256::percent(300),
+            &[("bluna".to_string(), Uint256::from(1000000u64))],
+        ),
+    ]);
+    // at current prices addr0003 is less healthy than addr0002, unlike their stored health
+    deps.querier.with_borrow_limit(&[
+        (&"addr0001".to_string(), &Uint256::from(250000u64)),
+        (&"addr0002".to_string(), &Uint256::from(900000u64)),
+        (&"addr0003".to_string(), &Uint256::from(450000u64)),
+    ]);
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::one(),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    // 0.5% floor + 1010000 / 101000000 / 2 base rate bound the redeemed amount to 1000000
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "redeemer".to_string(),
+        amount: Uint128::from(1010000u128),
+        msg: to_binary(&Cw20HookMsg::Redeem {
+            max_fee: Decimal256::permille(5),
+        })
+        .unwrap(),
+    });
+
+    // only solid can be redeemed
+    let res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg);
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "redeemer".to_string(),
+        amount: Uint128::from(1010000u128),
+        msg: to_binary(&Cw20HookMsg::Redeem {
+            max_fee: Decimal256::permille(4),
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), env.clone(), mock_info("solid", &[]), msg);
+    assert_eq!(
+        res,
+        Err(ContractError::RedemptionFeeExceedsMax("0.005".to_string()))
+    );
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "redeemer".to_string(),
+        amount: Uint128::from(1010000u128),
+        msg: to_binary(&Cw20HookMsg::Redeem {
+            max_fee: Decimal256::percent(1),
+        })
+        .unwrap(),
+    });
+    // addr0003 goes first, its term loan before its open loan;
+    // the base rate is bumped by the 600000 SOLID redeemed
+    let res = execute(deps.as_mut(), env.clone(), mock_info("solid", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::from(95000u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "collector".to_string(),
+                    amount: Uint128::from(5000u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::RedeemCollateral {
+                    borrower: "addr0003".to_string(),
+                    collaterals: vec![("bluna".to_string(), Uint256::from(300000u64))],
+                    recipient: "redeemer".to_string(),
+                    position_id: None,
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                    borrower: "addr0003".to_string(),
+                    loan_amount: Uint256::zero(),
+                    position_id: None,
+                    is_borrow: false,
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::RedeemCollateral {
+                    borrower: "addr0002".to_string(),
+                    collaterals: vec![("bluna".to_string(), Uint256::from(300000u64))],
+                    recipient: "redeemer".to_string(),
+                    position_id: None,
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                    borrower: "addr0002".to_string(),
+                    loan_amount: Uint256::zero(),
+                    position_id: None,
+                    is_borrow: false,
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::from(450000u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "collector".to_string(),
+                    amount: Uint128::from(54782u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "redeemer".to_string(),
+                    amount: Uint128::from(405218u128),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    let state: State = read_state(&deps.storage).unwrap();
+    assert_eq!(
+        state.total_liabilities,
+        Decimal256::from_uint256(100455000u64)
+    );
+    assert_eq!(
+        state.redemption_base_rate,
+        Decimal256::from_str("0.00297029702970297").unwrap()
+    );
+    assert_eq!(state.last_redemption_time, env.block.time.seconds());
+
+    // base rate halves in 12 hours
+    let config = read_config(&deps.storage).unwrap();
+    let decayed = compute_decayed_base_rate(&config, &state, env.block.time.seconds() + 12 * 3600);
+    assert!(decayed > Decimal256::from_ratio(1484u64, 1000000u64));
+    assert!(decayed < Decimal256::from_ratio(1486u64, 1000000u64));
+
+    // nothing left to redeem
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "redeemer".to_string(),
+        amount: Uint128::from(100000u128),
+        msg: to_binary(&Cw20HookMsg::Redeem {
+            max_fee: Decimal256::percent(10),
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), env, mock_info("solid", &[]), msg);
+    assert_eq!(res, Err(ContractError::NoRedeemablePosition {}));
+}
+
+#[test]
+fn term_loan() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_code_id: 123u64,
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
+        flash_mint_fee: None,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // Register solid token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("solid".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        collector_contract: "collector".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
+    };
+    let info = mock_info("owner", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let mut env = mock_env();
+    deps.querier.with_oracle_price(&[(
+        &("solid".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::one(),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::zero())]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(2000000u64))]);
+
+    let maturity = env.block.time.seconds() + SECONDS_PER_YEAR / 2;
+    let msg = ExecuteMsg::BorrowTerm {
+        borrow_amount: Uint256::from(1000000u64),
+        maturity,
+        max_fee: Uint256::from(30000u64),
+        to: None,
+        position_id: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+    assert_eq!(res, Err(ContractError::TermLoansDisabled {}));
+
+    // the penalty rate is required along with the term loan rate
+    let update_msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        liquidation_contract: None,
+        borrow_fee_curve: None,
+        peg_twap_window: None,
+        peg_price_timeframe: None,
+        flash_mint_fee: None,
+        oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: None,
+        term_loan_rate: Some(Decimal256::percent(5)),
+        term_loan_penalty_rate: None,
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        update_msg,
+    );
+    assert_eq!(res, Err(ContractError::InvalidTermLoanRates {}));
+
+    let update_msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        liquidation_contract: None,
+        borrow_fee_curve: None,
+        peg_twap_window: None,
+        peg_price_timeframe: None,
+        flash_mint_fee: None,
+        oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: None,
+        term_loan_rate: Some(Decimal256::percent(5)),
+        term_loan_penalty_rate: Some(Decimal256::percent(100)),
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        update_msg,
+    );
+    assert_eq!(res, Err(ContractError::InvalidTermLoanRates {}));
+
+    let update_msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        liquidation_contract: None,
+        borrow_fee_curve: None,
+        peg_twap_window: None,
+        peg_price_timeframe: None,
+        flash_mint_fee: None,
+        oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: None,
+        term_loan_rate: Some(Decimal256::percent(5)),
+        term_loan_penalty_rate: Some(Decimal256::percent(10)),
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
+    };
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        update_msg,
+    )
+    .unwrap();
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::BorrowTerm {
+            borrow_amount: Uint256::from(1000000u64),
+            maturity: env.block.time.seconds(),
+            max_fee: Uint256::from(30000u64),
+            to: None,
+            position_id: None,
+        },
+    );
+    assert_eq!(res, Err(ContractError::InvalidMaturity {}));
+
+    // 0.5% one time fee and 5% a year for half a year
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::TermLoanQuote {
+            borrower: "addr0000".to_string(),
+            amount: Uint256::from(1000000u64),
+            maturity,
+        },
+    )
+    .unwrap();
+    let quote: TermLoanQuoteResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        quote,
+        TermLoanQuoteResponse {
+            borrow_amount: Uint256::from(1000000u64),
+            maturity,
+            fee_amount: Uint256::from(30000u64),
+            loan_amount: Uint256::from(1030000u64),
+        }
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        ExecuteMsg::BorrowTerm {
+            borrow_amount: Uint256::from(1000000u64),
+            maturity,
+            max_fee: Uint256::from(29999u64),
+            to: None,
+            position_id: None,
+        },
+    );
+    assert_eq!(res, Err(ContractError::TermLoanFeeExceedsMax(30000u128)));
+
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Mint {
+                    recipient: "addr0000".to_string(),
+                    amount: Uint128::from(1000000u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                    borrower: "addr0000".to_string(),
+                    loan_amount: Uint256::from(1030000u64),
+                    position_id: None,
+                    is_borrow: true,
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+    assert_eq!(res.attributes[3], attr("loan_id", "1"));
+
+    // Term loans count in the borrower loan checked by the overseer
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowerInfo {
+            borrower: "addr0000".to_string(),
+            position_id: None,
+        },
+    )
+    .unwrap();
+    let liability: BorrowerInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        liability,
+        BorrowerInfoResponse {
+            borrower: "addr0000".to_string(),
+            loan_amount: Uint256::from(1030000u64),
+            term_loan_amount: Uint256::from(1030000u64),
+            principal_amount: Uint256::from(1000000u64),
+            position_id: 0,
+        }
+    );
+
+    // Overdue for a tenth of a year, 10% a year penalty
+    env.block.time = env
+        .block
+        .time
+        .plus_seconds(SECONDS_PER_YEAR / 2 + SECONDS_PER_YEAR / 10);
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::TermLoans {
+            borrower: "addr0000".to_string(),
+            start_after: None,
+            limit: None,
+            position_id: None,
+        },
+    )
+    .unwrap();
+    let term_loans: TermLoansResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        term_loans.term_loans,
+        vec![TermLoanResponse {
+            borrower: "addr0000".to_string(),
+            loan_id: 1,
+            loan_amount: Uint256::from(1040300u64),
+            maturity,
+            overdue: true,
+        }]
+    );
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(1100000u128),
+        msg: to_binary(&Cw20HookMsg::RepayTermLoan {
+            loan_id: 2,
+            borrower: None,
+            position_id: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), env.clone(), mock_info("solid", &[]), msg);
+    assert_eq!(res, Err(ContractError::TermLoanNotFound(2)));
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(1100000u128),
+        msg: to_binary(&Cw20HookMsg::RepayTermLoan {
+            loan_id: 1,
+            borrower: None,
+            position_id: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), env.clone(), mock_info("solid", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "addr0000".to_string(),
+                    amount: Uint128::from(59700u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::from(1000000u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "collector".to_string(),
+                    amount: Uint128::from(40300u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                    borrower: "addr0000".to_string(),
+                    loan_amount: Uint256::zero(),
+                    position_id: None,
+                    is_borrow: false,
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    let state: State = read_state(&deps.storage).unwrap();
+    assert_eq!(state.total_liabilities, Decimal256::zero());
+    let _res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::TermLoan {
+            borrower: "addr0000".to_string(),
+            loan_id: 1,
+            position_id: None,
+        },
+    )
+    .unwrap_err();
+
+    // clearing the rates disables new term loans
+    let update_msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        liquidation_contract: None,
+        borrow_fee_curve: None,
+        peg_twap_window: None,
+        peg_price_timeframe: None,
+        flash_mint_fee: None,
+        oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: None,
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        remove_term_loans: Some(true),
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
+    };
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        update_msg,
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        env,
+        mock_info("addr0000", &[]),
+        ExecuteMsg::BorrowTerm {
+            borrow_amount: Uint256::from(1000000u64),
+            maturity,
+            max_fee: Uint256::from(30000u64),
+            to: None,
+            position_id: None,
+        },
+    );
+    assert_eq!(res, Err(ContractError::TermLoansDisabled {}));
+}
+
+#[test]
+fn settle_bad_debt_with_term_loan() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_code_id: 123u64,
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
+        flash_mint_fee: None,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // Register solid token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("solid".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        collector_contract: "collector".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
+    };
+    let info = mock_info("owner", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let env = mock_env();
+    deps.querier.with_oracle_price(&[(
+        &("solid".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::one(),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+    deps.querier
+        .with_borrow_rate(&[(&"interest".to_string(), &Decimal256::zero())]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(2000000u64))]);
+
+    let maturity = env.block.time.seconds() + SECONDS_PER_YEAR / 2;
+    let msg = ExecuteMsg::BorrowTerm {
+        borrow_amount: Uint256::from(1000000u64),
+        maturity,
+        max_fee: Uint256::from(30000u64),
+        to: None,
+        position_id: None,
+    };
+    let info = mock_info("addr0000", &[]);
+
+    let update_msg = ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        liquidation_contract: None,
+        borrow_fee_curve: None,
+        peg_twap_window: None,
+        peg_price_timeframe: None,
+        flash_mint_fee: None,
+        oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: None,
+        term_loan_rate: Some(Decimal256::percent(5)),
+        term_loan_penalty_rate: Some(Decimal256::percent(10)),
+        remove_term_loans: None,
+        capa_gov_contract: None,
+        borrow_fee_discount_tiers: None,
+    };
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        update_msg,
+    )
+    .unwrap();
+
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // only the overseer settles bad debt
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "liquidation".to_string(),
+        amount: Uint128::from(600000u128),
+        msg: to_binary(&Cw20HookMsg::SettleBadDebt {
+            borrower: "addr0000".to_string(),
+            position_id: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), env.clone(), mock_info("solid", &[]), msg);
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    // the fixed fee is not paid from the reserve
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "overseer".to_string(),
+        amount: Uint128::from(1030000u128),
+        msg: to_binary(&Cw20HookMsg::SettleBadDebt {
+            borrower: "addr0000".to_string(),
+            position_id: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), env.clone(), mock_info("solid", &[]), msg);
+    assert_eq!(
+        res,
+        Err(ContractError::SettleAmountExceedsPrincipal(1000000u128))
+    );
+
+    // a partial settlement burns the principal and writes the fee off
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "overseer".to_string(),
+        amount: Uint128::from(600000u128),
+        msg: to_binary(&Cw20HookMsg::SettleBadDebt {
+            borrower: "addr0000".to_string(),
+            position_id: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), env.clone(), mock_info("solid", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::from(600000u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "overseer".to_string(),
+                funds: vec![],
+                msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                    borrower: "addr0000".to_string(),
+                    loan_amount: Uint256::from(400000u64),
+                    position_id: None,
+                    is_borrow: false,
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowerInfo {
+            borrower: "addr0000".to_string(),
+            position_id: None,
+        },
+    )
+    .unwrap();
+    let liability: BorrowerInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        liability,
+        BorrowerInfoResponse {
+            borrower: "addr0000".to_string(),
+            position_id: 0,
+            loan_amount: Uint256::from(400000u64),
+            term_loan_amount: Uint256::from(400000u64),
+            principal_amount: Uint256::from(400000u64),
+        }
+    );
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "overseer".to_string(),
+        amount: Uint128::from(400000u128),
+        msg: to_binary(&Cw20HookMsg::SettleBadDebt {
+            borrower: "addr0000".to_string(),
+            position_id: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), env.clone(), mock_info("solid", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "settle_bad_debt"),
+            attr("borrower", "addr0000"),
+            attr("position_id", "0"),
+            attr("settle_amount", "400000"),
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::BorrowerInfo {
+            borrower: "addr0000".to_string(),
+            position_id: None,
+        },
+    )
+    .unwrap();
+    let liability: BorrowerInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(liability.loan_amount, Uint256::zero());
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::State {}).unwrap();
+    let state: StateResponse = from_binary(&res).unwrap();
+    assert_eq!(state.total_liabilities, Decimal256::zero());
+}
+
+#[test]
+fn borrow_fee_discount() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_code_id: 123u64,
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
+        flash_mint_fee: None,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // Register solid token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("solid".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        collector_contract: "collector".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
+    };
+    let info = mock_info("owner", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let env = mock_env();
+    deps.querier.with_oracle_price(&[(
+        &("solid".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::one(),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(2000000u64))]);
+    deps.querier.with_gov_stakers(&[
+        (&"addr0000".to_string(), &Uint128::from(5000u128)),
+        (&"addr0001".to_string(), &Uint128::from(500u128)),
+    ]);
+
+    let update_config = |tiers: Vec<BorrowFeeDiscountTier>| ExecuteMsg::UpdateConfig {
+        owner_addr: None,
+        liquidation_contract: None,
+        borrow_fee_curve: None,
+        peg_twap_window: None,
+        peg_price_timeframe: None,
+        flash_mint_fee: None,
+        oracle_addr: None,
+        interest_model_contract: None,
+        mint_ceiling: None,
+        remove_mint_ceiling: None,
+        astroport_router: None,
+        psm_contract: None,
+        redemption_fee_floor: None,
+        redemption_decay_factor: None,
+        flash_mint_max_amount: None,
+        term_loan_rate: None,
+        term_loan_penalty_rate: None,
+        remove_term_loans: None,
+        capa_gov_contract: Some("gov".to_string()),
+        borrow_fee_discount_tiers: Some(tiers),
+    };
+
+    // tiers must be sorted by increasing stake
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        update_config(vec![
+            BorrowFeeDiscountTier {
+                min_staked: Uint256::from(1000u64),
+                discount: Decimal256::percent(10),
+            },
+            BorrowFeeDiscountTier {
+                min_staked: Uint256::from(1000u64),
+                discount: Decimal256::percent(20),
+            },
+        ]),
+    );
+    assert_eq!(res, Err(ContractError::InvalidBorrowFeeDiscountTiers {}));
+
+    // a discount can not exceed the fee
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        update_config(vec![BorrowFeeDiscountTier {
+            min_staked: Uint256::from(1000u64),
+            discount: Decimal256::percent(101),
+        }]),
+    );
+    assert_eq!(res, Err(ContractError::InvalidBorrowFeeDiscountTiers {}));
+
+    let tiers = vec![
+        BorrowFeeDiscountTier {
+            min_staked: Uint256::from(1000u64),
+            discount: Decimal256::percent(10),
+        },
+        BorrowFeeDiscountTier {
+            min_staked: Uint256::from(5000u64),
+            discount: Decimal256::percent(40),
+        },
+    ];
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        update_config(tiers.clone()),
+    )
+    .unwrap();
+
+    let res: BorrowFeeDiscountTiersResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::BorrowFeeDiscountTiers {},
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res, BorrowFeeDiscountTiersResponse { tiers });
+
+    let res: BorrowFeeDiscountResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::BorrowFeeDiscount {
+                borrower: "addr0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        res,
+        BorrowFeeDiscountResponse {
+            borrower: "addr0000".to_string(),
+            staked_amount: Uint256::from(5000u64),
+            discount: Decimal256::percent(40),
+        }
+    );
+
+    // below the lowest tier
+    let res: BorrowFeeDiscountResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::BorrowFeeDiscount {
+                borrower: "addr0001".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.discount, Decimal256::zero());
+
+    // 0.5% fee with a 40% discount
+    let res: SimulateBorrowResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SimulateBorrow {
+                borrower: "addr0000".to_string(),
+                amount: Uint256::from(1000000u64),
+                position_id: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.mint_fee, Uint256::from(3000u64));
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(1000000u64),
+        to: None,
+        on_behalf_of: None,
+        position_id: None,
+    };
+    let res = execute(deps.as_mut(), env, mock_info("addr0000", &[]), msg).unwrap();
+    assert_eq!(res.attributes[4], attr("mint_fee", "3000"));
+}
+
+#[test]
+fn fee_distribution() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_code_id: 123u64,
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
+        flash_mint_fee: None,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    // Register solid token contract
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("solid".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        collector_contract: "collector".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
+    };
+    let env = mock_env();
+    let info = mock_info("owner", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    deps.querier
+        .with_borrow_limit(&[(&"addr0000".to_string(), &Uint256::from(1000000u64))]);
+    deps.querier.with_oracle_price(&[(
+        &("solid".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_str("1.03").unwrap(),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let recipients = vec![
+        FeeRecipient {
+            kind: FeeRecipientKind::Collector,
+            address: "collector".to_string(),
+            weight: 6000,
+        },
+        FeeRecipient {
+            kind: FeeRecipientKind::Reserve,
+            address: "reserve".to_string(),
+            weight: 2500,
+        },
+        FeeRecipient {
+            kind: FeeRecipientKind::Treasury,
+            address: "treasury".to_string(),
+            weight: 1500,
+        },
+    ];
+
+    let msg = ExecuteMsg::UpdateFeeRecipients {
+        recipients: recipients.clone(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg);
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    // weights must sum to 10000
+    let mut invalid_recipients = recipients.clone();
+    invalid_recipients[2].weight = 1000;
+    let msg = ExecuteMsg::UpdateFeeRecipients {
+        recipients: invalid_recipients,
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg);
+    assert_eq!(
+        res,
+        Err(ContractError::Std(StdError::generic_err(
+            "Fee recipient weights must sum to 10000"
+        )))
+    );
+
+    let msg = ExecuteMsg::UpdateFeeRecipients {
+        recipients: recipients.clone(),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+        on_behalf_of: None,
+        position_id: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+
+    // the 498 of fee repaid is split by weight, the rounding goes to the collector
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(100000u128),
+        msg: to_binary(&Cw20HookMsg::RepayStable {
+            borrower: None,
+            position_id: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), env.clone(), mock_info("solid", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages[1..4],
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "collector".to_string(),
+                    amount: Uint128::from(300u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: "reserve".to_string(),
+                    amount: Uint128::from(124u128),
+                    msg: to_binary(&OverseerCw20HookMsg::FundReserve {}).unwrap(),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "treasury".to_string(),
+                    amount: Uint128::from(74u128),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    let res: FeeDistributionResponse =
+        from_binary(&query(deps.as_ref(), env, QueryMsg::FeeDistribution {}).unwrap()).unwrap();
+    assert_eq!(
+        res,
+        FeeDistributionResponse {
+            recipients,
+            paid: vec![
+                FeePaid {
+                    kind: FeeRecipientKind::Collector,
+                    amount: Uint256::from(300u64),
+                },
+                FeePaid {
+                    kind: FeeRecipientKind::Reserve,
+                    amount: Uint256::from(124u64),
+                },
+                FeePaid {
+                    kind: FeeRecipientKind::Treasury,
+                    amount: Uint256::from(74u64),
+                },
+            ],
+        }
+    );
+}
+
+#[test]
+fn isolated_positions() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "solid".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_code_id: 123u64,
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
+        flash_mint_fee: Some(Decimal256::from_str("0.00025").unwrap()),
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "solid".to_string(),
+            amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+        }],
+    );
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("solid".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        collector_contract: "collector".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
+    };
+    let env = mock_env();
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("solid".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::one(),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+    store_state(
+        deps.as_mut().storage,
+        &State {
+            total_liabilities: Decimal256::from_uint256(1000000u128),
+            global_interest_index: Decimal256::one(),
+            last_interest_updated: env.block.time.seconds(),
+            peg_twap: Decimal256::zero(),
+            peg_last_price: Decimal256::zero(),
+            peg_last_updated: 0u64,
+            redemption_base_rate: Decimal256::zero(),
+            last_redemption_time: 0u64,
+            last_term_loan_id: 0u64,
+        },
+    )
+    .unwrap();
+
+    // the default position and position 1 of addr0000 have their own borrow limit
+    deps.querier.with_borrow_limit(&[
+        (&"addr0000".to_string(), &Uint256::from(1000000u64)),
+        (&"addr0000/1".to_string(), &Uint256::from(100000u64)),
+    ]);
+
+    let info = mock_info("addr0000", &[]);
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(500000u64),
+        to: None,
+        on_behalf_of: None,
+        position_id: None,
+    };
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // the default position loan does not count against position 1
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(100000u64),
+        to: None,
+        on_behalf_of: None,
+        position_id: Some(1),
+    };
+    match execute(deps.as_mut(), env.clone(), info.clone(), msg) {
+        Err(ContractError::BorrowExceedsLimit(100000)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::BorrowStable {
+        borrow_amount: Uint256::from(50000u64),
+        to: None,
+        on_behalf_of: None,
+        position_id: Some(1),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.messages[1],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "overseer".to_string(),
+            funds: vec![],
+            msg: to_binary(&OverseerExecuteMsg::UpdateBorrowerDebt {
+                borrower: "addr0000".to_string(),
+                loan_amount: Uint256::from(50250u128),
+                position_id: Some(1),
+                is_borrow: true,
+            })
+            .unwrap(),
+        }))
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowerInfo {
+            borrower: "addr0000".to_string(),
+            position_id: Some(1),
+        },
+    )
+    .unwrap();
+    let borrower_info: BorrowerInfoResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        borrower_info,
+        BorrowerInfoResponse {
+            borrower: "addr0000".to_string(),
+            position_id: 1,
+            loan_amount: Uint256::from(50250u64),
+            term_loan_amount: Uint256::zero(),
+            principal_amount: Uint256::from(50000u64),
+        }
+    );
+
+    // positions are listed by address, then by position id
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowerInfos {
+            start_after: None,
+            start_after_position_id: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let borrower_infos: BorrowerInfosResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        borrower_infos.borrower_infos,
+        vec![
+            BorrowerInfoResponse {
+                borrower: "addr0000".to_string(),
+                position_id: 0,
+                loan_amount: Uint256::from(502500u64),
+                term_loan_amount: Uint256::zero(),
+                principal_amount: Uint256::from(500000u64),
+            },
+            BorrowerInfoResponse {
+                borrower: "addr0000".to_string(),
+                position_id: 1,
+                loan_amount: Uint256::from(50250u64),
+                term_loan_amount: Uint256::zero(),
+                principal_amount: Uint256::from(50000u64),
+            },
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::BorrowerInfos {
+            start_after: Some("addr0000".to_string()),
+            start_after_position_id: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let borrower_infos: BorrowerInfosResponse = from_binary(&res).unwrap();
+    assert_eq!(borrower_infos.borrower_infos.len(), 1);
+    assert_eq!(borrower_infos.borrower_infos[0].position_id, 1);
+}
+
+#[test]
+fn transfer_liability() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "solid".to_string(),
+        amount: Uint128::from(INITIAL_DEPOSIT_AMOUNT),
+    }]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_code_id: 123u64,
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
+        flash_mint_fee: Some(Decimal256::from_str("0.00025").unwrap()),
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        collector_contract: "collector".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+    let liability = BorrowerInfo {
+        loan_amount: Uint256::from(1000u64),
+        loan_amount_without_interest: Uint256::from(900u64),
+        interest_index: Decimal256::percent(110),
+    };
+    let term_loan = TermLoan {
+        loan_id: 1u64,
+        loan_amount: Uint256::from(500u64),
+        loan_amount_without_interest: Uint256::from(500u64),
+        maturity: 1000u64,
+        penalty_updated: 1000u64,
+    };
+    let owner = Addr::unchecked("addr0000");
+    let recipient = Addr::unchecked("addr0001");
+    store_borrower_info(deps.as_mut().storage, &owner, 1, &liability).unwrap();
+    store_term_loan(deps.as_mut().storage, &owner, 1, &term_loan).unwrap();
+
+    let msg = ExecuteMsg::TransferLiability {
+        borrower: "addr0000".to_string(),
+        recipient: "addr0001".to_string(),
+        position_id: Some(1),
+    };
+    match execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    ) {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("overseer", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "transfer_liability"),
+            attr("borrower", "addr0000"),
+            attr("recipient", "addr0001"),
+            attr("position_id", "1"),
+        ]
+    );
+    assert_eq!(read_borrower_info(&deps.storage, &recipient, 1), liability);
+    assert_eq!(
+        read_all_term_loans(&deps.storage, &recipient, 1).unwrap(),
+        vec![term_loan]
+    );
+    assert_eq!(
+        read_borrower_info(&deps.storage, &owner, 1).loan_amount,
+        Uint256::zero()
+    );
+    assert!(read_all_term_loans(&deps.storage, &owner, 1)
+        .unwrap()
+        .is_empty());
+
+    // the position of the recipient is not empty anymore
+    store_borrower_info(deps.as_mut().storage, &owner, 1, &liability).unwrap();
+    let msg = ExecuteMsg::TransferLiability {
+        borrower: "addr0000".to_string(),
+        recipient: "addr0001".to_string(),
+        position_id: Some(1),
+    };
+    match execute(deps.as_mut(), mock_env(), mock_info("overseer", &[]), msg) {
+        Err(ContractError::PositionAlreadyExists {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn query_cw20_token_balance() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_balances(&[(
+        &"solid".to_string(),
+        &[(&"addr0000".to_string(), &Uint128::from(1000u128))],
+    )]);
+
+    // The cw20 balance response is decoded
+    let balance = query_token_balance(
+        deps.as_ref(),
+        Addr::unchecked("solid"),
+        Addr::unchecked("addr0000"),
+    )
+    .unwrap();
+    assert_eq!(balance, Uint256::from(1000u64));
+
+    // Unknown accounts have no balance
+    let balance = query_token_balance(
+        deps.as_ref(),
+        Addr::unchecked("solid"),
+        Addr::unchecked("addr0001"),
+    )
+    .unwrap();
+    assert_eq!(balance, Uint256::zero());
+}
+
+#[test]
+fn redeem_pages_health_index() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        stable_code_id: 123u64,
+        borrow_fee_curve: borrow_fee_curve(),
+        peg_twap_window: 1200u64,
+        peg_price_timeframe: 60u64,
+        flash_mint_fee: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let mut token_inst_res = MsgInstantiateContractResponse::new();
+    token_inst_res.set_contract_address("solid".to_string());
+    let reply_msg = Reply {
+        id: 1,
+        result: SubMsgResult::Ok(SubMsgResponse {
+            events: vec![],
+            data: Some(token_inst_res.write_to_bytes().unwrap().into()),
+        }),
+    };
+    let _res = reply(deps.as_mut(), mock_env(), reply_msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterContracts {
+        overseer_contract: "overseer".to_string(),
+        collector_contract: "collector".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        oracle_contract: "oracle".to_string(),
+        interest_model_contract: "interest".to_string(),
+    };
+    let info = mock_info("owner", &[]);
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let env = mock_env();
+    let mut state: State = read_state(&deps.storage).unwrap();
+    state.total_liabilities = Decimal256::from_uint256(101000000u64);
+    state.last_interest_updated = env.block.time.seconds();
+    store_state(&mut deps.storage, &state).unwrap();
+
+    // a full page of repaid positions comes before the only redeemable one
+    let borrowers: Vec<String> = (0..31).map(|i| format!("addr{:04}", 100 + i)).collect();
+    let collaterals: Vec<(String, Uint256)> = vec![("bluna".to_string(), Uint256::from(600000u64))];
+    let health_factors: Vec<Decimal256> = (0..31u64).map(Decimal256::percent).collect();
+    deps.querier.with_collaterals(
+        &borrowers
+            .iter()
+            .zip(health_factors.iter())
+            .map(|(borrower, health_factor)| (borrower, health_factor, collaterals.as_slice()))
+            .collect::<Vec<(&String, &Decimal256, &[(String, Uint256)])>>(),
+    );
+
+    let last_borrower = borrowers.last().unwrap();
+    store_borrower_info(
+        &mut deps.storage,
+        &Addr::unchecked(last_borrower),
+        0,
+        &BorrowerInfo {
+            loan_amount: Uint256::from(300000u64),
+            loan_amount_without_interest: Uint256::from(300000u64),
+            interest_index: Decimal256::one(),
+        },
+    )
+    .unwrap();
+    deps.querier
+        .with_borrow_limit(&[(last_borrower, &Uint256::from(400000u64))]);
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::one(),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "redeemer".to_string(),
+        amount: Uint128::from(101000u128),
+        msg: to_binary(&Cw20HookMsg::Redeem {
+            max_fee: Decimal256::percent(1),
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), env, mock_info("solid", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "overseer".to_string(),
+            funds: vec![],
+            msg: to_binary(&OverseerExecuteMsg::RedeemCollateral {
+                borrower: last_borrower.to_string(),
+                collaterals: vec![("bluna".to_string(), Uint256::from(100447u64))],
+                recipient: "redeemer".to_string(),
+                position_id: None,
+            })
+            .unwrap(),
+        }))
+    );
 }
@@ -5,7 +5,7 @@ use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use moneymarket::overseer::{
     AllCollateralsResponse, BorrowLimitResponse, CollateralsResponse, ConfigResponse, ExecuteMsg,
-    InstantiateMsg, QueryMsg, WhitelistResponse,
+    InstantiateMsg, QueryMsg, ReserveResponse, WhitelistResponse,
 };
 
 fn main() {
@@ -21,5 +21,6 @@ fn main() {
     export_schema(&schema_for!(BorrowLimitResponse), &out_dir);
     export_schema(&schema_for!(CollateralsResponse), &out_dir);
     export_schema(&schema_for!(ConfigResponse), &out_dir);
+    export_schema(&schema_for!(ReserveResponse), &out_dir);
     export_schema(&schema_for!(WhitelistResponse), &out_dir);
 }
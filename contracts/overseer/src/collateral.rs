@@ -1,35 +1,89 @@
 use cosmwasm_bignumber::math::{Decimal256, Uint256};
 use cosmwasm_std::{
-    attr, to_binary, Addr, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StdResult, SubMsg,
-    WasmMsg,
+    attr, to_binary, Addr, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    Storage, SubMsg, WasmMsg,
 };
 
+use crate::emode::read_position_emode_category;
 use crate::error::ContractError;
-use crate::querier::{query_borrower_info, query_liquidation_amount};
+use crate::querier::{query_borrower_info, query_borrower_infos, query_liquidation_amount};
 use crate::state::{
-    read_all_collaterals, read_collaterals, read_config, read_whitelist_elem, store_collaterals,
-    Config, WhitelistElem,
+    clear_borrower_debts, read_all_collaterals, read_borrower_debts, read_borrowers_by_health,
+    read_collaterals, read_config, read_pending_liquidations, read_position_emode,
+    read_whitelist_elem, read_whitelist_elems, store_borrower_debts, store_borrower_health,
+    store_collaterals, store_pending_liquidations, store_whitelist_elem, Config, EmodeCategory,
+    PendingLiquidation, WhitelistElem,
 };
 
 use moneymarket::custody::ExecuteMsg as CustodyExecuteMsg;
 use moneymarket::liquidation_queue::LiquidationAmountResponse;
 use moneymarket::market::BorrowerInfoResponse;
 use moneymarket::oracle::PriceResponse;
-use moneymarket::overseer::{AllCollateralsResponse, BorrowLimitResponse, CollateralsResponse};
+use moneymarket::overseer::{
+    AllCollateralsResponse, BorrowLimitResponse, BorrowerPosition, BorrowersByHealthResponse,
+    CollateralHealth, CollateralStatus, CollateralsResponse, LiquidatablePosition,
+    LiquidatablePositionsResponse, PositionHealthResponse,
+};
+use moneymarket::position::{optional_position_id, PositionId, DEFAULT_POSITION_ID};
 use moneymarket::querier::{query_price, TimeConstraints};
-use moneymarket::tokens::{Tokens, TokensHuman, TokensMath, TokensToHuman, TokensToRaw};
+use moneymarket::tokens::{Token, Tokens, TokensHuman, TokensMath, TokensToHuman, TokensToRaw};
 
 pub fn lock_collateral(
     deps: DepsMut,
-    info: MessageInfo,
+    env: Env,
+    borrower: Addr,
+    position_id: PositionId,
     collaterals_human: TokensHuman,
 ) -> Result<Response, ContractError> {
-    let mut cur_collaterals: Tokens = read_collaterals(deps.storage, &info.sender);
+    let mut cur_collaterals: Tokens = read_collaterals(deps.storage, &borrower, position_id);
 
     let collaterals: Tokens = collaterals_human.to_raw(deps.as_ref())?;
+    let emode_category = read_position_emode_category(deps.storage, &borrower, position_id)?;
+    for collateral in collaterals.iter() {
+        let whitelist_elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
+        if whitelist_elem.status != CollateralStatus::Active {
+            return Err(ContractError::CollateralNotActive(collateral.0.to_string()));
+        }
+        if let Some(emode_category) = emode_category.as_ref() {
+            if !emode_category.collateral_tokens.contains(&collateral.0) {
+                return Err(ContractError::CollateralNotInEmodeCategory(
+                    collateral.0.to_string(),
+                ));
+            }
+        }
+    }
 
     cur_collaterals.add(collaterals.clone());
-    store_collaterals(deps.storage, &info.sender, &cur_collaterals)?;
+    store_collaterals(deps.storage, &borrower, position_id, &cur_collaterals)?;
+
+    // A position with a loan gets healthier and its debt is spread over the new collaterals
+    let loan_amount = read_borrower_debts(deps.storage, &borrower, position_id)
+        .iter()
+        .fold(Uint256::zero(), |total, debt| total + debt.1);
+    if !loan_amount.is_zero() {
+        let (borrow_limit, collateral_prices) = compute_borrow_limit(
+            deps.as_ref(),
+            &cur_collaterals,
+            emode_category.as_ref(),
+            env.block.time.seconds(),
+            false,
+        )?;
+        attribute_debts(
+            deps.storage,
+            &borrower,
+            position_id,
+            &cur_collaterals,
+            &collateral_prices,
+            loan_amount,
+            false,
+        )?;
+        store_borrower_health(
+            deps.storage,
+            &borrower,
+            position_id,
+            compute_borrow_health(borrow_limit, loan_amount),
+        )?;
+    }
 
     let mut messages: Vec<CosmosMsg> = vec![];
     for collateral in collaterals {
@@ -38,7 +92,7 @@ pub fn lock_collateral(
             contract_addr: whitelist_elem.custody_contract.to_string(),
             funds: vec![],
             msg: to_binary(&CustodyExecuteMsg::LockCollateral {
-                borrower: info.sender.to_string(),
+                borrower: borrower.to_string(),
                 amount: collateral.1,
             })?,
         }));
@@ -52,42 +106,88 @@ pub fn lock_collateral(
 
     Ok(Response::new().add_messages(messages).add_attributes(vec![
         attr("action", "lock_collateral"),
-        attr("borrower", info.sender),
+        attr("borrower", borrower),
+        attr("position_id", position_id.to_string()),
         attr("collaterals", collateral_logs.join(",")),
     ]))
 }
 
-pub fn unlock_collateral(
+/// Locks collaterals deposited on behalf of a borrower.
+/// Executor: market, to open a multiply position
+pub fn lock_collateral_for(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    borrower: Addr,
+    position_id: PositionId,
     collaterals_human: TokensHuman,
 ) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
-    let market = config.market_contract;
+    if info.sender != config.market_contract {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    lock_collateral(deps, env, borrower, position_id, collaterals_human)
+}
 
+pub fn unlock_collateral(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    position_id: PositionId,
+    collaterals_human: TokensHuman,
+) -> Result<Response, ContractError> {
     let borrower = info.sender;
-    let mut cur_collaterals: Tokens = read_collaterals(deps.storage, &borrower);
     let collaterals: Tokens = collaterals_human.to_raw(deps.as_ref())?;
+    remove_collaterals(deps.branch(), &env, &borrower, position_id, &collaterals)?;
 
-    // Underflow check is done in sub_collateral
-    if cur_collaterals.sub(collaterals.clone()).is_err() {
-        return Err(ContractError::UnlockExceedsLocked {});
+    let mut messages: Vec<SubMsg> = vec![];
+    for collateral in collaterals {
+        let whitelist_elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
+        messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: whitelist_elem.custody_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&CustodyExecuteMsg::UnlockCollateral {
+                borrower: borrower.to_string(),
+                amount: collateral.1,
+            })?,
+        })));
     }
 
-    // Compute borrow limit with collaterals except unlock target collaterals
-    let (borrow_limit, _) = compute_borrow_limit(
-        deps.as_ref(),
-        &cur_collaterals,
-        Some(env.block.time.seconds()),
-    )?;
-    let borrow_amount_res: BorrowerInfoResponse =
-        query_borrower_info(deps.as_ref(), market, borrower.clone())?;
-    if borrow_limit < borrow_amount_res.loan_amount {
-        return Err(ContractError::UnlockTooLarge(borrow_limit.into()));
+    // Logging stuff, so can be removed
+    let collateral_logs: Vec<String> = collaterals_human
+        .iter()
+        .map(|c| format!("{}{}", c.1, c.0))
+        .collect();
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attributes(vec![
+            attr("action", "unlock_collateral"),
+            attr("borrower", borrower),
+            attr("position_id", position_id.to_string()),
+            attr("collaterals", collateral_logs.join(",")),
+        ]))
+}
+
+/// Unlocks collaterals of a borrower and sends them to the recipient.
+/// Executor: market, to repay a loan with collateral
+pub fn release_collateral(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrower: Addr,
+    position_id: PositionId,
+    collaterals_human: TokensHuman,
+    recipient: Addr,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if info.sender != config.market_contract {
+        return Err(ContractError::Unauthorized {});
     }
 
-    store_collaterals(deps.storage, &borrower, &cur_collaterals)?;
+    let collaterals: Tokens = collaterals_human.to_raw(deps.as_ref())?;
+    remove_collaterals(deps.branch(), &env, &borrower, position_id, &collaterals)?;
 
     let mut messages: Vec<SubMsg> = vec![];
     for collateral in collaterals {
@@ -95,14 +195,14 @@ pub fn unlock_collateral(
         messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
             contract_addr: whitelist_elem.custody_contract.to_string(),
             funds: vec![],
-            msg: to_binary(&CustodyExecuteMsg::UnlockCollateral {
+            msg: to_binary(&CustodyExecuteMsg::WithdrawLockedCollateral {
                 borrower: borrower.to_string(),
+                recipient: recipient.to_string(),
                 amount: collateral.1,
             })?,
         })));
     }
 
-    // Logging stuff, so can be removed
     let collateral_logs: Vec<String> = collaterals_human
         .iter()
         .map(|c| format!("{}{}", c.1, c.0))
@@ -111,37 +211,268 @@ pub fn unlock_collateral(
     Ok(Response::new()
         .add_submessages(messages)
         .add_attributes(vec![
-            attr("action", "unlock_collateral"),
+            attr("action", "release_collateral"),
             attr("borrower", borrower),
+            attr("recipient", recipient),
             attr("collaterals", collateral_logs.join(",")),
         ]))
 }
 
+/// Sends collaterals of a borrower to a SOLID redeemer. The market reduces
+/// the loan by the collaterals value, so the position is not checked here.
+/// Executor: market, to redeem SOLID against the riskiest positions
+pub fn redeem_collateral(
+    deps: DepsMut,
+    info: MessageInfo,
+    borrower: Addr,
+    position_id: PositionId,
+    collaterals_human: TokensHuman,
+    recipient: Addr,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if info.sender != config.market_contract {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let collaterals: Tokens = collaterals_human.to_raw(deps.as_ref())?;
+    let mut cur_collaterals: Tokens = read_collaterals(deps.storage, &borrower, position_id);
+    if cur_collaterals.sub(collaterals.clone()).is_err() {
+        return Err(ContractError::UnlockExceedsLocked {});
+    }
+    store_collaterals(deps.storage, &borrower, position_id, &cur_collaterals)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for collateral in collaterals {
+        let whitelist_elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: whitelist_elem.custody_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&CustodyExecuteMsg::WithdrawLockedCollateral {
+                borrower: borrower.to_string(),
+                recipient: recipient.to_string(),
+                amount: collateral.1,
+            })?,
+        }));
+    }
+
+    let collateral_logs: Vec<String> = collaterals_human
+        .iter()
+        .map(|c| format!("{}{}", c.1, c.0))
+        .collect();
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "redeem_collateral"),
+        attr("borrower", borrower),
+        attr("recipient", recipient),
+        attr("collaterals", collateral_logs.join(",")),
+    ]))
+}
+
+/// Removes collaterals from a borrower position,
+/// failing if the remaining ones do not cover the loan
+fn remove_collaterals(
+    deps: DepsMut,
+    env: &Env,
+    borrower: &Addr,
+    position_id: PositionId,
+    collaterals: &Tokens,
+) -> Result<(), ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let mut cur_collaterals: Tokens = read_collaterals(deps.storage, borrower, position_id);
+
+    // Underflow check is done in sub_collateral
+    if cur_collaterals.sub(collaterals.clone()).is_err() {
+        return Err(ContractError::UnlockExceedsLocked {});
+    }
+
+    // Compute borrow limit with collaterals except unlock target collaterals
+    let emode_category = read_position_emode_category(deps.storage, borrower, position_id)?;
+    let (borrow_limit, collateral_prices) = compute_borrow_limit(
+        deps.as_ref(),
+        &cur_collaterals,
+        emode_category.as_ref(),
+        env.block.time.seconds(),
+        true,
+    )?;
+    let borrow_amount_res: BorrowerInfoResponse = query_borrower_info(
+        deps.as_ref(),
+        config.market_contract,
+        borrower.clone(),
+        position_id,
+    )?;
+    if borrow_limit < borrow_amount_res.loan_amount {
+        return Err(ContractError::UnlockTooLarge(borrow_limit.into()));
+    }
+
+    store_collaterals(deps.storage, borrower, position_id, &cur_collaterals)?;
+    attribute_debts(
+        deps.storage,
+        borrower,
+        position_id,
+        &cur_collaterals,
+        &collateral_prices,
+        borrow_amount_res.loan_amount,
+        false,
+    )?;
+    store_borrower_health(
+        deps.storage,
+        borrower,
+        position_id,
+        compute_borrow_health(borrow_limit, borrow_amount_res.loan_amount),
+    )?;
+
+    Ok(())
+}
+
 pub fn liquidate_collateral(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     borrower: Addr,
+    position_id: PositionId,
+) -> Result<Response, ContractError> {
+    let borrower_validated = deps.api.addr_validate(borrower.as_str())?;
+    let liquidation_messages =
+        liquidate_position(deps, &env, &info.sender, &borrower_validated, position_id)?;
+
+    Ok(Response::new().add_messages(liquidation_messages.into_iter().map(|(_, msg)| msg)))
+}
+
+/// Liquidates every unsafe position of the batch, the safe or failing ones are skipped
+/// and the outcome of each position is reported in the attributes. The messages of a
+/// position reply on error, so that a failing one gives its collaterals back to the position
+pub fn liquidate_collaterals(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrowers: Vec<BorrowerPosition>,
+) -> Result<Response, ContractError> {
+    let mut messages: Vec<SubMsg> = vec![];
+    let mut pending_liquidations: Vec<PendingLiquidation> = vec![];
+    let mut attributes = vec![attr("action", "liquidate_collaterals")];
+    let mut liquidated: Vec<(Addr, PositionId)> = vec![];
+    for target in borrowers {
+        let position_id = target.position_id.unwrap_or(DEFAULT_POSITION_ID);
+        attributes.push(attr("borrower", target.borrower.clone()));
+        attributes.push(attr("position_id", position_id.to_string()));
+
+        let borrower = match deps.api.addr_validate(&target.borrower) {
+            Ok(borrower) => borrower,
+            Err(err) => {
+                attributes.push(attr("result", "failed"));
+                attributes.push(attr("error", err.to_string()));
+                continue;
+            }
+        };
+        // the loan is only repaid once the liquidation messages are executed
+        if liquidated.contains(&(borrower.clone(), position_id)) {
+            attributes.push(attr("result", "duplicate"));
+            continue;
+        }
+
+        match liquidate_position(deps.branch(), &env, &info.sender, &borrower, position_id) {
+            Ok(liquidation_messages) => {
+                for (collateral, msg) in liquidation_messages {
+                    messages.push(SubMsg::reply_on_error(
+                        msg,
+                        pending_liquidations.len() as u64,
+                    ));
+                    pending_liquidations.push(PendingLiquidation {
+                        borrower: borrower.clone(),
+                        position_id,
+                        collateral_token: collateral.0,
+                        amount: collateral.1,
+                    });
+                }
+                liquidated.push((borrower, position_id));
+                attributes.push(attr("result", "liquidated"));
+            }
+            Err(ContractError::CannotLiquidateSafeLoan {}) => {
+                attributes.push(attr("result", "safe"));
+            }
+            Err(err) => {
+                attributes.push(attr("result", "failed"));
+                attributes.push(attr("error", err.to_string()));
+            }
+        }
+    }
+
+    store_pending_liquidations(deps.storage, &pending_liquidations)?;
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attributes(attributes))
+}
+
+/// Gives the collateral of a failed batch liquidation message back to its position
+pub fn restore_liquidated_collateral(
+    deps: DepsMut,
+    reply_id: u64,
+    error: String,
 ) -> Result<Response, ContractError> {
+    let pending_liquidation = read_pending_liquidations(deps.storage)?
+        .into_iter()
+        .nth(reply_id as usize)
+        .ok_or(ContractError::InvalidReplyId {})?;
+
+    let borrower = pending_liquidation.borrower;
+    let position_id = pending_liquidation.position_id;
+    let mut cur_collaterals: Tokens = read_collaterals(deps.storage, &borrower, position_id);
+    cur_collaterals.add(vec![(
+        pending_liquidation.collateral_token.clone(),
+        pending_liquidation.amount,
+    )]);
+    store_collaterals(deps.storage, &borrower, position_id, &cur_collaterals)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "restore_liquidated_collateral"),
+        attr("borrower", borrower),
+        attr("position_id", position_id.to_string()),
+        attr("collateral_token", pending_liquidation.collateral_token),
+        attr("amount", pending_liquidation.amount),
+        attr("result", "failed"),
+        attr("error", error),
+    ]))
+}
+
+/// Liquidation messages of an unsafe position. Every check and query is done
+/// before the position is updated, so that a failing liquidation leaves it untouched
+fn liquidate_position(
+    deps: DepsMut,
+    env: &Env,
+    liquidator: &Addr,
+    borrower: &Addr,
+    position_id: PositionId,
+) -> Result<Vec<(Token, CosmosMsg)>, ContractError> {
     let config: Config = read_config(deps.storage)?;
     let market = config.market_contract;
 
-    let borrower_validated = deps.api.addr_validate(borrower.as_str())?;
-    let mut cur_collaterals: Tokens = read_collaterals(deps.storage, &borrower_validated);
+    let mut cur_collaterals: Tokens = read_collaterals(deps.storage, borrower, position_id);
 
     // Compute borrow limit with collaterals except unlock target collaterals
+    let block_time = env.block.time.seconds();
+    let emode_category = read_position_emode_category(deps.storage, borrower, position_id)?;
     let (borrow_limit, collateral_prices) = compute_borrow_limit(
         deps.as_ref(),
         &cur_collaterals,
-        Some(env.block.time.seconds()),
+        emode_category.as_ref(),
+        block_time,
+        true,
     )?;
     let borrow_amount_res: BorrowerInfoResponse =
-        query_borrower_info(deps.as_ref(), market, borrower_validated.clone())?;
+        query_borrower_info(deps.as_ref(), market, borrower.clone(), position_id)?;
     let borrow_amount = borrow_amount_res.loan_amount;
 
-    // borrow limit is equal or bigger than loan amount
+    // liquidation limit is equal or bigger than loan amount
     // cannot liquidation collaterals
-    if borrow_limit >= borrow_amount {
+    let liquidation_limit = compute_liquidation_limit(
+        deps.as_ref(),
+        &cur_collaterals,
+        &collateral_prices,
+        emode_category.as_ref(),
+        block_time,
+    )?;
+    if liquidation_limit >= borrow_amount {
         return Err(ContractError::CannotLiquidateSafeLoan {});
     }
 
@@ -150,44 +481,256 @@ pub fn liquidate_collateral(
         config.liquidation_contract,
         borrow_amount,
         borrow_limit,
+        liquidation_limit,
         &cur_collaterals.to_human(deps.as_ref())?,
         collateral_prices,
+        compute_max_ltvs(
+            deps.as_ref(),
+            &cur_collaterals,
+            emode_category.as_ref(),
+            block_time,
+        )?,
     )?;
 
     let liquidation_amount = liquidation_amount_res.collaterals.to_raw(deps.as_ref())?;
-
-    // Store left collaterals
     cur_collaterals.sub(liquidation_amount.clone())?;
-    store_collaterals(deps.storage, &borrower_validated, &cur_collaterals)?;
 
-    let liquidation_messages: Vec<CosmosMsg> = liquidation_amount
+    let liquidation_messages: Vec<(Token, CosmosMsg)> = liquidation_amount
         .iter()
         .filter(|amount| amount.1.ge(&Uint256::one()))
         .map(|collateral| {
             let whitelist_elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
             // OVERSEER
-            Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            let msg = CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: whitelist_elem.custody_contract.to_string(),
                 funds: vec![],
                 msg: to_binary(&CustodyExecuteMsg::LiquidateCollateral {
-                    liquidator: info.sender.to_string(),
-                    borrower: borrower_validated.to_string(),
+                    liquidator: liquidator.to_string(),
+                    borrower: borrower.to_string(),
                     amount: collateral.1,
+                    position_id: optional_position_id(position_id),
                 })?,
-            }))
+            });
+            Ok((collateral.clone(), msg))
         })
         .filter(|msg| msg.is_ok())
-        .collect::<StdResult<Vec<CosmosMsg>>>()?;
+        .collect::<StdResult<Vec<(Token, CosmosMsg)>>>()?;
+
+    // Store left collaterals
+    store_collaterals(deps.storage, borrower, position_id, &cur_collaterals)?;
+
+    Ok(liquidation_messages)
+}
+
+/// Attribute the borrower loan to its collaterals pro-rata to their value and,
+/// on a borrow, reject the update when it pushes a collateral over its debt ceiling.
+/// Attributions are refreshed every time the market changes the loan.
+#[allow(clippy::too_many_arguments)]
+pub fn update_borrower_debt(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    borrower: Addr,
+    position_id: PositionId,
+    loan_amount: Uint256,
+    is_borrow: bool,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if info.sender != config.market_contract {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let collaterals: Tokens = read_collaterals(deps.storage, &borrower, position_id);
+
+    // prices are not time constrained so that repayments never fail on a stale feed
+    let emode_category = read_position_emode_category(deps.storage, &borrower, position_id)?;
+    let (borrow_limit, collateral_prices) = compute_borrow_limit(
+        deps.as_ref(),
+        &collaterals,
+        emode_category.as_ref(),
+        env.block.time.seconds(),
+        false,
+    )?;
+    attribute_debts(
+        deps.storage,
+        &borrower,
+        position_id,
+        &collaterals,
+        &collateral_prices,
+        loan_amount,
+        is_borrow,
+    )?;
+    store_borrower_health(
+        deps.storage,
+        &borrower,
+        position_id,
+        compute_borrow_health(borrow_limit, loan_amount),
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_borrower_debt"),
+        attr("borrower", borrower),
+        attr("position_id", position_id.to_string()),
+        attr("loan_amount", loan_amount),
+    ]))
+}
+
+/// Market positions queried per page when backfilling the debts
+const BACKFILL_PAGE_LIMIT: u32 = 30;
+
+/// Attribute every market loan to its collaterals and index the position health,
+/// so that the debt ceilings and the health index also cover the loans opened
+/// before they existed. Attributions are rebuilt from scratch
+pub fn backfill_borrower_debts(deps: DepsMut, block_time: u64) -> Result<(), ContractError> {
+    let config: Config = read_config(deps.storage)?;
+
+    clear_borrower_debts(deps.storage)?;
+    for (collateral_token, mut whitelist_elem) in read_whitelist_elems(deps.storage)? {
+        whitelist_elem.total_debt = Uint256::zero();
+        store_whitelist_elem(deps.storage, &collateral_token, &whitelist_elem)?;
+    }
+
+    let mut start_after: Option<(String, PositionId)> = None;
+    loop {
+        let borrower_infos = query_borrower_infos(
+            deps.as_ref(),
+            config.market_contract.clone(),
+            start_after,
+            BACKFILL_PAGE_LIMIT,
+        )?;
+
+        for borrower_info in borrower_infos.iter() {
+            let borrower = deps.api.addr_validate(&borrower_info.borrower)?;
+            let position_id = borrower_info.position_id;
+            let collaterals: Tokens = read_collaterals(deps.storage, &borrower, position_id);
+            let emode_category =
+                read_position_emode_category(deps.storage, &borrower, position_id)?;
+            let (borrow_limit, collateral_prices) = compute_borrow_limit(
+                deps.as_ref(),
+                &collaterals,
+                emode_category.as_ref(),
+                block_time,
+                false,
+            )?;
+            attribute_debts(
+                deps.storage,
+                &borrower,
+                position_id,
+                &collaterals,
+                &collateral_prices,
+                borrower_info.loan_amount,
+                false,
+            )?;
+            store_borrower_health(
+                deps.storage,
+                &borrower,
+                position_id,
+                compute_borrow_health(borrow_limit, borrower_info.loan_amount),
+            )?;
+        }
+
+        start_after = match borrower_infos.last() {
+            Some(last) if borrower_infos.len() == BACKFILL_PAGE_LIMIT as usize => {
+                Some((last.borrower.clone(), last.position_id))
+            }
+            _ => break,
+        };
+    }
+
+    Ok(())
+}
+
+/// Spread the loan of a position over its collaterals pro-rata to their value.
+/// Debt ceilings are only checked for the collaterals whose debt grows
+/// and when `check_ceilings` is set, so that repayments always go through.
+#[allow(clippy::ptr_arg)]
+fn attribute_debts(
+    storage: &mut dyn Storage,
+    borrower: &Addr,
+    position_id: PositionId,
+    collaterals: &Tokens,
+    collateral_prices: &[Decimal256],
+    loan_amount: Uint256,
+    check_ceilings: bool,
+) -> Result<(), ContractError> {
+    let prev_debts: Tokens = read_borrower_debts(storage, borrower, position_id);
+    let collateral_values: Vec<Uint256> = collaterals
+        .iter()
+        .zip(collateral_prices.iter())
+        .map(|(collateral, price)| collateral.1 * *price)
+        .collect();
+    let total_value = collateral_values
+        .iter()
+        .fold(Uint256::zero(), |total, value| total + *value);
+
+    let mut debts: Tokens = vec![];
+    if !total_value.is_zero() {
+        for (collateral, value) in collaterals.iter().zip(collateral_values.iter()) {
+            let debt = loan_amount.multiply_ratio(*value, total_value);
+            if !debt.is_zero() {
+                debts.push((collateral.0.clone(), debt));
+            }
+        }
+    }
+
+    // check the ceilings before touching any total debt
+    if check_ceilings {
+        for debt in debts.iter() {
+            let prev_debt = prev_debts
+                .iter()
+                .find(|prev_debt| prev_debt.0 == debt.0)
+                .map(|prev_debt| prev_debt.1)
+                .unwrap_or_default();
+            if debt.1 <= prev_debt {
+                continue;
+            }
+
+            let whitelist_elem: WhitelistElem = read_whitelist_elem(storage, &debt.0)?;
+            if let Some(debt_ceiling) = whitelist_elem.debt_ceiling {
+                if whitelist_elem.total_debt + (debt.1 - prev_debt) > debt_ceiling {
+                    return Err(ContractError::DebtCeilingExceeded(debt.0.to_string()));
+                }
+            }
+        }
+    }
+
+    for prev_debt in prev_debts.iter() {
+        let mut whitelist_elem: WhitelistElem = read_whitelist_elem(storage, &prev_debt.0)?;
+        // the totals are the exact sum of the attributions since the backfill
+        if whitelist_elem.total_debt < prev_debt.1 {
+            return Err(ContractError::DebtAttributionMismatch(
+                prev_debt.0.to_string(),
+            ));
+        }
+        whitelist_elem.total_debt = whitelist_elem.total_debt - prev_debt.1;
+        store_whitelist_elem(storage, &prev_debt.0, &whitelist_elem)?;
+    }
+
+    for debt in debts.iter() {
+        let mut whitelist_elem: WhitelistElem = read_whitelist_elem(storage, &debt.0)?;
+        whitelist_elem.total_debt += debt.1;
+        store_whitelist_elem(storage, &debt.0, &whitelist_elem)?;
+    }
 
-    Ok(Response::new().add_messages(liquidation_messages))
+    store_borrower_debts(storage, borrower, position_id, &debts)?;
+
+    Ok(())
 }
 
-pub fn query_collaterals(deps: Deps, borrower: Addr) -> StdResult<CollateralsResponse> {
-    let collaterals: Tokens =
-        read_collaterals(deps.storage, &deps.api.addr_validate(borrower.as_str())?);
+pub fn query_collaterals(
+    deps: Deps,
+    borrower: Addr,
+    position_id: PositionId,
+) -> StdResult<CollateralsResponse> {
+    let collaterals: Tokens = read_collaterals(
+        deps.storage,
+        &deps.api.addr_validate(borrower.as_str())?,
+        position_id,
+    );
 
     Ok(CollateralsResponse {
         borrower: borrower.to_string(),
+        position_id,
         collaterals: collaterals
             .iter()
             .map(|c| Ok((c.0.to_string(), c.1)))
@@ -198,10 +741,14 @@ pub fn query_collaterals(deps: Deps, borrower: Addr) -> StdResult<CollateralsRes
 pub fn query_all_collaterals(
     deps: Deps,
     start_after: Option<Addr>,
+    start_after_position_id: PositionId,
     limit: Option<u32>,
 ) -> StdResult<AllCollateralsResponse> {
     let start_after = if let Some(start_after) = start_after {
-        Some(deps.api.addr_validate(start_after.as_str())?)
+        Some((
+            deps.api.addr_validate(start_after.as_str())?,
+            start_after_position_id,
+        ))
     } else {
         None
     };
@@ -211,11 +758,141 @@ pub fn query_all_collaterals(
     Ok(AllCollateralsResponse { all_collaterals })
 }
 
+pub fn query_liquidatable_positions(
+    deps: Deps,
+    env: Env,
+    start_after: Option<Addr>,
+    start_after_position_id: PositionId,
+    limit: Option<u32>,
+) -> StdResult<LiquidatablePositionsResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let start_after = if let Some(start_after) = start_after {
+        Some((
+            deps.api.addr_validate(start_after.as_str())?,
+            start_after_position_id,
+        ))
+    } else {
+        None
+    };
+
+    let all_collaterals: Vec<CollateralsResponse> = read_all_collaterals(deps, start_after, limit)?;
+    let next_start_after = all_collaterals
+        .last()
+        .map(|position| (position.borrower.clone(), position.position_id));
+
+    let mut positions: Vec<LiquidatablePosition> = vec![];
+    let mut stale_positions: Vec<(String, PositionId)> = vec![];
+    for position in all_collaterals {
+        let borrower = position.borrower.clone();
+        let position_id = position.position_id;
+        // a position that can not be evaluated is reported instead of failing the whole page
+        match compute_liquidatable_position(deps, &config, env.block.time.seconds(), position) {
+            Ok(Some(position)) => positions.push(position),
+            Ok(None) => (),
+            Err(_) => stale_positions.push((borrower, position_id)),
+        }
+    }
+
+    Ok(LiquidatablePositionsResponse {
+        positions,
+        stale_positions,
+        next_start_after,
+    })
+}
+
+/// Shortfall and liquidation amounts of a position priced the same way as the
+/// liquidation itself, `None` when the position is not liquidatable
+fn compute_liquidatable_position(
+    deps: Deps,
+    config: &Config,
+    block_time: u64,
+    position: CollateralsResponse,
+) -> StdResult<Option<LiquidatablePosition>> {
+    let borrower = Addr::unchecked(position.borrower);
+    let collaterals: Tokens = position.collaterals.to_raw(deps)?;
+
+    let emode_category =
+        read_position_emode_category(deps.storage, &borrower, position.position_id)?;
+    let (borrow_limit, collateral_prices) = compute_borrow_limit(
+        deps,
+        &collaterals,
+        emode_category.as_ref(),
+        block_time,
+        true,
+    )?;
+    let liquidation_limit = compute_liquidation_limit(
+        deps,
+        &collaterals,
+        &collateral_prices,
+        emode_category.as_ref(),
+        block_time,
+    )?;
+    let loan_amount = query_borrower_info(
+        deps,
+        config.market_contract.clone(),
+        borrower.clone(),
+        position.position_id,
+    )?
+    .loan_amount;
+    if loan_amount <= liquidation_limit {
+        return Ok(None);
+    }
+
+    let liquidation_amount_res: LiquidationAmountResponse = query_liquidation_amount(
+        deps,
+        config.liquidation_contract.clone(),
+        loan_amount,
+        borrow_limit,
+        liquidation_limit,
+        &collaterals.to_human(deps)?,
+        collateral_prices,
+        compute_max_ltvs(deps, &collaterals, emode_category.as_ref(), block_time)?,
+    )?;
+
+    Ok(Some(LiquidatablePosition {
+        borrower: borrower.to_string(),
+        position_id: position.position_id,
+        loan_amount,
+        borrow_limit,
+        liquidation_limit,
+        shortfall: loan_amount - liquidation_limit,
+        liquidation_amount: liquidation_amount_res.collaterals,
+    }))
+}
+
+pub fn query_borrowers_by_health(
+    deps: Deps,
+    start_after: Option<Addr>,
+    start_after_position_id: PositionId,
+    limit: Option<u32>,
+) -> StdResult<BorrowersByHealthResponse> {
+    let start_after = start_after.map(|borrower| (borrower, start_after_position_id));
+    let borrowers = read_borrowers_by_health(deps, start_after, limit)?;
+
+    Ok(BorrowersByHealthResponse { borrowers })
+}
+
+/// Borrow limit over loan amount, positions without loan are not indexed
+pub(crate) fn compute_borrow_health(
+    borrow_limit: Uint256,
+    loan_amount: Uint256,
+) -> Option<Decimal256> {
+    if loan_amount.is_zero() {
+        None
+    } else {
+        Some(Decimal256::from_ratio(borrow_limit, loan_amount))
+    }
+}
+
+/// Collaterals value weighted by their max LTV at `block_time`, the prices
+/// have to be fresh at that time when they are `time_constrained`
 #[allow(clippy::ptr_arg)]
 pub(crate) fn compute_borrow_limit(
     deps: Deps,
     collaterals: &Tokens,
-    block_time: Option<u64>,
+    emode_category: Option<&EmodeCategory>,
+    block_time: u64,
+    time_constrained: bool,
 ) -> StdResult<(Uint256, Vec<Decimal256>)> {
     let config: Config = read_config(deps.storage)?;
 
@@ -230,15 +907,20 @@ pub(crate) fn compute_borrow_limit(
             config.oracle_contract.clone(),
             (deps.api.addr_validate(collateral_token.as_str())?).to_string(),
             "uusd".to_string(),
-            block_time.map(|block_time| TimeConstraints {
-                block_time,
-                valid_timeframe: config.price_timeframe,
-            }),
+            if time_constrained {
+                Some(TimeConstraints {
+                    block_time,
+                    valid_timeframe: config.price_timeframe,
+                })
+            } else {
+                None
+            },
         )?;
 
         let elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
         let collateral_value = collateral_amount * price.rate;
-        borrow_limit += collateral_value * elem.max_ltv;
+        let (max_ltv, _) = collateral_ltvs(&elem, &collateral.0, emode_category, block_time);
+        borrow_limit += collateral_value * max_ltv;
         collateral_prices.push(price.rate);
     }
 
@@ -246,18 +928,179 @@ pub(crate) fn compute_borrow_limit(
     Ok((borrow_limit, collateral_prices))
 }
 
+/// Loan over which the position is liquidated, the collaterals value
+/// weighted by their liquidation threshold at the given prices and time
+#[allow(clippy::ptr_arg)]
+pub(crate) fn compute_liquidation_limit(
+    deps: Deps,
+    collaterals: &Tokens,
+    collateral_prices: &[Decimal256],
+    emode_category: Option<&EmodeCategory>,
+    block_time: u64,
+) -> StdResult<Uint256> {
+    let mut liquidation_limit = Uint256::zero();
+    for (collateral, price) in collaterals.iter().zip(collateral_prices.iter()) {
+        let elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
+        let (_, liquidation_threshold) =
+            collateral_ltvs(&elem, &collateral.0, emode_category, block_time);
+        liquidation_limit += collateral.1 * *price * liquidation_threshold;
+    }
+
+    Ok(liquidation_limit)
+}
+
+/// Max LTV applied to each collateral, for the liquidation queue to weight them
+#[allow(clippy::ptr_arg)]
+fn compute_max_ltvs(
+    deps: Deps,
+    collaterals: &Tokens,
+    emode_category: Option<&EmodeCategory>,
+    block_time: u64,
+) -> StdResult<Vec<Decimal256>> {
+    collaterals
+        .iter()
+        .map(|collateral| {
+            let elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
+            Ok(collateral_ltvs(&elem, &collateral.0, emode_category, block_time).0)
+        })
+        .collect()
+}
+
+/// Max LTV and liquidation threshold of a collateral at the given time. An active or frozen
+/// collateral of the e-mode category of the position is boosted to the LTVs of the category
+pub(crate) fn collateral_ltvs(
+    elem: &WhitelistElem,
+    collateral_token: &Addr,
+    emode_category: Option<&EmodeCategory>,
+    block_time: u64,
+) -> (Decimal256, Decimal256) {
+    let max_ltv = elem.current_max_ltv(block_time);
+    let liquidation_threshold = elem.current_liquidation_threshold(block_time);
+    match emode_category {
+        Some(emode_category)
+            if matches!(
+                elem.status,
+                CollateralStatus::Active | CollateralStatus::BorrowFrozen
+            ) && emode_category.collateral_tokens.contains(collateral_token) =>
+        {
+            (
+                max_ltv.max(emode_category.current_max_ltv(block_time)),
+                liquidation_threshold.max(emode_category.current_liquidation_threshold(block_time)),
+            )
+        }
+        _ => (max_ltv, liquidation_threshold),
+    }
+}
+
 pub fn query_borrow_limit(
     deps: Deps,
+    env: Env,
     borrower: Addr,
+    position_id: PositionId,
     block_time: Option<u64>,
 ) -> StdResult<BorrowLimitResponse> {
-    let collaterals = read_collaterals(deps.storage, &deps.api.addr_validate(borrower.as_str())?);
+    let collaterals = read_collaterals(
+        deps.storage,
+        &deps.api.addr_validate(borrower.as_str())?,
+        position_id,
+    );
 
     // Compute borrow limit with collaterals
-    let (borrow_limit, _) = compute_borrow_limit(deps, &collaterals, block_time)?;
+    let emode_category = read_position_emode_category(
+        deps.storage,
+        &deps.api.addr_validate(borrower.as_str())?,
+        position_id,
+    )?;
+    let (borrow_limit, _) = compute_borrow_limit(
+        deps,
+        &collaterals,
+        emode_category.as_ref(),
+        block_time.unwrap_or_else(|| env.block.time.seconds()),
+        block_time.is_some(),
+    )?;
 
     Ok(BorrowLimitResponse {
         borrower: borrower.to_string(),
         borrow_limit,
     })
 }
+
+pub fn query_position_health(
+    deps: Deps,
+    env: Env,
+    borrower: Addr,
+    position_id: PositionId,
+) -> StdResult<PositionHealthResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let borrower = deps.api.addr_validate(borrower.as_str())?;
+    let collaterals: Tokens = read_collaterals(deps.storage, &borrower, position_id);
+
+    let block_time = env.block.time.seconds();
+    let emode_category_id = read_position_emode(deps.storage, &borrower, position_id)?;
+    let emode_category = read_position_emode_category(deps.storage, &borrower, position_id)?;
+    let (borrow_limit, collateral_prices) = compute_borrow_limit(
+        deps,
+        &collaterals,
+        emode_category.as_ref(),
+        block_time,
+        false,
+    )?;
+    let loan_amount =
+        query_borrower_info(deps, config.market_contract, borrower.clone(), position_id)?
+            .loan_amount;
+
+    let mut collateral_healths: Vec<CollateralHealth> = vec![];
+    let mut total_value = Uint256::zero();
+    let mut liquidation_limit = Uint256::zero();
+    for (collateral, price) in collaterals.iter().zip(collateral_prices.iter()) {
+        let elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
+        let value = collateral.1 * *price;
+        total_value += value;
+        let (max_ltv, liquidation_threshold) =
+            collateral_ltvs(&elem, &collateral.0, emode_category.as_ref(), block_time);
+        liquidation_limit += value * liquidation_threshold;
+        collateral_healths.push(CollateralHealth {
+            collateral_token: collateral.0.to_string(),
+            amount: collateral.1,
+            price: *price,
+            value,
+            max_ltv,
+            liquidation_threshold,
+            liquidation_price: None,
+        });
+    }
+
+    // the position is liquidatable once the liquidation limit of this collateral
+    // falls under the loan left after the liquidation limit of the others
+    for collateral_health in collateral_healths.iter_mut() {
+        let collateral_limit = collateral_health.value * collateral_health.liquidation_threshold;
+        let others_limit = liquidation_limit - collateral_limit;
+        let weighted_amount = Decimal256::from_uint256(collateral_health.amount)
+            * collateral_health.liquidation_threshold;
+        if loan_amount > others_limit && !weighted_amount.is_zero() {
+            collateral_health.liquidation_price =
+                Some(Decimal256::from_uint256(loan_amount - others_limit) / weighted_amount);
+        }
+    }
+
+    Ok(PositionHealthResponse {
+        borrower: borrower.to_string(),
+        position_id,
+        collaterals: collateral_healths,
+        total_value,
+        borrow_limit,
+        liquidation_limit,
+        loan_amount,
+        ltv: if total_value.is_zero() {
+            None
+        } else {
+            Some(Decimal256::from_ratio(loan_amount, total_value))
+        },
+        health_factor: if loan_amount.is_zero() {
+            None
+        } else {
+            Some(Decimal256::from_ratio(liquidation_limit, loan_amount))
+        },
+        emode_category: emode_category_id,
+    })
+}
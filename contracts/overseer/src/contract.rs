@@ -1,26 +1,39 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    attr, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult,
+    SubMsgResult,
 };
 
 use crate::collateral::{
-    liquidate_collateral, lock_collateral, query_all_collaterals, query_borrow_limit,
-    query_collaterals, unlock_collateral,
+    backfill_borrower_debts, liquidate_collateral, liquidate_collaterals, lock_collateral,
+    lock_collateral_for, query_all_collaterals, query_borrow_limit, query_borrowers_by_health,
+    query_collaterals, query_liquidatable_positions, query_position_health, redeem_collateral,
+    release_collateral, restore_liquidated_collateral, unlock_collateral, update_borrower_debt,
+};
+use crate::emode::{
+    query_emode_categories, query_emode_category, register_emode_category, set_emode_category,
+    update_emode_category,
 };
 use crate::error::ContractError;
+use crate::position_transfer::{
+    accept_position, cancel_position_transfer, query_position_transfer, transfer_position,
+};
+use crate::reserve::{query_reserve, receive_cw20, settle_bad_debt};
 
 use crate::state::{
-    read_config, read_whitelist, read_whitelist_elem, store_config, store_whitelist_elem, Config,
-    WhitelistElem,
+    read_config, read_total_locked, read_whitelist, read_whitelist_elem, read_whitelist_elems,
+    store_config, store_state, store_whitelist_elem, Config, LtvRamp, State, WhitelistElem,
 };
 
-use cosmwasm_bignumber::math::Decimal256;
+use cosmwasm_bignumber::math::{Decimal256, Uint256};
 use moneymarket::common::optional_addr_validate;
 use moneymarket::overseer::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, WhitelistResponse,
-    WhitelistResponseElem,
+    CollateralStatus, ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg,
+    WhitelistResponse, WhitelistResponseElem,
 };
+use moneymarket::position::DEFAULT_POSITION_ID;
+use moneymarket::tokens::Tokens;
 
 pub const BLOCKS_PER_YEAR: u128 = 4656810;
 
@@ -44,11 +57,35 @@ pub fn instantiate(
         },
     )?;
 
+    store_state(deps.storage, &State::default())?;
+
     Ok(Response::default())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // collaterals whitelisted before the liquidation threshold are liquidated at their max LTV
+    for (collateral_token, mut whitelist_elem) in read_whitelist_elems(deps.storage)? {
+        if whitelist_elem.liquidation_threshold.is_zero() {
+            whitelist_elem.liquidation_threshold = whitelist_elem.max_ltv;
+            store_whitelist_elem(deps.storage, &collateral_token, &whitelist_elem)?;
+        }
+    }
+
+    // the locked totals are only maintained since they exist, recount them
+    let total_locked: Tokens = read_total_locked(deps.storage)?;
+    for (collateral_token, mut whitelist_elem) in read_whitelist_elems(deps.storage)? {
+        whitelist_elem.total_locked = total_locked
+            .iter()
+            .find(|locked| locked.0 == collateral_token)
+            .map(|locked| locked.1)
+            .unwrap_or_default();
+        store_whitelist_elem(deps.storage, &collateral_token, &whitelist_elem)?;
+    }
+
+    // debt ceilings and the health index only know the loans touched since they exist
+    backfill_borrower_debts(deps, env.block.time.seconds())?;
+
     Ok(Response::default())
 }
 
@@ -60,6 +97,7 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, info, msg),
         ExecuteMsg::UpdateConfig {
             owner_addr,
             oracle_contract,
@@ -82,6 +120,8 @@ pub fn execute(
             collateral_token,
             custody_contract,
             max_ltv,
+            debt_ceiling,
+            liquidation_threshold,
         } => {
             let api = deps.api;
             register_whitelist(
@@ -92,33 +132,226 @@ pub fn execute(
                 api.addr_validate(&collateral_token)?,
                 api.addr_validate(&custody_contract)?,
                 max_ltv,
+                debt_ceiling,
+                liquidation_threshold,
             )
         }
         ExecuteMsg::UpdateWhitelist {
             collateral_token,
             custody_contract,
             max_ltv,
+            debt_ceiling,
+            remove_debt_ceiling,
+            liquidation_threshold,
+            status,
+            ramp_duration,
         } => {
             let api = deps.api;
             update_whitelist(
                 deps,
+                env,
                 info,
                 api.addr_validate(&collateral_token)?,
                 optional_addr_validate(api, custody_contract)?,
                 max_ltv,
+                debt_ceiling,
+                remove_debt_ceiling.unwrap_or(false),
+                liquidation_threshold,
+                status,
+                ramp_duration,
+            )
+        }
+        ExecuteMsg::RegisterEmodeCategory {
+            category_id,
+            label,
+            collateral_tokens,
+            max_ltv,
+            liquidation_threshold,
+        } => register_emode_category(
+            deps,
+            info,
+            category_id,
+            label,
+            collateral_tokens,
+            max_ltv,
+            liquidation_threshold,
+        ),
+        ExecuteMsg::UpdateEmodeCategory {
+            category_id,
+            collateral_tokens,
+            remove_collateral_tokens,
+            max_ltv,
+            liquidation_threshold,
+            ramp_duration,
+        } => update_emode_category(
+            deps,
+            env,
+            info,
+            category_id,
+            collateral_tokens,
+            remove_collateral_tokens,
+            max_ltv,
+            liquidation_threshold,
+            ramp_duration,
+        ),
+        ExecuteMsg::UpdateBorrowerDebt {
+            borrower,
+            loan_amount,
+            position_id,
+            is_borrow,
+        } => {
+            let api = deps.api;
+            update_borrower_debt(
+                deps,
+                env,
+                info,
+                api.addr_validate(&borrower)?,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
+                loan_amount,
+                is_borrow,
+            )
+        }
+        ExecuteMsg::ReleaseCollateral {
+            borrower,
+            collaterals,
+            recipient,
+            position_id,
+        } => {
+            let api = deps.api;
+            release_collateral(
+                deps,
+                env,
+                info,
+                api.addr_validate(&borrower)?,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
+                collaterals,
+                api.addr_validate(&recipient)?,
+            )
+        }
+        ExecuteMsg::LockCollateralFor {
+            borrower,
+            collaterals,
+            position_id,
+        } => {
+            let api = deps.api;
+            lock_collateral_for(
+                deps,
+                env,
+                info,
+                api.addr_validate(&borrower)?,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
+                collaterals,
+            )
+        }
+        ExecuteMsg::RedeemCollateral {
+            borrower,
+            collaterals,
+            recipient,
+            position_id,
+        } => {
+            let api = deps.api;
+            redeem_collateral(
+                deps,
+                info,
+                api.addr_validate(&borrower)?,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
+                collaterals,
+                api.addr_validate(&recipient)?,
+            )
+        }
+        ExecuteMsg::LockCollateral {
+            collaterals,
+            position_id,
+        } => lock_collateral(
+            deps,
+            env,
+            info.sender,
+            position_id.unwrap_or(DEFAULT_POSITION_ID),
+            collaterals,
+        ),
+        ExecuteMsg::UnlockCollateral {
+            collaterals,
+            position_id,
+        } => unlock_collateral(
+            deps,
+            env,
+            info,
+            position_id.unwrap_or(DEFAULT_POSITION_ID),
+            collaterals,
+        ),
+        ExecuteMsg::TransferPosition {
+            recipient,
+            position_id,
+        } => {
+            let api = deps.api;
+            transfer_position(
+                deps,
+                info,
+                api.addr_validate(&recipient)?,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
+            )
+        }
+        ExecuteMsg::CancelPositionTransfer { position_id } => {
+            cancel_position_transfer(deps, info, position_id.unwrap_or(DEFAULT_POSITION_ID))
+        }
+        ExecuteMsg::AcceptPosition { owner, position_id } => {
+            let api = deps.api;
+            accept_position(
+                deps,
+                info,
+                api.addr_validate(&owner)?,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
+            )
+        }
+        ExecuteMsg::SetEmodeCategory {
+            category_id,
+            position_id,
+        } => set_emode_category(
+            deps,
+            env,
+            info,
+            category_id,
+            position_id.unwrap_or(DEFAULT_POSITION_ID),
+        ),
+        ExecuteMsg::LiquidateCollateral {
+            borrower,
+            position_id,
+        } => {
+            let api = deps.api;
+            liquidate_collateral(
+                deps,
+                env,
+                info,
+                api.addr_validate(&borrower)?,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
             )
         }
-        ExecuteMsg::LockCollateral { collaterals } => lock_collateral(deps, info, collaterals),
-        ExecuteMsg::UnlockCollateral { collaterals } => {
-            unlock_collateral(deps, env, info, collaterals)
+        ExecuteMsg::LiquidateCollaterals { borrowers } => {
+            liquidate_collaterals(deps, env, info, borrowers)
         }
-        ExecuteMsg::LiquidateCollateral { borrower } => {
+        ExecuteMsg::SettleBadDebt {
+            borrower,
+            position_id,
+        } => {
             let api = deps.api;
-            liquidate_collateral(deps, env, info, api.addr_validate(&borrower)?)
+            settle_bad_debt(
+                deps,
+                api.addr_validate(&borrower)?,
+                position_id.unwrap_or(DEFAULT_POSITION_ID),
+            )
         }
     }
 }
 
+/// Batch liquidation messages reply on error, with their index in the batch as ID
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.result {
+        SubMsgResult::Err(error) => restore_liquidated_collateral(deps, msg.id, error),
+        SubMsgResult::Ok(_) => Err(ContractError::InvalidReplyId {}),
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn update_config(
     deps: DepsMut,
@@ -155,6 +388,7 @@ pub fn update_config(
     Ok(Response::new().add_attributes(vec![attr("action", "update_config")]))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn register_whitelist(
     deps: DepsMut,
     info: MessageInfo,
@@ -163,6 +397,8 @@ pub fn register_whitelist(
     collateral_token: Addr,
     custody_contract: Addr,
     max_ltv: Decimal256,
+    debt_ceiling: Option<Uint256>,
+    liquidation_threshold: Option<Decimal256>,
 ) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
     if info.sender != config.owner_addr {
@@ -177,6 +413,9 @@ pub fn register_whitelist(
     if max_ltv <= Decimal256::zero() || max_ltv >= Decimal256::from_ratio(100, 1) {
         return Err(ContractError::InvalidMaxLtv {});
     }
+    let liquidation_threshold = liquidation_threshold.unwrap_or(max_ltv);
+    assert_liquidation_threshold(max_ltv, liquidation_threshold)?;
+
     store_whitelist_elem(
         deps.storage,
         &collateral_token_validated,
@@ -185,6 +424,12 @@ pub fn register_whitelist(
             symbol: symbol.to_string(),
             custody_contract: deps.api.addr_validate(custody_contract.as_str())?,
             max_ltv,
+            liquidation_threshold,
+            debt_ceiling,
+            total_debt: Uint256::zero(),
+            status: CollateralStatus::Active,
+            total_locked: Uint256::zero(),
+            ltv_ramp: None,
         },
     )?;
 
@@ -195,15 +440,35 @@ pub fn register_whitelist(
         attr("collateral_token", collateral_token),
         attr("custody_contract", custody_contract),
         attr("LTV", max_ltv.to_string()),
+        attr("liquidation_threshold", liquidation_threshold.to_string()),
     ]))
 }
 
+pub(crate) fn assert_liquidation_threshold(
+    max_ltv: Decimal256,
+    liquidation_threshold: Decimal256,
+) -> Result<(), ContractError> {
+    // a position is liquidated before its loan exceeds its collateral value
+    if liquidation_threshold < max_ltv || liquidation_threshold >= Decimal256::one() {
+        return Err(ContractError::InvalidLiquidationThreshold {});
+    }
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn update_whitelist(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     collateral_token: Addr,
     custody_contract: Option<Addr>,
     max_ltv: Option<Decimal256>,
+    debt_ceiling: Option<Uint256>,
+    remove_debt_ceiling: bool,
+    liquidation_threshold: Option<Decimal256>,
+    status: Option<CollateralStatus>,
+    ramp_duration: Option<u64>,
 ) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
     if info.sender != config.owner_addr {
@@ -213,6 +478,12 @@ pub fn update_whitelist(
     let collateral_token_validated = deps.api.addr_validate(collateral_token.as_str())?;
     let mut whitelist_elem: WhitelistElem =
         read_whitelist_elem(deps.storage, &collateral_token_validated)?;
+    let prev_max_ltv = whitelist_elem.max_ltv;
+
+    // a new ramp starts from the values currently applied
+    let block_time = env.block.time.seconds();
+    let initial_max_ltv = whitelist_elem.ramped_max_ltv(block_time);
+    let initial_liquidation_threshold = whitelist_elem.current_liquidation_threshold(block_time);
 
     if let Some(custody_contract) = custody_contract {
         whitelist_elem.custody_contract = deps.api.addr_validate(custody_contract.as_str())?;
@@ -226,6 +497,59 @@ pub fn update_whitelist(
         whitelist_elem.max_ltv = max_ltv;
     }
 
+    if let Some(status) = status {
+        match status {
+            CollateralStatus::Deprecated {
+                start_time,
+                end_time,
+            } if end_time <= start_time => {
+                return Err(ContractError::InvalidDeprecationSchedule {});
+            }
+            CollateralStatus::Delisted if !whitelist_elem.total_locked.is_zero() => {
+                return Err(ContractError::CollateralStillLocked(
+                    collateral_token.to_string(),
+                ));
+            }
+            _ => {}
+        }
+
+        whitelist_elem.status = status;
+    }
+
+    // the borrowing power of a collateral which is not active can only decrease
+    if let Some(max_ltv) = max_ltv {
+        if whitelist_elem.status != CollateralStatus::Active && max_ltv > prev_max_ltv {
+            return Err(ContractError::CollateralNotActive(
+                collateral_token.to_string(),
+            ));
+        }
+    }
+
+    if let Some(liquidation_threshold) = liquidation_threshold {
+        whitelist_elem.liquidation_threshold = liquidation_threshold;
+    }
+    assert_liquidation_threshold(whitelist_elem.max_ltv, whitelist_elem.liquidation_threshold)?;
+
+    // both values move along the same ramp, so that the max LTV
+    // stays under the liquidation threshold all the way
+    if max_ltv.is_some() || liquidation_threshold.is_some() {
+        whitelist_elem.ltv_ramp = match ramp_duration {
+            Some(ramp_duration) if ramp_duration > 0 => Some(LtvRamp {
+                start_time: block_time,
+                end_time: block_time + ramp_duration,
+                initial_max_ltv,
+                initial_liquidation_threshold,
+            }),
+            _ => None,
+        };
+    }
+
+    if let Some(debt_ceiling) = debt_ceiling {
+        whitelist_elem.debt_ceiling = Some(debt_ceiling);
+    } else if remove_debt_ceiling {
+        whitelist_elem.debt_ceiling = None;
+    }
+
     store_whitelist_elem(deps.storage, &collateral_token_validated, &whitelist_elem)?;
 
     Ok(Response::new().add_attributes(vec![
@@ -233,11 +557,15 @@ pub fn update_whitelist(
         attr("collateral_token", collateral_token),
         attr("custody_contract", whitelist_elem.custody_contract),
         attr("LTV", whitelist_elem.max_ltv.to_string()),
+        attr(
+            "liquidation_threshold",
+            whitelist_elem.liquidation_threshold.to_string(),
+        ),
     ]))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::Whitelist {
@@ -246,27 +574,82 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             limit,
         } => to_binary(&query_whitelist(
             deps,
+            env,
             optional_addr_validate(deps.api, collateral_token)?,
             optional_addr_validate(deps.api, start_after)?,
             limit,
         )?),
-        QueryMsg::Collaterals { borrower } => to_binary(&query_collaterals(
+        QueryMsg::Collaterals {
+            borrower,
+            position_id,
+        } => to_binary(&query_collaterals(
             deps,
             deps.api.addr_validate(&borrower)?,
+            position_id.unwrap_or(DEFAULT_POSITION_ID),
         )?),
-        QueryMsg::AllCollaterals { start_after, limit } => to_binary(&query_all_collaterals(
+        QueryMsg::AllCollaterals {
+            start_after,
+            start_after_position_id,
+            limit,
+        } => to_binary(&query_all_collaterals(
             deps,
             optional_addr_validate(deps.api, start_after)?,
+            start_after_position_id.unwrap_or(DEFAULT_POSITION_ID),
             limit,
         )?),
         QueryMsg::BorrowLimit {
             borrower,
             block_time,
+            position_id,
         } => to_binary(&query_borrow_limit(
             deps,
+            env,
             deps.api.addr_validate(&borrower)?,
+            position_id.unwrap_or(DEFAULT_POSITION_ID),
             block_time,
         )?),
+        QueryMsg::Reserve {} => to_binary(&query_reserve(deps)?),
+        QueryMsg::BorrowersByHealth {
+            start_after,
+            start_after_position_id,
+            limit,
+        } => to_binary(&query_borrowers_by_health(
+            deps,
+            optional_addr_validate(deps.api, start_after)?,
+            start_after_position_id.unwrap_or(DEFAULT_POSITION_ID),
+            limit,
+        )?),
+        QueryMsg::LiquidatablePositions {
+            start_after,
+            start_after_position_id,
+            limit,
+        } => to_binary(&query_liquidatable_positions(
+            deps,
+            env,
+            optional_addr_validate(deps.api, start_after)?,
+            start_after_position_id.unwrap_or(DEFAULT_POSITION_ID),
+            limit,
+        )?),
+        QueryMsg::PositionHealth {
+            borrower,
+            position_id,
+        } => to_binary(&query_position_health(
+            deps,
+            env,
+            deps.api.addr_validate(&borrower)?,
+            position_id.unwrap_or(DEFAULT_POSITION_ID),
+        )?),
+        QueryMsg::PositionTransfer { owner, position_id } => to_binary(&query_position_transfer(
+            deps,
+            deps.api.addr_validate(&owner)?,
+            position_id.unwrap_or(DEFAULT_POSITION_ID),
+        )?),
+        QueryMsg::EmodeCategory { category_id } => {
+            to_binary(&query_emode_category(deps, env, category_id)?)
+        }
+        QueryMsg::EmodeCategories { start_after, limit } => {
+            to_binary(&query_emode_categories(deps, env, start_after, limit)?)
+        }
     }
 }
 
@@ -285,10 +668,12 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
 
 pub fn query_whitelist(
     deps: Deps,
+    env: Env,
     collateral_token: Option<Addr>,
     start_after: Option<Addr>,
     limit: Option<u32>,
 ) -> StdResult<WhitelistResponse> {
+    let block_time = env.block.time.seconds();
     if let Some(collateral_token) = collateral_token {
         let whitelist_elem: WhitelistElem = read_whitelist_elem(
             deps.storage,
@@ -296,11 +681,19 @@ pub fn query_whitelist(
         )?;
         Ok(WhitelistResponse {
             elems: vec![WhitelistResponseElem {
-                name: whitelist_elem.name,
-                symbol: whitelist_elem.symbol,
-                max_ltv: whitelist_elem.max_ltv,
+                name: whitelist_elem.name.clone(),
+                symbol: whitelist_elem.symbol.clone(),
+                max_ltv: whitelist_elem.current_max_ltv(block_time),
+                liquidation_threshold: whitelist_elem.current_liquidation_threshold(block_time),
+                target_max_ltv: whitelist_elem.max_ltv,
+                target_liquidation_threshold: whitelist_elem.liquidation_threshold,
+                ramp_end_time: whitelist_elem.ramp_end_time(block_time),
                 custody_contract: whitelist_elem.custody_contract.to_string(),
                 collateral_token: collateral_token.to_string(),
+                debt_ceiling: whitelist_elem.debt_ceiling,
+                total_debt: whitelist_elem.total_debt,
+                status: whitelist_elem.status,
+                total_locked: whitelist_elem.total_locked,
             }],
         })
     } else {
@@ -310,7 +703,8 @@ pub fn query_whitelist(
             None
         };
 
-        let whitelist: Vec<WhitelistResponseElem> = read_whitelist(deps, start_after, limit)?;
+        let whitelist: Vec<WhitelistResponseElem> =
+            read_whitelist(deps, block_time, start_after, limit)?;
         Ok(WhitelistResponse { elems: whitelist })
     }
 }
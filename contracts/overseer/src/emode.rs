@@ -0,0 +1,268 @@
+use cosmwasm_bignumber::math::Decimal256;
+use cosmwasm_std::{attr, Addr, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage};
+
+use crate::collateral::{compute_borrow_health, compute_borrow_limit};
+use crate::contract::assert_liquidation_threshold;
+use crate::error::ContractError;
+use crate::querier::query_borrower_info;
+use crate::state::{
+    read_collaterals, read_config, read_emode_categories, read_emode_category,
+    read_emode_positions, read_position_emode, read_whitelist_elem, store_borrower_health,
+    store_emode_category, store_position_emode, Config, EmodeCategory, LtvRamp,
+};
+
+use moneymarket::overseer::{EmodeCategoriesResponse, EmodeCategoryId, EmodeCategoryResponse};
+use moneymarket::position::PositionId;
+use moneymarket::tokens::Tokens;
+
+/// E-mode category the position opted in, if any
+pub(crate) fn read_position_emode_category(
+    storage: &dyn Storage,
+    borrower: &Addr,
+    position_id: PositionId,
+) -> StdResult<Option<EmodeCategory>> {
+    read_position_emode(storage, borrower, position_id)?
+        .map(|category_id| read_emode_category(storage, category_id))
+        .transpose()
+}
+
+/// Both LTVs stay under 100%, the liquidation threshold bound is checked with the whitelist one
+fn assert_emode_ltvs(
+    max_ltv: Decimal256,
+    liquidation_threshold: Decimal256,
+) -> Result<(), ContractError> {
+    if max_ltv <= Decimal256::zero() || max_ltv >= Decimal256::one() {
+        return Err(ContractError::InvalidMaxLtv {});
+    }
+
+    assert_liquidation_threshold(max_ltv, liquidation_threshold)
+}
+
+/// Collateral tokens of a category, which have to be whitelisted
+fn validate_collateral_tokens(
+    deps: Deps,
+    collateral_tokens: Vec<String>,
+) -> Result<Vec<Addr>, ContractError> {
+    collateral_tokens
+        .iter()
+        .map(|collateral_token| {
+            let collateral_token = deps.api.addr_validate(collateral_token)?;
+            read_whitelist_elem(deps.storage, &collateral_token)?;
+            Ok(collateral_token)
+        })
+        .collect()
+}
+
+pub fn register_emode_category(
+    deps: DepsMut,
+    info: MessageInfo,
+    category_id: EmodeCategoryId,
+    label: String,
+    collateral_tokens: Vec<String>,
+    max_ltv: Decimal256,
+    liquidation_threshold: Decimal256,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if info.sender != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if read_emode_category(deps.storage, category_id).is_ok() {
+        return Err(ContractError::EmodeCategoryAlreadyRegistered {});
+    }
+    assert_emode_ltvs(max_ltv, liquidation_threshold)?;
+    let collateral_tokens = validate_collateral_tokens(deps.as_ref(), collateral_tokens)?;
+
+    store_emode_category(
+        deps.storage,
+        category_id,
+        &EmodeCategory {
+            label: label.clone(),
+            collateral_tokens,
+            max_ltv,
+            liquidation_threshold,
+            ltv_ramp: None,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "register_emode_category"),
+        attr("category_id", category_id.to_string()),
+        attr("label", label),
+        attr("LTV", max_ltv.to_string()),
+        attr("liquidation_threshold", liquidation_threshold.to_string()),
+    ]))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_emode_category(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    category_id: EmodeCategoryId,
+    collateral_tokens: Option<Vec<String>>,
+    remove_collateral_tokens: Option<Vec<String>>,
+    max_ltv: Option<Decimal256>,
+    liquidation_threshold: Option<Decimal256>,
+    ramp_duration: Option<u64>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    if info.sender != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let mut category: EmodeCategory = read_emode_category(deps.storage, category_id)?;
+    if let Some(collateral_tokens) = collateral_tokens {
+        for collateral_token in validate_collateral_tokens(deps.as_ref(), collateral_tokens)? {
+            if !category.collateral_tokens.contains(&collateral_token) {
+                category.collateral_tokens.push(collateral_token);
+            }
+        }
+    }
+
+    if let Some(remove_collateral_tokens) = remove_collateral_tokens {
+        let remove_collateral_tokens = remove_collateral_tokens
+            .iter()
+            .map(|collateral_token| deps.api.addr_validate(collateral_token))
+            .collect::<StdResult<Vec<Addr>>>()?;
+
+        // the positions would lose the boost of their collaterals at once
+        for (borrower, position_id) in read_emode_positions(deps.storage, category_id)? {
+            let collaterals: Tokens = read_collaterals(deps.storage, &borrower, position_id);
+            if let Some(collateral) = collaterals
+                .iter()
+                .find(|collateral| remove_collateral_tokens.contains(&collateral.0))
+            {
+                return Err(ContractError::EmodeCollateralInUse(
+                    collateral.0.to_string(),
+                ));
+            }
+        }
+
+        category
+            .collateral_tokens
+            .retain(|collateral_token| !remove_collateral_tokens.contains(collateral_token));
+    }
+
+    // a new ramp starts from the values currently applied
+    let block_time = env.block.time.seconds();
+    let initial_max_ltv = category.current_max_ltv(block_time);
+    let initial_liquidation_threshold = category.current_liquidation_threshold(block_time);
+
+    if let Some(max_ltv) = max_ltv {
+        category.max_ltv = max_ltv;
+    }
+    if let Some(liquidation_threshold) = liquidation_threshold {
+        category.liquidation_threshold = liquidation_threshold;
+    }
+    assert_emode_ltvs(category.max_ltv, category.liquidation_threshold)?;
+
+    if max_ltv.is_some() || liquidation_threshold.is_some() {
+        category.ltv_ramp = match ramp_duration {
+            Some(ramp_duration) if ramp_duration > 0 => Some(LtvRamp {
+                start_time: block_time,
+                end_time: block_time + ramp_duration,
+                initial_max_ltv,
+                initial_liquidation_threshold,
+            }),
+            _ => None,
+        };
+    }
+
+    store_emode_category(deps.storage, category_id, &category)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "update_emode_category"),
+        attr("category_id", category_id.to_string()),
+        attr("LTV", category.max_ltv.to_string()),
+        attr(
+            "liquidation_threshold",
+            category.liquidation_threshold.to_string(),
+        ),
+    ]))
+}
+
+/// Moves the position of the sender in or out of an e-mode category. The collaterals
+/// must all belong to the new category and the new borrow limit must cover the loan
+pub fn set_emode_category(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    category_id: Option<EmodeCategoryId>,
+    position_id: PositionId,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let borrower = info.sender;
+    let collaterals: Tokens = read_collaterals(deps.storage, &borrower, position_id);
+
+    let emode_category = category_id
+        .map(|category_id| read_emode_category(deps.storage, category_id))
+        .transpose()?;
+    if let Some(emode_category) = emode_category.as_ref() {
+        for collateral in collaterals.iter() {
+            if !emode_category.collateral_tokens.contains(&collateral.0) {
+                return Err(ContractError::CollateralNotInEmodeCategory(
+                    collateral.0.to_string(),
+                ));
+            }
+        }
+    }
+
+    let (borrow_limit, _) = compute_borrow_limit(
+        deps.as_ref(),
+        &collaterals,
+        emode_category.as_ref(),
+        env.block.time.seconds(),
+        true,
+    )?;
+    let loan_amount = query_borrower_info(
+        deps.as_ref(),
+        config.market_contract,
+        borrower.clone(),
+        position_id,
+    )?
+    .loan_amount;
+    if borrow_limit < loan_amount {
+        return Err(ContractError::BorrowLimitExceeded(borrow_limit.into()));
+    }
+
+    store_position_emode(deps.storage, &borrower, position_id, category_id)?;
+    store_borrower_health(
+        deps.storage,
+        &borrower,
+        position_id,
+        compute_borrow_health(borrow_limit, loan_amount),
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_emode_category"),
+        attr("borrower", borrower),
+        attr("position_id", position_id.to_string()),
+        attr(
+            "category_id",
+            category_id.map_or_else(|| "none".to_string(), |id| id.to_string()),
+        ),
+    ]))
+}
+
+pub fn query_emode_category(
+    deps: Deps,
+    env: Env,
+    category_id: EmodeCategoryId,
+) -> StdResult<EmodeCategoryResponse> {
+    let category: EmodeCategory = read_emode_category(deps.storage, category_id)?;
+
+    Ok(category.to_response(category_id, env.block.time.seconds()))
+}
+
+pub fn query_emode_categories(
+    deps: Deps,
+    env: Env,
+    start_after: Option<EmodeCategoryId>,
+    limit: Option<u32>,
+) -> StdResult<EmodeCategoriesResponse> {
+    let categories =
+        read_emode_categories(deps.storage, env.block.time.seconds(), start_after, limit)?;
+
+    Ok(EmodeCategoriesResponse { categories })
+}
@@ -29,4 +29,55 @@ pub enum ContractError {
 
     #[error("max_ltv should be greater then zero and lower then 100")]
     InvalidMaxLtv {},
+
+    #[error("liquidation_threshold should not be lower then max_ltv and lower then 100")]
+    InvalidLiquidationThreshold {},
+
+    #[error("Collateral {0} is not active")]
+    CollateralNotActive(String),
+
+    #[error("Collateral {0} is still locked in some positions")]
+    CollateralStillLocked(String),
+
+    #[error("Deprecation should end after it starts")]
+    InvalidDeprecationSchedule {},
+
+    #[error("E-mode category is already registered")]
+    EmodeCategoryAlreadyRegistered {},
+
+    #[error("Collateral {0} is not part of the e-mode category")]
+    CollateralNotInEmodeCategory(String),
+
+    #[error("Collateral {0} is still held by positions in the e-mode category")]
+    EmodeCollateralInUse(String),
+
+    #[error("Loan liability becomes greater than borrow limit: {0}")]
+    BorrowLimitExceeded(u128),
+
+    #[error("Debt ceiling of collateral {0} exceeded")]
+    DebtCeilingExceeded(String),
+
+    #[error("Invalid request: \"fund reserve\" message not included in request")]
+    MissingFundReserveHook {},
+
+    #[error("Borrower has no bad debt to settle")]
+    NoBadDebt {},
+
+    #[error("Reserve is empty")]
+    EmptyReserve {},
+
+    #[error("Position has no collaterals nor loan to transfer")]
+    EmptyPosition {},
+
+    #[error("Recipient already has a position")]
+    PositionAlreadyExists {},
+
+    #[error("No pending transfer of the position to the sender")]
+    PositionTransferNotFound {},
+
+    #[error("Debt attributed to {0} exceeds its total debt")]
+    DebtAttributionMismatch(String),
+
+    #[error("Invalid reply ID")]
+    InvalidReplyId {},
 }
@@ -1,7 +1,10 @@
 pub mod collateral;
 pub mod contract;
+pub mod emode;
 pub mod error;
+pub mod position_transfer;
 pub mod querier;
+pub mod reserve;
 pub mod state;
 
 #[cfg(test)]
@@ -0,0 +1,184 @@
+use cosmwasm_bignumber::math::Uint256;
+use cosmwasm_std::{
+    attr, to_binary, Addr, CosmosMsg, Deps, DepsMut, MessageInfo, Response, StdError, StdResult,
+    SubMsg, WasmMsg,
+};
+
+use crate::error::ContractError;
+use crate::querier::query_borrower_info;
+use crate::state::{
+    read_borrower_debts, read_borrower_health, read_collaterals, read_config, read_position_emode,
+    read_position_transfer, read_whitelist_elem, remove_position_transfer, store_borrower_debts,
+    store_borrower_health, store_collaterals, store_position_emode, store_position_transfer,
+    Config, WhitelistElem,
+};
+
+use moneymarket::custody::ExecuteMsg as CustodyExecuteMsg;
+use moneymarket::market::ExecuteMsg as MarketExecuteMsg;
+use moneymarket::overseer::PositionTransferResponse;
+use moneymarket::position::{optional_position_id, PositionId};
+use moneymarket::tokens::Tokens;
+
+/// Loan of the position on the market, zero when it has none
+fn position_loan(
+    deps: Deps,
+    config: &Config,
+    borrower: &Addr,
+    position_id: PositionId,
+) -> StdResult<Uint256> {
+    Ok(query_borrower_info(
+        deps,
+        config.market_contract.clone(),
+        borrower.clone(),
+        position_id,
+    )?
+    .loan_amount)
+}
+
+fn is_empty_position(
+    deps: Deps,
+    config: &Config,
+    borrower: &Addr,
+    position_id: PositionId,
+) -> StdResult<bool> {
+    Ok(
+        read_collaterals(deps.storage, borrower, position_id).is_empty()
+            && position_loan(deps, config, borrower, position_id)?.is_zero(),
+    )
+}
+
+/// Offers the position of the sender to `recipient`, who has to accept it
+pub fn transfer_position(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipient: Addr,
+    position_id: PositionId,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let owner = info.sender;
+    if is_empty_position(deps.as_ref(), &config, &owner, position_id)? {
+        return Err(ContractError::EmptyPosition {});
+    }
+    if !is_empty_position(deps.as_ref(), &config, &recipient, position_id)? {
+        return Err(ContractError::PositionAlreadyExists {});
+    }
+
+    store_position_transfer(deps.storage, &owner, position_id, &recipient)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "transfer_position"),
+        attr("owner", owner),
+        attr("recipient", recipient),
+        attr("position_id", position_id.to_string()),
+    ]))
+}
+
+pub fn cancel_position_transfer(
+    deps: DepsMut,
+    info: MessageInfo,
+    position_id: PositionId,
+) -> Result<Response, ContractError> {
+    let owner = info.sender;
+    if read_position_transfer(deps.storage, &owner, position_id)?.is_none() {
+        return Err(ContractError::PositionTransferNotFound {});
+    }
+
+    remove_position_transfer(deps.storage, &owner, position_id);
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "cancel_position_transfer"),
+        attr("owner", owner),
+        attr("position_id", position_id.to_string()),
+    ]))
+}
+
+/// Moves the collaterals, the debts and the loan of the offered position
+/// to the same position of the sender, which must still be empty
+pub fn accept_position(
+    deps: DepsMut,
+    info: MessageInfo,
+    owner: Addr,
+    position_id: PositionId,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let recipient = info.sender;
+    if read_position_transfer(deps.storage, &owner, position_id)?.as_ref() != Some(&recipient) {
+        return Err(ContractError::PositionTransferNotFound {});
+    }
+    if !is_empty_position(deps.as_ref(), &config, &recipient, position_id)? {
+        return Err(ContractError::PositionAlreadyExists {});
+    }
+    remove_position_transfer(deps.storage, &owner, position_id);
+
+    let collaterals: Tokens = read_collaterals(deps.storage, &owner, position_id);
+    store_collaterals(deps.storage, &owner, position_id, &vec![])?;
+    store_collaterals(deps.storage, &recipient, position_id, &collaterals)?;
+
+    // the debts attributed to the collaterals and their total are unchanged
+    let debts: Tokens = read_borrower_debts(deps.storage, &owner, position_id);
+    store_borrower_debts(deps.storage, &owner, position_id, &vec![])?;
+    store_borrower_debts(deps.storage, &recipient, position_id, &debts)?;
+
+    let borrow_health = read_borrower_health(deps.storage, &owner, position_id);
+    store_borrower_health(deps.storage, &owner, position_id, None)?;
+    store_borrower_health(deps.storage, &recipient, position_id, borrow_health)?;
+
+    let emode_category = read_position_emode(deps.storage, &owner, position_id)?;
+    store_position_emode(deps.storage, &owner, position_id, None)?;
+    store_position_emode(deps.storage, &recipient, position_id, emode_category)?;
+
+    // The locked collaterals change owner in each custody
+    let mut messages: Vec<SubMsg> = vec![];
+    for collateral in collaterals {
+        let whitelist_elem: WhitelistElem = read_whitelist_elem(deps.storage, &collateral.0)?;
+        messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: whitelist_elem.custody_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&CustodyExecuteMsg::TransferLockedCollateral {
+                borrower: owner.to_string(),
+                recipient: recipient.to_string(),
+                amount: collateral.1,
+            })?,
+        })));
+    }
+
+    if !position_loan(deps.as_ref(), &config, &owner, position_id)?.is_zero() {
+        messages.push(SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.market_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&MarketExecuteMsg::TransferLiability {
+                borrower: owner.to_string(),
+                recipient: recipient.to_string(),
+                position_id: optional_position_id(position_id),
+            })?,
+        })));
+    }
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attributes(vec![
+            attr("action", "accept_position"),
+            attr("owner", owner),
+            attr("recipient", recipient),
+            attr("position_id", position_id.to_string()),
+        ]))
+}
+
+pub fn query_position_transfer(
+    deps: Deps,
+    owner: Addr,
+    position_id: PositionId,
+) -> StdResult<PositionTransferResponse> {
+    let recipient = read_position_transfer(
+        deps.storage,
+        &deps.api.addr_validate(owner.as_str())?,
+        position_id,
+    )?
+    .ok_or_else(|| StdError::not_found("position transfer"))?;
+
+    Ok(PositionTransferResponse {
+        owner: owner.to_string(),
+        position_id,
+        recipient: recipient.to_string(),
+    })
+}
@@ -3,7 +3,10 @@ use cosmwasm_std::{to_binary, Addr, Deps, QueryRequest, StdResult, WasmQuery};
 
 // use moneymarket::liquidation::{LiquidationAmountResponse, QueryMsg as LiquidationQueryMsg};
 use moneymarket::liquidation_queue::{LiquidationAmountResponse, QueryMsg as LiquidationQueryMsg};
-use moneymarket::market::{BorrowerInfoResponse, QueryMsg as MarketQueryMsg, StateResponse};
+use moneymarket::market::{
+    BorrowerInfoResponse, BorrowerInfosResponse, QueryMsg as MarketQueryMsg, StateResponse,
+};
+use moneymarket::position::{optional_position_id, PositionId};
 use moneymarket::tokens::TokensHuman;
 
 pub fn query_market_state(deps: Deps, market_addr: Addr) -> StdResult<StateResponse> {
@@ -20,26 +23,55 @@ pub fn query_borrower_info(
     deps: Deps,
     market_addr: Addr,
     borrower: Addr,
+    position_id: PositionId,
 ) -> StdResult<BorrowerInfoResponse> {
     let borrower_amount: BorrowerInfoResponse =
         deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
             contract_addr: market_addr.to_string(),
             msg: to_binary(&MarketQueryMsg::BorrowerInfo {
                 borrower: borrower.to_string(),
+                position_id: optional_position_id(position_id),
             })?,
         }))?;
 
     Ok(borrower_amount)
 }
 
+/// Query a page of the market positions, ordered by borrower and position id
+pub fn query_borrower_infos(
+    deps: Deps,
+    market_addr: Addr,
+    start_after: Option<(String, PositionId)>,
+    limit: u32,
+) -> StdResult<Vec<BorrowerInfoResponse>> {
+    let (start_after, start_after_position_id) = match start_after {
+        Some((borrower, position_id)) => (Some(borrower), Some(position_id)),
+        None => (None, None),
+    };
+    let borrower_infos: BorrowerInfosResponse =
+        deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+            contract_addr: market_addr.to_string(),
+            msg: to_binary(&MarketQueryMsg::BorrowerInfos {
+                start_after,
+                start_after_position_id,
+                limit: Some(limit),
+            })?,
+        }))?;
+
+    Ok(borrower_infos.borrower_infos)
+}
+
 #[allow(clippy::ptr_arg)]
+#[allow(clippy::too_many_arguments)]
 pub fn query_liquidation_amount(
     deps: Deps,
     liquidation_contract: Addr,
     borrow_amount: Uint256,
     borrow_limit: Uint256,
+    liquidation_limit: Uint256,
     collaterals: &TokensHuman,
     collateral_prices: Vec<Decimal256>,
+    collateral_max_ltvs: Vec<Decimal256>,
 ) -> StdResult<LiquidationAmountResponse> {
     let liquidation_amount_res: LiquidationAmountResponse =
         deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
@@ -47,8 +79,10 @@ pub fn query_liquidation_amount(
             msg: to_binary(&LiquidationQueryMsg::LiquidationAmount {
                 borrow_amount,
                 borrow_limit,
+                liquidation_limit: Some(liquidation_limit),
                 collaterals: collaterals.clone(),
                 collateral_prices,
+                collateral_max_ltvs: Some(collateral_max_ltvs),
             })?,
         }))?;
 
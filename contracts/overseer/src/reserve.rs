@@ -0,0 +1,110 @@
+use cosmwasm_bignumber::math::Uint256;
+use cosmwasm_std::{
+    attr, from_binary, to_binary, Addr, CosmosMsg, Deps, DepsMut, MessageInfo, Response, StdResult,
+    WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+
+use crate::error::ContractError;
+use crate::querier::query_borrower_info;
+use crate::state::{read_collaterals, read_config, read_state, store_state, Config, State};
+
+use moneymarket::market::{BorrowerInfoResponse, Cw20HookMsg as MarketCw20HookMsg};
+use moneymarket::overseer::{Cw20HookMsg, ReserveResponse};
+use moneymarket::position::{optional_position_id, PositionId};
+
+pub fn receive_cw20(
+    deps: DepsMut,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    match from_binary(&cw20_msg.msg) {
+        Ok(Cw20HookMsg::FundReserve {}) => {
+            let config: Config = read_config(deps.storage)?;
+            if info.sender != config.stable_contract {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            fund_reserve(deps, cw20_msg.sender, cw20_msg.amount.into())
+        }
+        _ => Err(ContractError::MissingFundReserveHook {}),
+    }
+}
+
+pub fn fund_reserve(
+    deps: DepsMut,
+    sender: String,
+    amount: Uint256,
+) -> Result<Response, ContractError> {
+    let mut state: State = read_state(deps.storage)?;
+    state.reserve += amount;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "fund_reserve"),
+        attr("sender", sender),
+        attr("amount", amount),
+    ]))
+}
+
+/// Burns reserve SOLID against the debt of a position that has no collaterals left.
+/// The settlement never exceeds the principal, term loans included, and the market
+/// writes off the interest and fees instead of paying them out of the reserve
+pub fn settle_bad_debt(
+    deps: DepsMut,
+    borrower: Addr,
+    position_id: PositionId,
+) -> Result<Response, ContractError> {
+    if !read_collaterals(deps.storage, &borrower, position_id).is_empty() {
+        return Err(ContractError::NoBadDebt {});
+    }
+
+    let config: Config = read_config(deps.storage)?;
+    let borrower_info: BorrowerInfoResponse = query_borrower_info(
+        deps.as_ref(),
+        config.market_contract.clone(),
+        borrower.clone(),
+        position_id,
+    )?;
+    if borrower_info.principal_amount.is_zero() {
+        return Err(ContractError::NoBadDebt {});
+    }
+
+    let mut state: State = read_state(deps.storage)?;
+    if state.reserve.is_zero() {
+        return Err(ContractError::EmptyReserve {});
+    }
+
+    let settle_amount = std::cmp::min(borrower_info.principal_amount, state.reserve);
+    state.reserve = state.reserve - settle_amount;
+    state.total_bad_debt += settle_amount;
+    store_state(deps.storage, &state)?;
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.stable_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: config.market_contract.to_string(),
+                amount: settle_amount.into(),
+                msg: to_binary(&MarketCw20HookMsg::SettleBadDebt {
+                    borrower: borrower.to_string(),
+                    position_id: optional_position_id(position_id),
+                })?,
+            })?,
+        }))
+        .add_attributes(vec![
+            attr("action", "settle_bad_debt"),
+            attr("borrower", borrower),
+            attr("position_id", position_id.to_string()),
+            attr("settle_amount", settle_amount),
+        ]))
+}
+
+pub fn query_reserve(deps: Deps) -> StdResult<ReserveResponse> {
+    let state: State = read_state(deps.storage)?;
+    Ok(ReserveResponse {
+        reserve: state.reserve,
+        total_bad_debt: state.total_bad_debt,
+    })
+}
@@ -5,13 +5,25 @@ use cosmwasm_bignumber::math::{Decimal256, Uint256};
 use cosmwasm_std::{Addr, Deps, Order, StdError, StdResult, Storage};
 use cosmwasm_storage::{Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
 
-use moneymarket::overseer::{CollateralsResponse, WhitelistResponseElem};
-use moneymarket::tokens::Tokens;
+use moneymarket::overseer::{
+    BorrowerHealthResponse, CollateralStatus, CollateralsResponse, EmodeCategoryId,
+    EmodeCategoryResponse, WhitelistResponseElem,
+};
+use moneymarket::position::{parse_position_key, position_key, position_range_start, PositionId};
+use moneymarket::tokens::{Tokens, TokensMath};
 
 const KEY_CONFIG: &[u8] = b"config";
+const KEY_STATE: &[u8] = b"state";
+const KEY_PENDING_LIQUIDATIONS: &[u8] = b"pending_liquidations";
 
 const PREFIX_WHITELIST: &[u8] = b"whitelist";
 const PREFIX_COLLATERALS: &[u8] = b"collateral";
+const PREFIX_BORROWER_DEBTS: &[u8] = b"borrower_debts";
+const PREFIX_BORROWER_HEALTH: &[u8] = b"borrower_health";
+const PREFIX_HEALTH_INDEX: &[u8] = b"health_index";
+const PREFIX_POSITION_TRANSFER: &[u8] = b"position_transfer";
+const PREFIX_EMODE_CATEGORY: &[u8] = b"emode_category";
+const PREFIX_POSITION_EMODE: &[u8] = b"position_emode";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct Config {
@@ -24,12 +36,186 @@ pub struct Config {
     pub price_timeframe: u64,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema, Default)]
+pub struct State {
+    pub reserve: Uint256,
+    pub total_bad_debt: Uint256,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct WhitelistElem {
     pub name: String,
     pub symbol: String,
+    /// Target of the max LTV ramp, see `current_max_ltv`
     pub max_ltv: Decimal256,
+    /// Loan To Value ratio over which a position is liquidated, not lower than `max_ltv`.
+    /// Zero on entries stored before it existed, the migration sets it to `max_ltv`
+    #[serde(default)]
+    pub liquidation_threshold: Decimal256,
     pub custody_contract: Addr,
+    #[serde(default)]
+    pub debt_ceiling: Option<Uint256>,
+    /// Sum of the borrower debts attributed to this collateral
+    #[serde(default)]
+    pub total_debt: Uint256,
+    #[serde(default)]
+    pub status: CollateralStatus,
+    /// Sum of the amounts locked in every position, maintained by `store_collaterals`
+    #[serde(default)]
+    pub total_locked: Uint256,
+    /// Ramp of the max LTV and liquidation threshold towards their stored values
+    #[serde(default)]
+    pub ltv_ramp: Option<LtvRamp>,
+}
+
+/// Collateral sent to the liquidation queue by a batch liquidation,
+/// given back to the position if its message fails
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PendingLiquidation {
+    pub borrower: Addr,
+    pub position_id: PositionId,
+    pub collateral_token: Addr,
+    pub amount: Uint256,
+}
+
+/// Correlated collaterals whose LTVs are boosted in the positions opting in
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct EmodeCategory {
+    pub label: String,
+    pub collateral_tokens: Vec<Addr>,
+    /// Target of the max LTV ramp, see `current_max_ltv`
+    pub max_ltv: Decimal256,
+    pub liquidation_threshold: Decimal256,
+    /// Ramp of the LTVs towards their stored values, as on the whitelist
+    #[serde(default)]
+    pub ltv_ramp: Option<LtvRamp>,
+}
+
+impl EmodeCategory {
+    /// Max LTV at the given time, following its ramp
+    pub fn current_max_ltv(&self, block_time: u64) -> Decimal256 {
+        match &self.ltv_ramp {
+            Some(ramp) => ramp.interpolate(ramp.initial_max_ltv, self.max_ltv, block_time),
+            None => self.max_ltv,
+        }
+    }
+
+    /// Liquidation threshold at the given time, following its ramp
+    pub fn current_liquidation_threshold(&self, block_time: u64) -> Decimal256 {
+        match &self.ltv_ramp {
+            Some(ramp) => ramp.interpolate(
+                ramp.initial_liquidation_threshold,
+                self.liquidation_threshold,
+                block_time,
+            ),
+            None => self.liquidation_threshold,
+        }
+    }
+
+    pub fn to_response(
+        &self,
+        category_id: EmodeCategoryId,
+        block_time: u64,
+    ) -> EmodeCategoryResponse {
+        EmodeCategoryResponse {
+            category_id,
+            label: self.label.clone(),
+            collateral_tokens: self
+                .collateral_tokens
+                .iter()
+                .map(|token| token.to_string())
+                .collect(),
+            max_ltv: self.current_max_ltv(block_time),
+            liquidation_threshold: self.current_liquidation_threshold(block_time),
+            target_max_ltv: self.max_ltv,
+            target_liquidation_threshold: self.liquidation_threshold,
+            ramp_end_time: self
+                .ltv_ramp
+                .as_ref()
+                .map(|ramp| ramp.end_time)
+                .filter(|end_time| *end_time > block_time),
+        }
+    }
+}
+
+/// Linear transition of the risk parameters, from their values
+/// when they were updated to the targets stored on the whitelist
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LtvRamp {
+    pub start_time: u64,
+    pub end_time: u64,
+    pub initial_max_ltv: Decimal256,
+    pub initial_liquidation_threshold: Decimal256,
+}
+
+impl LtvRamp {
+    fn interpolate(&self, initial: Decimal256, target: Decimal256, block_time: u64) -> Decimal256 {
+        if block_time >= self.end_time {
+            return target;
+        }
+
+        let progress = Decimal256::from_ratio(
+            block_time.saturating_sub(self.start_time),
+            self.end_time - self.start_time,
+        );
+        if target > initial {
+            initial + (target - initial) * progress
+        } else {
+            initial - (initial - target) * progress
+        }
+    }
+}
+
+impl WhitelistElem {
+    /// Max LTV at the given time following its ramp, before any deprecation
+    pub fn ramped_max_ltv(&self, block_time: u64) -> Decimal256 {
+        match &self.ltv_ramp {
+            Some(ramp) => ramp.interpolate(ramp.initial_max_ltv, self.max_ltv, block_time),
+            None => self.max_ltv,
+        }
+    }
+
+    /// Max LTV at the given time, following its ramp. A deprecated collateral
+    /// then scales it linearly down to zero over its deprecation schedule
+    pub fn current_max_ltv(&self, block_time: u64) -> Decimal256 {
+        let max_ltv = self.ramped_max_ltv(block_time);
+
+        match self.status {
+            CollateralStatus::Deprecated {
+                start_time,
+                end_time,
+            } => {
+                if block_time <= start_time {
+                    max_ltv
+                } else if block_time >= end_time {
+                    Decimal256::zero()
+                } else {
+                    max_ltv * Decimal256::from_ratio(end_time - block_time, end_time - start_time)
+                }
+            }
+            _ => max_ltv,
+        }
+    }
+
+    /// Liquidation threshold at the given time, following its ramp
+    pub fn current_liquidation_threshold(&self, block_time: u64) -> Decimal256 {
+        match &self.ltv_ramp {
+            Some(ramp) => ramp.interpolate(
+                ramp.initial_liquidation_threshold,
+                self.liquidation_threshold,
+                block_time,
+            ),
+            None => self.liquidation_threshold,
+        }
+    }
+
+    /// End of the ramp, none once the target values are reached
+    pub fn ramp_end_time(&self, block_time: u64) -> Option<u64> {
+        self.ltv_ramp
+            .as_ref()
+            .map(|ramp| ramp.end_time)
+            .filter(|end_time| *end_time > block_time)
+    }
 }
 
 pub fn store_config(storage: &mut dyn Storage, data: &Config) -> StdResult<()> {
@@ -40,6 +226,30 @@ pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
     ReadonlySingleton::new(storage, KEY_CONFIG).load()
 }
 
+pub fn store_state(storage: &mut dyn Storage, data: &State) -> StdResult<()> {
+    Singleton::new(storage, KEY_STATE).save(data)
+}
+
+pub fn read_state(storage: &dyn Storage) -> StdResult<State> {
+    Ok(ReadonlySingleton::new(storage, KEY_STATE)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+/// Liquidations of the last batch, indexed by the reply ID of their message
+pub fn store_pending_liquidations(
+    storage: &mut dyn Storage,
+    data: &[PendingLiquidation],
+) -> StdResult<()> {
+    Singleton::new(storage, KEY_PENDING_LIQUIDATIONS).save(&data.to_vec())
+}
+
+pub fn read_pending_liquidations(storage: &dyn Storage) -> StdResult<Vec<PendingLiquidation>> {
+    Ok(ReadonlySingleton::new(storage, KEY_PENDING_LIQUIDATIONS)
+        .may_load()?
+        .unwrap_or_default())
+}
+
 pub fn store_whitelist_elem(
     storage: &mut dyn Storage,
     collateral_token: &Addr,
@@ -65,8 +275,23 @@ pub fn read_whitelist_elem(
     }
 }
 
+/// Every whitelisted collateral, to migrate them
+pub fn read_whitelist_elems(storage: &dyn Storage) -> StdResult<Vec<(Addr, WhitelistElem)>> {
+    let whitelist_bucket: ReadonlyBucket<WhitelistElem> =
+        ReadonlyBucket::new(storage, PREFIX_WHITELIST);
+
+    whitelist_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| {
+            let (k, v) = elem?;
+            Ok((Addr::unchecked(String::from_utf8(k)?), v))
+        })
+        .collect()
+}
+
 pub fn read_whitelist(
     deps: Deps,
+    block_time: u64,
     start_after: Option<Addr>,
     limit: Option<u32>,
 ) -> StdResult<Vec<WhitelistResponseElem>> {
@@ -84,11 +309,19 @@ pub fn read_whitelist(
             let collateral_token = String::from_utf8(k)?;
             let custody_contract = v.custody_contract.to_string();
             Ok(WhitelistResponseElem {
-                name: v.name,
-                symbol: v.symbol,
+                name: v.name.clone(),
+                symbol: v.symbol.clone(),
                 collateral_token,
                 custody_contract,
-                max_ltv: v.max_ltv,
+                max_ltv: v.current_max_ltv(block_time),
+                liquidation_threshold: v.current_liquidation_threshold(block_time),
+                target_max_ltv: v.max_ltv,
+                target_liquidation_threshold: v.liquidation_threshold,
+                ramp_end_time: v.ramp_end_time(block_time),
+                debt_ceiling: v.debt_ceiling,
+                total_debt: v.total_debt,
+                status: v.status,
+                total_locked: v.total_locked,
             })
         })
         .collect()
@@ -98,47 +331,353 @@ pub fn read_whitelist(
 pub fn store_collaterals(
     storage: &mut dyn Storage,
     borrower: &Addr,
+    position_id: PositionId,
     collaterals: &Tokens,
 ) -> StdResult<()> {
+    let prev_collaterals = read_collaterals(storage, borrower, position_id);
+    update_total_locked(storage, &prev_collaterals, collaterals)?;
+
     let mut collaterals_bucket: Bucket<Tokens> = Bucket::new(storage, PREFIX_COLLATERALS);
+    let key = position_key(borrower, position_id);
     if collaterals.is_empty() {
-        collaterals_bucket.remove(borrower.as_bytes());
+        collaterals_bucket.remove(&key);
     } else {
-        collaterals_bucket.save(borrower.as_bytes(), collaterals)?;
+        collaterals_bucket.save(&key, collaterals)?;
     }
 
     Ok(())
 }
 
-pub fn read_collaterals(storage: &dyn Storage, borrower: &Addr) -> Tokens {
+/// Moves the locked totals of the whitelisted collaterals
+/// from the previous collaterals of a position to the new ones
+fn update_total_locked(
+    storage: &mut dyn Storage,
+    prev_collaterals: &[(Addr, Uint256)],
+    collaterals: &[(Addr, Uint256)],
+) -> StdResult<()> {
+    for prev_collateral in prev_collaterals.iter() {
+        let mut whitelist_elem = read_whitelist_elem(storage, &prev_collateral.0)?;
+        whitelist_elem.total_locked = if whitelist_elem.total_locked > prev_collateral.1 {
+            whitelist_elem.total_locked - prev_collateral.1
+        } else {
+            Uint256::zero()
+        };
+        store_whitelist_elem(storage, &prev_collateral.0, &whitelist_elem)?;
+    }
+
+    for collateral in collaterals.iter() {
+        let mut whitelist_elem = read_whitelist_elem(storage, &collateral.0)?;
+        whitelist_elem.total_locked += collateral.1;
+        store_whitelist_elem(storage, &collateral.0, &whitelist_elem)?;
+    }
+
+    Ok(())
+}
+
+/// Amount of each collateral locked in every position, to migrate the locked totals
+pub fn read_total_locked(storage: &dyn Storage) -> StdResult<Tokens> {
     let collaterals_bucket: ReadonlyBucket<Tokens> =
         ReadonlyBucket::new(storage, PREFIX_COLLATERALS);
-    match collaterals_bucket.load(borrower.as_bytes()) {
+
+    let mut total_locked: Tokens = vec![];
+    for elem in collaterals_bucket.range(None, None, Order::Ascending) {
+        let (_, collaterals) = elem?;
+        total_locked.add(collaterals);
+    }
+
+    Ok(total_locked)
+}
+
+pub fn read_collaterals(storage: &dyn Storage, borrower: &Addr, position_id: PositionId) -> Tokens {
+    let collaterals_bucket: ReadonlyBucket<Tokens> =
+        ReadonlyBucket::new(storage, PREFIX_COLLATERALS);
+    match collaterals_bucket.load(&position_key(borrower, position_id)) {
         Ok(v) => v,
         _ => vec![],
     }
 }
 
+/// Debt of the borrower attributed to each of its collaterals
+#[allow(clippy::ptr_arg)]
+pub fn store_borrower_debts(
+    storage: &mut dyn Storage,
+    borrower: &Addr,
+    position_id: PositionId,
+    debts: &Tokens,
+) -> StdResult<()> {
+    let mut debts_bucket: Bucket<Tokens> = Bucket::new(storage, PREFIX_BORROWER_DEBTS);
+    let key = position_key(borrower, position_id);
+    if debts.is_empty() {
+        debts_bucket.remove(&key);
+    } else {
+        debts_bucket.save(&key, debts)?;
+    }
+
+    Ok(())
+}
+
+/// Drop every debt attribution, the whitelist totals have to be reset along
+pub fn clear_borrower_debts(storage: &mut dyn Storage) -> StdResult<()> {
+    let keys: Vec<Vec<u8>> = ReadonlyBucket::<Tokens>::new(storage, PREFIX_BORROWER_DEBTS)
+        .range(None, None, Order::Ascending)
+        .map(|elem| elem.map(|(k, _)| k))
+        .collect::<StdResult<Vec<Vec<u8>>>>()?;
+
+    let mut debts_bucket: Bucket<Tokens> = Bucket::new(storage, PREFIX_BORROWER_DEBTS);
+    for key in keys {
+        debts_bucket.remove(&key);
+    }
+
+    Ok(())
+}
+
+pub fn read_borrower_debts(
+    storage: &dyn Storage,
+    borrower: &Addr,
+    position_id: PositionId,
+) -> Tokens {
+    let debts_bucket: ReadonlyBucket<Tokens> = ReadonlyBucket::new(storage, PREFIX_BORROWER_DEBTS);
+    debts_bucket
+        .load(&position_key(borrower, position_id))
+        .unwrap_or_default()
+}
+
+/// Health index key: the big endian borrow health followed by the position key,
+/// so that positions are iterated by increasing health
+fn health_index_key(
+    borrow_health: &Decimal256,
+    borrower: &Addr,
+    position_id: PositionId,
+) -> Vec<u8> {
+    let mut key = [0u8; 32].to_vec();
+    borrow_health.0.to_big_endian(&mut key);
+    key.extend_from_slice(&position_key(borrower, position_id));
+    key
+}
+
+/// Move the position to its new place in the health index,
+/// `None` removes a position without loan
+pub fn store_borrower_health(
+    storage: &mut dyn Storage,
+    borrower: &Addr,
+    position_id: PositionId,
+    borrow_health: Option<Decimal256>,
+) -> StdResult<()> {
+    if let Some(prev_borrow_health) = read_borrower_health(storage, borrower, position_id) {
+        let mut index_bucket: Bucket<bool> = Bucket::new(storage, PREFIX_HEALTH_INDEX);
+        index_bucket.remove(&health_index_key(
+            &prev_borrow_health,
+            borrower,
+            position_id,
+        ));
+    }
+
+    let mut health_bucket: Bucket<Decimal256> = Bucket::new(storage, PREFIX_BORROWER_HEALTH);
+    let key = position_key(borrower, position_id);
+    match borrow_health {
+        Some(borrow_health) => {
+            health_bucket.save(&key, &borrow_health)?;
+            let mut index_bucket: Bucket<bool> = Bucket::new(storage, PREFIX_HEALTH_INDEX);
+            index_bucket.save(
+                &health_index_key(&borrow_health, borrower, position_id),
+                &true,
+            )?;
+        }
+        None => health_bucket.remove(&key),
+    }
+
+    Ok(())
+}
+
+pub fn read_borrower_health(
+    storage: &dyn Storage,
+    borrower: &Addr,
+    position_id: PositionId,
+) -> Option<Decimal256> {
+    let health_bucket: ReadonlyBucket<Decimal256> =
+        ReadonlyBucket::new(storage, PREFIX_BORROWER_HEALTH);
+    health_bucket
+        .load(&position_key(borrower, position_id))
+        .ok()
+}
+
+pub fn read_borrowers_by_health(
+    deps: Deps,
+    start_after: Option<(Addr, PositionId)>,
+    limit: Option<u32>,
+) -> StdResult<Vec<BorrowerHealthResponse>> {
+    let index_bucket: ReadonlyBucket<bool> = ReadonlyBucket::new(deps.storage, PREFIX_HEALTH_INDEX);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.and_then(|(borrower, position_id)| {
+        read_borrower_health(deps.storage, &borrower, position_id).map(|borrow_health| {
+            let mut key = health_index_key(&borrow_health, &borrower, position_id);
+            key.push(1);
+            key
+        })
+    });
+
+    index_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, _) = elem?;
+            let (borrower, position_id) = parse_position_key(&k[32..])?;
+            let borrow_health = Decimal256(k[..32].into());
+            Ok(BorrowerHealthResponse {
+                borrower,
+                position_id,
+                borrow_health,
+            })
+        })
+        .collect()
+}
+
+/// Recipient of a pending transfer offer of the position
+pub fn store_position_transfer(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    position_id: PositionId,
+    recipient: &Addr,
+) -> StdResult<()> {
+    let mut transfer_bucket: Bucket<Addr> = Bucket::new(storage, PREFIX_POSITION_TRANSFER);
+    transfer_bucket.save(&position_key(owner, position_id), recipient)
+}
+
+pub fn remove_position_transfer(storage: &mut dyn Storage, owner: &Addr, position_id: PositionId) {
+    let mut transfer_bucket: Bucket<Addr> = Bucket::new(storage, PREFIX_POSITION_TRANSFER);
+    transfer_bucket.remove(&position_key(owner, position_id))
+}
+
+pub fn read_position_transfer(
+    storage: &dyn Storage,
+    owner: &Addr,
+    position_id: PositionId,
+) -> StdResult<Option<Addr>> {
+    let transfer_bucket: ReadonlyBucket<Addr> =
+        ReadonlyBucket::new(storage, PREFIX_POSITION_TRANSFER);
+    transfer_bucket.may_load(&position_key(owner, position_id))
+}
+
+pub fn store_emode_category(
+    storage: &mut dyn Storage,
+    category_id: EmodeCategoryId,
+    category: &EmodeCategory,
+) -> StdResult<()> {
+    let mut category_bucket: Bucket<EmodeCategory> = Bucket::new(storage, PREFIX_EMODE_CATEGORY);
+    category_bucket.save(&category_id.to_be_bytes(), category)
+}
+
+pub fn read_emode_category(
+    storage: &dyn Storage,
+    category_id: EmodeCategoryId,
+) -> StdResult<EmodeCategory> {
+    let category_bucket: ReadonlyBucket<EmodeCategory> =
+        ReadonlyBucket::new(storage, PREFIX_EMODE_CATEGORY);
+    match category_bucket.load(&category_id.to_be_bytes()) {
+        Ok(v) => Ok(v),
+        _ => Err(StdError::generic_err("E-mode category is not registered")),
+    }
+}
+
+pub fn read_emode_categories(
+    storage: &dyn Storage,
+    block_time: u64,
+    start_after: Option<EmodeCategoryId>,
+    limit: Option<u32>,
+) -> StdResult<Vec<EmodeCategoryResponse>> {
+    let category_bucket: ReadonlyBucket<EmodeCategory> =
+        ReadonlyBucket::new(storage, PREFIX_EMODE_CATEGORY);
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|category_id| {
+        let mut v = category_id.to_be_bytes().to_vec();
+        v.push(1);
+        v
+    });
+
+    category_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, v) = elem?;
+            let mut category_id = [0u8; 4];
+            category_id.copy_from_slice(&k);
+            Ok(v.to_response(EmodeCategoryId::from_be_bytes(category_id), block_time))
+        })
+        .collect()
+}
+
+/// E-mode category the position opted in, removed when opting out
+pub fn store_position_emode(
+    storage: &mut dyn Storage,
+    borrower: &Addr,
+    position_id: PositionId,
+    category_id: Option<EmodeCategoryId>,
+) -> StdResult<()> {
+    let mut emode_bucket: Bucket<EmodeCategoryId> = Bucket::new(storage, PREFIX_POSITION_EMODE);
+    let key = position_key(borrower, position_id);
+    match category_id {
+        Some(category_id) => emode_bucket.save(&key, &category_id),
+        None => {
+            emode_bucket.remove(&key);
+            Ok(())
+        }
+    }
+}
+
+pub fn read_position_emode(
+    storage: &dyn Storage,
+    borrower: &Addr,
+    position_id: PositionId,
+) -> StdResult<Option<EmodeCategoryId>> {
+    let emode_bucket: ReadonlyBucket<EmodeCategoryId> =
+        ReadonlyBucket::new(storage, PREFIX_POSITION_EMODE);
+    emode_bucket.may_load(&position_key(borrower, position_id))
+}
+
+/// Positions opted in the e-mode category `category_id`
+pub fn read_emode_positions(
+    storage: &dyn Storage,
+    category_id: EmodeCategoryId,
+) -> StdResult<Vec<(Addr, PositionId)>> {
+    let emode_bucket: ReadonlyBucket<EmodeCategoryId> =
+        ReadonlyBucket::new(storage, PREFIX_POSITION_EMODE);
+
+    emode_bucket
+        .range(None, None, Order::Ascending)
+        .filter_map(|elem| match elem {
+            Ok((k, v)) if v == category_id => Some(
+                parse_position_key(&k)
+                    .map(|(borrower, position_id)| (Addr::unchecked(borrower), position_id)),
+            ),
+            Ok(_) => None,
+            Err(err) => Some(Err(err)),
+        })
+        .collect()
+}
+
 // settings for pagination
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
 pub fn read_all_collaterals(
     deps: Deps,
-    start_after: Option<Addr>,
+    start_after: Option<(Addr, PositionId)>,
     limit: Option<u32>,
 ) -> StdResult<Vec<CollateralsResponse>> {
     let whitelist_bucket: ReadonlyBucket<Tokens> =
         ReadonlyBucket::new(deps.storage, PREFIX_COLLATERALS);
 
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = calc_range_start(start_after);
+    let start =
+        start_after.map(|(borrower, position_id)| position_range_start(&borrower, position_id));
 
     whitelist_bucket
         .range(start.as_deref(), None, Order::Ascending)
         .take(limit)
         .map(|elem| {
             let (k, v) = elem?;
-            let borrower = String::from_utf8(k)?;
+            let (borrower, position_id) = parse_position_key(&k)?;
             let collaterals: Vec<(String, Uint256)> = v
                 .iter()
                 .map(|c| Ok((c.0.to_string(), c.1)))
@@ -146,6 +685,7 @@ pub fn read_all_collaterals(
 
             Ok(CollateralsResponse {
                 borrower,
+                position_id,
                 collaterals,
             })
         })
@@ -35,6 +35,8 @@ fn proper_compute_borrow_limit() {
         collateral_token: "bluna".to_string(),
         custody_contract: "custody_bluna".to_string(),
         max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: None,
     };
 
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
@@ -45,6 +47,8 @@ fn proper_compute_borrow_limit() {
         collateral_token: "batom".to_string(),
         custody_contract: "custody_batom".to_string(),
         max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: None,
     };
 
     let _res = execute(deps.as_mut(), env.clone(), info, msg);
@@ -74,7 +78,14 @@ fn proper_compute_borrow_limit() {
     let token2: Token = (Addr::unchecked("batom"), Uint256::from(1000u128));
     collaterals.push(token2);
 
-    let res = compute_borrow_limit(deps.as_ref(), &collaterals, None).unwrap();
+    let res = compute_borrow_limit(
+        deps.as_ref(),
+        &collaterals,
+        None,
+        env.block.time.seconds(),
+        false,
+    )
+    .unwrap();
     let vec: Vec<Decimal256> = vec![
         Decimal256::from_uint256(1000u128),
         Decimal256::from_uint256(2000u128),
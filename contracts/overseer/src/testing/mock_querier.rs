@@ -11,7 +11,7 @@ use std::collections::HashMap;
 use std::marker::PhantomData;
 
 use moneymarket::liquidation::LiquidationAmountResponse;
-use moneymarket::market::{BorrowerInfoResponse, StateResponse};
+use moneymarket::market::{BorrowerInfoResponse, BorrowerInfosResponse, StateResponse};
 use moneymarket::oracle::PriceResponse;
 use moneymarket::tokens::TokensHuman;
 
@@ -26,6 +26,13 @@ pub enum QueryMsg {
     BorrowerInfo {
         borrower: String,
         block_height: Option<u64>,
+        position_id: Option<u32>,
+    },
+    /// Query a page of loans to market contract
+    BorrowerInfos {
+        start_after: Option<String>,
+        start_after_position_id: Option<u32>,
+        limit: Option<u32>,
     },
     /// Query oracle price to oracle contract
     Price { base: String, quote: String },
@@ -99,12 +106,15 @@ pub struct EpochStateQuerier {
 pub struct LoanAmountQuerier {
     // this lets us iterate over all pairs that match the first string
     borrower_amount: HashMap<String, Uint256>,
+    // principal of the loans, defaults to the loan amount
+    borrower_principal: HashMap<String, Uint256>,
 }
 
 impl LoanAmountQuerier {
     pub fn new(borrower_amount: &[(&String, &Uint256)]) -> Self {
         LoanAmountQuerier {
             borrower_amount: borrower_amount_to_map(borrower_amount),
+            borrower_principal: HashMap::new(),
         }
     }
 }
@@ -169,6 +179,11 @@ impl WasmMockQuerier {
                             Some(_v) => {
                                 SystemResult::Ok(ContractResult::from(to_binary(&StateResponse {
                                     total_liabilities: Decimal256::zero(),
+                                    global_interest_index: Decimal256::one(),
+                                    last_interest_updated: 0u64,
+                                    peg_twap: Decimal256::one(),
+                                    redemption_base_rate: Decimal256::zero(),
+                                    last_redemption_time: 0u64,
                                 })))
                             }
                             None => SystemResult::Err(SystemError::InvalidRequest {
@@ -181,18 +196,76 @@ impl WasmMockQuerier {
                     QueryMsg::BorrowerInfo {
                         borrower,
                         block_height: _,
-                    } => match self.loan_amount_querier.borrower_amount.get(&borrower) {
-                        Some(v) => SystemResult::Ok(ContractResult::from(to_binary(
-                            &BorrowerInfoResponse {
-                                borrower,
-                                loan_amount: *v,
-                            },
-                        ))),
-                        None => SystemResult::Err(SystemError::InvalidRequest {
-                            error: "No borrow amount exists".to_string(),
-                            request: msg.as_slice().into(),
-                        }),
-                    },
+                        position_id,
+                    } => {
+                        // positions other than the default one are registered as "borrower/id"
+                        let key = match position_id {
+                            Some(position_id) => format!("{}/{}", borrower, position_id),
+                            None => borrower.clone(),
+                        };
+                        match self.loan_amount_querier.borrower_amount.get(&key) {
+                            Some(v) => SystemResult::Ok(ContractResult::from(to_binary(
+                                &BorrowerInfoResponse {
+                                    borrower,
+                                    position_id: position_id.unwrap_or_default(),
+                                    loan_amount: *v,
+                                    term_loan_amount: Uint256::zero(),
+                                    principal_amount: *self
+                                        .loan_amount_querier
+                                        .borrower_principal
+                                        .get(&key)
+                                        .unwrap_or(v),
+                                },
+                            ))),
+                            None => SystemResult::Err(SystemError::InvalidRequest {
+                                error: "No borrow amount exists".to_string(),
+                                request: msg.as_slice().into(),
+                            }),
+                        }
+                    }
+                    QueryMsg::BorrowerInfos {
+                        start_after,
+                        start_after_position_id,
+                        limit,
+                    } => {
+                        let start_after = start_after.map(|borrower| {
+                            (borrower, start_after_position_id.unwrap_or_default())
+                        });
+                        let mut borrower_infos: Vec<BorrowerInfoResponse> = self
+                            .loan_amount_querier
+                            .borrower_amount
+                            .iter()
+                            .map(|(key, loan_amount)| {
+                                let (borrower, position_id) = match key.split_once('/') {
+                                    Some((borrower, position_id)) => {
+                                        (borrower.to_string(), position_id.parse().unwrap())
+                                    }
+                                    None => (key.clone(), 0u32),
+                                };
+                                BorrowerInfoResponse {
+                                    borrower,
+                                    position_id,
+                                    loan_amount: *loan_amount,
+                                    term_loan_amount: Uint256::zero(),
+                                    principal_amount: *loan_amount,
+                                }
+                            })
+                            .filter(|info| match &start_after {
+                                Some(start_after) => {
+                                    (info.borrower.clone(), info.position_id) > *start_after
+                                }
+                                None => true,
+                            })
+                            .collect();
+                        borrower_infos.sort_by(|a, b| {
+                            (&a.borrower, a.position_id).cmp(&(&b.borrower, b.position_id))
+                        });
+                        borrower_infos.truncate(limit.unwrap_or(10) as usize);
+
+                        SystemResult::Ok(ContractResult::from(to_binary(&BorrowerInfosResponse {
+                            borrower_infos,
+                        })))
+                    }
                     QueryMsg::Price { base, quote } => {
                         match self.oracle_price_querier.oracle_price.get(&(base, quote)) {
                             Some(v) => {
@@ -274,6 +347,10 @@ impl WasmMockQuerier {
         self.loan_amount_querier = LoanAmountQuerier::new(loan_amount);
     }
 
+    pub fn with_principal_amount(&mut self, principal_amount: &[(&String, &Uint256)]) {
+        self.loan_amount_querier.borrower_principal = borrower_amount_to_map(principal_amount);
+    }
+
     pub fn with_liquidation_percent(&mut self, liquidation_percent: &[(&String, &Decimal256)]) {
         self.liquidation_percent_querier = LiquidationPercentQuerier::new(liquidation_percent);
     }
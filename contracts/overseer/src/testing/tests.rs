@@ -1,17 +1,26 @@
 use crate::collateral::lock_collateral as _lock_collateral;
-use crate::contract::{execute, instantiate, query};
+use crate::contract::{execute, instantiate, migrate, query, reply};
 use crate::error::ContractError;
+use crate::state::{store_whitelist_elem, WhitelistElem};
 use crate::testing::mock_querier::mock_dependencies;
 
 use cosmwasm_bignumber::math::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, mock_info};
-use cosmwasm_std::{attr, from_binary, to_binary, CosmosMsg, SubMsg, WasmMsg};
+use cosmwasm_std::{
+    attr, from_binary, to_binary, Addr, CosmosMsg, Deps, Env, Reply, SubMsg, SubMsgResult, WasmMsg,
+};
 
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 use moneymarket::custody::ExecuteMsg as CustodyExecuteMsg;
+use moneymarket::market::{Cw20HookMsg as MarketCw20HookMsg, ExecuteMsg as MarketExecuteMsg};
 
 use moneymarket::overseer::{
-    AllCollateralsResponse, BorrowLimitResponse, CollateralsResponse, ConfigResponse, ExecuteMsg,
-    InstantiateMsg, QueryMsg, WhitelistResponse, WhitelistResponseElem,
+    AllCollateralsResponse, BorrowLimitResponse, BorrowerHealthResponse, BorrowerPosition,
+    BorrowersByHealthResponse, CollateralHealth, CollateralStatus, CollateralsResponse,
+    ConfigResponse, Cw20HookMsg, EmodeCategoriesResponse, EmodeCategoryResponse, ExecuteMsg,
+    InstantiateMsg, LiquidatablePosition, LiquidatablePositionsResponse, MigrateMsg,
+    PositionHealthResponse, PositionTransferResponse, QueryMsg, ReserveResponse, WhitelistResponse,
+    WhitelistResponseElem,
 };
 
 #[test]
@@ -144,6 +153,8 @@ fn whitelist() {
         collateral_token: "bluna".to_string(),
         custody_contract: "custody".to_string(),
         max_ltv: Decimal256::from_ratio(100, 1),
+        debt_ceiling: None,
+        liquidation_threshold: None,
     };
 
     let info = mock_info("owner", &[]);
@@ -159,6 +170,8 @@ fn whitelist() {
         collateral_token: "bluna".to_string(),
         custody_contract: "custody".to_string(),
         max_ltv: Decimal256::zero(),
+        debt_ceiling: None,
+        liquidation_threshold: None,
     };
 
     let info = mock_info("owner", &[]);
@@ -174,6 +187,8 @@ fn whitelist() {
         collateral_token: "bluna".to_string(),
         custody_contract: "custody".to_string(),
         max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: None,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -193,6 +208,7 @@ fn whitelist() {
             attr("collateral_token", "bluna"),
             attr("custody_contract", "custody"),
             attr("LTV", "0.6"),
+            attr("liquidation_threshold", "0.6"),
         ]
     );
 
@@ -216,6 +232,14 @@ fn whitelist() {
                 collateral_token: "bluna".to_string(),
                 custody_contract: "custody".to_string(),
                 max_ltv: Decimal256::percent(60),
+                debt_ceiling: None,
+                total_debt: Uint256::zero(),
+                liquidation_threshold: Decimal256::percent(60),
+                status: CollateralStatus::Active,
+                total_locked: Uint256::zero(),
+                target_max_ltv: Decimal256::percent(60),
+                target_liquidation_threshold: Decimal256::percent(60),
+                ramp_end_time: None,
             }]
         }
     );
@@ -227,6 +251,8 @@ fn whitelist() {
         collateral_token: "bluna".to_string(),
         custody_contract: "custody".to_string(),
         max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: None,
     };
 
     let info = mock_info("owner", &[]);
@@ -240,6 +266,11 @@ fn whitelist() {
         collateral_token: "bluna".to_string(),
         custody_contract: Some("custody2".to_string()),
         max_ltv: Some(Decimal256::from_ratio(105, 1)),
+        debt_ceiling: None,
+        remove_debt_ceiling: None,
+        liquidation_threshold: None,
+        status: None,
+        ramp_duration: None,
     };
 
     let info = mock_info("owner", &[]);
@@ -253,6 +284,11 @@ fn whitelist() {
         collateral_token: "bluna".to_string(),
         custody_contract: Some("custody2".to_string()),
         max_ltv: Some(Decimal256::zero()),
+        debt_ceiling: None,
+        remove_debt_ceiling: None,
+        liquidation_threshold: None,
+        status: None,
+        ramp_duration: None,
     };
 
     let info = mock_info("owner", &[]);
@@ -266,6 +302,11 @@ fn whitelist() {
         collateral_token: "bluna".to_string(),
         custody_contract: Some("custody2".to_string()),
         max_ltv: Some(Decimal256::percent(30)),
+        debt_ceiling: None,
+        remove_debt_ceiling: None,
+        liquidation_threshold: None,
+        status: None,
+        ramp_duration: None,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -284,6 +325,7 @@ fn whitelist() {
             attr("collateral_token", "bluna"),
             attr("custody_contract", "custody2"),
             attr("LTV", "0.3"),
+            attr("liquidation_threshold", "0.6"),
         ]
     );
 
@@ -307,6 +349,14 @@ fn whitelist() {
                 collateral_token: "bluna".to_string(),
                 custody_contract: "custody2".to_string(),
                 max_ltv: Decimal256::percent(30),
+                debt_ceiling: None,
+                total_debt: Uint256::zero(),
+                liquidation_threshold: Decimal256::percent(60),
+                status: CollateralStatus::Active,
+                total_locked: Uint256::zero(),
+                target_max_ltv: Decimal256::percent(30),
+                target_liquidation_threshold: Decimal256::percent(60),
+                ramp_end_time: None,
             }]
         }
     );
@@ -341,6 +391,8 @@ fn lock_collateral() {
         collateral_token: bluna_collat_token.clone(),
         custody_contract: "custody_bluna".to_string(),
         max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: None,
     };
 
     let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
@@ -351,6 +403,8 @@ fn lock_collateral() {
         collateral_token: batom_collat_token.clone(),
         custody_contract: "custody_batom".to_string(),
         max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: None,
     };
 
     let _res = execute(deps.as_mut(), mock_env(), info, msg);
@@ -360,6 +414,7 @@ fn lock_collateral() {
             (bluna_collat_token.clone(), Uint256::from(1000000u64)),
             (batom_collat_token.clone(), Uint256::from(10000000u64)),
         ],
+        position_id: None,
     };
     let info = mock_info("addr0000", &[]);
     let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -392,6 +447,7 @@ fn lock_collateral() {
         vec![
             attr("action", "lock_collateral"),
             attr("borrower", "addr0000"),
+            attr("position_id", "0"),
             attr(
                 "collaterals",
                 format!(
@@ -407,6 +463,7 @@ fn lock_collateral() {
         mock_env(),
         QueryMsg::Collaterals {
             borrower: "addr0000".to_string(),
+            position_id: None,
         },
     )
     .unwrap();
@@ -418,7 +475,8 @@ fn lock_collateral() {
             collaterals: vec![
                 (batom_collat_token.clone(), Uint256::from(10000000u64)),
                 (bluna_collat_token.clone(), Uint256::from(1000000u64)),
-            ]
+            ],
+            position_id: 0,
         }
     );
 
@@ -428,6 +486,7 @@ fn lock_collateral() {
         QueryMsg::AllCollaterals {
             start_after: None,
             limit: None,
+            start_after_position_id: None,
         },
     )
     .unwrap();
@@ -439,11 +498,62 @@ fn lock_collateral() {
                 borrower: "addr0000".to_string(),
                 collaterals: vec![
                     (batom_collat_token, Uint256::from(10000000u64)),
-                    (bluna_collat_token, Uint256::from(1000000u64)),
-                ]
+                    (bluna_collat_token.clone(), Uint256::from(1000000u64)),
+                ],
+                position_id: 0,
             }]
         }
     );
+
+    // Only the market can lock on behalf of a borrower
+    let msg = ExecuteMsg::LockCollateralFor {
+        borrower: "addr0001".to_string(),
+        collaterals: vec![(bluna_collat_token.clone(), Uint256::from(1000u64))],
+        position_id: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    );
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("market", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "custody_bluna".to_string(),
+            funds: vec![],
+            msg: to_binary(&CustodyExecuteMsg::LockCollateral {
+                borrower: "addr0001".to_string(),
+                amount: Uint256::from(1000u64),
+            })
+            .unwrap(),
+        }))]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Collaterals {
+            borrower: "addr0001".to_string(),
+            position_id: None,
+        },
+    )
+    .unwrap();
+    let collaterals_res: CollateralsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        collaterals_res,
+        CollateralsResponse {
+            borrower: "addr0001".to_string(),
+            collaterals: vec![(bluna_collat_token, Uint256::from(1000u64))],
+            position_id: 0,
+        }
+    );
 }
 
 #[test]
@@ -472,6 +582,8 @@ fn unlock_collateral() {
         collateral_token: "bluna".to_string(),
         custody_contract: "custody_bluna".to_string(),
         max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: None,
     };
 
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
@@ -482,6 +594,8 @@ fn unlock_collateral() {
         collateral_token: "batom".to_string(),
         custody_contract: "custody_batom".to_string(),
         max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: None,
     };
 
     let _res = execute(deps.as_mut(), env.clone(), info, msg);
@@ -494,7 +608,14 @@ fn unlock_collateral() {
     let info = mock_info("addr0000", &[]);
 
     // simulate lock collateral
-    _lock_collateral(deps.as_mut(), info.clone(), collaterals).unwrap();
+    _lock_collateral(
+        deps.as_mut(),
+        mock_env(),
+        info.sender.clone(),
+        0,
+        collaterals,
+    )
+    .unwrap();
 
     // Failed to unlock more than locked amount
     let msg = ExecuteMsg::UnlockCollateral {
@@ -502,6 +623,7 @@ fn unlock_collateral() {
             ("bluna".to_string(), Uint256::from(1000001u64)),
             ("batom".to_string(), Uint256::from(10000001u64)),
         ],
+        position_id: None,
     };
     let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
     match res {
@@ -537,6 +659,7 @@ fn unlock_collateral() {
     // Failed to unlock more than locked amount
     let msg = ExecuteMsg::UnlockCollateral {
         collaterals: vec![("bluna".to_string(), Uint256::one())],
+        position_id: None,
     };
     let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
     match res {
@@ -546,6 +669,7 @@ fn unlock_collateral() {
 
     let msg = ExecuteMsg::UnlockCollateral {
         collaterals: vec![("batom".to_string(), Uint256::one())],
+        position_id: None,
     };
     let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
     match res {
@@ -563,6 +687,7 @@ fn unlock_collateral() {
         QueryMsg::BorrowLimit {
             borrower: "addr0000".to_string(),
             block_time: None,
+            position_id: None,
         },
     )
     .unwrap();
@@ -572,6 +697,7 @@ fn unlock_collateral() {
     // Cannot unlock 2bluna
     let msg = ExecuteMsg::UnlockCollateral {
         collaterals: vec![("bluna".to_string(), Uint256::from(2u64))],
+        position_id: None,
     };
     let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
     match res {
@@ -582,6 +708,7 @@ fn unlock_collateral() {
     // Can unlock 1bluna
     let msg = ExecuteMsg::UnlockCollateral {
         collaterals: vec![("bluna".to_string(), Uint256::one())],
+        position_id: None,
     };
     let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
     assert_eq!(
@@ -602,6 +729,7 @@ fn unlock_collateral() {
         vec![
             attr("action", "unlock_collateral"),
             attr("borrower", "addr0000"),
+            attr("position_id", "0"),
             attr("collaterals", "1bluna"),
         ]
     );
@@ -615,6 +743,7 @@ fn unlock_collateral() {
             ("bluna".to_string(), Uint256::from(1u128)),
             ("batom".to_string(), Uint256::from(1u128)),
         ],
+        position_id: None,
     };
     let res = execute(deps.as_mut(), env, info, msg).unwrap();
     assert_eq!(
@@ -645,6 +774,7 @@ fn unlock_collateral() {
         vec![
             attr("action", "unlock_collateral"),
             attr("borrower", "addr0000"),
+            attr("position_id", "0"),
             attr("collaterals", "1bluna,1batom"),
         ]
     );
@@ -682,6 +812,8 @@ fn liquidate_collateral() {
         collateral_token: bluna_collat_token.clone(),
         custody_contract: "custody_bluna".to_string(),
         max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: None,
     };
 
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
@@ -692,6 +824,8 @@ fn liquidate_collateral() {
         collateral_token: batom_collat_token.clone(),
         custody_contract: "custody_batom".to_string(),
         max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: None,
     };
 
     let _res = execute(deps.as_mut(), env.clone(), info, msg);
@@ -704,7 +838,7 @@ fn liquidate_collateral() {
     let info = mock_info("addr0000", &[]);
 
     // simulate lock collateral
-    _lock_collateral(deps.as_mut(), info, collaterals).unwrap();
+    _lock_collateral(deps.as_mut(), mock_env(), info.sender, 0, collaterals).unwrap();
 
     deps.querier.with_oracle_price(&[
         (
@@ -732,6 +866,7 @@ fn liquidate_collateral() {
 
     let msg = ExecuteMsg::LiquidateCollateral {
         borrower: "addr0000".to_string(),
+        position_id: None,
     };
     let info = mock_info("addr0001", &[]);
     let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
@@ -753,6 +888,7 @@ fn liquidate_collateral() {
                     liquidator: "addr0001".to_string(),
                     borrower: "addr0000".to_string(),
                     amount: Uint256::from(100000u64),
+                    position_id: None,
                 })
                 .unwrap(),
             })),
@@ -763,6 +899,7 @@ fn liquidate_collateral() {
                     liquidator: "addr0001".to_string(),
                     borrower: "addr0000".to_string(),
                     amount: Uint256::from(10000u64),
+                    position_id: None,
                 })
                 .unwrap(),
             })),
@@ -774,6 +911,7 @@ fn liquidate_collateral() {
         env,
         QueryMsg::Collaterals {
             borrower: "addr0000".to_string(),
+            position_id: None,
         },
     )
     .unwrap();
@@ -785,7 +923,2436 @@ fn liquidate_collateral() {
             collaterals: vec![
                 (batom_collat_token, Uint256::from(9900000u64)),
                 (bluna_collat_token, Uint256::from(990000u64)),
-            ]
+            ],
+            position_id: 0,
         }
     );
 }
+
+#[test]
+fn debt_ceiling() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("owner", &[]);
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_contract: "uusd".to_string(),
+        price_timeframe: 60u64,
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    // bluna debt is capped, batom is not
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        debt_ceiling: Some(Uint256::from(1000000000u64)),
+        liquidation_threshold: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "batom".to_string(),
+        symbol: "batom".to_string(),
+        collateral_token: "batom".to_string(),
+        custody_contract: "custody_batom".to_string(),
+        max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let env = mock_env();
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time.seconds(),
+                env.block.time.seconds(),
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(2000u64, 1u64),
+                env.block.time.seconds(),
+                env.block.time.seconds(),
+            ),
+        ),
+    ]);
+
+    // same value locked in both collaterals
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![
+            ("bluna".to_string(), Uint256::from(1000000u64)),
+            ("batom".to_string(), Uint256::from(500000u64)),
+        ],
+        position_id: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(1000000u64))],
+        position_id: None,
+    };
+    let info = mock_info("addr0001", &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // only the market can update debts
+    let msg = ExecuteMsg::UpdateBorrowerDebt {
+        borrower: "addr0000".to_string(),
+        loan_amount: Uint256::from(1000000000u64),
+        position_id: None,
+        is_borrow: true,
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg.clone());
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    // debt is split pro-rata to the collateral value
+    let info = mock_info("market", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_borrower_debt"),
+            attr("borrower", "addr0000"),
+            attr("position_id", "0"),
+            attr("loan_amount", "1000000000"),
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Whitelist {
+            collateral_token: None,
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let whitelist_res: WhitelistResponse = from_binary(&res).unwrap();
+    assert_eq!(whitelist_res.elems[0].collateral_token, "batom");
+    assert_eq!(
+        whitelist_res.elems[0].total_debt,
+        Uint256::from(500000000u64)
+    );
+    assert_eq!(whitelist_res.elems[1].collateral_token, "bluna");
+    assert_eq!(
+        whitelist_res.elems[1].total_debt,
+        Uint256::from(500000000u64)
+    );
+    assert_eq!(
+        whitelist_res.elems[1].debt_ceiling,
+        Some(Uint256::from(1000000000u64))
+    );
+
+    // bluna debt would reach 1,100,000,000
+    let msg = ExecuteMsg::UpdateBorrowerDebt {
+        borrower: "addr0001".to_string(),
+        loan_amount: Uint256::from(600000000u64),
+        position_id: None,
+        is_borrow: true,
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    assert_eq!(
+        res,
+        Err(ContractError::DebtCeilingExceeded("bluna".to_string()))
+    );
+
+    let msg = ExecuteMsg::UpdateBorrowerDebt {
+        borrower: "addr0001".to_string(),
+        loan_amount: Uint256::from(500000000u64),
+        position_id: None,
+        is_borrow: true,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // lower the ceiling below the current debt
+    let msg = ExecuteMsg::UpdateWhitelist {
+        collateral_token: "bluna".to_string(),
+        custody_contract: None,
+        max_ltv: None,
+        debt_ceiling: Some(Uint256::from(800000000u64)),
+        remove_debt_ceiling: None,
+        liquidation_threshold: None,
+        status: None,
+        ramp_duration: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+    // repayments are always allowed
+    let msg = ExecuteMsg::UpdateBorrowerDebt {
+        borrower: "addr0000".to_string(),
+        loan_amount: Uint256::from(900000000u64),
+        position_id: None,
+        is_borrow: false,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Whitelist {
+            collateral_token: Some("bluna".to_string()),
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let whitelist_res: WhitelistResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        whitelist_res.elems[0].total_debt,
+        Uint256::from(950000000u64)
+    );
+
+    // but borrowing more is not
+    let msg = ExecuteMsg::UpdateBorrowerDebt {
+        borrower: "addr0001".to_string(),
+        loan_amount: Uint256::from(500000001u64),
+        position_id: None,
+        is_borrow: true,
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    assert_eq!(
+        res,
+        Err(ContractError::DebtCeilingExceeded("bluna".to_string()))
+    );
+
+    // a repayment goes through even if the accrued interest
+    // leaves the loan above its previous attribution
+    let msg = ExecuteMsg::UpdateBorrowerDebt {
+        borrower: "addr0001".to_string(),
+        loan_amount: Uint256::from(520000000u64),
+        position_id: None,
+        is_borrow: false,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Whitelist {
+            collateral_token: Some("bluna".to_string()),
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let whitelist_res: WhitelistResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        whitelist_res.elems[0].total_debt,
+        Uint256::from(970000000u64)
+    );
+
+    // locking more collaterals spreads the debt over them
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("batom".to_string(), Uint256::from(500000u64))],
+        position_id: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0001", &[]), msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Whitelist {
+            collateral_token: Some("bluna".to_string()),
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let whitelist_res: WhitelistResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        whitelist_res.elems[0].total_debt,
+        Uint256::from(710000000u64)
+    );
+
+    // without a ceiling the borrow goes through
+    let msg = ExecuteMsg::UpdateWhitelist {
+        collateral_token: "bluna".to_string(),
+        custody_contract: None,
+        max_ltv: None,
+        debt_ceiling: None,
+        remove_debt_ceiling: Some(true),
+        liquidation_threshold: None,
+        status: None,
+        ramp_duration: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("owner", &[]), msg).unwrap();
+
+    let msg = ExecuteMsg::UpdateBorrowerDebt {
+        borrower: "addr0000".to_string(),
+        loan_amount: Uint256::from(1200000000u64),
+        position_id: None,
+        is_borrow: true,
+    };
+    let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+}
+
+#[test]
+fn settle_bad_debt() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("owner", &[]);
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_contract: "uusd".to_string(),
+        price_timeframe: 60u64,
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+    deps.querier.with_loan_amount(&[
+        (&"addr0000".to_string(), &Uint256::from(1000u64)),
+        (&"addr0001".to_string(), &Uint256::from(1000u64)),
+        (&"addr0002".to_string(), &Uint256::zero()),
+    ]);
+    // the interest accrued by addr0000 is written off, not paid from the reserve
+    deps.querier
+        .with_principal_amount(&[(&"addr0000".to_string(), &Uint256::from(900u64))]);
+
+    // only the stable token can fund the reserve
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: 1500u128.into(),
+        msg: to_binary(&Cw20HookMsg::FundReserve {}).unwrap(),
+    });
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("bluna", &[]),
+        msg.clone(),
+    );
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    // reserve is empty
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0003", &[]),
+        ExecuteMsg::SettleBadDebt {
+            borrower: "addr0000".to_string(),
+            position_id: None,
+        },
+    );
+    assert_eq!(res, Err(ContractError::EmptyReserve {}));
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info("uusd", &[]), msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "fund_reserve"),
+            attr("sender", "addr0000"),
+            attr("amount", "1500"),
+        ]
+    );
+
+    // borrower without debt
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0003", &[]),
+        ExecuteMsg::SettleBadDebt {
+            borrower: "addr0002".to_string(),
+            position_id: None,
+        },
+    );
+    assert_eq!(res, Err(ContractError::NoBadDebt {}));
+
+    // borrower with collaterals
+    _lock_collateral(
+        deps.as_mut(),
+        mock_env(),
+        Addr::unchecked("addr0001"),
+        0,
+        vec![("bluna".to_string(), Uint256::from(1000u64))],
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0003", &[]),
+        ExecuteMsg::SettleBadDebt {
+            borrower: "addr0001".to_string(),
+            position_id: None,
+        },
+    );
+    assert_eq!(res, Err(ContractError::NoBadDebt {}));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0003", &[]),
+        ExecuteMsg::SettleBadDebt {
+            borrower: "addr0000".to_string(),
+            position_id: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "uusd".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: "market".to_string(),
+                amount: 900u128.into(),
+                msg: to_binary(&MarketCw20HookMsg::SettleBadDebt {
+                    borrower: "addr0000".to_string(),
+                    position_id: None,
+                })
+                .unwrap(),
+            })
+            .unwrap(),
+        }))]
+    );
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Reserve {}).unwrap();
+    let reserve_res: ReserveResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        reserve_res,
+        ReserveResponse {
+            reserve: Uint256::from(600u64),
+            total_bad_debt: Uint256::from(900u64),
+        }
+    );
+
+    // settlement is capped by the reserve
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0003", &[]),
+        ExecuteMsg::SettleBadDebt {
+            borrower: "addr0000".to_string(),
+            position_id: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "settle_bad_debt"),
+            attr("borrower", "addr0000"),
+            attr("position_id", "0"),
+            attr("settle_amount", "600"),
+        ]
+    );
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Reserve {}).unwrap();
+    let reserve_res: ReserveResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        reserve_res,
+        ReserveResponse {
+            reserve: Uint256::zero(),
+            total_bad_debt: Uint256::from(1500u64),
+        }
+    );
+}
+
+#[test]
+fn redeem_collateral() {
+    let mut deps = mock_dependencies(&[]);
+
+    let info = mock_info("owner", &[]);
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_contract: "uusd".to_string(),
+        price_timeframe: 60u64,
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: None,
+    };
+    let _res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let env = mock_env();
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    for borrower in ["addr0000", "addr0001"] {
+        let msg = ExecuteMsg::LockCollateral {
+            collaterals: vec![("bluna".to_string(), Uint256::from(1000000u64))],
+            position_id: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), mock_info(borrower, &[]), msg).unwrap();
+    }
+
+    // borrow limits are 600,000,000
+    let info = mock_info("market", &[]);
+    for (borrower, loan_amount) in [("addr0000", 300000000u64), ("addr0001", 500000000u64)] {
+        let msg = ExecuteMsg::UpdateBorrowerDebt {
+            borrower: borrower.to_string(),
+            loan_amount: Uint256::from(loan_amount),
+            position_id: None,
+            is_borrow: true,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    }
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowersByHealth {
+            start_after: None,
+            limit: None,
+            start_after_position_id: None,
+        },
+    )
+    .unwrap();
+    let health_res: BorrowersByHealthResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        health_res.borrowers,
+        vec![
+            BorrowerHealthResponse {
+                borrower: "addr0001".to_string(),
+                borrow_health: Decimal256::from_ratio(6u64, 5u64),
+                position_id: 0,
+            },
+            BorrowerHealthResponse {
+                borrower: "addr0000".to_string(),
+                borrow_health: Decimal256::from_ratio(2u64, 1u64),
+                position_id: 0,
+            },
+        ]
+    );
+
+    // locking more collateral moves the position
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(2000000u64))],
+        position_id: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), mock_info("addr0001", &[]), msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowersByHealth {
+            start_after: Some("addr0000".to_string()),
+            limit: None,
+            start_after_position_id: None,
+        },
+    )
+    .unwrap();
+    let health_res: BorrowersByHealthResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        health_res.borrowers,
+        vec![BorrowerHealthResponse {
+            borrower: "addr0001".to_string(),
+            borrow_health: Decimal256::from_ratio(18u64, 5u64),
+            position_id: 0,
+        }]
+    );
+
+    // only the market can redeem
+    let msg = ExecuteMsg::RedeemCollateral {
+        borrower: "addr0000".to_string(),
+        collaterals: vec![("bluna".to_string(), Uint256::from(100000u64))],
+        recipient: "redeemer".to_string(),
+        position_id: None,
+    };
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("redeemer", &[]),
+        msg.clone(),
+    );
+    assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "custody_bluna".to_string(),
+            funds: vec![],
+            msg: to_binary(&CustodyExecuteMsg::WithdrawLockedCollateral {
+                borrower: "addr0000".to_string(),
+                recipient: "redeemer".to_string(),
+                amount: Uint256::from(100000u64),
+            })
+            .unwrap(),
+        }))]
+    );
+
+    let msg = ExecuteMsg::RedeemCollateral {
+        borrower: "addr0000".to_string(),
+        collaterals: vec![("bluna".to_string(), Uint256::from(1000000u64))],
+        recipient: "redeemer".to_string(),
+        position_id: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+    assert_eq!(res, Err(ContractError::UnlockExceedsLocked {}));
+
+    // a repaid position leaves the index
+    let msg = ExecuteMsg::UpdateBorrowerDebt {
+        borrower: "addr0000".to_string(),
+        loan_amount: Uint256::zero(),
+        position_id: None,
+        is_borrow: false,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::BorrowersByHealth {
+            start_after: None,
+            limit: None,
+            start_after_position_id: None,
+        },
+    )
+    .unwrap();
+    let health_res: BorrowersByHealthResponse = from_binary(&res).unwrap();
+    assert_eq!(health_res.borrowers.len(), 1);
+    assert_eq!(health_res.borrowers[0].borrower, "addr0001");
+}
+
+#[test]
+fn isolated_positions() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+
+    let info = mock_info("owner", &[]);
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_contract: "uusd".to_string(),
+        price_timeframe: 60u64,
+    };
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let info = mock_info("addr0000", &[]);
+    for position_id in [None, Some(1)] {
+        let msg = ExecuteMsg::LockCollateral {
+            collaterals: vec![("bluna".to_string(), Uint256::from(1000000u64))],
+            position_id,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    }
+
+    // every position has its own collaterals and borrow limit
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::AllCollaterals {
+            start_after: None,
+            start_after_position_id: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let all_collaterals_res: AllCollateralsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        all_collaterals_res.all_collaterals,
+        vec![
+            CollateralsResponse {
+                borrower: "addr0000".to_string(),
+                position_id: 0,
+                collaterals: vec![("bluna".to_string(), Uint256::from(1000000u64))],
+            },
+            CollateralsResponse {
+                borrower: "addr0000".to_string(),
+                position_id: 1,
+                collaterals: vec![("bluna".to_string(), Uint256::from(1000000u64))],
+            },
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::AllCollaterals {
+            start_after: Some("addr0000".to_string()),
+            start_after_position_id: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let all_collaterals_res: AllCollateralsResponse = from_binary(&res).unwrap();
+    assert_eq!(all_collaterals_res.all_collaterals.len(), 1);
+    assert_eq!(all_collaterals_res.all_collaterals[0].position_id, 1);
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowLimit {
+            borrower: "addr0000".to_string(),
+            block_time: None,
+            position_id: Some(1),
+        },
+    )
+    .unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(600000000u64));
+
+    // the default position is at its borrow limit, position 1 has no loan
+    deps.querier.with_loan_amount(&[
+        (&"addr0000".to_string(), &Uint256::from(600000000u64)),
+        (&"addr0000/1".to_string(), &Uint256::zero()),
+    ]);
+
+    let msg = ExecuteMsg::UnlockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::one())],
+        position_id: None,
+    };
+    match execute(deps.as_mut(), env.clone(), info.clone(), msg) {
+        Err(ContractError::UnlockTooLarge(599999400)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::UnlockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(1000000u64))],
+        position_id: Some(1),
+    };
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::Collaterals {
+            borrower: "addr0000".to_string(),
+            position_id: Some(1),
+        },
+    )
+    .unwrap();
+    let collaterals_res: CollateralsResponse = from_binary(&res).unwrap();
+    assert!(collaterals_res.collaterals.is_empty());
+}
+
+#[test]
+fn transfer_position() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+
+    let info = mock_info("owner", &[]);
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_contract: "uusd".to_string(),
+        price_timeframe: 60u64,
+    };
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+    deps.querier.with_loan_amount(&[
+        (&"addr0000/1".to_string(), &Uint256::from(300000000u64)),
+        (&"addr0001/1".to_string(), &Uint256::zero()),
+        (&"addr0002/1".to_string(), &Uint256::zero()),
+    ]);
+
+    let info = mock_info("addr0000", &[]);
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(1000000u64))],
+        position_id: Some(1),
+    };
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::UpdateBorrowerDebt {
+        borrower: "addr0000".to_string(),
+        loan_amount: Uint256::from(300000000u64),
+        position_id: Some(1),
+        is_borrow: true,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("market", &[]), msg).unwrap();
+
+    // the recipient already has a position
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(10u64))],
+        position_id: Some(1),
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("addr0002", &[]), msg).unwrap();
+    let msg = ExecuteMsg::TransferPosition {
+        recipient: "addr0002".to_string(),
+        position_id: Some(1),
+    };
+    match execute(deps.as_mut(), env.clone(), info.clone(), msg) {
+        Err(ContractError::PositionAlreadyExists {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::TransferPosition {
+        recipient: "addr0001".to_string(),
+        position_id: Some(1),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "transfer_position"),
+            attr("owner", "addr0000"),
+            attr("recipient", "addr0001"),
+            attr("position_id", "1"),
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::PositionTransfer {
+            owner: "addr0000".to_string(),
+            position_id: Some(1),
+        },
+    )
+    .unwrap();
+    let transfer_res: PositionTransferResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        transfer_res,
+        PositionTransferResponse {
+            owner: "addr0000".to_string(),
+            position_id: 1,
+            recipient: "addr0001".to_string(),
+        }
+    );
+
+    // only the recipient can accept the position
+    let msg = ExecuteMsg::AcceptPosition {
+        owner: "addr0000".to_string(),
+        position_id: Some(1),
+    };
+    match execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0002", &[]),
+        msg.clone(),
+    ) {
+        Err(ContractError::PositionTransferNotFound {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = execute(deps.as_mut(), env.clone(), mock_info("addr0001", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "custody_bluna".to_string(),
+                funds: vec![],
+                msg: to_binary(&CustodyExecuteMsg::TransferLockedCollateral {
+                    borrower: "addr0000".to_string(),
+                    recipient: "addr0001".to_string(),
+                    amount: Uint256::from(1000000u64),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "market".to_string(),
+                funds: vec![],
+                msg: to_binary(&MarketExecuteMsg::TransferLiability {
+                    borrower: "addr0000".to_string(),
+                    recipient: "addr0001".to_string(),
+                    position_id: Some(1),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Collaterals {
+            borrower: "addr0001".to_string(),
+            position_id: Some(1),
+        },
+    )
+    .unwrap();
+    let collaterals_res: CollateralsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        collaterals_res.collaterals,
+        vec![("bluna".to_string(), Uint256::from(1000000u64))]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Collaterals {
+            borrower: "addr0000".to_string(),
+            position_id: Some(1),
+        },
+    )
+    .unwrap();
+    let collaterals_res: CollateralsResponse = from_binary(&res).unwrap();
+    assert!(collaterals_res.collaterals.is_empty());
+
+    // the health index follows the position
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowersByHealth {
+            start_after: None,
+            start_after_position_id: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let borrowers_res: BorrowersByHealthResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        borrowers_res.borrowers,
+        vec![BorrowerHealthResponse {
+            borrower: "addr0001".to_string(),
+            position_id: 1,
+            borrow_health: Decimal256::from_ratio(2u64, 1u64),
+        }]
+    );
+
+    // the offer is consumed
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::PositionTransfer {
+            owner: "addr0000".to_string(),
+            position_id: Some(1),
+        },
+    );
+    assert!(res.is_err());
+}
+
+#[test]
+fn liquidation_threshold() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_liquidation_percent(&[(&"liquidation".to_string(), &Decimal256::percent(1))]);
+    let env = mock_env();
+
+    let info = mock_info("owner", &[]);
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_contract: "uusd".to_string(),
+        price_timeframe: 60u64,
+    };
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: Some(Decimal256::percent(50)),
+    };
+    match execute(deps.as_mut(), env.clone(), info.clone(), msg) {
+        Err(ContractError::InvalidLiquidationThreshold {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: Some(Decimal256::percent(100)),
+    };
+    match execute(deps.as_mut(), env.clone(), info.clone(), msg) {
+        Err(ContractError::InvalidLiquidationThreshold {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: Some(Decimal256::percent(80)),
+    };
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    // max_ltv can not be raised over the liquidation threshold
+    let msg = ExecuteMsg::UpdateWhitelist {
+        collateral_token: "bluna".to_string(),
+        custody_contract: None,
+        max_ltv: Some(Decimal256::percent(90)),
+        debt_ceiling: None,
+        remove_debt_ceiling: None,
+        liquidation_threshold: None,
+        status: None,
+        ramp_duration: None,
+    };
+    match execute(deps.as_mut(), env.clone(), info, msg) {
+        Err(ContractError::InvalidLiquidationThreshold {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+    _lock_collateral(
+        deps.as_mut(),
+        mock_env(),
+        Addr::unchecked("addr0000"),
+        0,
+        vec![("bluna".to_string(), Uint256::from(1000000u64))],
+    )
+    .unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowLimit {
+            borrower: "addr0000".to_string(),
+            block_time: None,
+            position_id: None,
+        },
+    )
+    .unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(600000000u64));
+
+    // over the borrow limit, under the liquidation limit = 1000 * 1000000 * 0.8
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(800000000u64))]);
+    let msg = ExecuteMsg::LiquidateCollateral {
+        borrower: "addr0000".to_string(),
+        position_id: None,
+    };
+    let info = mock_info("addr0001", &[]);
+    match execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()) {
+        Err(ContractError::CannotLiquidateSafeLoan {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(800000001u64))]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "custody_bluna".to_string(),
+            funds: vec![],
+            msg: to_binary(&CustodyExecuteMsg::LiquidateCollateral {
+                liquidator: "addr0001".to_string(),
+                borrower: "addr0000".to_string(),
+                amount: Uint256::from(10000u64),
+                position_id: None,
+            })
+            .unwrap(),
+        }))]
+    );
+}
+
+#[test]
+fn migrate_liquidation_threshold() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_contract: "uusd".to_string(),
+        price_timeframe: 60u64,
+    };
+    let _res = instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+
+    // entry stored before the liquidation threshold
+    store_whitelist_elem(
+        deps.as_mut().storage,
+        &Addr::unchecked("bluna"),
+        &WhitelistElem {
+            name: "bluna".to_string(),
+            symbol: "bluna".to_string(),
+            max_ltv: Decimal256::percent(60),
+            liquidation_threshold: Decimal256::zero(),
+            custody_contract: Addr::unchecked("custody_bluna"),
+            debt_ceiling: None,
+            total_debt: Uint256::zero(),
+            status: CollateralStatus::Active,
+            total_locked: Uint256::zero(),
+            ltv_ramp: None,
+        },
+    )
+    .unwrap();
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Whitelist {
+            collateral_token: Some("bluna".to_string()),
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let whitelist_res: WhitelistResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        whitelist_res.elems[0].liquidation_threshold,
+        Decimal256::percent(60)
+    );
+}
+
+#[test]
+fn migrate_backfill_debts() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+
+    let info = mock_info("owner", &[]);
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_contract: "uusd".to_string(),
+        price_timeframe: 60u64,
+    };
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    for collateral_token in ["bluna", "batom"] {
+        let msg = ExecuteMsg::Whitelist {
+            name: collateral_token.to_string(),
+            symbol: collateral_token.to_string(),
+            collateral_token: collateral_token.to_string(),
+            custody_contract: format!("custody_{}", collateral_token),
+            max_ltv: Decimal256::percent(60),
+            debt_ceiling: Some(Uint256::from(1000000000u64)),
+            liquidation_threshold: None,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    }
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time.seconds(),
+                env.block.time.seconds(),
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(2000u64, 1u64),
+                env.block.time.seconds(),
+                env.block.time.seconds(),
+            ),
+        ),
+    ]);
+
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![
+            ("bluna".to_string(), Uint256::from(1000000u64)),
+            ("batom".to_string(), Uint256::from(500000u64)),
+        ],
+        position_id: None,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(1000000u64))],
+        position_id: None,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("addr0001", &[]), msg).unwrap();
+
+    // loans opened before the debts were attributed
+    deps.querier.with_loan_amount(&[
+        (&"addr0000".to_string(), &Uint256::from(600000000u64)),
+        (&"addr0001".to_string(), &Uint256::from(400000000u64)),
+    ]);
+
+    migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::Whitelist {
+            collateral_token: None,
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let whitelist_res: WhitelistResponse = from_binary(&res).unwrap();
+    assert_eq!(whitelist_res.elems[0].collateral_token, "batom");
+    assert_eq!(
+        whitelist_res.elems[0].total_debt,
+        Uint256::from(300000000u64)
+    );
+    assert_eq!(whitelist_res.elems[1].collateral_token, "bluna");
+    assert_eq!(
+        whitelist_res.elems[1].total_debt,
+        Uint256::from(700000000u64)
+    );
+
+    // the positions are indexed by health, addr0001 is the riskiest
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowersByHealth {
+            start_after: None,
+            start_after_position_id: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let health_res: BorrowersByHealthResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        health_res
+            .borrowers
+            .iter()
+            .map(|health| health.borrower.as_str())
+            .collect::<Vec<&str>>(),
+        vec!["addr0001", "addr0000"]
+    );
+
+    // the backfill is rebuilt from scratch when run again
+    migrate(deps.as_mut(), env.clone(), MigrateMsg {}).unwrap();
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::Whitelist {
+            collateral_token: Some("bluna".to_string()),
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let whitelist_res: WhitelistResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        whitelist_res.elems[0].total_debt,
+        Uint256::from(700000000u64)
+    );
+}
+
+#[test]
+fn position_health() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+
+    let info = mock_info("owner", &[]);
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_contract: "uusd".to_string(),
+        price_timeframe: 60u64,
+    };
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: Some(Decimal256::percent(80)),
+    };
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::Whitelist {
+        name: "batom".to_string(),
+        symbol: "batom".to_string(),
+        collateral_token: "batom".to_string(),
+        custody_contract: "custody_batom".to_string(),
+        max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: None,
+    };
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time.seconds(),
+                env.block.time.seconds(),
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(2000u64, 1u64),
+                env.block.time.seconds(),
+                env.block.time.seconds(),
+            ),
+        ),
+    ]);
+    _lock_collateral(
+        deps.as_mut(),
+        mock_env(),
+        Addr::unchecked("addr0000"),
+        0,
+        vec![
+            ("bluna".to_string(), Uint256::from(1000000u64)),
+            ("batom".to_string(), Uint256::from(10000000u64)),
+        ],
+    )
+    .unwrap();
+
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(6800000000u64))]);
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::PositionHealth {
+            borrower: "addr0000".to_string(),
+            position_id: None,
+        },
+    )
+    .unwrap();
+    let health_res: PositionHealthResponse = from_binary(&res).unwrap();
+
+    // liquidation limit = 1000 * 1000000 * 0.8 + 2000 * 10000000 * 0.6
+    // = 12,800,000,000 uusd
+    // batom liquidation price = (6,800,000,000 - 800,000,000) / (10000000 * 0.6) = 1000 uusd
+    assert_eq!(
+        health_res,
+        PositionHealthResponse {
+            borrower: "addr0000".to_string(),
+            position_id: 0,
+            collaterals: vec![
+                CollateralHealth {
+                    collateral_token: "batom".to_string(),
+                    amount: Uint256::from(10000000u64),
+                    price: Decimal256::from_ratio(2000u64, 1u64),
+                    value: Uint256::from(20000000000u64),
+                    max_ltv: Decimal256::percent(60),
+                    liquidation_threshold: Decimal256::percent(60),
+                    liquidation_price: Some(Decimal256::from_ratio(1000u64, 1u64)),
+                },
+                CollateralHealth {
+                    collateral_token: "bluna".to_string(),
+                    amount: Uint256::from(1000000u64),
+                    price: Decimal256::from_ratio(1000u64, 1u64),
+                    value: Uint256::from(1000000000u64),
+                    max_ltv: Decimal256::percent(60),
+                    liquidation_threshold: Decimal256::percent(80),
+                    liquidation_price: None,
+                },
+            ],
+            total_value: Uint256::from(21000000000u64),
+            borrow_limit: Uint256::from(12600000000u64),
+            liquidation_limit: Uint256::from(12800000000u64),
+            loan_amount: Uint256::from(6800000000u64),
+            ltv: Some(Decimal256::from_ratio(68u64, 210u64)),
+            health_factor: Some(Decimal256::from_ratio(128u64, 68u64)),
+            emode_category: None,
+        }
+    );
+}
+
+#[test]
+fn liquidatable_positions() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_liquidation_percent(&[(&"liquidation".to_string(), &Decimal256::percent(1))]);
+    let env = mock_env();
+
+    let info = mock_info("owner", &[]);
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_contract: "uusd".to_string(),
+        price_timeframe: 60u64,
+    };
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: Some(Decimal256::percent(80)),
+    };
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+    for borrower in ["addr0000", "addr0001", "addr0002"] {
+        _lock_collateral(
+            deps.as_mut(),
+            mock_env(),
+            Addr::unchecked(borrower),
+            0,
+            vec![("bluna".to_string(), Uint256::from(1000000u64))],
+        )
+        .unwrap();
+    }
+
+    // liquidation limit = 1000 * 1000000 * 0.8 = 800,000,000 uusd,
+    // addr0001 is over its borrow limit only
+    deps.querier.with_loan_amount(&[
+        (&"addr0000".to_string(), &Uint256::from(900000000u64)),
+        (&"addr0001".to_string(), &Uint256::from(700000000u64)),
+        (&"addr0002".to_string(), &Uint256::from(800000001u64)),
+    ]);
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::LiquidatablePositions {
+            start_after: None,
+            start_after_position_id: None,
+            limit: Some(2),
+        },
+    )
+    .unwrap();
+    let liquidatable_res: LiquidatablePositionsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        liquidatable_res,
+        LiquidatablePositionsResponse {
+            positions: vec![LiquidatablePosition {
+                borrower: "addr0000".to_string(),
+                position_id: 0,
+                loan_amount: Uint256::from(900000000u64),
+                borrow_limit: Uint256::from(600000000u64),
+                liquidation_limit: Uint256::from(800000000u64),
+                shortfall: Uint256::from(100000000u64),
+                liquidation_amount: vec![("bluna".to_string(), Uint256::from(10000u64))],
+            }],
+            stale_positions: vec![],
+            next_start_after: Some(("addr0001".to_string(), 0)),
+        }
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::LiquidatablePositions {
+            start_after: Some("addr0001".to_string()),
+            start_after_position_id: None,
+            limit: Some(2),
+        },
+    )
+    .unwrap();
+    let liquidatable_res: LiquidatablePositionsResponse = from_binary(&res).unwrap();
+    assert_eq!(liquidatable_res.positions.len(), 1);
+    assert_eq!(liquidatable_res.positions[0].borrower, "addr0002");
+    assert_eq!(liquidatable_res.positions[0].shortfall, Uint256::one());
+    assert_eq!(
+        liquidatable_res.next_start_after,
+        Some(("addr0002".to_string(), 0))
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::LiquidatablePositions {
+            start_after: Some("addr0002".to_string()),
+            start_after_position_id: None,
+            limit: Some(2),
+        },
+    )
+    .unwrap();
+    let liquidatable_res: LiquidatablePositionsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        liquidatable_res,
+        LiquidatablePositionsResponse {
+            positions: vec![],
+            stale_positions: vec![],
+            next_start_after: None,
+        }
+    );
+
+    // stale prices skip the positions without failing the page
+    let mut stale_env = env.clone();
+    stale_env.block.time = stale_env.block.time.plus_seconds(61);
+    let res = query(
+        deps.as_ref(),
+        stale_env,
+        QueryMsg::LiquidatablePositions {
+            start_after: None,
+            start_after_position_id: None,
+            limit: Some(2),
+        },
+    )
+    .unwrap();
+    let liquidatable_res: LiquidatablePositionsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        liquidatable_res,
+        LiquidatablePositionsResponse {
+            positions: vec![],
+            stale_positions: vec![("addr0000".to_string(), 0), ("addr0001".to_string(), 0)],
+            next_start_after: Some(("addr0001".to_string(), 0)),
+        }
+    );
+
+    // a failing market query skips the position as well
+    deps.querier.with_loan_amount(&[
+        (&"addr0001".to_string(), &Uint256::from(700000000u64)),
+        (&"addr0002".to_string(), &Uint256::from(800000001u64)),
+    ]);
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::LiquidatablePositions {
+            start_after: None,
+            start_after_position_id: None,
+            limit: Some(3),
+        },
+    )
+    .unwrap();
+    let liquidatable_res: LiquidatablePositionsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        liquidatable_res.stale_positions,
+        vec![("addr0000".to_string(), 0)]
+    );
+    assert_eq!(liquidatable_res.positions.len(), 1);
+    assert_eq!(liquidatable_res.positions[0].borrower, "addr0002");
+}
+
+#[test]
+fn liquidate_collaterals() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_liquidation_percent(&[(&"liquidation".to_string(), &Decimal256::percent(1))]);
+    let env = mock_env();
+
+    let info = mock_info("owner", &[]);
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_contract: "uusd".to_string(),
+        price_timeframe: 60u64,
+    };
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: None,
+    };
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+    for borrower in ["addr0000", "addr0001", "addr0002"] {
+        _lock_collateral(
+            deps.as_mut(),
+            mock_env(),
+            Addr::unchecked(borrower),
+            0,
+            vec![("bluna".to_string(), Uint256::from(1000000u64))],
+        )
+        .unwrap();
+    }
+
+    // borrow limit = 1000 * 1000000 * 0.6 = 600,000,000 uusd,
+    // the loan of addr0002 can not be queried
+    deps.querier.with_loan_amount(&[
+        (&"addr0000".to_string(), &Uint256::from(600000001u64)),
+        (&"addr0001".to_string(), &Uint256::from(600000000u64)),
+    ]);
+
+    let msg = ExecuteMsg::LiquidateCollaterals {
+        borrowers: ["addr0000", "addr0001", "addr0002", "addr0000"]
+            .iter()
+            .map(|borrower| BorrowerPosition {
+                borrower: borrower.to_string(),
+                position_id: None,
+            })
+            .collect(),
+    };
+    let res = execute(deps.as_mut(), env.clone(), mock_info("addr0003", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "custody_bluna".to_string(),
+                funds: vec![],
+                msg: to_binary(&CustodyExecuteMsg::LiquidateCollateral {
+                    liquidator: "addr0003".to_string(),
+                    borrower: "addr0000".to_string(),
+                    amount: Uint256::from(10000u64),
+                    position_id: None,
+                })
+                .unwrap(),
+            }),
+            0
+        )]
+    );
+    assert_eq!(res.attributes[0], attr("action", "liquidate_collaterals"));
+    let results: Vec<String> = res
+        .attributes
+        .iter()
+        .filter(|attribute| attribute.key == "result")
+        .map(|attribute| attribute.value.clone())
+        .collect();
+    assert_eq!(results, vec!["liquidated", "safe", "failed", "duplicate"]);
+
+    // only the unsafe position is touched
+    for (borrower, amount) in [("addr0000", 990000u64), ("addr0001", 1000000u64)] {
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::Collaterals {
+                borrower: borrower.to_string(),
+                position_id: None,
+            },
+        )
+        .unwrap();
+        let collaterals_res: CollateralsResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            collaterals_res.collaterals,
+            vec![("bluna".to_string(), Uint256::from(amount))]
+        );
+    }
+
+    // the liquidation fails on execution, the collaterals go back to the position
+    let res = reply(
+        deps.as_mut(),
+        env.clone(),
+        Reply {
+            id: 1,
+            result: SubMsgResult::Err("no bids".to_string()),
+        },
+    );
+    assert_eq!(res, Err(ContractError::InvalidReplyId {}));
+
+    let res = reply(
+        deps.as_mut(),
+        env.clone(),
+        Reply {
+            id: 0,
+            result: SubMsgResult::Err("no bids".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "restore_liquidated_collateral"),
+            attr("borrower", "addr0000"),
+            attr("position_id", "0"),
+            attr("collateral_token", "bluna"),
+            attr("amount", "10000"),
+            attr("result", "failed"),
+            attr("error", "no bids"),
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::Collaterals {
+            borrower: "addr0000".to_string(),
+            position_id: None,
+        },
+    )
+    .unwrap();
+    let collaterals_res: CollateralsResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        collaterals_res.collaterals,
+        vec![("bluna".to_string(), Uint256::from(1000000u64))]
+    );
+}
+
+#[test]
+fn collateral_status() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+
+    let info = mock_info("owner", &[]);
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_contract: "uusd".to_string(),
+        price_timeframe: 60u64,
+    };
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: None,
+    };
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::zero())]);
+
+    let lock_msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(1000000u64))],
+        position_id: None,
+    };
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        lock_msg.clone(),
+    )
+    .unwrap();
+
+    let update_status = |status: CollateralStatus| ExecuteMsg::UpdateWhitelist {
+        collateral_token: "bluna".to_string(),
+        custody_contract: None,
+        max_ltv: None,
+        debt_ceiling: None,
+        remove_debt_ceiling: None,
+        liquidation_threshold: None,
+        status: Some(status),
+        ramp_duration: None,
+    };
+
+    // frozen collateral can not be locked anymore nor get a higher max LTV
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        update_status(CollateralStatus::BorrowFrozen),
+    )
+    .unwrap();
+    match execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        lock_msg,
+    ) {
+        Err(ContractError::CollateralNotActive(token)) => assert_eq!(token, "bluna"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    let msg = ExecuteMsg::UpdateWhitelist {
+        collateral_token: "bluna".to_string(),
+        custody_contract: None,
+        max_ltv: Some(Decimal256::percent(70)),
+        debt_ceiling: None,
+        remove_debt_ceiling: None,
+        liquidation_threshold: Some(Decimal256::percent(70)),
+        status: None,
+        ramp_duration: None,
+    };
+    match execute(deps.as_mut(), env.clone(), info.clone(), msg) {
+        Err(ContractError::CollateralNotActive(token)) => assert_eq!(token, "bluna"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    match execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        update_status(CollateralStatus::Deprecated {
+            start_time: env.block.time.seconds(),
+            end_time: env.block.time.seconds(),
+        }),
+    ) {
+        Err(ContractError::InvalidDeprecationSchedule {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // halfway through the deprecation, the max LTV is halved
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        update_status(CollateralStatus::Deprecated {
+            start_time: env.block.time.seconds() - 50,
+            end_time: env.block.time.seconds() + 50,
+        }),
+    )
+    .unwrap();
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowLimit {
+            borrower: "addr0000".to_string(),
+            block_time: None,
+            position_id: None,
+        },
+    )
+    .unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(300000000u64));
+
+    match execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        update_status(CollateralStatus::Delisted),
+    ) {
+        Err(ContractError::CollateralStillLocked(token)) => assert_eq!(token, "bluna"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::UnlockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(1000000u64))],
+        position_id: None,
+    };
+    execute(deps.as_mut(), env.clone(), mock_info("addr0000", &[]), msg).unwrap();
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        update_status(CollateralStatus::Delisted),
+    )
+    .unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::Whitelist {
+            collateral_token: Some("bluna".to_string()),
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let whitelist_res: WhitelistResponse = from_binary(&res).unwrap();
+    assert_eq!(whitelist_res.elems[0].status, CollateralStatus::Delisted);
+    assert_eq!(whitelist_res.elems[0].total_locked, Uint256::zero());
+}
+
+#[test]
+fn ltv_ramp() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_liquidation_percent(&[(&"liquidation".to_string(), &Decimal256::percent(1))]);
+    let env = mock_env();
+
+    let info = mock_info("owner", &[]);
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_contract: "uusd".to_string(),
+        price_timeframe: 60u64,
+    };
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let msg = ExecuteMsg::Whitelist {
+        name: "bluna".to_string(),
+        symbol: "bluna".to_string(),
+        collateral_token: "bluna".to_string(),
+        custody_contract: "custody_bluna".to_string(),
+        max_ltv: Decimal256::percent(60),
+        debt_ceiling: None,
+        liquidation_threshold: Some(Decimal256::percent(80)),
+    };
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+    _lock_collateral(
+        deps.as_mut(),
+        env.clone(),
+        Addr::unchecked("addr0000"),
+        0,
+        vec![("bluna".to_string(), Uint256::from(1000000u64))],
+    )
+    .unwrap();
+
+    let msg = ExecuteMsg::UpdateWhitelist {
+        collateral_token: "bluna".to_string(),
+        custody_contract: None,
+        max_ltv: Some(Decimal256::percent(40)),
+        debt_ceiling: None,
+        remove_debt_ceiling: None,
+        liquidation_threshold: Some(Decimal256::percent(50)),
+        status: None,
+        ramp_duration: Some(100u64),
+    };
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let query_whitelist = |deps: Deps, env: Env| -> WhitelistResponseElem {
+        let res = query(
+            deps,
+            env,
+            QueryMsg::Whitelist {
+                collateral_token: Some("bluna".to_string()),
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+        let whitelist_res: WhitelistResponse = from_binary(&res).unwrap();
+        whitelist_res.elems[0].clone()
+    };
+    let query_borrow_limit = |deps: Deps, env: Env| -> Uint256 {
+        let res = query(
+            deps,
+            env,
+            QueryMsg::BorrowLimit {
+                borrower: "addr0000".to_string(),
+                block_time: None,
+                position_id: None,
+            },
+        )
+        .unwrap();
+        let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+        borrow_limit_res.borrow_limit
+    };
+
+    // nothing changes in the block of the update
+    let elem = query_whitelist(deps.as_ref(), env.clone());
+    assert_eq!(elem.max_ltv, Decimal256::percent(60));
+    assert_eq!(elem.liquidation_threshold, Decimal256::percent(80));
+    assert_eq!(elem.target_max_ltv, Decimal256::percent(40));
+    assert_eq!(elem.target_liquidation_threshold, Decimal256::percent(50));
+    assert_eq!(elem.ramp_end_time, Some(env.block.time.seconds() + 100));
+    assert_eq!(
+        query_borrow_limit(deps.as_ref(), env.clone()),
+        Uint256::from(600000000u64)
+    );
+
+    // halfway through the ramp
+    let mut mid_env = env.clone();
+    mid_env.block.time = env.block.time.plus_seconds(50);
+    let elem = query_whitelist(deps.as_ref(), mid_env.clone());
+    assert_eq!(elem.max_ltv, Decimal256::percent(50));
+    assert_eq!(elem.liquidation_threshold, Decimal256::percent(65));
+    assert_eq!(
+        query_borrow_limit(deps.as_ref(), mid_env.clone()),
+        Uint256::from(500000000u64)
+    );
+
+    // liquidated over the current liquidation limit = 1000 * 1000000 * 0.65
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(650000000u64))]);
+    let msg = ExecuteMsg::LiquidateCollateral {
+        borrower: "addr0000".to_string(),
+        position_id: None,
+    };
+    let liquidator = mock_info("addr0001", &[]);
+    match execute(
+        deps.as_mut(),
+        mid_env.clone(),
+        liquidator.clone(),
+        msg.clone(),
+    ) {
+        Err(ContractError::CannotLiquidateSafeLoan {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(650000001u64))]);
+    execute(deps.as_mut(), mid_env, liquidator, msg).unwrap();
+
+    // the targets apply once the ramp is over
+    let mut end_env = env.clone();
+    end_env.block.time = env.block.time.plus_seconds(100);
+    let elem = query_whitelist(deps.as_ref(), end_env);
+    assert_eq!(elem.max_ltv, Decimal256::percent(40));
+    assert_eq!(elem.liquidation_threshold, Decimal256::percent(50));
+    assert_eq!(elem.ramp_end_time, None);
+}
+
+#[test]
+fn emode_category() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_liquidation_percent(&[(&"liquidation".to_string(), &Decimal256::percent(1))]);
+    let env = mock_env();
+
+    let info = mock_info("owner", &[]);
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_contract: "uusd".to_string(),
+        price_timeframe: 60u64,
+    };
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    for collateral_token in ["bluna", "lunax", "batom"] {
+        let msg = ExecuteMsg::Whitelist {
+            name: collateral_token.to_string(),
+            symbol: collateral_token.to_string(),
+            collateral_token: collateral_token.to_string(),
+            custody_contract: format!("custody_{}", collateral_token),
+            max_ltv: Decimal256::percent(60),
+            debt_ceiling: None,
+            liquidation_threshold: Some(Decimal256::percent(70)),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    }
+    deps.querier.with_oracle_price(&[
+        (
+            &("bluna".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time.seconds(),
+                env.block.time.seconds(),
+            ),
+        ),
+        (
+            &("batom".to_string(), "uusd".to_string()),
+            &(
+                Decimal256::from_ratio(1000u64, 1u64),
+                env.block.time.seconds(),
+                env.block.time.seconds(),
+            ),
+        ),
+    ]);
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::zero())]);
+
+    let msg = ExecuteMsg::RegisterEmodeCategory {
+        category_id: 1u32,
+        label: "luna".to_string(),
+        collateral_tokens: vec!["bluna".to_string(), "lunax".to_string()],
+        max_ltv: Decimal256::percent(85),
+        liquidation_threshold: Decimal256::percent(90),
+    };
+    match execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        msg.clone(),
+    ) {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    match execute(deps.as_mut(), env.clone(), info, msg) {
+        Err(ContractError::EmodeCategoryAlreadyRegistered {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::EmodeCategories {
+            start_after: None,
+            limit: None,
+        },
+    )
+    .unwrap();
+    let categories_res: EmodeCategoriesResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        categories_res.categories,
+        vec![EmodeCategoryResponse {
+            category_id: 1u32,
+            label: "luna".to_string(),
+            collateral_tokens: vec!["bluna".to_string(), "lunax".to_string()],
+            max_ltv: Decimal256::percent(85),
+            liquidation_threshold: Decimal256::percent(90),
+            target_max_ltv: Decimal256::percent(85),
+            target_liquidation_threshold: Decimal256::percent(90),
+            ramp_end_time: None,
+        }]
+    );
+
+    // every collateral of the position has to belong to the category
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![
+            ("bluna".to_string(), Uint256::from(1000000u64)),
+            ("batom".to_string(), Uint256::from(1000000u64)),
+        ],
+        position_id: None,
+    };
+    let borrower = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env.clone(), borrower.clone(), msg).unwrap();
+    let set_emode = |category_id: Option<u32>| ExecuteMsg::SetEmodeCategory {
+        category_id,
+        position_id: None,
+    };
+    match execute(
+        deps.as_mut(),
+        env.clone(),
+        borrower.clone(),
+        set_emode(Some(1u32)),
+    ) {
+        Err(ContractError::CollateralNotInEmodeCategory(token)) => assert_eq!(token, "batom"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let msg = ExecuteMsg::UnlockCollateral {
+        collaterals: vec![("batom".to_string(), Uint256::from(1000000u64))],
+        position_id: None,
+    };
+    execute(deps.as_mut(), env.clone(), borrower.clone(), msg).unwrap();
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        borrower.clone(),
+        set_emode(Some(1u32)),
+    )
+    .unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::BorrowLimit {
+            borrower: "addr0000".to_string(),
+            block_time: None,
+            position_id: None,
+        },
+    )
+    .unwrap();
+    let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+    assert_eq!(borrow_limit_res.borrow_limit, Uint256::from(850000000u64));
+
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("batom".to_string(), Uint256::from(1000000u64))],
+        position_id: None,
+    };
+    match execute(deps.as_mut(), env.clone(), borrower.clone(), msg) {
+        Err(ContractError::CollateralNotInEmodeCategory(token)) => assert_eq!(token, "batom"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // out of the category the borrow limit would not cover the loan anymore
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(800000000u64))]);
+    match execute(deps.as_mut(), env.clone(), borrower, set_emode(None)) {
+        Err(ContractError::BorrowLimitExceeded(borrow_limit)) => {
+            assert_eq!(borrow_limit, 600000000u128)
+        }
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let res = query(
+        deps.as_ref(),
+        env.clone(),
+        QueryMsg::PositionHealth {
+            borrower: "addr0000".to_string(),
+            position_id: None,
+        },
+    )
+    .unwrap();
+    let health_res: PositionHealthResponse = from_binary(&res).unwrap();
+    assert_eq!(health_res.emode_category, Some(1u32));
+    assert_eq!(health_res.collaterals[0].max_ltv, Decimal256::percent(85));
+    assert_eq!(health_res.liquidation_limit, Uint256::from(900000000u64));
+
+    // liquidated over the liquidation limit of the category
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(900000000u64))]);
+    let msg = ExecuteMsg::LiquidateCollateral {
+        borrower: "addr0000".to_string(),
+        position_id: None,
+    };
+    let liquidator = mock_info("addr0001", &[]);
+    match execute(deps.as_mut(), env.clone(), liquidator.clone(), msg.clone()) {
+        Err(ContractError::CannotLiquidateSafeLoan {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::from(900000001u64))]);
+    execute(deps.as_mut(), env, liquidator, msg).unwrap();
+}
+
+#[test]
+fn emode_category_update() {
+    let mut deps = mock_dependencies(&[]);
+    let env = mock_env();
+
+    let info = mock_info("owner", &[]);
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        oracle_contract: "oracle".to_string(),
+        market_contract: "market".to_string(),
+        liquidation_contract: "liquidation".to_string(),
+        collector_contract: "collector".to_string(),
+        stable_contract: "uusd".to_string(),
+        price_timeframe: 1000u64,
+    };
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    for collateral_token in ["bluna", "lunax"] {
+        let msg = ExecuteMsg::Whitelist {
+            name: collateral_token.to_string(),
+            symbol: collateral_token.to_string(),
+            collateral_token: collateral_token.to_string(),
+            custody_contract: format!("custody_{}", collateral_token),
+            max_ltv: Decimal256::percent(60),
+            debt_ceiling: None,
+            liquidation_threshold: Some(Decimal256::percent(70)),
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    }
+    deps.querier.with_oracle_price(&[(
+        &("bluna".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::from_ratio(1000u64, 1u64),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+    deps.querier
+        .with_loan_amount(&[(&"addr0000".to_string(), &Uint256::zero())]);
+
+    let msg = ExecuteMsg::RegisterEmodeCategory {
+        category_id: 1u32,
+        label: "luna".to_string(),
+        collateral_tokens: vec!["bluna".to_string(), "lunax".to_string()],
+        max_ltv: Decimal256::percent(85),
+        liquidation_threshold: Decimal256::percent(90),
+    };
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let borrower = mock_info("addr0000", &[]);
+    let msg = ExecuteMsg::LockCollateral {
+        collaterals: vec![("bluna".to_string(), Uint256::from(1000000u64))],
+        position_id: None,
+    };
+    execute(deps.as_mut(), env.clone(), borrower.clone(), msg).unwrap();
+    let msg = ExecuteMsg::SetEmodeCategory {
+        category_id: Some(1u32),
+        position_id: None,
+    };
+    execute(deps.as_mut(), env.clone(), borrower, msg).unwrap();
+
+    let update_category =
+        |max_ltv: Option<Decimal256>,
+         liquidation_threshold: Option<Decimal256>,
+         remove_collateral_tokens: Option<Vec<String>>| {
+            ExecuteMsg::UpdateEmodeCategory {
+                category_id: 1u32,
+                collateral_tokens: None,
+                remove_collateral_tokens,
+                max_ltv,
+                liquidation_threshold,
+                ramp_duration: Some(100u64),
+            }
+        };
+    let borrow_limit = |deps: Deps, block_time: u64| -> Uint256 {
+        let res = query(
+            deps,
+            mock_env(),
+            QueryMsg::BorrowLimit {
+                borrower: "addr0000".to_string(),
+                block_time: Some(block_time),
+                position_id: None,
+            },
+        )
+        .unwrap();
+        let borrow_limit_res: BorrowLimitResponse = from_binary(&res).unwrap();
+        borrow_limit_res.borrow_limit
+    };
+
+    // the LTVs of a category stay under 100%
+    let msg = update_category(
+        Some(Decimal256::percent(100)),
+        Some(Decimal256::percent(100)),
+        None,
+    );
+    match execute(deps.as_mut(), env.clone(), info.clone(), msg) {
+        Err(ContractError::InvalidMaxLtv {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // category LTVs ramp down like the whitelist ones
+    let msg = update_category(
+        Some(Decimal256::percent(75)),
+        Some(Decimal256::percent(80)),
+        None,
+    );
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+    let mut mid_env = env.clone();
+    mid_env.block.time = env.block.time.plus_seconds(50);
+    let res = query(
+        deps.as_ref(),
+        mid_env.clone(),
+        QueryMsg::EmodeCategory { category_id: 1u32 },
+    )
+    .unwrap();
+    let category_res: EmodeCategoryResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        category_res,
+        EmodeCategoryResponse {
+            category_id: 1u32,
+            label: "luna".to_string(),
+            collateral_tokens: vec!["bluna".to_string(), "lunax".to_string()],
+            max_ltv: Decimal256::percent(80),
+            liquidation_threshold: Decimal256::percent(85),
+            target_max_ltv: Decimal256::percent(75),
+            target_liquidation_threshold: Decimal256::percent(80),
+            ramp_end_time: Some(env.block.time.seconds() + 100),
+        }
+    );
+    assert_eq!(
+        borrow_limit(deps.as_ref(), mid_env.block.time.seconds()),
+        Uint256::from(800000000u64)
+    );
+
+    // a frozen collateral keeps the boost of its category
+    let msg = ExecuteMsg::UpdateWhitelist {
+        collateral_token: "bluna".to_string(),
+        custody_contract: None,
+        max_ltv: None,
+        debt_ceiling: None,
+        remove_debt_ceiling: None,
+        liquidation_threshold: None,
+        status: Some(CollateralStatus::BorrowFrozen),
+        ramp_duration: None,
+    };
+    execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let end_time = env.block.time.seconds() + 100;
+    assert_eq!(
+        borrow_limit(deps.as_ref(), end_time),
+        Uint256::from(750000000u64)
+    );
+
+    // a collateral held by opted in positions can not be removed
+    let msg = update_category(None, None, Some(vec!["bluna".to_string()]));
+    match execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()) {
+        Err(ContractError::EmodeCollateralInUse(collateral)) => assert_eq!(collateral, "bluna"),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // a removed collateral loses it once the positions opted out
+    let msg_opt_out = ExecuteMsg::SetEmodeCategory {
+        category_id: None,
+        position_id: None,
+    };
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("addr0000", &[]),
+        msg_opt_out,
+    )
+    .unwrap();
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let res = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::EmodeCategory { category_id: 1u32 },
+    )
+    .unwrap();
+    let category_res: EmodeCategoryResponse = from_binary(&res).unwrap();
+    assert_eq!(category_res.collateral_tokens, vec!["lunax".to_string()]);
+    assert_eq!(
+        borrow_limit(deps.as_ref(), end_time),
+        Uint256::from(600000000u64)
+    );
+}
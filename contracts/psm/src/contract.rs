@@ -0,0 +1,299 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use crate::error::ContractError;
+use crate::state::{
+    read_asset, read_assets, read_config, read_fees_paid, store_asset, store_config, Config,
+    PsmAsset,
+};
+use crate::swap::{mint, redeem};
+
+use astroport::asset::AssetInfo;
+use astroport::querier::query_token_precision;
+use cosmwasm_bignumber::math::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, from_binary, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response,
+    StdError, StdResult,
+};
+use cw20::Cw20ReceiveMsg;
+
+use moneymarket::common::optional_addr_validate;
+use moneymarket::fee_distribution::{self, FeeDistributionResponse, FeeRecipient};
+use moneymarket::psm::{
+    AssetResponse, AssetsResponse, ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg,
+    MigrateMsg, QueryMsg,
+};
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    store_config(
+        deps.storage,
+        &Config {
+            owner_addr: deps.api.addr_validate(&msg.owner_addr)?,
+            market_contract: deps.api.addr_validate(&msg.market_contract)?,
+            stable_contract: deps.api.addr_validate(&msg.stable_contract)?,
+            collector_contract: deps.api.addr_validate(&msg.collector_contract)?,
+            fee_recipients: vec![],
+        },
+    )?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
+        ExecuteMsg::UpdateConfig {
+            owner_addr,
+            collector_contract,
+        } => {
+            let api = deps.api;
+            update_config(
+                deps,
+                info,
+                optional_addr_validate(api, owner_addr)?,
+                optional_addr_validate(api, collector_contract)?,
+            )
+        }
+        ExecuteMsg::UpdateFeeRecipients { recipients } => {
+            update_fee_recipients(deps, info, recipients)
+        }
+        ExecuteMsg::RegisterAsset {
+            asset_info,
+            mint_fee,
+            redeem_fee,
+            cap,
+        } => register_asset(deps, info, asset_info, mint_fee, redeem_fee, cap),
+        ExecuteMsg::Mint { recipient } => {
+            if info.funds.len() != 1 {
+                return Err(ContractError::TooManyCoins());
+            }
+
+            let coin = info.funds[0].clone();
+            let recipient = deps
+                .api
+                .addr_validate(&recipient.unwrap_or_else(|| info.sender.to_string()))?;
+            mint(
+                deps,
+                env,
+                AssetInfo::NativeToken { denom: coin.denom },
+                recipient,
+                coin.amount.into(),
+            )
+        }
+    }
+}
+
+pub fn receive_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let contract_addr = info.sender;
+    match from_binary(&cw20_msg.msg) {
+        Ok(Cw20HookMsg::Mint { recipient }) => {
+            // The sent token is the deposited asset, the whitelist validates it
+            let recipient = deps
+                .api
+                .addr_validate(&recipient.unwrap_or(cw20_msg.sender))?;
+            mint(
+                deps,
+                env,
+                AssetInfo::Token { contract_addr },
+                recipient,
+                cw20_msg.amount.into(),
+            )
+        }
+        Ok(Cw20HookMsg::Redeem {
+            asset_info,
+            recipient,
+        }) => {
+            let config: Config = read_config(deps.storage)?;
+            if contract_addr != config.stable_contract {
+                return Err(ContractError::Unauthorized {});
+            }
+
+            let recipient = deps
+                .api
+                .addr_validate(&recipient.unwrap_or(cw20_msg.sender))?;
+            redeem(deps, asset_info, recipient, cw20_msg.amount.into())
+        }
+        _ => Err(ContractError::MissingHook {}),
+    }
+}
+
+pub fn update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    owner_addr: Option<Addr>,
+    collector_contract: Option<Addr>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+
+    // permission check
+    if info.sender != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(owner_addr) = owner_addr {
+        config.owner_addr = owner_addr;
+    }
+
+    if let Some(collector_contract) = collector_contract {
+        config.collector_contract = collector_contract;
+    }
+
+    store_config(deps.storage, &config)?;
+    Ok(Response::new().add_attributes(vec![attr("action", "update_config")]))
+}
+
+pub fn update_fee_recipients(
+    deps: DepsMut,
+    info: MessageInfo,
+    recipients: Vec<FeeRecipient>,
+) -> Result<Response, ContractError> {
+    let mut config: Config = read_config(deps.storage)?;
+
+    // permission check
+    if info.sender != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let res =
+        fee_distribution::update_fee_recipients(deps.api, &mut config.fee_recipients, recipients)?;
+    store_config(deps.storage, &config)?;
+
+    Ok(res)
+}
+
+pub fn register_asset(
+    deps: DepsMut,
+    info: MessageInfo,
+    asset_info: AssetInfo,
+    mint_fee: Decimal256,
+    redeem_fee: Decimal256,
+    cap: Uint256,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+
+    // permission check
+    if info.sender != config.owner_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if mint_fee >= Decimal256::one() || redeem_fee >= Decimal256::one() {
+        return Err(ContractError::InvalidFee {});
+    }
+
+    asset_info.check(deps.api)?;
+    if asset_info
+        == (AssetInfo::Token {
+            contract_addr: config.stable_contract.clone(),
+        })
+    {
+        return Err(ContractError::Std(StdError::generic_err(
+            "SOLID can not back itself",
+        )));
+    }
+
+    // Swaps are 1:1 in raw amounts, the asset must share the SOLID decimals
+    let stable_decimals = query_token_precision(
+        &deps.querier,
+        &AssetInfo::Token {
+            contract_addr: config.stable_contract,
+        },
+    )?;
+    if query_token_precision(&deps.querier, &asset_info)? != stable_decimals {
+        return Err(ContractError::InvalidDecimals(stable_decimals));
+    }
+
+    // Keep the reserve of an already whitelisted asset
+    let reserve = read_asset(deps.storage, &asset_info)?
+        .map(|asset| asset.reserve)
+        .unwrap_or_else(Uint256::zero);
+
+    store_asset(
+        deps.storage,
+        &PsmAsset {
+            asset_info: asset_info.clone(),
+            mint_fee,
+            redeem_fee,
+            cap,
+            reserve,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "register_asset"),
+        attr("asset", asset_info.to_string()),
+        attr("cap", cap),
+    ]))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::FeeDistribution {} => to_binary(&query_fee_distribution(deps)?),
+        QueryMsg::Asset { asset_info } => to_binary(&query_asset(deps, asset_info)?),
+        QueryMsg::Assets {} => to_binary(&query_assets(deps)?),
+    }
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config: Config = read_config(deps.storage)?;
+    Ok(ConfigResponse {
+        owner_addr: config.owner_addr.to_string(),
+        market_contract: config.market_contract.to_string(),
+        stable_contract: config.stable_contract.to_string(),
+        collector_contract: config.collector_contract.to_string(),
+    })
+}
+
+fn to_asset_response(asset: PsmAsset) -> AssetResponse {
+    AssetResponse {
+        asset_info: asset.asset_info,
+        mint_fee: asset.mint_fee,
+        redeem_fee: asset.redeem_fee,
+        cap: asset.cap,
+        reserve: asset.reserve,
+    }
+}
+
+pub fn query_fee_distribution(deps: Deps) -> StdResult<FeeDistributionResponse> {
+    let config: Config = read_config(deps.storage)?;
+    fee_distribution::query_fee_distribution(deps.storage, read_fees_paid, config.fee_recipients)
+}
+
+pub fn query_asset(deps: Deps, asset_info: AssetInfo) -> StdResult<AssetResponse> {
+    let asset: PsmAsset = read_asset(deps.storage, &asset_info)?
+        .ok_or_else(|| StdError::generic_err(format!("Asset not whitelisted: {}", asset_info)))?;
+
+    Ok(to_asset_response(asset))
+}
+
+pub fn query_assets(deps: Deps) -> StdResult<AssetsResponse> {
+    let assets: Vec<AssetResponse> = read_assets(deps.storage)?
+        .into_iter()
+        .map(to_asset_response)
+        .collect();
+
+    Ok(AssetsResponse { assets })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
+    Ok(Response::default())
+}
@@ -0,0 +1,38 @@
+use cosmwasm_std::{OverflowError, StdError};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    OverflowError(#[from] OverflowError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Invalid Zero Amount")]
+    InvalidZeroAmount {},
+
+    #[error("Must send only one coin")]
+    TooManyCoins(),
+
+    #[error("Asset not whitelisted: {0}")]
+    AssetNotWhitelisted(String),
+
+    #[error("Fees must be lower than 100%")]
+    InvalidFee {},
+
+    #[error("Asset decimals must match the SOLID decimals: {0}")]
+    InvalidDecimals(u8),
+
+    #[error("Deposit exceeds the asset cap, {0} left")]
+    CapExceeded(u128),
+
+    #[error("Not enough reserve to redeem, {0} available")]
+    InsufficientReserve(u128),
+
+    #[error("Invalid request: \"mint\" or \"redeem\" message not included in request")]
+    MissingHook {},
+}
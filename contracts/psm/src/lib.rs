@@ -0,0 +1,7 @@
+pub mod contract;
+pub mod error;
+pub mod state;
+pub mod swap;
+
+#[cfg(test)]
+mod testing;
@@ -0,0 +1,71 @@
+use astroport::asset::AssetInfo;
+use cosmwasm_bignumber::math::{Decimal256, Uint256};
+use cosmwasm_std::{Addr, Order, StdResult, Storage};
+use cosmwasm_storage::{
+    bucket, bucket_read, singleton, singleton_read, ReadonlySingleton, Singleton,
+};
+use moneymarket::fee_distribution::{FeePaid, FeeRecipient};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub const KEY_CONFIG: &[u8] = b"config";
+const KEY_FEES_PAID: &[u8] = b"fees_paid";
+
+const PREFIX_ASSET: &[u8] = b"asset";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Config {
+    pub owner_addr: Addr,
+    pub market_contract: Addr,
+    pub stable_contract: Addr,
+    pub collector_contract: Addr,
+    /// Split of the swap fees, all of them go to the collector when empty
+    #[serde(default)]
+    pub fee_recipients: Vec<FeeRecipient>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PsmAsset {
+    pub asset_info: AssetInfo,
+    pub mint_fee: Decimal256,
+    pub redeem_fee: Decimal256,
+    pub cap: Uint256,
+    pub reserve: Uint256,
+}
+
+pub fn store_config(storage: &mut dyn Storage, data: &Config) -> StdResult<()> {
+    Singleton::new(storage, KEY_CONFIG).save(data)
+}
+
+pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
+    ReadonlySingleton::new(storage, KEY_CONFIG).load()
+}
+
+/// Cumulative swap fees paid to each recipient kind
+pub fn store_fees_paid(storage: &mut dyn Storage, fees_paid: &[FeePaid]) -> StdResult<()> {
+    singleton(storage, KEY_FEES_PAID).save(&fees_paid.to_vec())
+}
+
+pub fn read_fees_paid(storage: &dyn Storage) -> StdResult<Vec<FeePaid>> {
+    Ok(singleton_read(storage, KEY_FEES_PAID)
+        .may_load()?
+        .unwrap_or_default())
+}
+
+pub fn store_asset(storage: &mut dyn Storage, asset: &PsmAsset) -> StdResult<()> {
+    bucket(storage, PREFIX_ASSET).save(asset.asset_info.as_bytes(), asset)
+}
+
+pub fn read_asset(storage: &dyn Storage, asset_info: &AssetInfo) -> StdResult<Option<PsmAsset>> {
+    bucket_read(storage, PREFIX_ASSET).may_load(asset_info.as_bytes())
+}
+
+pub fn read_assets(storage: &dyn Storage) -> StdResult<Vec<PsmAsset>> {
+    bucket_read(storage, PREFIX_ASSET)
+        .range(None, None, Order::Ascending)
+        .map(|item| {
+            let (_, asset) = item?;
+            Ok(asset)
+        })
+        .collect()
+}
@@ -0,0 +1,153 @@
+use astroport::asset::{Asset, AssetInfo};
+use cosmwasm_bignumber::math::Uint256;
+use cosmwasm_std::{attr, to_binary, Addr, CosmosMsg, DepsMut, Env, Response, WasmMsg};
+use cw20::Cw20ExecuteMsg;
+use moneymarket::fee_distribution::{distribute_fee, fee_recipients};
+use moneymarket::market::ExecuteMsg as MarketExecuteMsg;
+
+use crate::error::ContractError;
+use crate::state::{
+    read_asset, read_config, read_fees_paid, store_asset, store_fees_paid, Config, PsmAsset,
+};
+
+fn load_asset(deps: &DepsMut, asset_info: &AssetInfo) -> Result<PsmAsset, ContractError> {
+    read_asset(deps.storage, asset_info)?
+        .ok_or_else(|| ContractError::AssetNotWhitelisted(asset_info.to_string()))
+}
+
+/// Take `amount` of a whitelisted stable into the reserve and mint
+/// the same amount of SOLID to the recipient, minus the mint fee.
+/// The fee is minted to the module and paid to the fee recipients in SOLID
+pub fn mint(
+    deps: DepsMut,
+    env: Env,
+    asset_info: AssetInfo,
+    recipient: Addr,
+    amount: Uint256,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let mut asset: PsmAsset = load_asset(&deps, &asset_info)?;
+
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let fee_amount = amount * asset.mint_fee;
+    let mint_amount = amount - fee_amount;
+
+    if asset.reserve + amount > asset.cap {
+        let available = if asset.cap > asset.reserve {
+            asset.cap - asset.reserve
+        } else {
+            Uint256::zero()
+        };
+        return Err(ContractError::CapExceeded(available.into()));
+    }
+
+    asset.reserve += amount;
+    store_asset(deps.storage, &asset)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.market_contract.to_string(),
+        funds: vec![],
+        msg: to_binary(&MarketExecuteMsg::MintStable {
+            recipient: recipient.to_string(),
+            amount: mint_amount,
+        })?,
+    })];
+
+    if !fee_amount.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.market_contract.to_string(),
+            funds: vec![],
+            msg: to_binary(&MarketExecuteMsg::MintStable {
+                recipient: env.contract.address.to_string(),
+                amount: fee_amount,
+            })?,
+        }));
+        messages.extend(distribute_fee(
+            deps.storage,
+            read_fees_paid,
+            store_fees_paid,
+            config.stable_contract.to_string(),
+            &fee_recipients(
+                &config.fee_recipients,
+                config.collector_contract.to_string(),
+            ),
+            fee_amount,
+            None,
+        )?);
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "mint"),
+        attr("asset", asset_info.to_string()),
+        attr("recipient", recipient),
+        attr("mint_amount", mint_amount),
+        attr("fee_amount", fee_amount),
+    ]))
+}
+
+/// Burn `amount` of the SOLID sent to the module and release the same
+/// amount of the asset to the recipient, minus the redeem fee.
+/// The fee is paid to the fee recipients in SOLID
+pub fn redeem(
+    deps: DepsMut,
+    asset_info: AssetInfo,
+    recipient: Addr,
+    amount: Uint256,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let mut asset: PsmAsset = load_asset(&deps, &asset_info)?;
+
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let fee_amount = amount * asset.redeem_fee;
+    let redeem_amount = amount - fee_amount;
+
+    if redeem_amount > asset.reserve {
+        return Err(ContractError::InsufficientReserve(asset.reserve.into()));
+    }
+
+    asset.reserve = asset.reserve - redeem_amount;
+    store_asset(deps.storage, &asset)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.stable_contract.to_string(),
+        funds: vec![],
+        msg: to_binary(&Cw20ExecuteMsg::Burn {
+            amount: redeem_amount.into(),
+        })?,
+    })];
+
+    messages.extend(distribute_fee(
+        deps.storage,
+        read_fees_paid,
+        store_fees_paid,
+        config.stable_contract.to_string(),
+        &fee_recipients(
+            &config.fee_recipients,
+            config.collector_contract.to_string(),
+        ),
+        fee_amount,
+        None,
+    )?);
+
+    messages.push(
+        Asset {
+            info: asset_info.clone(),
+            amount: redeem_amount.into(),
+        }
+        .into_msg(&deps.querier, recipient.clone())?,
+    );
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "redeem"),
+        attr("asset", asset_info.to_string()),
+        attr("recipient", recipient),
+        attr("redeem_amount", redeem_amount),
+        attr("fee_amount", fee_amount),
+    ]))
+}
@@ -0,0 +1,463 @@
+use crate::contract::{execute, instantiate, query};
+use crate::error::ContractError;
+use crate::testing::mock_querier::{mock_dependencies, WasmMockQuerier};
+
+use astroport::asset::AssetInfo;
+use cosmwasm_bignumber::math::{Decimal256, Uint256};
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    attr, from_binary, to_binary, Addr, BankMsg, Coin, CosmosMsg, OwnedDeps, SubMsg, Uint128,
+    WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use moneymarket::fee_distribution::{
+    FeeDistributionResponse, FeePaid, FeeRecipient, FeeRecipientKind,
+};
+use moneymarket::market::ExecuteMsg as MarketExecuteMsg;
+use moneymarket::overseer::Cw20HookMsg as OverseerCw20HookMsg;
+use moneymarket::psm::{
+    AssetResponse, AssetsResponse, ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg,
+    QueryMsg,
+};
+
+fn usdc() -> AssetInfo {
+    AssetInfo::NativeToken {
+        denom: "uusdc".to_string(),
+    }
+}
+
+fn usdt() -> AssetInfo {
+    AssetInfo::Token {
+        contract_addr: Addr::unchecked("usdt"),
+    }
+}
+
+fn setup() -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier.with_token_decimals(&[
+        (&"solid".to_string(), &6u8),
+        (&"usdt".to_string(), &6u8),
+        (&"wbtc".to_string(), &8u8),
+    ]);
+
+    let msg = InstantiateMsg {
+        owner_addr: "owner".to_string(),
+        market_contract: "market".to_string(),
+        stable_contract: "solid".to_string(),
+        collector_contract: "collector".to_string(),
+    };
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let info = mock_info("owner", &[]);
+    for asset_info in [usdc(), usdt()] {
+        let msg = ExecuteMsg::RegisterAsset {
+            asset_info,
+            mint_fee: Decimal256::permille(1),
+            redeem_fee: Decimal256::permille(2),
+            cap: Uint256::from(1000000u64),
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    }
+
+    deps
+}
+
+#[test]
+fn proper_initialization() {
+    let deps = setup();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+    let config_res: ConfigResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        config_res,
+        ConfigResponse {
+            owner_addr: "owner".to_string(),
+            market_contract: "market".to_string(),
+            stable_contract: "solid".to_string(),
+            collector_contract: "collector".to_string(),
+        }
+    );
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::Assets {}).unwrap();
+    let assets_res: AssetsResponse = from_binary(&res).unwrap();
+    assert_eq!(assets_res.assets.len(), 2);
+}
+
+#[test]
+fn register_asset() {
+    let mut deps = setup();
+
+    // Unauthorized
+    let msg = ExecuteMsg::RegisterAsset {
+        asset_info: usdc(),
+        mint_fee: Decimal256::zero(),
+        redeem_fee: Decimal256::zero(),
+        cap: Uint256::zero(),
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // Invalid fee
+    let msg = ExecuteMsg::RegisterAsset {
+        asset_info: usdc(),
+        mint_fee: Decimal256::one(),
+        redeem_fee: Decimal256::zero(),
+        cap: Uint256::zero(),
+    };
+    let info = mock_info("owner", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(ContractError::InvalidFee {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // SOLID can not be registered
+    let msg = ExecuteMsg::RegisterAsset {
+        asset_info: AssetInfo::Token {
+            contract_addr: Addr::unchecked("solid"),
+        },
+        mint_fee: Decimal256::zero(),
+        redeem_fee: Decimal256::zero(),
+        cap: Uint256::from(1000000u64),
+    };
+    let _res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap_err();
+
+    // Decimals must match the SOLID ones
+    let msg = ExecuteMsg::RegisterAsset {
+        asset_info: AssetInfo::Token {
+            contract_addr: Addr::unchecked("wbtc"),
+        },
+        mint_fee: Decimal256::zero(),
+        redeem_fee: Decimal256::zero(),
+        cap: Uint256::from(1000000u64),
+    };
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+    match res {
+        Err(ContractError::InvalidDecimals(decimals)) => assert_eq!(decimals, 6u8),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // Updating an asset keeps its reserve
+    let msg = ExecuteMsg::Mint { recipient: None };
+    let info_mint = mock_info("addr0000", &[Coin::new(100000u128, "uusdc")]);
+    execute(deps.as_mut(), mock_env(), info_mint, msg).unwrap();
+
+    let msg = ExecuteMsg::RegisterAsset {
+        asset_info: usdc(),
+        mint_fee: Decimal256::zero(),
+        redeem_fee: Decimal256::zero(),
+        cap: Uint256::from(500000u64),
+    };
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Asset { asset_info: usdc() },
+    )
+    .unwrap();
+    let asset_res: AssetResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        asset_res,
+        AssetResponse {
+            asset_info: usdc(),
+            mint_fee: Decimal256::zero(),
+            redeem_fee: Decimal256::zero(),
+            cap: Uint256::from(500000u64),
+            reserve: Uint256::from(100000u64),
+        }
+    );
+}
+
+#[test]
+fn mint() {
+    let mut deps = setup();
+
+    // Not whitelisted native
+    let msg = ExecuteMsg::Mint { recipient: None };
+    let info = mock_info("addr0000", &[Coin::new(100000u128, "uluna")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::AssetNotWhitelisted(_)) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // Native mint, fee minted to the module and sent to the collector in SOLID
+    let msg = ExecuteMsg::Mint { recipient: None };
+    let info = mock_info("addr0000", &[Coin::new(100000u128, "uusdc")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "market".to_string(),
+                funds: vec![],
+                msg: to_binary(&MarketExecuteMsg::MintStable {
+                    recipient: "addr0000".to_string(),
+                    amount: Uint256::from(99900u64),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "market".to_string(),
+                funds: vec![],
+                msg: to_binary(&MarketExecuteMsg::MintStable {
+                    recipient: MOCK_CONTRACT_ADDR.to_string(),
+                    amount: Uint256::from(100u64),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "collector".to_string(),
+                    amount: Uint128::from(100u128),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "mint"),
+            attr("asset", "uusdc"),
+            attr("recipient", "addr0000"),
+            attr("mint_amount", "99900"),
+            attr("fee_amount", "100"),
+        ]
+    );
+
+    // Cw20 mint to another recipient
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(200000u128),
+        msg: to_binary(&Cw20HookMsg::Mint {
+            recipient: Some("addr0001".to_string()),
+        })
+        .unwrap(),
+    });
+    let info = mock_info("usdt", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "market".to_string(),
+                funds: vec![],
+                msg: to_binary(&MarketExecuteMsg::MintStable {
+                    recipient: "addr0001".to_string(),
+                    amount: Uint256::from(199800u64),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "market".to_string(),
+                funds: vec![],
+                msg: to_binary(&MarketExecuteMsg::MintStable {
+                    recipient: MOCK_CONTRACT_ADDR.to_string(),
+                    amount: Uint256::from(200u64),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "collector".to_string(),
+                    amount: Uint128::from(200u128),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    // Cap exceeded
+    let msg = ExecuteMsg::Mint { recipient: None };
+    let info = mock_info("addr0000", &[Coin::new(1000000u128, "uusdc")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::CapExceeded(left)) => assert_eq!(left, 900000u128),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn redeem() {
+    let mut deps = setup();
+
+    let msg = ExecuteMsg::Mint { recipient: None };
+    let info = mock_info("addr0000", &[Coin::new(100000u128, "uusdc")]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Only SOLID can be redeemed
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(50000u128),
+        msg: to_binary(&Cw20HookMsg::Redeem {
+            asset_info: usdc(),
+            recipient: None,
+        })
+        .unwrap(),
+    });
+    let info = mock_info("usdt", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    // SOLID burned, fee sent to the collector in SOLID
+    let info = mock_info("solid", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Burn {
+                    amount: Uint128::from(49900u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "collector".to_string(),
+                    amount: Uint128::from(100u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "addr0000".to_string(),
+                amount: vec![Coin::new(49900u128, "uusdc")],
+            })),
+        ]
+    );
+
+    let res = query(
+        deps.as_ref(),
+        mock_env(),
+        QueryMsg::Asset { asset_info: usdc() },
+    )
+    .unwrap();
+    let asset_res: AssetResponse = from_binary(&res).unwrap();
+    assert_eq!(asset_res.reserve, Uint256::from(50100u64));
+
+    // Not enough reserve
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(100000u128),
+        msg: to_binary(&Cw20HookMsg::Redeem {
+            asset_info: usdc(),
+            recipient: None,
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), mock_env(), info, msg);
+    match res {
+        Err(ContractError::InsufficientReserve(available)) => assert_eq!(available, 50100u128),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+}
+
+#[test]
+fn fee_recipients() {
+    let mut deps = setup();
+
+    let recipients = vec![
+        FeeRecipient {
+            kind: FeeRecipientKind::Collector,
+            address: "collector".to_string(),
+            weight: 6000,
+        },
+        FeeRecipient {
+            kind: FeeRecipientKind::Reserve,
+            address: "overseer".to_string(),
+            weight: 4000,
+        },
+    ];
+
+    // Unauthorized
+    let msg = ExecuteMsg::UpdateFeeRecipients {
+        recipients: recipients.clone(),
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+    match res {
+        Err(ContractError::Unauthorized {}) => (),
+        _ => panic!("DO NOT ENTER HERE"),
+    }
+
+    let info = mock_info("owner", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // Mint fee split between the collector and the overseer reserve
+    let msg = ExecuteMsg::Mint { recipient: None };
+    let info = mock_info("addr0000", &[Coin::new(100000u128, "uusdc")]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages[2..],
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "collector".to_string(),
+                    amount: Uint128::from(60u128),
+                })
+                .unwrap(),
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "solid".to_string(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: "overseer".to_string(),
+                    amount: Uint128::from(40u128),
+                    msg: to_binary(&OverseerCw20HookMsg::FundReserve {}).unwrap(),
+                })
+                .unwrap(),
+            })),
+        ]
+    );
+
+    // Redeem fee split the same way
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "addr0000".to_string(),
+        amount: Uint128::from(50000u128),
+        msg: to_binary(&Cw20HookMsg::Redeem {
+            asset_info: usdc(),
+            recipient: None,
+        })
+        .unwrap(),
+    });
+    let info = mock_info("solid", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let res = query(deps.as_ref(), mock_env(), QueryMsg::FeeDistribution {}).unwrap();
+    let distribution_res: FeeDistributionResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        distribution_res,
+        FeeDistributionResponse {
+            recipients,
+            paid: vec![
+                FeePaid {
+                    kind: FeeRecipientKind::Collector,
+                    amount: Uint256::from(120u64),
+                },
+                FeePaid {
+                    kind: FeeRecipientKind::Reserve,
+                    amount: Uint256::from(80u64),
+                },
+            ],
+        }
+    );
+}
@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_bignumber::math::Uint256;
 use cw20::Cw20ReceiveMsg;
 
+use crate::position::PositionId;
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InstantiateMsg {
@@ -46,6 +48,20 @@ pub enum ExecuteMsg {
         liquidator: String,
         borrower: String,
         amount: Uint256,
+        /// Position liquidated, its loan is repaid with the liquidation proceeds
+        position_id: Option<PositionId>,
+    },
+    /// Release locked collateral of a borrower and send it to `recipient`
+    WithdrawLockedCollateral {
+        borrower: String,
+        recipient: String,
+        amount: Uint256,
+    },
+    /// Move locked collateral of a borrower to `recipient`, keeping it locked
+    TransferLockedCollateral {
+        borrower: String,
+        recipient: String,
+        amount: Uint256,
     },
 
     ////////////////////
@@ -62,7 +78,10 @@ pub enum ExecuteMsg {
 #[serde(rename_all = "snake_case")]
 pub enum Cw20HookMsg {
     /// Deposit collateral token
-    DepositCollateral {},
+    DepositCollateral {
+        /// Borrower credited with the deposit, defaults to the sender
+        borrower: Option<String>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -0,0 +1,228 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::math::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, to_binary, Api, CosmosMsg, Response, StdError, StdResult, Storage, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+
+use crate::overseer::Cw20HookMsg as OverseerCw20HookMsg;
+
+/// Weights are expressed in basis points of the distributed fee
+pub const TOTAL_FEE_WEIGHT: u64 = 10000;
+
+/// Storage accessors of the cumulative fees paid by a contract
+pub type ReadFeesPaid = fn(&dyn Storage) -> StdResult<Vec<FeePaid>>;
+pub type StoreFeesPaid = fn(&mut dyn Storage, &[FeePaid]) -> StdResult<()>;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeRecipientKind {
+    Collector,
+    /// The overseer, funded through its `FundReserve` hook
+    Reserve,
+    CapaStakers,
+    Treasury,
+}
+
+impl FeeRecipientKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeeRecipientKind::Collector => "collector",
+            FeeRecipientKind::Reserve => "reserve",
+            FeeRecipientKind::CapaStakers => "capa_stakers",
+            FeeRecipientKind::Treasury => "treasury",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeeRecipient {
+    pub kind: FeeRecipientKind,
+    pub address: String,
+    /// Share of every fee in basis points
+    pub weight: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeePaid {
+    pub kind: FeeRecipientKind,
+    pub amount: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeeDistributionResponse {
+    /// Empty when every fee goes to the default fee address
+    pub recipients: Vec<FeeRecipient>,
+    /// Cumulative amounts paid to each recipient kind
+    pub paid: Vec<FeePaid>,
+}
+
+/// Recipients must be unique per kind with valid addresses
+/// and weights summing to `TOTAL_FEE_WEIGHT`. An empty list is valid
+pub fn validate_fee_recipients(api: &dyn Api, recipients: &[FeeRecipient]) -> StdResult<()> {
+    if recipients.is_empty() {
+        return Ok(());
+    }
+
+    let mut total_weight = 0u64;
+    for (i, recipient) in recipients.iter().enumerate() {
+        api.addr_validate(&recipient.address)?;
+        if recipients[..i].iter().any(|r| r.kind == recipient.kind) {
+            return Err(StdError::generic_err(format!(
+                "Duplicate fee recipient: {}",
+                recipient.kind.as_str()
+            )));
+        }
+        total_weight += recipient.weight;
+    }
+
+    if total_weight != TOTAL_FEE_WEIGHT {
+        return Err(StdError::generic_err(format!(
+            "Fee recipient weights must sum to {}",
+            TOTAL_FEE_WEIGHT
+        )));
+    }
+
+    Ok(())
+}
+
+/// Split `amount` over the recipients by weight,
+/// the rounding remainder goes to the first recipient
+pub fn split_fee(recipients: &[FeeRecipient], amount: Uint256) -> Vec<(FeeRecipient, Uint256)> {
+    let mut shares: Vec<(FeeRecipient, Uint256)> = recipients
+        .iter()
+        .map(|recipient| {
+            let share = amount * Decimal256::from_ratio(recipient.weight, TOTAL_FEE_WEIGHT);
+            (recipient.clone(), share)
+        })
+        .collect();
+
+    let distributed = shares
+        .iter()
+        .fold(Uint256::zero(), |total, (_, share)| total + *share);
+    if let Some((_, share)) = shares.first_mut() {
+        *share += amount - distributed;
+    }
+
+    shares
+        .into_iter()
+        .filter(|(_, share)| !share.is_zero())
+        .collect()
+}
+
+/// Add `amount` to the cumulative fees paid to `kind`
+pub fn record_fee_paid(fees_paid: &mut Vec<FeePaid>, kind: FeeRecipientKind, amount: Uint256) {
+    match fees_paid.iter_mut().find(|paid| paid.kind == kind) {
+        Some(paid) => paid.amount += amount,
+        None => fees_paid.push(FeePaid { kind, amount }),
+    }
+}
+
+/// Message paying `amount` of the stable token to the recipient, pulled from
+/// `owner` when set. The reserve is funded through the overseer hook
+pub fn fee_payment_msg(
+    stable_contract: String,
+    recipient: &FeeRecipient,
+    amount: Uint256,
+    owner: Option<String>,
+) -> StdResult<CosmosMsg> {
+    let msg = match (recipient.kind, owner) {
+        (FeeRecipientKind::Reserve, Some(owner)) => Cw20ExecuteMsg::SendFrom {
+            owner,
+            contract: recipient.address.clone(),
+            amount: amount.into(),
+            msg: to_binary(&OverseerCw20HookMsg::FundReserve {})?,
+        },
+        (FeeRecipientKind::Reserve, None) => Cw20ExecuteMsg::Send {
+            contract: recipient.address.clone(),
+            amount: amount.into(),
+            msg: to_binary(&OverseerCw20HookMsg::FundReserve {})?,
+        },
+        (_, Some(owner)) => Cw20ExecuteMsg::TransferFrom {
+            owner,
+            recipient: recipient.address.clone(),
+            amount: amount.into(),
+        },
+        (_, None) => Cw20ExecuteMsg::Transfer {
+            recipient: recipient.address.clone(),
+            amount: amount.into(),
+        },
+    };
+
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: stable_contract,
+        funds: vec![],
+        msg: to_binary(&msg)?,
+    }))
+}
+
+/// Configured fee recipients, `default_address` takes everything when none are set
+pub fn fee_recipients(recipients: &[FeeRecipient], default_address: String) -> Vec<FeeRecipient> {
+    if recipients.is_empty() {
+        vec![FeeRecipient {
+            kind: FeeRecipientKind::Collector,
+            address: default_address,
+            weight: TOTAL_FEE_WEIGHT,
+        }]
+    } else {
+        recipients.to_vec()
+    }
+}
+
+/// Messages transferring `fee_amount` of the stable token to the recipients,
+/// pulled from `owner` when set, and record the amounts paid
+pub fn distribute_fee(
+    storage: &mut dyn Storage,
+    read_fees_paid: ReadFeesPaid,
+    store_fees_paid: StoreFeesPaid,
+    stable_contract: String,
+    recipients: &[FeeRecipient],
+    fee_amount: Uint256,
+    owner: Option<String>,
+) -> StdResult<Vec<CosmosMsg>> {
+    if fee_amount.is_zero() {
+        return Ok(vec![]);
+    }
+
+    let mut fees_paid = read_fees_paid(storage)?;
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for (recipient, amount) in split_fee(recipients, fee_amount) {
+        record_fee_paid(&mut fees_paid, recipient.kind, amount);
+        messages.push(fee_payment_msg(
+            stable_contract.clone(),
+            &recipient,
+            amount,
+            owner.clone(),
+        )?);
+    }
+    store_fees_paid(storage, &fees_paid)?;
+
+    Ok(messages)
+}
+
+/// Replace the configured `fee_recipients` once validated,
+/// the caller checks the permission and stores its config
+pub fn update_fee_recipients(
+    api: &dyn Api,
+    fee_recipients: &mut Vec<FeeRecipient>,
+    recipients: Vec<FeeRecipient>,
+) -> StdResult<Response> {
+    validate_fee_recipients(api, &recipients)?;
+    *fee_recipients = recipients;
+
+    Ok(Response::new().add_attributes(vec![attr("action", "update_fee_recipients")]))
+}
+
+pub fn query_fee_distribution(
+    storage: &dyn Storage,
+    read_fees_paid: ReadFeesPaid,
+    recipients: Vec<FeeRecipient>,
+) -> StdResult<FeeDistributionResponse> {
+    Ok(FeeDistributionResponse {
+        recipients,
+        paid: read_fees_paid(storage)?,
+    })
+}
@@ -6,8 +6,16 @@ use cosmwasm_bignumber::math::Decimal256;
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct InstantiateMsg {
     pub owner: String,
+    /// Yearly borrow rate applied while SOLID trades at `base_peg`
     pub base_rate: Decimal256,
+    /// Target price of SOLID in the oracle base asset
     pub base_peg: Decimal256,
+    /// Rate added per unit of peg deviation up to the kink
+    pub multiplier: Decimal256,
+    /// Deviation below `base_peg` from which `jump_multiplier` applies
+    pub kink: Decimal256,
+    /// Rate added per unit of peg deviation past the kink
+    pub jump_multiplier: Decimal256,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -17,6 +25,9 @@ pub enum ExecuteMsg {
         owner: Option<String>,
         base_rate: Option<Decimal256>,
         base_peg: Option<Decimal256>,
+        multiplier: Option<Decimal256>,
+        kink: Option<Decimal256>,
+        jump_multiplier: Option<Decimal256>,
     },
 }
 
@@ -33,6 +44,9 @@ pub struct ConfigResponse {
     pub owner: String,
     pub base_rate: Decimal256,
     pub base_peg: Decimal256,
+    pub multiplier: Decimal256,
+    pub kink: Decimal256,
+    pub jump_multiplier: Decimal256,
 }
 
 // We define a custom struct for each query response
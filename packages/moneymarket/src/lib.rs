@@ -2,6 +2,7 @@ pub mod common;
 pub mod custody;
 pub mod custody_deposit_cap;
 pub mod distribution_model;
+pub mod fee_distribution;
 pub mod interest_model;
 pub mod liquidation;
 pub mod liquidation_queue;
@@ -9,6 +10,8 @@ pub mod market;
 pub mod native_wrapper;
 pub mod oracle;
 pub mod overseer;
+pub mod position;
+pub mod psm;
 pub mod querier;
 pub mod terraswap;
 pub mod tokens;
@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use cosmwasm_bignumber::math::{Decimal256, Uint256};
 use cw20::Cw20ReceiveMsg;
 
+use crate::position::PositionId;
 use crate::tokens::TokensHuman;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -60,13 +61,17 @@ pub enum Cw20HookMsg {
         fee_address: Option<String>,
         repay_address: Option<String>,
         borrower_address: Option<String>,
+        position_id: Option<PositionId>,
     },
 }
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 
 pub enum MarketExecuteMsg {
-    RepayStableFromLiquidation { borrower: String },
+    RepayStableFromLiquidation {
+        borrower: String,
+        position_id: Option<PositionId>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -5,6 +5,8 @@ use cosmwasm_bignumber::math::{Decimal256, Uint256};
 use cosmwasm_std::Uint128;
 use cw20::Cw20ReceiveMsg;
 
+use crate::fee_distribution::FeeRecipient;
+use crate::position::PositionId;
 use crate::tokens::TokensHuman;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -75,6 +77,12 @@ pub enum ExecuteMsg {
         collateral_token: String,
         bids_idx: Option<Vec<Uint128>>,
     },
+    /// Owner operation to split the bid fee by weight, an empty list sends it
+    /// all to the fee address. Once set, the recipients replace the fee address
+    /// of every bid, so they should mirror the market ones
+    UpdateFeeRecipients {
+        recipients: Vec<FeeRecipient>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -86,6 +94,7 @@ pub enum Cw20HookMsg {
         fee_address: Option<String>,
         repay_address: Option<String>,
         borrower_address: Option<String>,
+        position_id: Option<PositionId>,
     },
     SubmitBid {
         collateral_token: String,
@@ -97,11 +106,19 @@ pub enum Cw20HookMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Config {},
+    /// Bid fee recipients and the cumulative fees paid to each of them
+    FeeDistribution {},
     LiquidationAmount {
         borrow_amount: Uint256,
+        /// Limit the loan is brought back under, by the safe ratio
         borrow_limit: Uint256,
+        /// Limit over which the loan is liquidated, `borrow_limit` when unset
+        liquidation_limit: Option<Uint256>,
         collaterals: TokensHuman,
         collateral_prices: Vec<Decimal256>,
+        /// Max LTV applied to each collateral of the position,
+        /// the overseer whitelist ones when unset
+        collateral_max_ltvs: Option<Vec<Decimal256>>,
     },
     CollateralInfo {
         collateral_token: String,
@@ -4,6 +4,9 @@ use serde::{Deserialize, Serialize};
 
 use cosmwasm_bignumber::math::{Decimal256, Uint256};
 use cw20::Cw20ReceiveMsg;
+
+use crate::fee_distribution::FeeRecipient;
+use crate::position::PositionId;
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InstantiateMsg {
@@ -11,16 +14,37 @@ pub struct InstantiateMsg {
     pub owner_addr: String,
     /// Cw20 code id for Solid
     pub stable_code_id: u64,
-    /// Base borrow fee:
-    pub base_borrow_fee: Decimal256,
-    // Base fee increase factor
-    pub fee_increase_factor: Decimal256,
+    /// Borrow fee charged as a function of the SOLID peg
+    pub borrow_fee_curve: BorrowFeeCurve,
+    /// Seconds over which the SOLID peg is time-weighted
+    pub peg_twap_window: u64,
+    /// Max age in seconds of the SOLID price sampled into the peg twap
+    pub peg_price_timeframe: u64,
     // Base flash mint fee
     pub flash_mint_fee: Option<Decimal256>,
 }
 
+/// Piecewise-linear borrow fee curve over the SOLID peg
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+pub struct BorrowFeeCurve {
+    /// (peg, fee) points sorted by increasing peg, the fee is interpolated between them
+    /// and flat beyond the first and the last one
+    pub breakpoints: Vec<(Decimal256, Decimal256)>,
+    pub min_fee: Decimal256,
+    pub max_fee: Decimal256,
+}
+
+/// Share of the one time borrow fee waived for borrowers
+/// staking at least `min_staked` CAPA in gov
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BorrowFeeDiscountTier {
+    pub min_staked: Uint256,
+    pub discount: Decimal256,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
     Receive(Cw20ReceiveMsg),
 
@@ -37,28 +61,101 @@ pub enum ExecuteMsg {
         liquidation_contract: String,
 
         oracle_contract: String,
+        /// Interest model contract to query the borrow rate from
+        interest_model_contract: String,
     },
 
     /// Update config values
     UpdateConfig {
         owner_addr: Option<String>,
         liquidation_contract: Option<String>,
-        base_borrow_fee: Option<Decimal256>,
-        fee_increase_factor: Option<Decimal256>,
+        borrow_fee_curve: Option<BorrowFeeCurve>,
+        peg_twap_window: Option<u64>,
+        peg_price_timeframe: Option<u64>,
         flash_mint_fee: Option<Decimal256>,
         oracle_addr: Option<String>,
+        interest_model_contract: Option<String>,
+        /// Max SOLID that can be borrowed across all positions,
+        /// the SOLID minted by the PSM is not counted
+        mint_ceiling: Option<Uint256>,
+        /// Remove the mint ceiling, ignored with a new `mint_ceiling`
+        remove_mint_ceiling: Option<bool>,
+        /// Astroport router used to swap between collaterals and SOLID
+        astroport_router: Option<String>,
+        /// Peg stability module allowed to mint SOLID against its reserve
+        psm_contract: Option<String>,
+        /// Redemption fee charged when the base rate is fully decayed
+        redemption_fee_floor: Option<Decimal256>,
+        /// Redemption base rate decay per minute
+        redemption_decay_factor: Option<Decimal256>,
+        /// Max SOLID that can be flash minted in a single flash mint
+        flash_mint_max_amount: Option<Uint256>,
+        /// Yearly rate of the fixed term loan fee
+        term_loan_rate: Option<Decimal256>,
+        /// Yearly penalty rate accrued by term loans past their maturity,
+        /// required along with the term loan rate
+        term_loan_penalty_rate: Option<Decimal256>,
+        /// Clear both term loan rates to disable new term loans, the overdue ones
+        /// stop accruing penalties. Ignored with new term loan rates
+        remove_term_loans: Option<bool>,
+        /// CAPA gov contract queried for the staked balance of the borrowers
+        capa_gov_contract: Option<String>,
+        /// Borrow fee discount tiers sorted by increasing staked CAPA
+        borrow_fee_discount_tiers: Option<Vec<BorrowFeeDiscountTier>>,
+    },
+
+    /// Restrict flash mints to the given initiators, `None` opens them to anyone
+    UpdateFlashMinters {
+        flash_minters: Option<Vec<String>>,
+    },
+
+    /// Split the protocol fees by weight, an empty list sends them all to the collector
+    UpdateFeeRecipients {
+        recipients: Vec<FeeRecipient>,
     },
 
     /// Borrow stable asset with collaterals in overseer contract
     BorrowStable {
         borrow_amount: Uint256,
         to: Option<String>,
+        /// Borrow against the position of an owner who approved the sender
+        on_behalf_of: Option<String>,
+        /// Isolated position borrowing, the default one when unset
+        position_id: Option<PositionId>,
+    },
+
+    /// Borrow stable asset until `maturity` for a fixed fee, as a term loan
+    /// kept apart from the open-ended loan
+    BorrowTerm {
+        borrow_amount: Uint256,
+        maturity: u64,
+        /// Max fee accepted by the borrower, see the `TermLoanQuote` query
+        max_fee: Uint256,
+        to: Option<String>,
+        position_id: Option<PositionId>,
     },
 
-    /// Require a flash mint specifying a callback msg that will be send back to the calling contract
+    /// Allow `spender` to borrow up to `amount` against the sender position,
+    /// until the `expires` block time if given. A zero amount revokes the allowance
+    ApproveBorrow {
+        spender: String,
+        amount: Uint256,
+        expires: Option<u64>,
+        /// Position the allowance is granted on, the default one when unset
+        position_id: Option<PositionId>,
+    },
+
+    /// Sample the SOLID peg into the time-weighted peg, anyone can call it
+    /// so the average keeps up with the peg between borrows
+    UpdatePegTwap {},
+
+    /// Flash mint `amount` to the receiver, the sender by default, and call it back with
+    /// [`FlashMintCallbackMsg`]. The receiver must approve the amount plus the fee to the market
     FlashMint {
         amount: Uint256,
-        msg_callback: Binary,
+        receiver: Option<String>,
+        /// Passed through to the receiver callback
+        data: Binary,
     },
 
     /// Private msg that burn the requested amount from the flash minter and send fee to the collector
@@ -67,6 +164,62 @@ pub enum ExecuteMsg {
         burn_amount: Uint256,
         fee_amount: Uint256,
     },
+
+    /// Mint SOLID without debt, only the peg stability module can mint
+    /// against the stables it holds
+    MintStable {
+        recipient: String,
+        amount: Uint256,
+    },
+
+    /// Repay the loan by selling locked collateral on astroport, in a single transaction
+    RepayWithCollateral {
+        collateral_token: String,
+        /// Amount of collateral to sell
+        amount: Uint256,
+        /// Min SOLID the collateral sale must return, it covers the repay and the flash mint fee
+        min_solid_out: Uint256,
+        position_id: Option<PositionId>,
+    },
+
+    /// Private msg that burn the flash minted amount, send fee to the collector
+    /// and the left over SOLID to the borrower
+    PrivateRepayWithCollateralEnd {
+        borrower: String,
+        burn_amount: Uint256,
+        fee_amount: Uint256,
+        /// Market SOLID balance before the repay
+        prev_balance: Uint256,
+    },
+
+    /// Private msg that deposits the bought and the initial collateral
+    /// on behalf of the borrower and locks it
+    PrivateMultiplyLock {
+        borrower: String,
+        collateral_token: String,
+        /// Collateral sent by the borrower
+        collateral_amount: Uint256,
+        /// Market collateral balance before the swap
+        prev_balance: Uint256,
+        position_id: Option<PositionId>,
+    },
+
+    /// Move the loan and the term loans of a position to the same position of `recipient`,
+    /// only the overseer can move them when a position transfer is accepted
+    TransferLiability {
+        borrower: String,
+        recipient: String,
+        position_id: Option<PositionId>,
+    },
+
+    /// Private msg that borrows for the borrower the flash minted amount plus its fee,
+    /// burn the flash minted amount and send the fee to the collector
+    PrivateMultiplyEnd {
+        borrower: String,
+        flash_amount: Uint256,
+        fee_amount: Uint256,
+        position_id: Option<PositionId>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -74,9 +227,58 @@ pub enum ExecuteMsg {
 pub enum Cw20HookMsg {
     /// Return stable coins to a user
     /// according to exchange rate
-    RepayStable {},
+    RepayStable {
+        /// Borrower whose loan is repaid, defaults to the sender
+        borrower: Option<String>,
+        position_id: Option<PositionId>,
+    },
+    /// Repay the term loans then the open loan of a position, sent by
+    /// the liquidation queue
     RepayStableFromLiquidation {
         borrower: String,
+        position_id: Option<PositionId>,
+    },
+    /// Burn the principal of a position without collaterals and write off
+    /// its interest, sent by the overseer from its reserve
+    SettleBadDebt {
+        borrower: String,
+        position_id: Option<PositionId>,
+    },
+    /// Repay a term loan, any amount exceeding it is refunded
+    RepayTermLoan {
+        loan_id: u64,
+        /// Borrower whose term loan is repaid, defaults to the sender
+        borrower: Option<String>,
+        position_id: Option<PositionId>,
+    },
+    /// Open a leveraged position with the sent collateral:
+    /// flash mint SOLID, buy more collateral on astroport, lock it and borrow the flash minted SOLID
+    Multiply {
+        /// LTV of the position once opened, it can not exceed the collateral max LTV
+        target_ltv: Decimal256,
+        /// Min collateral the SOLID sale must return
+        min_collateral_out: Uint256,
+        position_id: Option<PositionId>,
+    },
+    /// Redeem the sent SOLID for collaterals at face value,
+    /// repaying the loans of the positions with the lowest health factor
+    Redeem {
+        /// Max redemption fee accepted by the redeemer
+        max_fee: Decimal256,
+    },
+}
+
+/// Callback the flash mint receivers implement, it is executed once
+/// the SOLID is minted and must leave the amount plus the fee approved to the market
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FlashMintCallbackMsg {
+    FlashMintCallback {
+        /// Address that requested the flash mint
+        initiator: String,
+        amount: Uint256,
+        fee: Uint256,
+        data: Binary,
     },
 }
 
@@ -87,8 +289,64 @@ pub enum QueryMsg {
     State {},
     BorrowerInfo {
         borrower: String,
+        position_id: Option<PositionId>,
     },
+    /// Loans of every position, by address and then position id
     BorrowerInfos {
+        start_after: Option<String>,
+        start_after_position_id: Option<PositionId>,
+        limit: Option<u32>,
+    },
+    /// Quote a borrow of `amount` SOLID by `borrower` at the current block
+    SimulateBorrow {
+        borrower: String,
+        amount: Uint256,
+        position_id: Option<PositionId>,
+    },
+    /// Quote a repay of `amount` SOLID on the loan of `borrower` at the current block
+    SimulateRepay {
+        borrower: String,
+        amount: Uint256,
+        position_id: Option<PositionId>,
+    },
+    TermLoan {
+        borrower: String,
+        loan_id: u64,
+        position_id: Option<PositionId>,
+    },
+    TermLoans {
+        borrower: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+        position_id: Option<PositionId>,
+    },
+    /// Fixed fee of a term borrow of `amount` SOLID by `borrower` until `maturity`
+    TermLoanQuote {
+        borrower: String,
+        amount: Uint256,
+        maturity: u64,
+    },
+    BorrowFeeDiscountTiers {},
+    /// Borrow fee discount of `borrower` given its CAPA staked in gov
+    BorrowFeeDiscount {
+        borrower: String,
+    },
+    /// Fee charged on a flash mint of `amount` SOLID
+    FlashMintFee {
+        amount: Uint256,
+    },
+    /// Fee recipients and the cumulative fees paid to each of them
+    FeeDistribution {},
+    /// Amount `spender` can borrow against the position of `owner`
+    BorrowAllowance {
+        owner: String,
+        spender: String,
+        position_id: Option<PositionId>,
+    },
+    /// Borrow allowances granted by `owner` on one of its positions
+    BorrowAllowances {
+        owner: String,
+        position_id: Option<PositionId>,
         start_after: Option<String>,
         limit: Option<u32>,
     },
@@ -103,19 +361,91 @@ pub struct ConfigResponse {
     pub collector_contract: String,
     pub liquidation_contract: String,
     pub oracle_contract: String,
+    pub interest_model_contract: String,
     pub flash_mint_fee: Option<Decimal256>,
+    pub flash_mint_max_amount: Option<Uint256>,
+    pub flash_minters: Option<Vec<String>>,
+    pub mint_ceiling: Option<Uint256>,
+    pub astroport_router: Option<String>,
+    pub psm_contract: Option<String>,
+    pub redemption_fee_floor: Option<Decimal256>,
+    pub redemption_decay_factor: Option<Decimal256>,
+    pub term_loan_rate: Option<Decimal256>,
+    pub term_loan_penalty_rate: Option<Decimal256>,
+    pub capa_gov_contract: Option<String>,
+    pub borrow_fee_curve: BorrowFeeCurve,
+    pub peg_twap_window: u64,
+    pub peg_price_timeframe: u64,
 }
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct StateResponse {
     pub total_liabilities: Decimal256,
+    pub global_interest_index: Decimal256,
+    pub last_interest_updated: u64,
+    /// Time-weighted SOLID peg used to compute the borrow fee
+    pub peg_twap: Decimal256,
+    /// Redemption base rate at the last redemption, it decays over time
+    pub redemption_base_rate: Decimal256,
+    pub last_redemption_time: u64,
 }
 
 // We define a custom struct for each query response
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct BorrowerInfoResponse {
     pub borrower: String,
+    #[serde(default)]
+    pub position_id: PositionId,
+    /// Open-ended loan plus the term loans
+    pub loan_amount: Uint256,
+    /// Part of the loan owed on term loans
+    #[serde(default)]
+    pub term_loan_amount: Uint256,
+    /// Principal of the open loan and the term loans, without interest, fees nor penalties
+    #[serde(default)]
+    pub principal_amount: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BorrowFeeDiscountTiersResponse {
+    pub tiers: Vec<BorrowFeeDiscountTier>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BorrowFeeDiscountResponse {
+    pub borrower: String,
+    pub staked_amount: Uint256,
+    pub discount: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TermLoanResponse {
+    pub borrower: String,
+    pub loan_id: u64,
+    /// Principal plus the fixed fee and the overdue penalty left to repay
+    pub loan_amount: Uint256,
+    pub maturity: u64,
+    /// The loan is past its maturity and accrues the penalty
+    pub overdue: bool,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TermLoansResponse {
+    pub term_loans: Vec<TermLoanResponse>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct TermLoanQuoteResponse {
+    pub borrow_amount: Uint256,
+    pub maturity: u64,
+    pub fee_amount: Uint256,
+    /// Amount to repay by the maturity
     pub loan_amount: Uint256,
 }
 
@@ -125,6 +455,61 @@ pub struct BorrowerInfosResponse {
     pub borrower_infos: Vec<BorrowerInfoResponse>,
 }
 
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SimulateBorrowResponse {
+    pub borrower: String,
+    pub borrow_amount: Uint256,
+    /// One time fee added to the loan
+    pub mint_fee: Uint256,
+    /// Loan amount after the borrow, interest included
+    pub loan_amount: Uint256,
+    pub borrow_limit: Uint256,
+    /// Whether the borrow would fail with `BorrowExceedsLimit`
+    pub exceeds_limit: bool,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SimulateRepayResponse {
+    pub borrower: String,
+    /// Part of the sent amount applied to the loan
+    pub repay_amount: Uint256,
+    /// Principal burned
+    pub burn_amount: Uint256,
+    /// Interest sent to the collector
+    pub collector_amount: Uint256,
+    /// Amount exceeding the loan, refunded to the payer
+    pub refund_amount: Uint256,
+    /// Loan amount left after the repay
+    pub loan_amount: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FlashMintFeeResponse {
+    pub amount: Uint256,
+    pub fee_amount: Uint256,
+    /// Amount the flash minter has to approve to the market: `amount` + `fee_amount`
+    pub total_amount: Uint256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BorrowAllowanceResponse {
+    pub owner: String,
+    pub position_id: PositionId,
+    pub spender: String,
+    pub amount: Uint256,
+    pub expires: Option<u64>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BorrowAllowancesResponse {
+    pub allowances: Vec<BorrowAllowanceResponse>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct MigrateMsg {}
@@ -1,8 +1,10 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::position::PositionId;
 use crate::tokens::TokensHuman;
 use cosmwasm_bignumber::math::{Decimal256, Uint256};
+use cw20::Cw20ReceiveMsg;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -28,10 +30,29 @@ pub struct InstantiateMsg {
 #[serde(rename_all = "snake_case")]
 pub struct MigrateMsg {}
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+/// Identifier of an e-mode category, chosen by the owner
+pub type EmodeCategoryId = u32;
+
+/// Lifecycle of a whitelisted collateral
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CollateralStatus {
+    #[default]
+    Active,
+    /// No new collateral can be locked and the max LTV cannot be raised
+    BorrowFrozen,
+    /// Frozen, with the max LTV ramping down to zero between both times (seconds)
+    Deprecated { start_time: u64, end_time: u64 },
+    /// Removed from the protocol once nothing is locked anymore
+    Delisted,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 #[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
+    Receive(Cw20ReceiveMsg),
+
     ////////////////////
     /// Owner operations
     ////////////////////
@@ -44,17 +65,84 @@ pub enum ExecuteMsg {
     },
     /// Create new custody contract for the given collateral token
     Whitelist {
-        name: String,             // bAsset name
-        symbol: String,           // bAsset symbol
-        collateral_token: String, // bAsset token contract
-        custody_contract: String, // bAsset custody contract
-        max_ltv: Decimal256,      // Loan To Value ratio
+        name: String,                  // bAsset name
+        symbol: String,                // bAsset symbol
+        collateral_token: String,      // bAsset token contract
+        custody_contract: String,      // bAsset custody contract
+        max_ltv: Decimal256,           // Loan To Value ratio
+        debt_ceiling: Option<Uint256>, // Max SOLID debt backed by this collateral
+        /// Loan To Value ratio over which a position is liquidated, `max_ltv` when unset
+        liquidation_threshold: Option<Decimal256>,
     },
     /// Update registered whitelist info
     UpdateWhitelist {
         collateral_token: String,         // bAsset token contract
         custody_contract: Option<String>, // bAsset custody contract
         max_ltv: Option<Decimal256>,      // Loan To Value ratio
+        debt_ceiling: Option<Uint256>,    // Max SOLID debt backed by this collateral
+        /// Remove the debt ceiling of the collateral, ignored with a new `debt_ceiling`
+        remove_debt_ceiling: Option<bool>,
+        liquidation_threshold: Option<Decimal256>,
+        /// Lifecycle state, delisting requires nothing locked anymore
+        status: Option<CollateralStatus>,
+        /// Seconds over which the new max LTV and liquidation threshold are
+        /// reached linearly, applied instantly when unset
+        ramp_duration: Option<u64>,
+    },
+    /// Group correlated collaterals in an efficiency mode category with boosted LTVs
+    RegisterEmodeCategory {
+        category_id: EmodeCategoryId,
+        label: String,
+        collateral_tokens: Vec<String>,
+        max_ltv: Decimal256,
+        liquidation_threshold: Decimal256,
+    },
+    /// Update the LTVs of an e-mode category, add collaterals to it or remove some.
+    /// A collateral can only be removed once no opted in position holds it, the
+    /// category LTVs can be ramped down to the collateral ones beforehand
+    UpdateEmodeCategory {
+        category_id: EmodeCategoryId,
+        collateral_tokens: Option<Vec<String>>,
+        remove_collateral_tokens: Option<Vec<String>>,
+        max_ltv: Option<Decimal256>,
+        liquidation_threshold: Option<Decimal256>,
+        /// Seconds over which the new max LTV and liquidation threshold are
+        /// reached linearly, applied instantly when unset
+        ramp_duration: Option<u64>,
+    },
+
+    ////////////////////
+    /// Market operations
+    ////////////////////
+    /// Attribute the borrower loan to its collaterals pro-rata to their value
+    UpdateBorrowerDebt {
+        borrower: String,
+        loan_amount: Uint256,
+        position_id: Option<PositionId>,
+        /// The debt ceilings are only enforced on borrows,
+        /// repayments and liquidations always go through
+        is_borrow: bool,
+    },
+    /// Unlock collaterals of a borrower and send them to `recipient`
+    ReleaseCollateral {
+        borrower: String,
+        collaterals: TokensHuman,
+        recipient: String,
+        position_id: Option<PositionId>,
+    },
+    /// Lock collaterals deposited by the market on behalf of a borrower
+    LockCollateralFor {
+        borrower: String,
+        collaterals: TokensHuman,
+        position_id: Option<PositionId>,
+    },
+    /// Send collaterals of a borrower to a SOLID redeemer,
+    /// the market reduces the loan by their value
+    RedeemCollateral {
+        borrower: String,
+        collaterals: TokensHuman,
+        recipient: String,
+        position_id: Option<PositionId>,
     },
 
     ////////////////////
@@ -62,15 +150,58 @@ pub enum ExecuteMsg {
     ////////////////////
     LockCollateral {
         collaterals: TokensHuman, // <(Collateral Token, Amount)>
+        /// Isolated position receiving the collaterals, the default one when unset
+        position_id: Option<PositionId>,
     },
     UnlockCollateral {
         collaterals: TokensHuman, // <(Collateral Token, Amount)>
+        position_id: Option<PositionId>,
+    },
+    /// Offer a position, its collaterals and its loan, to `recipient`.
+    /// Nothing moves until the recipient accepts it
+    TransferPosition {
+        recipient: String,
+        position_id: Option<PositionId>,
+    },
+    /// Withdraw a pending position transfer offer
+    CancelPositionTransfer {
+        position_id: Option<PositionId>,
+    },
+    /// Accept the position offered by `owner`, it is moved to the same position of the sender
+    /// which must be empty
+    AcceptPosition {
+        owner: String,
+        position_id: Option<PositionId>,
+    },
+    /// Opt the position in an e-mode category holding all of its collaterals,
+    /// or out of its category when unset
+    SetEmodeCategory {
+        category_id: Option<EmodeCategoryId>,
+        position_id: Option<PositionId>,
     },
 
     /////////////////////////////
     /// Permissionless operations
     /////////////////////////////
-    LiquidateCollateral { borrower: String },
+    LiquidateCollateral {
+        borrower: String,
+        position_id: Option<PositionId>,
+    },
+    /// Liquidate every unsafe position of the batch, skipping the safe or failing ones
+    LiquidateCollaterals {
+        borrowers: Vec<BorrowerPosition>,
+    },
+    /// Repay with reserve SOLID the debt left on a position without collaterals
+    SettleBadDebt {
+        borrower: String,
+        position_id: Option<PositionId>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BorrowerPosition {
+    pub borrower: String,
+    pub position_id: Option<PositionId>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
@@ -90,14 +221,50 @@ pub enum QueryMsg {
     },
     Collaterals {
         borrower: String,
+        position_id: Option<PositionId>,
     },
+    /// Collaterals of every position, by address and then position id
     AllCollaterals {
         start_after: Option<String>,
+        start_after_position_id: Option<PositionId>,
         limit: Option<u32>,
     },
     BorrowLimit {
         borrower: String,
         block_time: Option<u64>,
+        position_id: Option<PositionId>,
+    },
+    Reserve {},
+    /// Borrowers with a loan sorted by increasing health factor,
+    /// as computed on the last update of their position
+    BorrowersByHealth {
+        start_after: Option<String>,
+        start_after_position_id: Option<PositionId>,
+        limit: Option<u32>,
+    },
+    /// Positions whose loan exceeds their liquidation limit at current prices,
+    /// scanning `limit` positions by address and then position id
+    LiquidatablePositions {
+        start_after: Option<String>,
+        start_after_position_id: Option<PositionId>,
+        limit: Option<u32>,
+    },
+    /// Collaterals, loan and liquidation prices of a position
+    PositionHealth {
+        borrower: String,
+        position_id: Option<PositionId>,
+    },
+    /// Pending transfer offer of a position
+    PositionTransfer {
+        owner: String,
+        position_id: Option<PositionId>,
+    },
+    EmodeCategory {
+        category_id: EmodeCategoryId,
+    },
+    EmodeCategories {
+        start_after: Option<EmodeCategoryId>,
+        limit: Option<u32>,
     },
 }
 
@@ -118,9 +285,24 @@ pub struct ConfigResponse {
 pub struct WhitelistResponseElem {
     pub name: String,
     pub symbol: String,
+    /// Max LTV currently applied to borrow limits
     pub max_ltv: Decimal256,
+    /// Loan To Value ratio over which a position is currently liquidated
+    pub liquidation_threshold: Decimal256,
+    /// Max LTV reached at the end of the ramp
+    pub target_max_ltv: Decimal256,
+    /// Liquidation threshold reached at the end of the ramp
+    pub target_liquidation_threshold: Decimal256,
+    /// End of the ramp towards the target values, none when they are reached
+    pub ramp_end_time: Option<u64>,
     pub custody_contract: String,
     pub collateral_token: String,
+    pub debt_ceiling: Option<Uint256>,
+    /// SOLID debt currently attributed to this collateral
+    pub total_debt: Uint256,
+    pub status: CollateralStatus,
+    /// Amount locked in all positions
+    pub total_locked: Uint256,
 }
 
 // We define a custom struct for each query response
@@ -133,6 +315,8 @@ pub struct WhitelistResponse {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct CollateralsResponse {
     pub borrower: String,
+    #[serde(default)]
+    pub position_id: PositionId,
     pub collaterals: TokensHuman, // <(Collateral Token, Amount)>
 }
 
@@ -147,3 +331,119 @@ pub struct BorrowLimitResponse {
     pub borrower: String,
     pub borrow_limit: Uint256,
 }
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ReserveResponse {
+    /// SOLID available to settle bad debt
+    pub reserve: Uint256,
+    /// Bad debt settled with the reserve so far
+    pub total_bad_debt: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BorrowerHealthResponse {
+    pub borrower: String,
+    #[serde(default)]
+    pub position_id: PositionId,
+    /// Borrow limit over loan amount, unlike the `health_factor` of
+    /// `PositionHealthResponse` which uses the liquidation limit
+    pub borrow_health: Decimal256,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct BorrowersByHealthResponse {
+    pub borrowers: Vec<BorrowerHealthResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LiquidatablePosition {
+    pub borrower: String,
+    pub position_id: PositionId,
+    pub loan_amount: Uint256,
+    pub borrow_limit: Uint256,
+    pub liquidation_limit: Uint256,
+    /// Loan amount exceeding the liquidation limit
+    pub shortfall: Uint256,
+    /// Collaterals the liquidation queue would liquidate
+    pub liquidation_amount: TokensHuman,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct LiquidatablePositionsResponse {
+    pub positions: Vec<LiquidatablePosition>,
+    /// Positions skipped as they could not be evaluated, usually for a collateral
+    /// price older than the price timeframe or a failing market or liquidation query
+    pub stale_positions: Vec<(String, PositionId)>,
+    /// Last position scanned, the `start_after` of the next page.
+    /// `None` when no position is left to scan
+    pub next_start_after: Option<(String, PositionId)>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct CollateralHealth {
+    pub collateral_token: String,
+    pub amount: Uint256,
+    /// Oracle price in uusd
+    pub price: Decimal256,
+    pub value: Uint256,
+    pub max_ltv: Decimal256,
+    pub liquidation_threshold: Decimal256,
+    /// Price under which the position becomes liquidatable, the other collateral prices
+    /// being unchanged. `None` when a price drop of this collateral alone can not get it liquidated
+    pub liquidation_price: Option<Decimal256>,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PositionHealthResponse {
+    pub borrower: String,
+    pub position_id: PositionId,
+    pub collaterals: Vec<CollateralHealth>,
+    /// Value of the collaterals in uusd
+    pub total_value: Uint256,
+    pub borrow_limit: Uint256,
+    /// Loan over which the position is liquidated
+    pub liquidation_limit: Uint256,
+    /// Open-ended loan plus the term loans, from the market
+    pub loan_amount: Uint256,
+    /// Loan over collaterals value, `None` without collaterals
+    pub ltv: Option<Decimal256>,
+    /// Liquidation limit over loan amount, the position is liquidatable under one.
+    /// `None` without loan
+    pub health_factor: Option<Decimal256>,
+    /// E-mode category the position opted in
+    pub emode_category: Option<EmodeCategoryId>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct PositionTransferResponse {
+    pub owner: String,
+    pub position_id: PositionId,
+    /// Address allowed to accept the position
+    pub recipient: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct EmodeCategoryResponse {
+    pub category_id: EmodeCategoryId,
+    pub label: String,
+    pub collateral_tokens: Vec<String>,
+    /// Max LTV currently applied to the collaterals of the positions in the category
+    pub max_ltv: Decimal256,
+    /// Liquidation threshold currently applied to the same collaterals
+    pub liquidation_threshold: Decimal256,
+    /// Max LTV reached at the end of the ramp
+    pub target_max_ltv: Decimal256,
+    /// Liquidation threshold reached at the end of the ramp
+    pub target_liquidation_threshold: Decimal256,
+    /// End of the ramp towards the target values, none when they are reached
+    pub ramp_end_time: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct EmodeCategoriesResponse {
+    pub categories: Vec<EmodeCategoryResponse>,
+}
@@ -0,0 +1,56 @@
+use cosmwasm_std::{Addr, StdError, StdResult};
+use std::convert::TryInto;
+
+/// Isolated position of an address, with its own collaterals and loan
+pub type PositionId = u32;
+
+/// Position used when none is given, it keeps the storage keys of the address
+pub const DEFAULT_POSITION_ID: PositionId = 0;
+
+/// Separates the address from the position id in storage keys,
+/// it never appears in an address
+const POSITION_KEY_SEPARATOR: u8 = b'/';
+
+/// Storage key of a position: the address for the default position, otherwise
+/// the address followed by the separator and the big endian position id,
+/// so the positions of an address are stored right after its default one
+pub fn position_key(owner: &Addr, position_id: PositionId) -> Vec<u8> {
+    let mut key = owner.as_bytes().to_vec();
+    if position_id != DEFAULT_POSITION_ID {
+        key.push(POSITION_KEY_SEPARATOR);
+        key.extend_from_slice(&position_id.to_be_bytes());
+    }
+    key
+}
+
+/// Address and position id of a position storage key
+pub fn parse_position_key(key: &[u8]) -> StdResult<(String, PositionId)> {
+    match key.iter().position(|b| *b == POSITION_KEY_SEPARATOR) {
+        None => Ok((String::from_utf8(key.to_vec())?, DEFAULT_POSITION_ID)),
+        Some(i) => {
+            let position_id: [u8; 4] = key[i + 1..]
+                .try_into()
+                .map_err(|_| StdError::generic_err("Invalid position key"))?;
+            Ok((
+                String::from_utf8(key[..i].to_vec())?,
+                PositionId::from_be_bytes(position_id),
+            ))
+        }
+    }
+}
+
+/// First key after the given position, to paginate over positions
+pub fn position_range_start(owner: &Addr, position_id: PositionId) -> Vec<u8> {
+    let mut key = position_key(owner, position_id);
+    key.push(1);
+    key
+}
+
+/// Position id of a message, omitted for the default position
+pub fn optional_position_id(position_id: PositionId) -> Option<PositionId> {
+    if position_id == DEFAULT_POSITION_ID {
+        None
+    } else {
+        Some(position_id)
+    }
+}
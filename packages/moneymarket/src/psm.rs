@@ -0,0 +1,112 @@
+use astroport::asset::AssetInfo;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_bignumber::math::{Decimal256, Uint256};
+use cw20::Cw20ReceiveMsg;
+
+use crate::fee_distribution::FeeRecipient;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct InstantiateMsg {
+    /// Owner address for config update
+    pub owner_addr: String,
+    /// Market contract minting SOLID for the module
+    pub market_contract: String,
+    /// SOLID token contract
+    pub stable_contract: String,
+    /// Collector contract receiving the swap fees when no fee recipients are set
+    pub collector_contract: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    Receive(Cw20ReceiveMsg),
+
+    ////////////////////
+    /// Owner operations
+    ////////////////////
+    /// Update config values
+    UpdateConfig {
+        owner_addr: Option<String>,
+        collector_contract: Option<String>,
+    },
+
+    /// Split the swap fees by weight, an empty list sends them all to the collector
+    UpdateFeeRecipients {
+        recipients: Vec<FeeRecipient>,
+    },
+
+    /// Whitelist a stable asset or update its fees and cap.
+    /// The asset must have the SOLID decimals as it is swapped 1:1
+    RegisterAsset {
+        asset_info: AssetInfo,
+        mint_fee: Decimal256,
+        redeem_fee: Decimal256,
+        /// Max amount of the asset the module can hold
+        cap: Uint256,
+    },
+
+    ////////////////////
+    /// User operations
+    ////////////////////
+    /// Deposit a whitelisted native stable and mint SOLID 1:1, minus the mint fee
+    Mint {
+        recipient: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    /// Deposit a whitelisted cw20 stable and mint SOLID 1:1, minus the mint fee
+    Mint { recipient: Option<String> },
+    /// Burn the sent SOLID and release the asset 1:1, minus the redeem fee
+    Redeem {
+        asset_info: AssetInfo,
+        recipient: Option<String>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    Config {},
+    /// Swap fee recipients and the cumulative fees paid to each of them
+    FeeDistribution {},
+    Asset {
+        asset_info: AssetInfo,
+    },
+    Assets {},
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ConfigResponse {
+    pub owner_addr: String,
+    pub market_contract: String,
+    pub stable_contract: String,
+    pub collector_contract: String,
+}
+
+// We define a custom struct for each query response
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AssetResponse {
+    pub asset_info: AssetInfo,
+    pub mint_fee: Decimal256,
+    pub redeem_fee: Decimal256,
+    pub cap: Uint256,
+    /// Amount of the asset held by the module, it backs the SOLID minted against it
+    pub reserve: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct AssetsResponse {
+    pub assets: Vec<AssetResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MigrateMsg {}
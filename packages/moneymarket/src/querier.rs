@@ -6,7 +6,7 @@ use cosmwasm_std::{
     to_binary, Addr, AllBalanceResponse, BalanceResponse, BankQuery, Coin, Deps, QueryRequest,
     StdError, StdResult, Uint128, WasmQuery,
 };
-use cw20::{Cw20QueryMsg, TokenInfoResponse};
+use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg, TokenInfoResponse};
 
 use crate::oracle::{PriceResponse, QueryMsg as OracleQueryMsg};
 
@@ -35,7 +35,7 @@ pub fn query_token_balance(
     account_addr: Addr,
 ) -> StdResult<Uint256> {
     // load balance form the token contract
-    let balance: Uint128 = deps
+    let balance: Cw20BalanceResponse = deps
         .querier
         .query(&QueryRequest::Wasm(WasmQuery::Smart {
             contract_addr: contract_addr.to_string(),
@@ -43,9 +43,11 @@ pub fn query_token_balance(
                 address: account_addr.to_string(),
             })?,
         }))
-        .unwrap_or_else(|_| Uint128::zero());
+        .unwrap_or_else(|_| Cw20BalanceResponse {
+            balance: Uint128::zero(),
+        });
 
-    Ok(balance.into())
+    Ok(balance.balance.into())
 }
 
 pub fn query_supply(deps: Deps, contract_addr: Addr) -> StdResult<Uint256> {